@@ -17,6 +17,33 @@
 //!
 //! ## Generate clone/drop functions.
 //!
+//! ## Generate builders for implementing traits in C.
+//!
+//! For every trait, a `{TRAIT}_VTBL(impl)` macro is defined, that initializes a vtable out of
+//! `impl_{function}` functions. Said functions must accept the exact container type as their first
+//! argument. The vtable's `descriptor` is left `NULL`, which makes Rust side fall back to the
+//! descriptor of the trait definition.
+//!
+//! For every boxed trait object and group, a `{name}_new` function is emitted (following the same
+//! naming rules as other wrappers), that wraps an instance pointer, its drop, and free functions into
+//! the object, alongside its context and vtables:
+//!
+//! ```ignore
+//! static void my_print_self(const struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void *cont) {
+//!     printf("%d\n", *(int *)cont->instance.instance);
+//! }
+//!
+//! static const struct MainFeatureVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void
+//!     main_feature_vtbl = MAINFEATURE_VTBL(my);
+//!
+//! FeaturesGroup obj = featuresgroup_new(instance, free, free, context, &main_feature_vtbl, NULL, NULL, NULL);
+//! ```
+//!
+//! ## Generate capability functions for groups.
+//!
+//! `{group}_capabilities` returns a bitset of traits the group object implements, and
+//! `{group}_capability_names` lists the names of the traits in the order of their bits.
+//!
 //! ## Add shortened typedefs for default types.
 //!
 //! Basically, `PluginInnerArcBox` drops the suffix and becomes just `PluginInner`.
@@ -33,79 +60,85 @@
 //! `MUT_SLICE`, `REF_SLICE`, `STR` macros allow to build slices out of strings or buffers,
 //! `COLLECT_CB` and its derivatives deal with buffer callbacks, `COUNT_CB` creates a callback that
 //! simply counts elements, `BUF_ITER` and its derivatives deal with constructing iterators from
-//! buffers.
+//! buffers. `FN_MUT`, `FN_REF` and their derivatives build callbacks that return values, while
+//! `FN_BOX`, `FN_ONCE_BOX` and their derivatives build owned callbacks out of a context, a
+//! function, and a drop function.
 //!
 
 use crate::config::*;
+use crate::ir::*;
 use crate::types::*;
 use itertools::Itertools;
 use log::trace;
 use regex::*;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 pub fn is_c(header: &str) -> Result<bool> {
-    Ok(Regex::new(
-        r"
-typedef struct [^\s]+ \{|",
-    )?
-    .is_match(header))
+    let ir = Header::parse(header)?;
+    Ok(!ir.is_cpp() && (ir.structs().next().is_some() || ir.typedefs().next().is_some()))
 }
 
 pub fn parse_header(header: &str, config: &Config) -> Result<String> {
+    let ir = Header::parse(header)?;
+
     // COLLECTION:
 
-    // Collect all zsized ret tmps
-    let zsr_regex = zero_sized_ret_regex()?;
-    let zst_rets = zsr_regex
-        .captures_iter(header)
-        .map(|c| (c["trait"].to_string(), c["context"].to_string()))
+    // Collect all zsized ret tmps - they are declared, but never defined
+    let zst_rets = ir
+        .typedefs()
+        .filter(|(_, t)| t.target == format!("struct {}", t.name))
+        .filter(|(_, t)| ir.find_struct(&t.name).is_none())
+        .filter_map(|(item, t)| {
+            let idx = t.name.rfind("RetTmp_")?;
+            Some((
+                item,
+                t.name[..idx].to_string(),
+                t.name[(idx + "RetTmp_".len())..].to_string(),
+            ))
+        })
         .collect::<Vec<_>>();
 
     // Collect all contexts
     let mut contexts = HashSet::new();
 
-    for (cap, ctx) in &zst_rets {
+    for (_, cap, ctx) in &zst_rets {
         contexts.insert(ctx.clone());
         trace!("CAP: {} {}", cap, ctx);
     }
 
     // Check if we need to typedef `TypeLayout`
-    let type_layout_re = Regex::new(r"((typedef [\s]+)|struct) TypeLayout")?;
-    let needs_type_layout =
-        header.contains("const TypeLayout *") && !type_layout_re.is_match(header);
+    let needs_type_layout = header.contains("const TypeLayout *") && !ir.has_type("TypeLayout");
 
     // PROCESSING:
 
-    // Remove zsized ret tmps
-    let header = zsr_regex.replace_all(header, "");
+    // Remove zsized ret tmps, and their usage in groups and objects
+    let mut edits = Edits::default();
 
-    let gr_regex = group_ret_tmp_regex(&zst_rets)?;
-    let header = gr_regex.replace_all(&header, "");
+    let zst_types = zst_rets
+        .iter()
+        .map(|(item, t, ctx)| {
+            edits.remove(ir.line_span(item));
+            format!("struct {}RetTmp_{}", t, ctx)
+        })
+        .collect::<HashSet<_>>();
 
-    let cr_regex = ret_tmp_regex(&zst_rets)?;
-    let header = cr_regex.replace_all(&header, "");
+    for (_, s) in ir.structs() {
+        for f in s.fields().iter().filter(|f| zst_types.contains(&f.ty)) {
+            edits.remove(f.span.clone());
+        }
+    }
+
+    let header = edits.apply(header)?;
 
     // Monomorphize Contexts:
     contexts.remove("Context");
-    let header = monomorphize_contexts(header, &contexts)?;
+    let header = monomorphize_contexts(&header, &contexts)?;
 
     // MORE COLLECTION:
 
-    // Collect all vtables
-    let vtbl_regex = vtbl_regex()?;
-
-    let vtbls = vtbl_regex
-        .captures_iter(&header)
-        .map(|c| {
-            (
-                c["trait"].to_string(),
-                c["container"].to_string(),
-                c["context"].to_string(),
-                c["functions"].to_string(),
-            )
-        })
-        .collect::<Vec<_>>();
+    let ir = Header::parse(&header)?;
 
+    // Collect all vtables
     let mut group_vtbls = vec![];
     let mut obj_vtbls = vec![];
 
@@ -113,14 +146,17 @@ pub fn parse_header(header: &str, config: &Config) -> Result<String> {
         Regex::new("(?P<inner>[^\\s]+[^_])__(___)?(?P<ctx>[^_][^\\s]+[^_])__.+RetTmp_(?P<ctx2>)")?;
     let gobj_cont_regex = Regex::new("(?P<inner>[^\\s]+[^_])__(___)?(?P<ctx>[^_][^\\s]+[^_])")?;
 
-    for (t, cont, ctx, funcs) in vtbls {
+    for (t, cont, ctx, funcs) in ir.structs().filter_map(|(_, s)| {
+        let (t, cont, ctx) = split_vtbl_name(&s.name)?;
+        Some((t, cont, ctx, s.fields().to_vec())).filter(|_| s.is_fn_table())
+    }) {
         if cont == "CGlueObjContainer" {
             let captures = obj_cont_regex
-                .captures(&ctx)
+                .captures(ctx)
                 .ok_or("Failed to parse captures")?;
 
             obj_vtbls.push((
-                t,
+                t.to_string(),
                 ctx.to_string(),
                 captures["inner"].to_string(),
                 captures["ctx"].to_string(),
@@ -128,13 +164,11 @@ pub fn parse_header(header: &str, config: &Config) -> Result<String> {
             ));
         } else {
             let captures = gobj_cont_regex
-                .captures(&ctx)
+                .captures(ctx)
                 .ok_or("Failed to parse captures")?;
             group_vtbls.push((
-                t,
-                Regex::new("(?P<group>.*)Container")?
-                    .replace(&cont, "$group")
-                    .to_string(),
+                t.to_string(),
+                cont.strip_suffix("Container").unwrap_or(cont).to_string(),
                 ctx.to_string(),
                 captures["inner"].to_string(),
                 captures["ctx"].to_string(),
@@ -151,7 +185,20 @@ pub fn parse_header(header: &str, config: &Config) -> Result<String> {
 
     // Remove any NoContext usage
 
-    let header = Regex::new(r"\s+(struct )?NoContext context;\n")?.replace_all(&header, "");
+    let mut edits = Edits::default();
+
+    for (_, s) in ir.structs() {
+        for f in s
+            .fields()
+            .iter()
+            .filter(|f| f.name == "context" && (f.ty == "NoContext" || f.ty == "struct NoContext"))
+        {
+            edits.remove(f.span.clone());
+        }
+    }
+
+    let header = edits.apply(&header)?;
+    let ir = Header::parse(&header)?;
 
     // Wrapper rules:
     //
@@ -166,25 +213,33 @@ pub fn parse_header(header: &str, config: &Config) -> Result<String> {
     let mut fwd_declarations = String::new();
     let mut shortened_typedefs = vec![];
 
-    // Create context clone/drop wrappers
-    for (ty, prefix, impl_clone, impl_drop) in context_map.iter().filter_map(
-        |(
-            ty,
-            ContextType {
-                ty_prefix,
-                clone_impl,
-                drop_impl,
-                ..
-            },
-        )| {
-            match (clone_impl, drop_impl) {
-                (Some(impl_clone), Some(impl_drop)) => {
-                    Some((ty, ty_prefix.to_lowercase(), impl_clone, impl_drop))
+    // Create context clone/drop wrappers for the context types present in the header
+    let mut context_wrappers = context_map
+        .iter()
+        .filter(|(ty, _)| ir.has_type(ty))
+        .filter_map(
+            |(
+                ty,
+                ContextType {
+                    ty_prefix,
+                    clone_impl,
+                    drop_impl,
+                    ..
+                },
+            )| {
+                match (clone_impl, drop_impl) {
+                    (Some(impl_clone), Some(impl_drop)) => {
+                        Some((ty, ty_prefix.to_lowercase(), impl_clone, impl_drop))
+                    }
+                    _ => None,
                 }
-                _ => None,
-            }
-        },
-    ) {
+            },
+        )
+        .collect::<Vec<_>>();
+
+    context_wrappers.sort_by(|a, b| a.0.cmp(b.0));
+
+    for (ty, prefix, impl_clone, impl_drop) in context_wrappers {
         all_wrappers += &format!(
             r"static {ty} ctx_{prefix}_clone({ty} *self) {{
     {ty} ret = *self;
@@ -230,10 +285,12 @@ void ctx_{prefix}_drop({ty} *self) {{
 
     let mut vtbl_types: HashMap<String, HashSet<String>> = HashMap::new();
 
+    let mut trait_funcs: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
     for (t, second_half, _, _, funcs) in &obj_vtbls {
         let container_ty = format!("struct CGlueObjContainer_{}", second_half);
 
-        let vtbl = Vtable::new(t.to_string(), funcs, &container_ty)?;
+        let vtbl = Vtable::new(t.to_string(), funcs, &container_ty);
 
         for f in vtbl.functions {
             vtbl_types.entry(f.name).or_default().insert(t.clone());
@@ -241,13 +298,13 @@ void ctx_{prefix}_drop({ty} *self) {{
     }
 
     for (t, second_half, inner, context, funcs) in obj_vtbls {
-        trace!("{}Vtbl_CGlueObjContainer_{}", t, second_half);
+        let vtbl_ty = format!("struct {}Vtbl_CGlueObjContainer_{}", t, second_half);
 
-        let obj_regex = obj_regex(&format!("{}Vtbl_CGlueObjContainer_{}", t, second_half))?;
+        trace!("{}", vtbl_ty);
 
-        let this_ty = &obj_regex
-            .captures(&header)
-            .ok_or("Unable to find trait obj")?["objtype"];
+        let this_ty = &find_trait_obj(&ir, &vtbl_ty)
+            .ok_or("Unable to find trait obj")?
+            .name;
 
         let this_ty = format!("struct {}", this_ty);
         let container_ty = format!("struct CGlueObjContainer_{}", second_half);
@@ -255,7 +312,11 @@ void ctx_{prefix}_drop({ty} *self) {{
         fwd_declarations += &format!("{};\n", this_ty);
         fwd_declarations += &format!("{};\n", container_ty);
 
-        let vtbl = Vtable::new(t.clone(), &funcs, &container_ty)?;
+        let vtbl = Vtable::new(t.clone(), &funcs, &container_ty);
+
+        trait_funcs
+            .entry(t.clone())
+            .or_insert_with(|| vtbl_function_names(&funcs));
 
         let ContainerType {
             ty_prefix: cont,
@@ -290,6 +351,15 @@ void ctx_{prefix}_drop({ty} *self) {{
             config,
         );
 
+        if inner == "CBox_c_void" {
+            all_wrappers += &create_builder(
+                &t,
+                (&this_ty, &[(vtbl_ty, "vtbl".to_string())]),
+                (cont, ctx, &context),
+                config,
+            );
+        }
+
         if config.default_context.as_deref() == Some(ctx)
             && config.default_container.as_deref() == Some(cont)
         {
@@ -301,14 +371,27 @@ void ctx_{prefix}_drop({ty} *self) {{
 
     // Create wrappers to group objects
 
+    let mut capability_groups = HashMap::new();
+    let mut group_builders = HashMap::new();
+
     for (t, cont, second_half, inner, context, funcs) in group_vtbls {
         let this_ty = format!("struct {}_{}", cont, second_half);
         let container_ty = format!("struct {}Container_{}", cont, second_half);
 
+        capability_groups
+            .entry(cont.clone())
+            .or_insert_with(|| format!("{}_{}", cont, second_half));
+
         fwd_declarations += &format!("{};\n", this_ty);
         fwd_declarations += &format!("{};\n", container_ty);
 
-        let vtbl = Vtable::new(t, &funcs, &container_ty)?;
+        let vtbl = Vtable::new(t, &funcs, &container_ty);
+
+        trait_funcs
+            .entry(vtbl.name.clone())
+            .or_insert_with(|| vtbl_function_names(&funcs));
+
+        let boxed = inner == "CBox_c_void";
 
         let ContainerType {
             ty_prefix: inner,
@@ -337,6 +420,17 @@ void ctx_{prefix}_drop({ty} *self) {{
             config,
         );
 
+        if boxed {
+            group_builders.entry(this_ty.clone()).or_insert_with(|| {
+                (
+                    cont.clone(),
+                    inner.to_string(),
+                    ctx.to_string(),
+                    context.clone(),
+                )
+            });
+        }
+
         if config.default_context.as_deref() == Some(ctx)
             && config.default_container.as_deref() == Some(inner)
         {
@@ -346,36 +440,58 @@ void ctx_{prefix}_drop({ty} *self) {{
         all_wrappers += &wrappers;
     }
 
-    let mut header = header.to_string();
+    // Create capability functions for group objects
+
+    for (group, this_ty) in capability_groups.iter().sorted() {
+        all_wrappers += &create_capabilities(&ir, group, this_ty, config)?;
+    }
+
+    // Create builders for group objects
+
+    for (this_ty, (group, inner, ctx, context)) in group_builders.iter().sorted() {
+        let (_, group_struct) = ir
+            .find_struct(this_ty.trim_start_matches("struct "))
+            .ok_or("Unable to find group struct")?;
+
+        let vtbls = group_vtbl_fields(group_struct)
+            .map(|(vtbl_ty, _, field)| (vtbl_ty, field.to_string()))
+            .collect::<Vec<_>>();
+
+        all_wrappers += &create_builder(group, (this_ty, &vtbls), (inner, ctx, context), config);
+    }
+
+    // Create vtable builder macros
 
-    let strip_root_regex = Regex::new("(struct )?(?P<root>.*)")?;
+    let mut vtbl_macros = String::new();
+
+    for (t, funcs) in &trait_funcs {
+        vtbl_macros += &create_vtbl_macro(t, funcs, config);
+    }
+
+    let mut edits = Edits::default();
 
     // Add shortened typedefs for config
+    let mut typedefed = HashSet::new();
+
     for (root, ty) in shortened_typedefs {
-        if Regex::new(&format!(r"typedef (struct )?[^\s]+ {};", ty))?.is_match(&header) {
+        if ir.has_type(&ty) || !typedefed.insert(ty.clone()) {
             continue;
         }
 
-        let root = strip_root_regex.replace(&root, "$root");
-
-        let regex = Regex::new(&format!(
-            r"(/\*[^*]*\*+(?:[^/*][^*]*\*+)*/
-)?typedef (?P<old_ty_def>struct {root})( \{{[^\}}]*\}})? {root};",
-            root = root
-        ))?;
+        let root = root.trim_start_matches("struct ");
 
-        header = regex
-            .replace(
-                &header,
+        if let Some(item) = find_typedef(&ir, root) {
+            edits.insert(
+                item.span.end,
                 format!(
-                    r"$0
+                    r"
 
 // Typedef for default container and context type
 typedef struct {} {};",
                     root, ty
                 ),
-            )
-            .into();
+            );
+        }
     }
 
     // Create callback and iterator wrappers
@@ -438,7 +554,11 @@ static bool buf_iter_next(struct BufferIterator *iter, void *out) {
 }
 ";
 
-    for caps in callback_regex()?.captures_iter(&header) {
+    for typename in ir
+        .structs()
+        .filter(|(_, s)| s.body.is_some())
+        .filter_map(|(_, s)| s.name.strip_prefix("Callback_c_void__"))
+    {
         all_wrappers += &format!(
             r"
 static inline bool cb_collect_static_{typename}(struct CollectBase *ctx, {typename} info) {{
@@ -453,7 +573,7 @@ static inline bool cb_count_{typename}(size_t *cnt, {typename} info) {{
     return ++(*cnt);
 }}
 ",
-            typename = &caps["typename"]
+            typename = typename
         );
     }
 
@@ -532,16 +652,65 @@ static inline bool cb_count_{typename}(size_t *cnt, {typename} info) {{
     BUF_ITER_SPEC(ty, ty, name, buf, len)
 
 #define BUF_ITER_ARR(ty, name, buf) BUF_ITER(ty, name, buf, sizeof(buf) / sizeof(*buf))
+
+// Constructs a borrowed callback returning a value
+//
+// `func` gets called with `ctx` and the argument. Multiple arguments get passed as a `CTup`.
+//
+// `ty` is the suffix of the callback type, for instance, `u32__u64` for `CFnMut_u32__u64`,
+// while `arg` and `ret` are the C types of the argument and the return value.
+#define FN_MUT_SPEC(ty, arg, ret, ctx, func) \
+    (struct CFnMut_##ty){ (void *)(ctx), (ret (*)(void *, arg))(func) }
+
+#define FN_MUT(arg, ret, ctx, func) \
+    FN_MUT_SPEC(arg##__##ret, arg, ret, ctx, func)
+
+// Constructs a borrowed callback that does not mutate the context
+//
+// This is the same as `FN_MUT_SPEC`, but the resulting type is `CFn_##ty`.
+#define FN_REF_SPEC(ty, arg, ret, ctx, func) \
+    (struct CFn_##ty){ (const void *)(ctx), (ret (*)(const void *, arg))(func) }
+
+#define FN_REF(arg, ret, ctx, func) \
+    FN_REF_SPEC(arg##__##ret, arg, ret, ctx, func)
+
+// Constructs an owned callback
+//
+// `func` gets called with `ctx` and the argument. Once the callback is no longer needed, `drop`
+// gets called with `ctx`. `drop` may be `NULL`, if there is nothing to free.
+//
+// `ty` is the suffix of the callback type, for instance, `u32__bool` for `CFnBox_u32__bool`,
+// while `arg` and `ret` are the C types of the argument and the return value.
+#define FN_BOX_SPEC(ty, arg, ret, ctx, func, drop) \
+    (struct CFnBox_##ty){ \
+        (struct CBox_c_void){ (void *)(ctx), (void (*)(void *))(drop) }, \
+        (ret (*)(void *, arg))(func) \
+    }
+
+#define FN_BOX(arg, ret, ctx, func, drop) \
+    FN_BOX_SPEC(arg##__##ret, arg, ret, ctx, func, drop)
+
+// Constructs an owned callback that can only be called once
+//
+// This is the same as `FN_BOX_SPEC`, but the resulting type is `CFnOnceBox_##ty`.
+#define FN_ONCE_BOX_SPEC(ty, arg, ret, ctx, func, drop) \
+    (struct CFnOnceBox_##ty){ \
+        (struct CBox_c_void){ (void *)(ctx), (void (*)(void *))(drop) }, \
+        (ret (*)(void *, arg))(func) \
+    }
+
+#define FN_ONCE_BOX(arg, ret, ctx, func, drop) \
+    FN_ONCE_BOX_SPEC(arg##__##ret, arg, ret, ctx, func, drop)
 ";
 
     // Insert forward decls at the start
 
     let fwd_declarations = if fwd_declarations.is_empty() {
-        format!("$0\n{}", helper_macros)
+        format!("\n{}{}", helper_macros, vtbl_macros)
     } else {
         format!(
-            "$0\n{}\n// Forward declarations for vtables and their wrappers\n{}",
-            helper_macros, fwd_declarations
+            "\n{}{}\n// Forward declarations for vtables and their wrappers\n{}",
+            helper_macros, vtbl_macros, fwd_declarations
         )
     };
 
@@ -554,300 +723,406 @@ static inline bool cb_count_{typename}(size_t *cnt, {typename} info) {{
         fwd_declarations
     };
 
-    // TODO: improve start detection
-    let header = Regex::new("(#ifndef [^\n]+\n#define [^\n]+\n|.*(#include [^\n]+\n)+)|^")?
-        .replace(&header, fwd_declarations);
+    edits.insert(ir.preamble_end(), fwd_declarations);
 
-    // Insert the wrappers at the end
+    // Insert the wrappers at the end, but within the `extern "C"` block, if there is one
 
-    all_wrappers += "\n$0";
+    all_wrappers += "\n";
 
-    let header = Regex::new(
-        "(
-#ifdef __cplusplus
-} // extern \"C\"
-#endif // __cplusplus|$)",
-    )?
-    .replace(&header, all_wrappers);
+    edits.insert(wrappers_pos(&ir), all_wrappers);
 
-    Ok(header.to_string())
+    edits.apply(&header)
 }
 
-fn monomorphize_contexts(
-    header: std::borrow::Cow<str>,
-    contexts: &HashSet<String>,
-) -> Result<std::borrow::Cow<'static, str>> {
-    // Replace all structs that end with _Context with N specifications that use individual context
-    // Within them, replace all types that have _Context with the said context.
-    // Replace `Context context;` with specific context.
+/// Split vtable name into trait, container, and context type names.
+///
+/// Vtable names take the form of `{trait}Vtbl_{container}Container_{context}`.
+pub(super) fn split_vtbl_name(name: &str) -> Option<(&str, &str, &str)> {
+    let cont_end = name.rfind("Container_")? + "Container".len();
+    let trait_end = name[..cont_end].rfind("Vtbl_")?;
 
-    let ctx_matches =
-        Itertools::intersperse(contexts.iter().map(String::as_str), "|").collect::<String>();
+    let t = &name[..trait_end];
 
-    let context_match = "Context context;";
-
-    let cfield_regex = Regex::new("(?P<typename>.+)_Context")?;
+    if t.is_empty() || !t.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
 
-    let cstruct_regex = Regex::new(
-        r"(?P<comment>/\*[^*]*\*+(?:[^/*][^*]*\*+)*/
-)?typedef struct (?P<name>.+)_Context(?P<inside>( \{[^\}]*\} | ))(?P<name2>[^;]+)_Context;
+    Some((
+        t,
+        &name[(trait_end + "Vtbl_".len())..cont_end],
+        &name[(cont_end + 1)..],
+    ))
+}
 
-",
-    )?;
+/// Find trait object that uses given vtable type.
+fn find_trait_obj<'a>(ir: &'a Header, vtbl_ty: &str) -> Option<&'a Struct> {
+    let field_ty = format!("const {} *", vtbl_ty);
 
-    let header = cstruct_regex.replace_all(&header, |caps: &Captures| {
-        let mut ret = String::new();
+    ir.structs()
+        .map(|(_, s)| s)
+        .find(|s| s.field("vtbl").map(|f| f.ty == field_ty).unwrap_or(false))
+}
 
-        let name = caps["name"].to_string();
-        let name2 = caps["name2"].to_string();
+/// Find the item that defines `typedef struct {name} {name}`.
+fn find_typedef<'a>(ir: &'a Header, name: &str) -> Option<&'a Item> {
+    ir.items.iter().find(|i| match &i.kind {
+        ItemKind::Struct(s) => s.name == name && s.alias.as_deref() == Some(name),
+        ItemKind::Typedef(t) => t.name == name && t.target == format!("struct {}", name),
+        _ => false,
+    })
+}
 
-        for context in contexts {
-            // TODO: is this always correct?? Seems to be for CArc_c_void
-            let context_ty = format!("_{}____", context);
+/// Collect vtable fields of a group as `(vtable type, trait, field)`.
+fn group_vtbl_fields(group: &Struct) -> impl Iterator<Item = (String, &str, &str)> {
+    group
+        .fields()
+        .iter()
+        .filter(|f| f.name.starts_with("vtbl_"))
+        .filter_map(|f| {
+            let vtbl_ty = f.ty.strip_prefix("const ")?.strip_suffix('*')?.trim();
+            let t = vtbl_ty.strip_prefix("struct ")?;
+            let t = &t[..t.find("Vtbl_")?];
+            Some((vtbl_ty.to_string(), t, f.name.as_str()))
+        })
+}
 
-            let name = name.replace("_Context_", &context_ty);
-            let name2 = name2.replace("_Context_", &context_ty);
+/// Collect names of all functions within the vtable.
+fn vtbl_function_names(functions: &[Field]) -> Vec<String> {
+    functions
+        .iter()
+        .filter(|f| f.is_fn_ptr())
+        .map(|f| f.name.clone())
+        .collect()
+}
 
-            if header.contains(&format!("typedef struct {}_{}", name, context)) {
-                continue;
-            }
+/// Position where the wrappers are to be inserted.
+///
+/// This is right before the closing `extern "C"` block (and its `#ifdef __cplusplus` guard), or the
+/// end of the header.
+fn wrappers_pos(ir: &Header) -> usize {
+    let end = match ir
+        .items
+        .iter()
+        .rposition(|i| matches!(i.kind, ItemKind::ScopeEnd))
+    {
+        Some(end) => end,
+        None => return ir.source.len(),
+    };
 
-            ret.push_str(&format!(
-                r"{}typedef struct {}_{}{}{}_{};
-",
-                caps.name("comment").map(|m| m.as_str()).unwrap_or(""),
-                name,
-                context,
-                cfield_regex
-                    .replace_all(&caps["inside"], |caps: &Captures| {
-                        let ty = caps["typename"]
-                            .to_string()
-                            .replace("_Context_", &context_ty);
-                        format!("{}_{}", ty, context)
-                    })
-                    .replace(context_match, &format!("{} context;", context)),
-                name2,
-                context
-            ));
-        }
+    match ir.items.get(end.wrapping_sub(1)).map(|i| (i, &i.kind)) {
+        Some((i, ItemKind::Directive(d))) if d.starts_with("#if") => i.span.start,
+        _ => ir.items[end].span.start,
+    }
+}
 
-        ret
-    });
+/// Create functions for retrieving the set of implemented traits of a group.
+///
+/// The bits are ordered the same way as the vtables are laid out in the group structure.
+fn create_capabilities(ir: &Header, group: &str, this_ty: &str, config: &Config) -> Result<String> {
+    let (_, group_struct) = ir
+        .find_struct(this_ty)
+        .ok_or("Unable to find group struct")?;
 
-    // Go through each struct that matches a context, and replace all types within that have _Context with parent type
+    let mut checks = String::new();
+    let mut names = String::new();
 
-    let cstruct_regex = Regex::new(&format!(
-        r"(?P<comment>/\*[^*]*\*+(?:[^/*][^*]*\*+)*/
-)?typedef struct (?P<name>.+)_(?P<context>{}) \{{
-(?P<inside>[^\}}]*)
-\}} (?P<fulltype>[^;]+);
-",
-        ctx_matches
-    ))?;
+    for (i, (_, t, field)) in group_vtbl_fields(group_struct).enumerate() {
+        checks += &format!("\n    if (group->{}) ret |= 1ull << {};", field, i);
+        names += &format!("\n    \"{}\",", t);
+    }
 
-    let mut header = cstruct_regex
-        .replace_all(&header, |caps: &Captures| {
-            let context = &caps["context"];
+    let prefix = if let Some(p) = config.function_prefix.as_deref() {
+        format!("{}_{}", p, group.to_lowercase())
+    } else {
+        group.to_lowercase()
+    };
 
-            format!(
-                r"{}typedef struct {}_{} {{
-{}
-}} {}_{};
+    Ok(format!(
+        r"
+// Names of the traits in {group}, ordered by their bit in `{prefix}_capabilities`
+static const char * const {prefix}_capability_names[] = {{{names}
+    NULL
+}};
+
+static inline uint64_t {prefix}_capabilities(const void *self) {{
+    const struct {this_ty} *group = (const struct {this_ty} *)self;
+    uint64_t ret = 0;{checks}
+    return ret;
+}}
 ",
-                caps.name("comment").map(|m| m.as_str()).unwrap_or(""),
-                &caps["name"],
-                context,
-                cfield_regex.replace_all(&caps["inside"], &format!("${{typename}}_{}", context)),
-                &caps["name"],
-                context
-            )
-        })
-        .to_string();
+        group = group,
+        prefix = prefix,
+        this_ty = this_ty,
+        names = names,
+        checks = checks
+    ))
+}
 
-    let mut types_to_explore = VecDeque::new();
+/// Create a macro that initializes vtable of given trait out of `impl_{function}` functions.
+fn create_vtbl_macro(t: &str, funcs: &[String], config: &Config) -> String {
+    let name = if let Some(p) = config.function_prefix.as_deref() {
+        format!("{}_{}_VTBL", p, t).to_uppercase()
+    } else {
+        format!("{}_VTBL", t).to_uppercase()
+    };
 
-    // Go through each struct that matches a context, find any of its typedefs, and move them after
-    // the said struct to avoid incomplete types.
+    let mut fields = String::new();
 
-    for cap in cstruct_regex.captures_iter(&header) {
-        types_to_explore.push_back((
-            cap["fulltype"].to_string(),
-            format!("struct {}", &cap["fulltype"]),
-            cap["fulltype"].to_string(),
-        ));
+    for f in funcs {
+        fields += &format!(" \\\n    .{f} = impl##_{f},", f = f);
     }
 
-    while let Some((root, old_ty, ty)) = types_to_explore.pop_front() {
-        // Check if the type has users beforehand. If so, move the root above there and restart the
-        // flow...
-        let ty_regex = Regex::new(&format!(
-            r"
-(?P<user_typedef>/\*[^*]*\*+(?:[^/*][^*]*\*+)*/
-typedef struct .+ \{{[^\}}]*{ty}[^\}}]*\}} .+;
-)(?P<inbetween>[\s\S]*)
-(?P<ty_typedef>/\*[^*]*\*+(?:[^/*][^*]*\*+)*/
-typedef (?P<old_ty_def>struct {old_ty})( \{{[^\}}]*\}})? {ty};
-)",
-            ty = ty,
-            old_ty = old_ty
-        ))?;
+    format!(
+        r"
+// Initializes {t} vtable out of `impl_` prefixed functions
+//
+// The descriptor is left NULL, thus Rust side uses the descriptor of the trait definition.
+#define {name}(impl) {{{fields} \
+}}
+",
+        t = t,
+        name = name,
+        fields = fields
+    )
+}
 
-        let mut matched = false;
+/// Create a function that builds boxed trait object, or group out of an instance, its drop
+/// function, context, and vtables.
+fn create_builder(
+    ty: &str,
+    (this_ty, vtbls): (&str, &[(String, String)]),
+    (cont, ctx, context): (&str, &str, &str),
+    config: &Config,
+) -> String {
+    let config_match = config.default_context.as_deref() == Some(ctx)
+        && config.default_container.as_deref() == Some(cont);
+
+    let ctx_prefix = if ctx.is_empty() || config_match {
+        String::new()
+    } else {
+        format!("{}_", ctx.to_lowercase())
+    };
 
-        // TODO: move non-ptr objects referenced by struct above it
+    let container_prefix = if config_match {
+        String::new()
+    } else {
+        format!("{}_", cont.to_lowercase())
+    };
 
-        header = ty_regex
-            .replace(&header, |caps: &Captures| {
-                matched = true;
+    let prefix = format!("{}_{}{}", ty.to_lowercase(), ctx_prefix, container_prefix);
 
-                format!(
-                    "{}{}{}",
-                    &caps["ty_typedef"], &caps["inbetween"], &caps["user_typedef"]
-                )
-            })
-            .into();
+    let prefix = if let Some(p) = config.function_prefix.as_deref() {
+        format!("{}_{}", p, prefix)
+    } else {
+        prefix
+    };
 
-        if matched {
-            types_to_explore.push_back((root, old_ty, ty));
-            continue;
-        }
+    let mut args = "void *instance, void (*drop_fn)(void *), void (*free_fn)(void *)".to_string();
+    let mut body = String::new();
 
-        let typedef_regex = Regex::new(&format!(
-            r"(?P<old_typedef>(/\*[^*]*\*+(?:[^/*][^*]*\*+)*/)?
-typedef (?P<old_ty>(struct )?{old_ty})( \{{[^\}}]*\}})? {ty};
-)(?P<inbetween>[\s\S]*)
-(?P<new_typedef>(/\*[^*]*\*+(?:[^/*][^*]*\*+)*/)
-typedef (struct )?{ty} (?P<new_ty>.+);
-)",
-            old_ty = old_ty,
-            ty = ty
-        ))?;
-
-        header = typedef_regex
-            .replace(&header, |caps: &Captures| {
-                let new_ty = caps["new_ty"].to_string();
-                types_to_explore.push_front((root.clone(), ty.clone(), new_ty));
-                format!(
-                    "{}{}{}",
-                    &caps["old_typedef"], &caps["new_typedef"], &caps["inbetween"]
-                )
-            })
-            .into();
+    if !ctx.is_empty() {
+        args += &format!(", struct {} context", context);
     }
 
-    // Move all mentioned context types above their first use to avoid incomplete types.
+    for (vtbl_ty, field) in vtbls {
+        args += &format!(", const {} *{}", vtbl_ty, field);
+        body += &format!("\n    ret.{f} = {f};", f = field);
+    }
 
-    for context in contexts {
-        let ctx_def_regex = Regex::new(&format!(
-            r"(/\\*[^*]*\\*+(?:[^/*][^*]*\\*+)*/
-)?typedef struct {context} \{{
-[^\}}]*
-\}} {context};
-",
-            context = context
-        ))?;
-
-        let ctx_user_regex = Regex::new(&format!(
-            r"(?P<comment>/\*[^*]*\*+(?:[^/*][^*]*\*+)*/)?
-typedef struct .+_{} \{{
-(?P<inside>[^\}}]*)
-\}} .+_{};
+    let set_context = if ctx.is_empty() {
+        ""
+    } else {
+        "\n    ret.container.context = context;"
+    };
+
+    format!(
+        r"
+// Builds {ty} object, that takes ownership of the instance and the context.
+//
+// `drop_fn` gets called on the instance when the object is dropped. `free_fn` releases the
+// instance's storage without dropping it, and gets called when Rust moves the instance out of the
+// object. Optional vtables may be NULL.
+static inline {this_ty} {prefix}new({args}) {{
+    {this_ty} ret = {{ 0 }};{body}
+    ret.container.instance.instance = instance;
+    ret.container.instance.drop_fn = drop_fn;
+    ret.container.instance.free_fn = free_fn;{set_context}
+    return ret;
+}}
 ",
-            context, context
-        ))?;
+        ty = ty,
+        this_ty = this_ty,
+        prefix = prefix,
+        args = args,
+        body = body,
+        set_context = set_context
+    )
+}
 
-        if let Some(mut m) = ctx_def_regex.find(&header).map(|m| m.as_str().to_string()) {
-            m.push_str("$0");
+/// Specialize all types generic over `Context` for each of the given contexts.
+///
+/// cbindgen emits types with `_Context` suffix for objects, whose context is generic. These get
+/// replaced with copies for every context used in the header, unless cbindgen had already emitted
+/// the specialized type. Definitions are then moved above their first use, since specialized types
+/// may be used before the context type is defined.
+fn monomorphize_contexts(header: &str, contexts: &HashSet<String>) -> Result<String> {
+    let contexts = contexts
+        .iter()
+        .map(String::as_str)
+        .sorted()
+        .collect::<Vec<_>>();
 
-            header = ctx_def_regex.splitn(&header, 2).collect::<String>();
-            header = ctx_user_regex.replace(&header, &m).to_string();
+    let ir = Header::parse(header)?;
+    let mut edits = Edits::default();
+
+    for item in &ir.items {
+        let name = match &item.kind {
+            ItemKind::Struct(s) => &s.name,
+            ItemKind::Typedef(t) => &t.name,
+            _ => continue,
+        };
+
+        let doc = ir.text(item.span.start..item.decl.start);
+        let decl = ir.text(item.decl.clone());
+
+        if name.ends_with("_Context") {
+            let specialized = contexts
+                .iter()
+                .filter(|ctx| !ir.has_type(&specialize_context(name, ctx).unwrap()))
+                .map(|ctx| {
+                    let decl = map_idents(decl, |ident| specialize_context(ident, ctx));
+                    format!("{}{}", doc, decl)
+                })
+                .join("\n\n");
+
+            if specialized.is_empty() {
+                edits.remove(ir.line_span(item));
+            } else {
+                edits.replace(item.span.clone(), specialized);
+            }
+        } else if let Some(ctx) = contexts
+            .iter()
+            .find(|ctx| name.strip_suffix(*ctx).map(|n| n.ends_with('_')) == Some(true))
+        {
+            // Types that are specific to a context may still refer to the generic types.
+            let specialized = map_idents(decl, |ident| specialize_context(ident, ctx));
+
+            if specialized != decl {
+                edits.replace(item.decl.clone(), specialized);
+            }
         }
     }
 
-    Ok(header.into())
-}
+    let mut header = edits.apply(header)?;
 
-fn vtbl_regex() -> Result<Regex> {
-    Regex::new(
-        r"(?P<declaration>/\*\*
- \* CGlue vtable for trait (?P<trait2>\w+).
- \*
- \* This virtual function table contains ABI-safe interface for the given trait.
- \*/
-typedef struct (?P<trait>\w+)Vtbl)_(?P<container>.*Container)_(?P<context>[^\s]*) \{
-    (?P<functions>[^\}]+)
-\} (.*)Vtbl_[^;]+;",
-    )
-    .map_err(Into::into)
+    // Move the definitions above the users, one at a time, until there is nothing left to move.
+    loop {
+        let ir = Header::parse(&header)?;
+
+        let misplaced = ir.items.iter().enumerate().find_map(|(idx, item)| {
+            type_deps(&ir, item)
+                .into_iter()
+                .find(|&dep| dep > idx)
+                .map(|dep| (item, &ir.items[dep]))
+        });
+
+        let (user, def) = match misplaced {
+            Some(v) => v,
+            None => break,
+        };
+
+        let mut edits = Edits::default();
+        edits.insert(
+            user.span.start,
+            format!("{}\n\n", ir.text(def.span.clone())),
+        );
+        edits.remove(ir.line_span(def));
+        header = edits.apply(&header)?;
+    }
+
+    Ok(header)
 }
 
-fn obj_regex(vtbl: &str) -> Result<Regex> {
-    Regex::new(&format!(
-        r"/\*\*
- \* Simple CGlue trait object\.
- \*
- \* This is the simplest form of CGlue object, represented by a container and vtable for a single
- \* trait\.
- \*
- \* Container merely is a this pointer with some optional temporary return reference context\.
- \*/
-typedef struct (?P<objtype>CGlueTraitObj_[^\s]+_{}_[^\s]+) \{{",
-        vtbl
+/// Name of the type generic over `Context`, specialized for the given context.
+///
+/// Returns `None` if the type is not generic.
+fn specialize_context(name: &str, context: &str) -> Option<String> {
+    if name == "Context" {
+        return Some(context.to_string());
+    }
+
+    let name = name.strip_suffix("_Context")?;
+
+    Some(format!(
+        "{}_{}",
+        name.replace("_Context_", &format!("_{}____", context)),
+        context
     ))
-    .map_err(Into::into)
 }
 
-fn callback_regex() -> Result<Regex> {
-    Regex::new(
-        r"typedef struct Callback_c_void__(?P<typename>[^\s]+) \{[^}]*\} Callback_c_void__[^\s]+;",
-    )
-    .map_err(Into::into)
-}
+/// Indices of items, that need to be placed before the given item.
+///
+/// Types used by value need to be defined, while other types referred to by name need to be
+/// declared. Structs referred to with the `struct` keyword do not need a prior declaration.
+fn type_deps(ir: &Header, item: &Item) -> Vec<usize> {
+    let mut ret = vec![];
+
+    match &item.kind {
+        ItemKind::Struct(s) => {
+            for f in s.fields() {
+                if let Some(args) = &f.args {
+                    for ty in std::iter::once(&f.ty).chain(args.iter().map(|(ty, _)| ty)) {
+                        type_dep(ir, ty, false, &mut ret);
+                    }
+                } else {
+                    type_dep(ir, &f.ty, !f.ty.contains('*'), &mut ret);
+                }
+            }
+        }
+        ItemKind::Typedef(t) => type_dep(ir, &t.target, false, &mut ret),
+        _ => {}
+    }
 
-fn zero_sized_ret_regex() -> Result<Regex> {
-    Regex::new(
-        r"
-/\*\*
- \* Type definition for temporary return value wrapping storage.
- \*
- \* The trait does not use return wrapping, thus is a typedef to `PhantomData`.
- \*
- \* Note that `cbindgen` will generate wrong structures for this type. It is important
- \* to go inside the generated headers and fix it - all RetTmp structures without a
- \* body should be completely deleted, both as types, and as fields in the
- \* groups/objects. If C\+\+11 templates are generated, it is important to define a
- \* custom type for CGlueTraitObj that does not have `ret_tmp` defined, and change all
- \* type aliases of this trait to use that particular structure.
- \*/
-typedef struct (?P<trait>\w+)RetTmp_(?P<context>.+) \w+RetTmp_.+;
-",
-    )
-    .map_err(Into::into)
+    ret
 }
 
-fn group_ret_tmp_regex(zero_sized: &[(String, String)]) -> Result<Regex> {
-    let typenames = Itertools::intersperse(
-        zero_sized.iter().map(|(a, b)| format!("{}RetTmp_{}", a, b)),
-        "|".to_string(),
-    )
-    .collect::<String>();
-    let typenames_lc = Itertools::intersperse(
-        zero_sized.iter().map(|(a, _)| a.to_lowercase()),
-        "|".to_string(),
-    )
-    .collect::<String>();
-    Regex::new(&format!(
-        "\\s*struct ({}) ret_tmp_({});",
-        typenames, typenames_lc
-    ))
-    .map_err(Into::into)
-}
+/// Push indices of items declaring the type, or defining it, if it is used by value.
+fn type_dep(ir: &Header, ty: &str, by_value: bool, out: &mut Vec<usize>) {
+    let is_struct = ty.split_whitespace().any(|w| w == "struct");
 
-fn ret_tmp_regex(zero_sized: &[(String, String)]) -> Result<Regex> {
-    let typenames = Itertools::intersperse(
-        zero_sized.iter().map(|(a, b)| format!("{}RetTmp_{}", a, b)),
-        "|".to_string(),
-    )
-    .collect::<String>();
-    Regex::new(&format!("\\s*struct ({}) ret_tmp;", typenames)).map_err(Into::into)
+    let name = match ty
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .rfind(|w| !w.is_empty() && !w.starts_with(|c: char| c.is_ascii_digit()))
+    {
+        Some(name) => name,
+        None => return,
+    };
+
+    let declared_as = |i: &Item| match &i.kind {
+        ItemKind::Struct(s) => {
+            (s.name == name && (is_struct || by_value)) || s.alias.as_deref() == Some(name)
+        }
+        ItemKind::Typedef(t) => !is_struct && t.name == name,
+        _ => false,
+    };
+
+    if by_value {
+        // Typedefs need to come first, alongside with the definition of their target.
+        if let Some((idx, t)) = ir.items.iter().enumerate().find_map(|(idx, i)| {
+            Some((idx, i.as_typedef()?)).filter(|_| !is_struct && declared_as(i))
+        }) {
+            out.push(idx);
+            type_dep(ir, &t.target, true, out);
+        } else if let Some(idx) = ir
+            .items
+            .iter()
+            .position(|i| i.as_struct().map(|s| s.body.is_some()) == Some(true) && declared_as(i))
+        {
+            out.push(idx);
+        }
+    } else if !is_struct {
+        if let Some(idx) = ir.items.iter().position(declared_as) {
+            out.push(idx);
+        }
+    }
 }
@@ -12,6 +12,9 @@
 //! the vtable. In groups, if 2 traits have the same function name, all such functions will be
 //! prefixed with each trait's name.
 //!
+//! Groups also have `cglue_capabilities` function that returns a bitset of implemented traits,
+//! and `cglue_capability_names` that lists the names of the traits in the order of their bits.
+//!
 //! ## Generate clone/drop functions.
 //!
 //! Copy constructor is not the way to clone objects. Use `object.clone()` function.
@@ -36,6 +39,15 @@
 //!
 //! Containers must be passed by pointer, while lambdas are taken in as const references.
 //!
+//! ## Allow `CFn` and `CFnMut` to be built with lambdas.
+//!
+//! Just like with `Callback`s, the lambda must outlive the callback.
+//!
+//! ## Allow `CFnBox` and `CFnOnceBox` to be built with lambdas.
+//!
+//! Unlike `Callback`s, the lambda is moved into a heap allocation, which gets freed once the
+//! callback gets dropped.
+//!
 //! ## Create vtable impl types.
 //!
 //! Basically, this is a quick way to build a vtable for an object, if you have a struct defined in
@@ -62,6 +74,9 @@
 //! };
 //! ```
 //!
+//! The vtable's `descriptor` is left null, which makes Rust side fall back to the descriptor of the
+//! trait definition.
+//!
 //! To allow building a vtable, provide an implementation something like this:
 //!
 //! ```ignore
@@ -87,26 +102,27 @@
 //! constexpr MainFeatureVtblImpl<KvStoreContainer<KvStore *>> main_feature_mut_vtbl;
 //! ```
 //!
-//! You may still have to cast the address of the vtable to erase the type information, and it is
-//! recommended to do that at the very last step. However, this is error prone, and in the future
-//! type erasure on C++ side may be done automatically.
+//! To avoid casting the address of the vtable manually, use `cglue::make_obj` and
+//! `cglue::make_group` helpers. They build the vtables, erase the type information, and initialize
+//! the container with given arguments:
+//!
+//! ```ignore
+//! auto obj = cglue::make_obj<MainFeatureVtblImpl, KvStoreContainer<>>(
+//!     CBox<KvStore>::new_box(), ctx.clone());
+//!
+//! auto group = cglue::make_group<FeaturesGroup, KvStoreContainer<>,
+//!     MainFeatureVtblImpl, KeyValueStoreVtblImpl>(CBox<KvStore>::new_box(), ctx.clone());
+//! ```
 //!
 
 use crate::config::*;
+use crate::ir::*;
 use crate::types::*;
-use itertools::Itertools;
 use log::trace;
-use regex::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 pub fn is_cpp(header: &str) -> Result<bool> {
-    Ok(Regex::new(
-        r"
-template<typename|
-using [^\s]+ =|
-#include <cstd[^>]+>",
-    )?
-    .is_match(header))
+    Ok(Header::parse(header)?.is_cpp())
 }
 
 pub fn parse_header(header: &str, config: &Config) -> Result<String> {
@@ -117,56 +133,77 @@ pub fn parse_header(header: &str, config: &Config) -> Result<String> {
 
     let header = &strip_maybe_uninit(header);
 
+    let ir = Header::parse(header)?;
+
     // COLLECTION:
 
-    // Collect zsized ret tmps
-    let zsr_regex = zero_sized_ret_regex()?;
-    let zst_rets = zsr_regex
-        .captures_iter(header)
-        .map(|c| c["trait"].to_string())
+    // Collect zsized ret tmps - they are declared, but never defined
+    let zst_rets = ir
+        .structs()
+        .filter(|(i, s)| s.body.is_none() && !i.template_params().is_empty())
+        .filter(|(_, s)| ir.find_struct(&s.name).is_none())
+        .filter_map(|(i, s)| Some((i, s.name.strip_suffix("RetTmp")?)))
         .collect::<Vec<_>>();
 
-    for cap in &zst_rets {
+    for (_, cap) in &zst_rets {
         trace!("CAP: {}", cap);
     }
 
     // Collect all vtables
-    let vtbl_regex = vtbl_regex()?;
-    let vtbls = vtbl_regex
-        .captures_iter(header)
-        .filter(|c| c["trait"] == c["trait2"])
-        .map(|c| Vtable::new(c["trait"].to_string(), &c["functions"], "CGlueC"))
-        .collect::<Result<Vec<_>>>()?;
+    let vtbls = ir
+        .structs()
+        .filter(|(i, s)| i.template_params() == ["typename CGlueC"] && s.is_fn_table())
+        .filter_map(|(i, s)| {
+            let name = s.name.strip_suffix("Vtbl")?;
+            Some((i, s, Vtable::new(name.to_string(), s.fields(), "CGlueC")))
+        })
+        .collect::<Vec<_>>();
 
     let mut vtbls_map = HashMap::new();
 
-    for vtbl in &vtbls {
+    for (_, _, vtbl) in &vtbls {
         vtbls_map.insert(vtbl.name.as_str(), vtbl);
         trace!("TRAIT: {}", vtbl.name);
     }
 
     // Collect groups
-    let groups_regex = groups_regex(&vtbls, None)?;
-    let groups = groups_regex
-        .captures_iter(header)
-        .map(|c| Group::new(c["group"].to_string(), &c["vtbls"]))
-        .collect::<Result<Vec<_>>>()?;
+    let groups = ir
+        .structs()
+        .filter(|(i, _)| i.template_params() == ["typename CGlueInst", "typename CGlueCtx"])
+        .filter(|(_, s)| {
+            s.field("container").map(|f| f.ty.as_str())
+                == Some(&format!("{}Container<CGlueInst, CGlueCtx>", s.name))
+        })
+        .map(|(i, s)| {
+            let mut group = Group::new(s.name.clone(), s.fields());
+            group
+                .vtables
+                .retain(|(v, _)| vtbls_map.contains_key(v.as_str()));
+            (i, s, group)
+        })
+        .collect::<Vec<_>>();
 
-    for g in &groups {
+    for (_, _, g) in &groups {
         trace!("GROUP: {} {:?}", g.name, g.vtables);
     }
 
     // Check if we need to typedef `TypeLayout`
-    let type_layout_re =
-        Regex::new(r"((((typedef [\s]+)|struct) TypeLayout)|(using TypeLayout =))")?;
-    let needs_type_layout =
-        header.contains("const TypeLayout *") && !type_layout_re.is_match(header);
+    let needs_type_layout = header.contains("const TypeLayout *") && !ir.has_type("TypeLayout");
 
     // PROCESSING:
 
-    let header = header.replace(
-        "struct MaybeUninit;",
-        r"struct alignas(alignof(T)) RustMaybeUninit {
+    let mut edits = Edits::default();
+
+    if let Some((item, _)) = ir
+        .structs()
+        .find(|(_, s)| s.name == "MaybeUninit" && s.body.is_none())
+    {
+        let start = item.template.as_ref().map(|t| t.span.end + 1);
+
+        edits.replace(
+            start.unwrap_or(item.decl.start)..item.decl.end,
+            r"
+struct alignas(alignof(T)) RustMaybeUninit {
     char pad[sizeof(T)];
     inline T &assume_init() {
         return *(T *)this;
@@ -175,19 +212,20 @@ pub fn parse_header(header: &str, config: &Config) -> Result<String> {
         return *(const T *)this;
     }
 };",
-    );
+        );
+    }
 
     // Add string conversion to CSlices
-    let header = Regex::new(
-        r"(?P<definition>template<typename T>
-struct (?P<class>CSlice(Ref|Mut)) \{
-    (?P<constness>(const )?)T \*data;
-    uintptr_t len;)
-\};"
-    )?
-.replace_all(
-        &header,
-    r"$definition
+    for class in &["CSliceRef", "CSliceMut"] {
+        if let Some((_, s)) = ir.find_struct(class) {
+            let constness = match s.field("data") {
+                Some(f) if f.ty.starts_with("const ") => "const ",
+                _ => "",
+            };
+
+            edits.insert(
+                s.fields_end().unwrap(),
+                r"
 
     $class () = default;
 
@@ -221,28 +259,25 @@ struct (?P<class>CSlice(Ref|Mut)) \{
     >::type>
     inline operator std::string() const {
         return std::string((char *)data, len);
+    }"
+                .replace("${constness}", constness)
+                .replace("$class", class),
+            );
+        }
     }
-};"
-    );
 
     // Add cstring include if it wasn't included
-    let header = if header.contains("#include <cstring>") {
-        header
-    } else {
-        Regex::new("#include <c[^>]+>")?.replace(&header, "$0\n#include <cstring>")
-    };
+    if !ir.directives().any(|(_, d)| d == "#include <cstring>") {
+        if let Some((item, _)) = ir.directives().find(|(_, d)| d.starts_with("#include <c")) {
+            edits.insert(item.span.end, "\n#include <cstring>");
+        }
+    }
 
     // Bridge common stl containers to callbacks and iterators
-    let header = Regex::new(
-        r"(?P<definition_start>template<typename T>
-struct CIterator \{
-    void \*iter;
-    int32_t \(\*func\)\(void\*, T \*out\);)
-\};",
-    )?
-    .replace(
-        &header,
-        r"$definition_start
+    if let Some((item, s)) = ir.find_struct("CIterator") {
+        edits.insert(
+            s.fields_end().unwrap(),
+            r"
 
     class iterator : std::iterator<std::input_iterator_tag, T> {
         CIterator<T> *iter;
@@ -291,8 +326,12 @@ struct CIterator \{
 
     constexpr iterator end() {
         return iterator();
-    }
-};
+    }",
+        );
+
+        edits.insert(
+            item.span.end,
+            r"
 
 template<typename Container>
 struct CPPIterator {
@@ -339,18 +378,13 @@ struct CPPIterator {
         return iter;
     }
 };",
-    );
-
-    let header = Regex::new(
-        r"(?P<definition>template<typename T, typename F>
-struct Callback \{
-    T \*context;
-    bool \(\*func\)\(T\*, F\);)
-\};",
-    )?
-    .replace(
-        &header,
-        r"$definition
+        );
+    }
+
+    if let Some((_, s)) = ir.find_struct("Callback") {
+        edits.insert(
+            s.fields_end().unwrap(),
+            r"
 
     template<typename Container>
     static bool push_back(Container *context, F data) {
@@ -377,20 +411,113 @@ struct Callback \{
 
     constexpr operator Callback<void, F> &() {
         return *((Callback<void, F> *)this);
+    }",
+        );
     }
-};",
-    );
-
-    let header_regex = Regex::new(
-        r"(?P<start>(/\*[^*]*\*+(?:[^/*][^*]*\*+)*/
-)?template<typename)",
-    )?;
-
-    // Add mem_drop and mem_forget methods
-    let header = header_regex.replace(
-        &header,
-        r"
-/** Destruct the object. */
+
+    // Build borrowed callbacks out of lambdas
+    for (name, constness) in [("CFnMut", ""), ("CFn", "const ")] {
+        if let Some((_, s)) = ir.find_struct(name) {
+            edits.insert(
+                s.fields_end().unwrap(),
+                format!(
+                    r"
+
+    template<typename Function>
+    static R call_fn({c}Function *function, A args) {{
+        return (*function)(args);
+    }}
+
+    {name}() = default;
+
+    template<typename Function, typename = decltype(std::declval<{c}Function &>()(std::declval<A>()))>
+    {name}({c}Function &function) :
+        context(({c}void *)&function),
+        func((decltype(func))&{name}::call_fn<Function>) {{}}
+
+    inline R call(A args) {c}{{
+        return func(context, args);
+    }}",
+                    name = name,
+                    c = constness
+                ),
+            );
+        }
+    }
+
+    // Build owned callbacks out of lambdas
+    for (name, call) in [
+        (
+            "CFnBox",
+            r"
+    inline R call(T arg) {
+        return func(context.instance, arg);
+    }",
+        ),
+        (
+            "CFnOnceBox",
+            r"
+    inline R call(T arg) && {
+        struct Dropper {
+            CBox<void> &context;
+            ~Dropper() {
+                std::move(context).drop();
+            }
+        } dropper { context };
+        return func(context.instance, arg);
+    }",
+        ),
+    ] {
+        if let Some((_, s)) = ir.find_struct(name) {
+            edits.insert(
+                s.fields_end().unwrap(),
+                format!(
+                    r"
+
+    template<typename Function>
+    static R call_fn(Function *function, T arg) {{
+        return (*function)(arg);
+    }}
+
+    template<typename Function>
+    static void drop_fn(Function *function) {{
+        delete function;
+    }}
+
+    {name}() = default;
+
+    template<typename Function, typename = decltype(std::declval<Function &>()(std::declval<T>()))>
+    {name}(Function function) :
+        context((void *)new Function(std::move(function)), (void (*)(void *))&{name}::drop_fn<Function>),
+        func((decltype(func))&{name}::call_fn<Function>) {{}}
+{call}
+
+    inline void drop() && noexcept {{
+        std::move(context).drop();
+    }}
+
+    inline void forget() noexcept {{
+        context.forget();
+    }}",
+                    name = name,
+                    call = call
+                ),
+            );
+        }
+    }
+
+    // Add mem_drop and mem_forget methods, and TypeLayout forward decl if needed
+    if let Some(item) = ir.items.iter().find(|i| i.template.is_some()) {
+        edits.insert(
+            item.span.start,
+            format!(
+                "\n{}{}",
+                if needs_type_layout {
+                    "struct TypeLayout;\n\n"
+                } else {
+                    ""
+                },
+                r"/** Destruct the object. */
 template<typename T>
 inline typename std::enable_if<!std::is_pointer<T>::value>::type mem_drop(T &&self) noexcept {
     std::move(self).drop();
@@ -437,47 +564,36 @@ struct StoreAll {
     }
 };
 
-$start",
-    );
-
-    // Add TypeLayout forward decl if needed
-    let header = if needs_type_layout {
-        header_regex.replace(
-            &header,
-            r"struct TypeLayout;
-
-$start",
-        )
-    } else {
-        header
-    };
+"
+            ),
+        );
+    }
 
     // Add CBox drop and methods
-    let header = Regex::new(
-        r"(?P<definition>template<typename T>
-struct CBox \{
-    T \*instance;
-    void \(\*drop_fn\)\(T\*\);)
-\};",
-    )?
-    .replace(
-        &header,
-        r"${definition}
+    if let Some((_, s)) = ir.find_struct("CBox") {
+        edits.insert(
+            s.fields_end().unwrap(),
+            r"
 
     CBox() = default;
-    CBox(T *instance) : instance(instance), drop_fn(nullptr) {}
-    CBox(T *instance, void (*drop_fn)(T *)) : instance(instance), drop_fn(drop_fn) {}
+    CBox(T *instance) : instance(instance), drop_fn(nullptr), free_fn(nullptr) {}
+    CBox(T *instance, void (*drop_fn)(T *), void (*free_fn)(T *)) : instance(instance), drop_fn(drop_fn), free_fn(free_fn) {}
     template<typename U = T, class = typename std::enable_if<std::is_same<U, T>::value>::type, class = typename std::enable_if<!std::is_same<U, void>::value>::type>
-    CBox(U &&instance) : instance(new U(instance)), drop_fn(&CBox::delete_fn) {}
+    CBox(U &&instance) : instance(new U(instance)), drop_fn(&CBox::delete_fn), free_fn(&CBox::operator_delete_fn) {}
 
     static void delete_fn(T *v) {
         delete v;
     }
 
+    static void operator_delete_fn(T *v) {
+        ::operator delete((void *)v);
+    }
+
     inline operator CBox<void> () const {
         CBox<void> ret;
         ret.instance = (void*)instance;
         ret.drop_fn = (void(*)(void *))drop_fn;
+        ret.free_fn = (void(*)(void *))free_fn;
         return ret;
     }
 
@@ -485,6 +601,7 @@ struct CBox \{
         CBox ret;
         ret.instance = new T;
         ret.drop_fn = &CBox::delete_fn;
+        ret.free_fn = &CBox::operator_delete_fn;
         return ret;
     }
 
@@ -497,6 +614,7 @@ struct CBox \{
     inline void forget() noexcept {
         instance = nullptr;
         drop_fn = nullptr;
+        free_fn = nullptr;
     }
 
     inline T *operator->() {
@@ -505,67 +623,71 @@ struct CBox \{
 
     inline const T *operator->() const {
         return instance;
+    }",
+        );
     }
-};",
-    );
-
-    // Add CArc clone and drop methods
-    let header = Regex::new(
-        r"(?P<definition>template<typename T>
-struct CArc \{
-    const T \*instance;
-    const T \*\(\*clone_fn\)\(const T\*\);
-    void \(\*drop_fn\)\(const T\*\);)
-\};",
-    )?
-    .replace(
-        &header,
-        r"${definition}
-
-    inline CArc clone() const noexcept {
-        CArc ret;
+
+    // Add CArc and CRc clone and drop methods
+    for name in ["CArc", "CRc"] {
+        if let Some((_, s)) = ir.find_struct(name) {
+            edits.insert(
+                s.fields_end().unwrap(),
+                format!(
+                    r"
+
+    inline {name} clone() const noexcept {{
+        {name} ret;
         ret.instance = clone_fn(instance);
         ret.clone_fn = clone_fn;
         ret.drop_fn = drop_fn;
         return ret;
-    }
+    }}
 
-    inline void drop() && noexcept {
+    inline void drop() && noexcept {{
         if (drop_fn)
             drop_fn(instance);
         forget();
-    }
+    }}
 
-    inline void forget() noexcept {
+    inline void forget() noexcept {{
         instance = nullptr;
         clone_fn = nullptr;
         drop_fn = nullptr;
+    }}"
+                ),
+            );
+        }
     }
-};",
-    );
 
     // Remove zsized ret tmps
-    let header = zsr_regex.replace_all(
-        &header,
-        r"
-template<typename CGlueCtx = void>
-using ${trait}RetTmp = void;
-",
-    );
+    for (item, tr) in &zst_rets {
+        edits.replace(
+            item.span.clone(),
+            format!(
+                r"template<typename CGlueCtx = void>
+using {}RetTmp = void;",
+                tr
+            ),
+        );
+    }
+
+    let zst_types = zst_rets
+        .iter()
+        .map(|(_, tr)| format!("{}RetTmp<CGlueCtx>", tr))
+        .collect::<Vec<_>>();
 
-    let gr_regex = group_ret_tmp_regex(&zst_rets)?;
-    let header = gr_regex.replace_all(&header, "");
+    for (_, s) in ir.structs() {
+        for f in s.fields().iter().filter(|f| zst_types.contains(&f.ty)) {
+            edits.remove(f.span.clone());
+        }
+    }
 
     // Add `typedef typename CGlueC::Context Context;` to each vtable
     // Also add vtable builder to each vtable
-    let header = vtbl_regex.replace_all(&header, |caps: &Captures| {
-        let decl = &caps["declaration"];
-        let funcs = &caps["functions"];
-        let tr = &caps["trait"];
-
+    for (item, s, vtbl) in &vtbls {
         let mut impl_definitions = String::new();
 
-        for (i, v) in vtbls_map[&tr].functions.iter().enumerate() {
+        for (i, v) in vtbl.functions.iter().enumerate() {
             if i > 0 {
                 impl_definitions += ",\n        ";
             }
@@ -573,36 +695,45 @@ using ${trait}RetTmp = void;
             impl_definitions += &v.name;
         }
 
-        format!(
-            r"{decl} {{
-    typedef typename CGlueC::Context Context;
-    {funcs}
-}};
+        edits.insert(
+            s.body.as_ref().unwrap().span.start,
+            "\n    typedef typename CGlueC::Context Context;",
+        );
+
+        edits.insert(
+            item.span.end,
+            format!(
+                r"
 
 template<typename Impl>
 struct {tr}VtblImpl : {tr}Vtbl<typename Impl::Parent> {{
+template<typename CGlueC>
+using Rebind = {tr}Vtbl<CGlueC>;
+
 constexpr {tr}VtblImpl() :
     {tr}Vtbl<typename Impl::Parent> {{
         {impl_definitions}
     }} {{}}
 }};",
-            decl = decl,
-            funcs = funcs,
-            tr = tr,
-            impl_definitions = impl_definitions
-        )
-    });
+                tr = vtbl.name,
+                impl_definitions = impl_definitions
+            ),
+        );
+    }
 
     // Add Context typedef to CGlueObjContainer
     // Create CGlueObjContainer type specializations
     // Add drop and forget methods to it.
-
-    let header = obj_container_regex()?.replace_all(
-        &header,
-        r"$declaration {
-    typedef C Context;
-    $fields
-    RustMaybeUninit<R> ret_tmp;
+    if let Some((item, s)) = ir.find_struct("CGlueObjContainer") {
+        if let Some(ret_tmp) = s.field("ret_tmp") {
+            edits.insert(
+                s.body.as_ref().unwrap().span.start,
+                "\n    typedef C Context;",
+            );
+            edits.replace(ret_tmp.span.clone(), "\n    RustMaybeUninit<R> ret_tmp;");
+            edits.insert(
+                s.fields_end().unwrap(),
+                r"
 
     inline Context clone_context() noexcept {
         return context.clone();
@@ -616,8 +747,11 @@ constexpr {tr}VtblImpl() :
     inline void forget() noexcept {
         mem_forget(instance);
         mem_forget(context);
-    }
-};
+    }",
+            );
+            edits.insert(
+                item.span.end,
+                r"
 
 template<typename T, typename R>
 struct CGlueObjContainer<T, void, R> {
@@ -672,35 +806,40 @@ struct CGlueObjContainer<T, void, void> {
         mem_forget(instance);
     }
 };",
-    );
+            );
+        }
+    }
 
     // Add Context typedef to group containers
     // Create group container specializations
+    for (_, _, g) in &groups {
+        if let Some((item, s)) = ir.find_struct(&format!("{}Container", g.name)) {
+            edits.insert(
+                s.body.as_ref().unwrap().span.start,
+                "\n    typedef CGlueCtx Context;",
+            );
+            edits.insert(
+                s.fields_end().unwrap(),
+                r"
 
-    let gr_regex = Regex::new("\\s(?P<ret_tmp>([^\\s])RetTmp<CGlueCtx>)")?;
-
-    let header = group_container_regex(&groups)?.replace_all(&header, |caps: &Captures| {
-        let ret_tmps = gr_regex.replace_all(&caps["ret_tmps"], "RustMaybeUninit<$ret_tmp>");
-
-        format!(
-            r"{declaration} {{
-    typedef CGlueCtx Context;
-    {fields}{ret_tmps}
-
-    inline Context clone_context() noexcept {{
+    inline Context clone_context() noexcept {
         return context.clone();
-    }}
+    }
 
-    inline void drop() && noexcept {{
+    inline void drop() && noexcept {
         mem_drop(std::move(instance));
         mem_drop(std::move(context));
-    }}
+    }
 
-    inline void forget() noexcept {{
+    inline void forget() noexcept {
         mem_forget(instance);
         mem_forget(context);
-    }}
-}};
+    }",
+            );
+            edits.insert(
+                item.span.end,
+                format!(
+                    r"
 
 template<typename CGlueInst>
 struct {group}Container<CGlueInst, void> {{
@@ -717,18 +856,17 @@ struct {group}Container<CGlueInst, void> {{
         mem_forget(instance);
     }}
 }};",
-            declaration = &caps["declaration"],
-            fields = &caps["fields"],
-            group = &caps["group"],
-            ret_tmps = ret_tmps
-        )
-    });
-
-    let mut header = header.to_string();
+                    group = g.name
+                ),
+            );
+        }
+    }
 
     // Create vtable functions to group objects
-    for g in groups {
-        let helpers = g.create_wrappers(&vtbls_map, "container");
+    for (_, s, g) in &groups {
+        let helpers = g.create_capabilities()
+            + &g.create_vtbl_setters()
+            + &g.create_wrappers(&vtbls_map, "container");
 
         let mut init_vtbls = String::new();
 
@@ -736,11 +874,10 @@ struct {group}Container<CGlueInst, void> {{
             init_vtbls += &format!(", {}{{}}", v);
         }
 
-        header = self::groups_regex(&vtbls, Some(g.name.as_str()))?
-            .replace_all(
-                &header,
-                &format!(
-                    r"$definition_start
+        edits.insert(
+            s.fields_end().unwrap(),
+            format!(
+                r"
 
     {name}() : container{{}} {init_vtbls} {{}}
 
@@ -749,20 +886,18 @@ struct {group}Container<CGlueInst, void> {{
     }}
 
     typedef CGlueCtx Context;
-{helpers}
-}};",
-                    name = g.name,
-                    helpers = helpers,
-                    init_vtbls = init_vtbls
-                ),
-            )
-            .to_string();
+{helpers}",
+                name = g.name,
+                helpers = helpers,
+                init_vtbls = init_vtbls
+            ),
+        );
     }
 
     // Create CGlueTraitObj vtable functions
     let mut trait_obj_specs = String::new();
 
-    for v in &vtbls {
+    for (_, _, v) in &vtbls {
         trait_obj_specs.push_str(&format!(
             r"
 template<typename T, typename C, typename R>
@@ -789,13 +924,25 @@ struct CGlueTraitObj<T, {vtbl}Vtbl<CGlueObjContainer<T, C, R>>, C, R> {{
         ));
     }
 
-    trait_obj_specs.push_str("\n$0");
+    trait_obj_specs.push_str(TYPE_ERASURE_HELPERS);
+
+    trait_obj_specs.push('\n');
+
+    // Probably a more hacky version - put specializations at the end of the file (but before the
+    // include guard's `#endif`), so that we do not encounter incomplete types.
+    let specs_pos = match ir.items.last() {
+        Some(
+            item @ Item {
+                kind: ItemKind::Directive(d),
+                ..
+            },
+        ) if d.starts_with("#endif") => item.span.start,
+        _ => header.len(),
+    };
+
+    edits.insert(specs_pos, trait_obj_specs);
 
-    // Probably a more hacky version - put specializations at the end of the file,
-    // so that we do not encounter incomplete types.
-    let mut header = Regex::new(r"(#endif|$)")?
-        .replace(&header, trait_obj_specs)
-        .to_string();
+    let mut header = edits.apply(header)?;
 
     // Create shortened typedefs for all the types (if configured)
     if config.default_container.is_some() || config.default_context.is_some() {
@@ -805,96 +952,198 @@ struct CGlueTraitObj<T, {vtbl}Vtbl<CGlueObjContainer<T, C, R>>, C, R> {{
         let default_cont = config.default_container.as_deref().unwrap_or("");
         let default_ctx = config.default_context.as_deref().unwrap_or("");
 
+        let ir = Header::parse(&header)?;
+        let mut edits = Edits::default();
+
         let mut ty = String::new();
+        let mut defaults = vec![];
 
         // Groups merely need default types (TODO: Handle extra generics somehow?)
 
         if let Some(ctx) = context_map.get(default_ctx) {
-            let ctx_regex = Regex::new(
-                r"typename CGlueCtx(?P<eq> =)?(?P<specialization>.*>
-struct [^\{\}\n]+<.*CGlueCtx.*>)?",
-            )?;
-
-            header = ctx_regex
-                .replace_all(&header, |caps: &Captures| {
-                    if caps.name("eq").is_some() || caps.name("specialization").is_some() {
-                        caps.get(0).unwrap().as_str().to_string()
-                    } else {
-                        format!("typename CGlueCtx = {}", ctx.cpp_type)
-                    }
-                })
-                .to_string();
-
+            defaults.push(("typename CGlueCtx", ctx.cpp_type));
             ty += ctx.ty_prefix;
         }
 
-        if let Some(ctx) = container_map.get(default_cont) {
-            let ctx_regex = Regex::new(
-                r"typename CGlueInst(?P<eq> =)?(?P<specialization>.*>
-struct [^\{\}\n]+<.*CGlueInst.*>)?",
-            )?;
-
-            header = ctx_regex
-                .replace_all(&header, |caps: &Captures| {
-                    if caps.name("eq").is_some() || caps.name("specialization").is_some() {
-                        caps.get(0).unwrap().as_str().to_string()
-                    } else {
-                        format!("typename CGlueInst = {}", ctx.cpp_type)
+        if let Some(cont) = container_map.get(default_cont) {
+            defaults.push(("typename CGlueInst", cont.cpp_type));
+            ty += cont.ty_prefix;
+        }
+
+        for item in &ir.items {
+            let template = match (&item.template, &item.kind) {
+                (Some(t), ItemKind::Typedef(_)) => t,
+                // Specializations can not have defaults
+                (Some(t), ItemKind::Struct(s)) if !s.name.contains('<') => t,
+                _ => continue,
+            };
+
+            let mut changed = false;
+
+            let params = template
+                .params
+                .iter()
+                .map(|p| match defaults.iter().find(|(name, _)| name == p) {
+                    Some((name, default)) => {
+                        changed = true;
+                        format!("{} = {}", name, default)
                     }
+                    None => p.clone(),
                 })
-                .to_string();
+                .collect::<Vec<_>>();
 
-            ty += ctx.ty_prefix;
+            if changed {
+                edits.replace(template.span.clone(), params.join(", "));
+            }
         }
 
         if !ty.is_empty() {
-            let using_regex = Regex::new(&format!(
-                r"(?P<template>template<(?P<template_args>.*)>
-)?using (?P<type>.+){} = [^;]+;",
-                ty
-            ))?;
-
-            header = using_regex
-                .replace_all(&header, |caps: &Captures| {
-                    let mut ret = caps.get(0).unwrap().as_str().to_string();
-
-                    let base_ty = &caps["type"];
-
-                    if !header.contains(&format!("using {} =", base_ty))
-                        && !header.contains(&format!("struct {} {{", base_ty))
-                    {
-                        ret += "\n// Typedef for default contaienr and context type\n";
-
-                        if let Some(tmplt) = caps.name("template") {
-                            ret += tmplt.as_str();
-                        }
-
-                        ret += &format!(
-                            "using {base_ty} = {base_ty}{ctxcont}",
-                            base_ty = base_ty,
-                            ctxcont = ty
-                        );
-
-                        if let Some(args) = caps.name("template_args") {
-                            ret += "<";
-                            for arg in args.as_str().split("typename").map(|s| s.trim()) {
-                                ret += arg;
-                            }
-                            ret += ">";
-                        }
-
-                        ret += ";";
-                    }
+            let mut typedefed = HashSet::new();
 
-                    ret
-                })
-                .into();
+            for (item, base_ty) in ir.typedefs().filter_map(|(i, t)| {
+                Some((i, t.name.strip_suffix(ty.as_str())?)).filter(|(_, b)| !b.is_empty())
+            }) {
+                if ir.has_type(base_ty) || !typedefed.insert(base_ty) {
+                    continue;
+                }
+
+                let mut ret = "\n// Typedef for default container and context type\n".to_string();
+
+                if let Some(t) = &item.template {
+                    ret += &format!("template<{}>\n", t.params.join(", "));
+                }
+
+                ret += &format!(
+                    "using {base_ty} = {base_ty}{ctxcont}",
+                    base_ty = base_ty,
+                    ctxcont = ty
+                );
+
+                if let Some(t) = &item.template {
+                    let args = t
+                        .params
+                        .iter()
+                        .filter_map(|p| p.split('=').next()?.split_whitespace().last())
+                        .collect::<Vec<_>>();
+                    ret += &format!("<{}>", args.join(", "));
+                }
+
+                ret += ";";
+
+                edits.insert(item.span.end, ret);
+            }
         }
+
+        header = edits.apply(&header)?;
     }
 
     Ok(header)
 }
 
+/// Helpers for building type erased objects and groups out of `VtblImpl` implementations.
+const TYPE_ERASURE_HELPERS: &str = r"
+namespace cglue {
+
+/** Opaque equivalent of the instance type. */
+template<typename T>
+struct OpaqueInst { typedef T type; };
+
+template<typename T>
+struct OpaqueInst<CBox<T>> { typedef CBox<void> type; };
+
+template<typename T>
+struct OpaqueInst<T *> { typedef void *type; };
+
+template<typename T>
+struct OpaqueInst<const T *> { typedef const void *type; };
+
+/** Container with its instance type replaced with an opaque one. */
+template<typename T>
+struct OpaqueCont;
+
+template<template<typename...> class Cont, typename T, typename... Rest>
+struct OpaqueCont<Cont<T, Rest...>> {
+    typedef Cont<typename OpaqueInst<T>::type, Rest...> type;
+};
+
+/** Group type that contains given container. */
+template<template<typename, typename> class Group, typename Cont>
+struct GroupOf;
+
+template<template<typename, typename> class Group, template<typename, typename> class Cont, typename T, typename C>
+struct GroupOf<Group, Cont<T, C>> {
+    typedef Group<T, C> type;
+};
+
+/** Trait object type that contains given container. */
+template<typename Vtbl, typename Cont>
+struct TraitObjOf;
+
+template<typename Vtbl, typename T, typename C, typename R>
+struct TraitObjOf<Vtbl, CGlueObjContainer<T, C, R>> {
+    typedef CGlueTraitObj<T, Vtbl, C, R> type;
+};
+
+/** Statically allocated vtable. */
+template<typename VtblImpl>
+inline const VtblImpl &static_vtbl() noexcept {
+    static const VtblImpl vtbl;
+    return vtbl;
+}
+
+/** Type erased vtable of `VtblImpl` for container `Cont`. */
+template<template<typename> class VtblImpl, typename Impl, typename Cont>
+inline const typename VtblImpl<Impl>::template Rebind<Cont> *opaque_vtbl() noexcept {
+    return (const typename VtblImpl<Impl>::template Rebind<Cont> *)&static_vtbl<VtblImpl<Impl>>();
+}
+
+/**
+ * Build a type erased trait object.
+ *
+ * `Impl` is the implementation of the trait, with `Impl::Parent` being the `CGlueObjContainer` it
+ * is built for. The arguments are used to initialize the container - typically it is an instance
+ * and a context.
+ *
+ * `make_obj<MainFeatureVtblImpl, Impl>(CBox<T>::new_box(), ctx)`
+ */
+template<template<typename> class VtblImpl, typename Impl, typename... Args>
+inline typename TraitObjOf<
+    typename VtblImpl<Impl>::template Rebind<typename OpaqueCont<typename Impl::Parent>::type>,
+    typename OpaqueCont<typename Impl::Parent>::type
+>::type make_obj(Args &&... args) noexcept {
+    typedef typename OpaqueCont<typename Impl::Parent>::type Cont;
+    typename Impl::Parent container { std::forward<Args>(args)... };
+
+    typename TraitObjOf<typename VtblImpl<Impl>::template Rebind<Cont>, Cont>::type ret;
+    ret.vtbl = opaque_vtbl<VtblImpl, Impl, Cont>();
+    ret.container = *(Cont *)&container;
+    return ret;
+}
+
+/**
+ * Build a type erased group object.
+ *
+ * `Impl` is the implementation of the listed traits, with `Impl::Parent` being the group's
+ * container it is built for. The arguments are used to initialize the container - typically it is
+ * an instance and a context. Traits that are not listed are left unimplemented.
+ *
+ * `make_group<FeaturesGroup, Impl, MainFeatureVtblImpl, KeyValueStoreVtblImpl>(CBox<T>::new_box(), ctx)`
+ */
+template<template<typename, typename> class Group, typename Impl, template<typename> class... VtblImpls, typename... Args>
+inline typename GroupOf<Group, typename OpaqueCont<typename Impl::Parent>::type>::type make_group(Args &&... args) noexcept {
+    typedef typename OpaqueCont<typename Impl::Parent>::type Cont;
+    typename Impl::Parent container { std::forward<Args>(args)... };
+
+    typename GroupOf<Group, Cont>::type ret;
+    int set[] = { 0, (ret.set_vtbl(opaque_vtbl<VtblImpls, Impl, Cont>()), 0)... };
+    (void)set;
+    ret.container = *(Cont *)&container;
+    return ret;
+}
+
+} // namespace cglue
+";
+
 fn strip_maybe_uninit(header: &str) -> String {
     let mut out = String::new();
 
@@ -927,107 +1176,3 @@ fn strip_maybe_uninit(header: &str) -> String {
 
     out
 }
-
-fn zero_sized_ret_regex() -> Result<Regex> {
-    Regex::new(
-        r"
-/\*\*
- \* Type definition for temporary return value wrapping storage.
- \*
- \* The trait does not use return wrapping, thus is a typedef to `PhantomData`.
- \*
- \* Note that `cbindgen` will generate wrong structures for this type. It is important
- \* to go inside the generated headers and fix it - all RetTmp structures without a
- \* body should be completely deleted, both as types, and as fields in the
- \* groups/objects. If C\+\+11 templates are generated, it is important to define a
- \* custom type for CGlueTraitObj that does not have `ret_tmp` defined, and change all
- \* type aliases of this trait to use that particular structure.
- \*/
-template<typename CGlueCtx = void>
-struct (?P<trait>\w+)RetTmp;
-",
-    )
-    .map_err(Into::into)
-}
-
-fn group_ret_tmp_regex(zero_sized: &[String]) -> Result<Regex> {
-    let typenames = zero_sized.join("|");
-    let typenames_lc = zero_sized
-        .iter()
-        .map(String::as_str)
-        .map(str::to_lowercase)
-        .collect::<Vec<_>>()
-        .join("|");
-    Regex::new(&format!(
-        "\\s*({})RetTmp<CGlueCtx> ret_tmp_({});",
-        typenames, typenames_lc
-    ))
-    .map_err(Into::into)
-}
-
-fn vtbl_regex() -> Result<Regex> {
-    Regex::new(
-        r"(?P<declaration>/\*\*
- \* CGlue vtable for trait (?P<trait2>\w+).
- \*
- \* This virtual function table contains ABI-safe interface for the given trait.
- \*/
-template<typename CGlueC>
-struct (?P<trait>\w+)Vtbl) \{
-    (?P<functions>[^\}]+)
-\};",
-    )
-    .map_err(Into::into)
-}
-
-fn groups_regex(vtbls: &[Vtable], explicit_group: Option<&str>) -> Result<Regex> {
-    let group_fmt = explicit_group.unwrap_or("\\w+");
-
-    let vtbl_names =
-        Itertools::intersperse(vtbls.iter().map(|v| v.name.as_str()), "|").collect::<String>();
-
-    Regex::new(
-        &format!(r"(?P<definition_start> \* `as_ref_`, and `as_mut_` functions obtain references to safe objects, but do not
- \* perform any memory transformations either. They are the safest to use, because
- \* there is no risk of accidentally consuming the whole object.
- \*/
-template<typename CGlueInst, typename CGlueCtx>
-struct (?P<group>{}) \{{
-    (?P<vtbls>(\s*const ({})Vtbl<.*> \*vtbl_\w+;)*)
-    (?P<group2>\w+)Container<CGlueInst, CGlueCtx> container;)
-\}};", group_fmt, vtbl_names),
-    )
-    .map_err(Into::into)
-}
-
-fn obj_container_regex() -> Result<Regex> {
-    Regex::new(
-        r"(?P<declaration>template<typename T, typename C, typename R>
-struct CGlueObjContainer) \{
-    (?P<fields>T instance;
-    C context;)
-    R ret_tmp;
-\};",
-    )
-    .map_err(Into::into)
-}
-
-fn group_container_regex(groups: &[Group]) -> Result<Regex> {
-    let typenames =
-        Itertools::intersperse(groups.iter().map(|g| g.name.as_str()), "|").collect::<String>();
-    let typenames_lc = groups
-        .iter()
-        .map(|g| g.name.to_lowercase())
-        .collect::<Vec<_>>()
-        .join("|");
-    Regex::new(&format!(
-        r"(?P<declaration>template<typename CGlueInst, typename CGlueCtx>
-struct (?P<group>{})Container) \{{
-    (?P<fields>CGlueInst instance;
-    CGlueCtx context;)(?P<ret_tmps>(
-    ({})RetTmp ret_tmp_{};)*)
-\}};",
-        typenames, typenames, typenames_lc
-    ))
-    .map_err(Into::into)
-}
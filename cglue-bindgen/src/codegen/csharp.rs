@@ -0,0 +1,920 @@
+//! # C# code generator.
+//!
+//! This generator builds a C# source file with P/Invoke bindings out of the C header. The C header
+//! is cleaned up by the [C generator](super::c) first, so the same monomorphized types are used in
+//! both languages. All types are placed within the `CGlue` namespace.
+//!
+//! Pass `-t csharp` to `cglue-bindgen` before the `--` (and `-l C` to cbindgen), or use
+//! `Builder::with_target(Target::CSharp)` when generating bindings in-process. The generated code
+//! uses unsafe pointers, so it has to be compiled with unsafe code allowed (`-unsafe`, or
+//! `AllowUnsafeBlocks`).
+//!
+//! ## Structures
+//!
+//! Every struct becomes a `[StructLayout(LayoutKind.Sequential)]` struct of the same name. Typedefs
+//! are resolved to their targets. Function pointers are stored as `IntPtr` fields, and every
+//! struct defines a `{field}_delegate` delegate type for each of them.
+//!
+//! ## Wrapper classes
+//!
+//! For every trait object, and group, an `IDisposable` wrapper class is generated. The class is
+//! named after the trait, or the group, if the object uses the default container and context
+//! types (see `default_container`, and `default_context` options). Objects with other container
+//! types are named after their structs, with an `Object` suffix.
+//!
+//! Trait functions are available as PascalCase methods that call through the vtable. In groups, if
+//! 2 traits have the same function name, all such functions will be prefixed with each trait's name.
+//!
+//! Every trait gets an `I{Trait}` interface that the wrappers implement. `TryAs<IKeyValueStore>()`
+//! returns the object as the given trait interface, or `null`, if the optional trait is not
+//! implemented. Calling a function of such trait directly throws `NotSupportedException`.
+//!
+//! Disposing an owned wrapper (or finalizing it) drops its container (`CBox` instance and `CArc`
+//! context). Functions that consume the object leave the wrapper disposed. References returned by
+//! the object are wrapped as borrowed objects that keep their parent alive and never drop anything.
+//!
+//! ## Conversions
+//!
+//! * `CSliceRef`, and `CSliceMut` are passed in, and returned as arrays.
+//!
+//! * `ReprCStr` is passed in, and returned as `string`.
+//!
+//! * `Callback` is built out of a `Func<T, bool>`. Returning `false` stops the iteration.
+//!
+//! * `CIterator` is built out of any `IEnumerable<T>`.
+//!
+//! * Functions that return a result through `ok_out` return the value directly, and throw
+//!   `CGlueException` with the error code on failure.
+//!
+//! ## Exported functions
+//!
+//! Functions exported by the library are available in the static `Library` class. The library is
+//! imported by the name set in the `library_name` option:
+//!
+//! ```ignore
+//! using (var plugin = CGlue.Library.LoadPlugin("plugin_lib"))
+//! using (var features = plugin.BorrowFeatures())
+//! {
+//!     features.WriteKeyValue(Encoding.UTF8.GetBytes("hello"), (UIntPtr)42);
+//!     features.TryAs<CGlue.IMainFeature>()?.PrintSelf();
+//! }
+//! ```
+//!
+
+use super::c;
+use super::model::*;
+use crate::config::*;
+use crate::ir::*;
+use crate::types::*;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+pub fn parse_header(header: &str, config: &Config) -> Result<String> {
+    let header = c::parse_header(header, config)?;
+    let ir = Header::parse(&header)?;
+    let model = Model::new(&ir);
+
+    let gen = Generator::new(&model);
+
+    let mut out = String::from(PREAMBLE);
+
+    // Structures
+
+    for name in model.structs() {
+        out += &gen.create_struct(name)?;
+    }
+
+    // Trait interfaces, with the methods that have the same signature in all objects.
+
+    let mut traits: Vec<(&str, Vec<String>)> = vec![];
+
+    for obj in &model.objects {
+        let sigs = obj
+            .methods()
+            .iter()
+            .map(|m| (m.trait_name.to_string(), gen.method(obj, m).0))
+            .collect::<Vec<_>>();
+
+        for (t, _, _) in &obj.vtbls {
+            let own = sigs
+                .iter()
+                .filter(|(mt, _)| mt == t)
+                .map(|(_, s)| s.clone())
+                .collect::<Vec<_>>();
+
+            match traits.iter_mut().find(|(n, _)| n == t) {
+                Some((_, common)) => common.retain(|s| own.contains(s)),
+                None => traits.push((t, own)),
+            }
+        }
+    }
+
+    for (t, sigs) in &traits {
+        write!(out, "\n    public interface I{}\n    {{\n", t)?;
+        for sig in sigs {
+            writeln!(out, "        {};", sig)?;
+        }
+        out += "    }\n";
+    }
+
+    // Wrappers
+
+    for obj in &model.objects {
+        out += &gen.create_wrapper(obj)?;
+    }
+
+    out += &gen.create_library(config)?;
+
+    out += "}\n";
+
+    Ok(out)
+}
+
+struct Generator<'a> {
+    model: &'a Model<'a>,
+    /// Struct name to the name of its wrapper class.
+    classes: HashMap<&'a str, String>,
+}
+
+/// Statements needed to call a function.
+#[derive(Default)]
+struct Call {
+    params: Vec<String>,
+    args: Vec<String>,
+    pre: Vec<String>,
+    /// Blocks (`fixed` statements) the call is nested in.
+    blocks: Vec<String>,
+    /// Statements to run after the call, even if it throws.
+    post: Vec<String>,
+}
+
+impl<'a> Generator<'a> {
+    fn new(model: &'a Model<'a>) -> Self {
+        let classes = model
+            .objects
+            .iter()
+            .map(|o| (o.raw.name.as_str(), model.wrapper_name(o)))
+            .collect();
+
+        Self { model, classes }
+    }
+
+    fn class(&self, obj: &Object) -> &str {
+        &self.classes[obj.raw.name.as_str()]
+    }
+
+    /// C# type of the given C type, as laid out in memory.
+    fn cs_type(&self, ty: &str) -> String {
+        let (name, ptrs, _) = split_type(&self.model.expand(ty));
+
+        match (name.as_str(), ptrs) {
+            ("void", 0) => "void".into(),
+            ("void", _) | ("char", 1..) => format!("IntPtr{}", "*".repeat(ptrs - 1)),
+            (name, _) => format!("{}{}", primitive(name).unwrap_or(name), "*".repeat(ptrs)),
+        }
+    }
+
+    /// Parameter declaration of the given C type, with marshaling attributes, if needed.
+    fn cs_param(&self, ty: &str, name: &str) -> String {
+        match self.cs_type(ty).as_str() {
+            "bool" => format!("[MarshalAs(UnmanagedType.I1)] bool {}", name),
+            ty => format!("{} {}", ty, name),
+        }
+    }
+
+    fn cs_ret(&self, ty: &str) -> String {
+        match self.cs_type(ty).as_str() {
+            "bool" => "[return: MarshalAs(UnmanagedType.I1)]\n        ".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    fn create_struct(&self, name: &str) -> Result<String> {
+        let mut out = String::new();
+
+        let s = match self.model.ir.find_struct(name) {
+            Some((_, s)) => s,
+            None => {
+                writeln!(out, "\n    public struct {}\n    {{\n    }}", name)?;
+                return Ok(out);
+            }
+        };
+
+        if s.fields()
+            .iter()
+            .any(|f| !is_ident(&f.name) || f.ty.contains('}'))
+        {
+            writeln!(
+                out,
+                "\n    // {} has unsupported layout, and stays opaque.\n    public struct {}\n    {{\n    }}",
+                name, name
+            )?;
+            return Ok(out);
+        }
+
+        write!(
+            out,
+            "\n    [StructLayout(LayoutKind.Sequential)]\n    public unsafe struct {}\n    {{\n",
+            name
+        )?;
+
+        for f in s.fields() {
+            let field = cs_ident(&f.name);
+
+            if f.is_fn_ptr() {
+                writeln!(out, "        public IntPtr {};", field)?;
+                continue;
+            }
+
+            let ty = self.cs_type(&f.ty);
+
+            match split_type(&f.ty).2 {
+                Some(len) if primitive_name(&ty) => {
+                    writeln!(out, "        public fixed {} {}[{}];", ty, field, len)?
+                }
+                Some(len) => {
+                    for i in 0..len.parse::<usize>()? {
+                        writeln!(out, "        public {} {}_{};", ty, f.name, i)?;
+                    }
+                }
+                None if ty == "bool" => writeln!(
+                    out,
+                    "        [MarshalAs(UnmanagedType.I1)]\n        public bool {};",
+                    field
+                )?,
+                None => writeln!(out, "        public {} {};", ty, field)?,
+            }
+        }
+
+        for f in s.fields() {
+            let args = match &f.args {
+                Some(args) => args,
+                None => continue,
+            };
+
+            write!(
+                out,
+                "\n        [UnmanagedFunctionPointer(CallingConvention.Cdecl)]\n        {}public delegate {} {}_delegate(",
+                self.cs_ret(&f.ty),
+                self.cs_type(&f.ty),
+                f.name
+            )?;
+
+            for (i, (ty, name)) in args.iter().enumerate() {
+                if i > 0 {
+                    out += ", ";
+                }
+                let name = if name.is_empty() {
+                    format!("arg{}", i)
+                } else {
+                    cs_ident(name)
+                };
+                out += &self.cs_param(ty, &name);
+            }
+
+            out += ");\n";
+        }
+
+        out += "    }\n";
+
+        Ok(out)
+    }
+
+    /// Signature of the method in C#, and its call.
+    fn method(&self, obj: &Object, method: &Method) -> (String, Call) {
+        let name = if method.name == method.func.name {
+            pascal_case(&method.name)
+        } else {
+            format!("{}{}", method.trait_name, pascal_case(&method.func.name))
+        };
+
+        let mut call = Call::default();
+
+        if method.consumes {
+            call.pre.push("var __cont = IntoRaw().container;".into());
+            call.args.push("__cont".into());
+        } else {
+            call.args.push("&Get()->container".into());
+        }
+
+        let ret = self.convert_args(&mut call, &method.sig, Some(obj));
+
+        (
+            format!("{} {}({})", ret, name, call.params.join(", ")),
+            call,
+        )
+    }
+
+    /// Convert the arguments of the call, and return the C# return type.
+    fn convert_args(&self, call: &mut Call, sig: &Signature, obj: Option<&Object>) -> String {
+        for (i, (ty, name)) in sig.args.iter().enumerate() {
+            let name = if name.is_empty() {
+                format!("arg{}", i)
+            } else {
+                cs_ident(name)
+            };
+
+            let n = name.trim_start_matches('@');
+
+            match self.model.conv(ty, obj) {
+                Conv::Str => {
+                    call.params.push(format!("string {}", name));
+                    call.pre.push(format!(
+                        "var {}_ptr = Marshal.StringToCoTaskMemUTF8({});",
+                        n, name
+                    ));
+                    call.post.push(format!("Marshal.FreeCoTaskMem({}_ptr);", n));
+                    call.args.push(format!("{}_ptr", n));
+                }
+                Conv::Slice(t) => {
+                    let elem = self.slice_elem(&t);
+                    call.params.push(format!("{}[] {}", elem, name));
+                    call.blocks
+                        .push(format!("fixed ({}* {}_ptr = {})", elem, n, name));
+                    call.args.push(format!(
+                        "new {} {{ data = Runtime.NonNull({}_ptr), len = (UIntPtr){}.Length }}",
+                        t, n, name
+                    ));
+                }
+                Conv::Callback(t) => {
+                    let elem = self.fn_arg(&t, 1);
+                    call.params.push(format!("Func<{}, bool> {}", elem, name));
+                    call.pre.push(format!(
+                        "{}.func_delegate {}_fn = ({}_ctx, {}_value) => {}({}_value);",
+                        t, n, n, n, name, n
+                    ));
+                    call.post.push(format!("GC.KeepAlive({}_fn);", n));
+                    call.args.push(format!(
+                        "new {} {{ context = IntPtr.Zero, func = Marshal.GetFunctionPointerForDelegate({}_fn) }}",
+                        t, n
+                    ));
+                }
+                Conv::Iter(t) => {
+                    let elem = self.fn_arg(&t, 1);
+                    let elem = elem.strip_suffix('*').unwrap_or(&elem);
+                    call.params.push(format!("IEnumerable<{}> {}", elem, name));
+                    call.pre
+                        .push(format!("var {}_iter = {}.GetEnumerator();", n, name));
+                    call.pre.push(format!(
+                        "{}.func_delegate {}_fn = ({}_ctx, {}_out) =>\n{{\n    if (!{}_iter.MoveNext())\n        return 1;\n    *{}_out = {}_iter.Current;\n    return 0;\n}};",
+                        t, n, n, n, n, n, n
+                    ));
+                    call.post.push(format!("GC.KeepAlive({}_fn);", n));
+                    call.args.push(format!(
+                        "new {} {{ iter = IntPtr.Zero, func = Marshal.GetFunctionPointerForDelegate({}_fn) }}",
+                        t, n
+                    ));
+                }
+                Conv::Object(o) => {
+                    call.params.push(format!("{} {}", self.class(o), name));
+                    call.args.push(format!("{}.IntoRaw()", name));
+                }
+                Conv::ObjectRef(o) => {
+                    call.params.push(format!("{} {}", self.class(o), name));
+                    call.args.push(format!("{}.AsPtr()", name));
+                }
+                Conv::Plain | Conv::Container => {
+                    call.params.push(self.cs_param(ty, &name));
+                    call.args.push(name);
+                }
+            }
+        }
+
+        if let Some(ok_ty) = sig.ok_out {
+            call.pre
+                .push(format!("{} __ok = default;", self.cs_type(ok_ty)));
+            call.args.push("&__ok".into());
+        }
+
+        match self.model.conv(sig.ret_ty(), obj) {
+            Conv::Str => "string".into(),
+            Conv::Slice(t) => format!("{}[]", self.slice_elem(&t)),
+            Conv::Object(o) | Conv::ObjectRef(o) => self.class(o).into(),
+            Conv::Container => obj.map(|o| self.class(o)).unwrap_or_default().into(),
+            _ => self.cs_type(sig.ret_ty()),
+        }
+    }
+
+    /// Element type of a slice struct.
+    fn slice_elem(&self, slice: &str) -> String {
+        let data = self
+            .model
+            .ir
+            .find_struct(slice)
+            .and_then(|(_, s)| s.field("data"))
+            .map(|f| f.ty.as_str())
+            .unwrap_or("void *");
+        let ty = self.cs_type(data);
+        ty.strip_suffix('*').unwrap_or(&ty).to_string()
+    }
+
+    /// Type of the argument of the `func` field of a callback, or iterator struct.
+    fn fn_arg(&self, s: &str, idx: usize) -> String {
+        self.model
+            .ir
+            .find_struct(s)
+            .and_then(|(_, s)| s.field("func"))
+            .and_then(|f| f.args.as_ref())
+            .and_then(|args| args.get(idx))
+            .map(|(ty, _)| self.cs_type(ty))
+            .unwrap_or_else(|| "IntPtr".into())
+    }
+
+    /// Write the body of a function that calls `func`.
+    fn write_call(
+        &self,
+        out: &mut String,
+        func: &str,
+        call: &Call,
+        sig: &Signature,
+        obj: Option<&Object>,
+    ) -> Result<()> {
+        let mut indent = 3;
+        let mut lines = vec![];
+
+        let mut push = |indent: usize, text: &str| {
+            for line in text.lines() {
+                lines.push(format!("{}{}", "    ".repeat(indent), line));
+            }
+        };
+
+        for pre in &call.pre {
+            push(indent, pre);
+        }
+
+        for block in &call.blocks {
+            push(indent, block);
+            push(indent, "{");
+            indent += 1;
+        }
+
+        if !call.post.is_empty() {
+            push(indent, "try");
+            push(indent, "{");
+            indent += 1;
+        }
+
+        let invoke = format!("{}({})", func, call.args.join(", "));
+
+        let ret = if let Some(ok_ty) = sig.ok_out {
+            push(indent, &format!("var __code = {};", invoke));
+            push(indent, "if (__code != 0)");
+            push(indent + 1, "throw new CGlueException(__code);");
+            Some((ok_ty, "__ok".to_string()))
+        } else if self.cs_type(sig.ret) == "void" {
+            push(indent, &format!("{};", invoke));
+            None
+        } else {
+            Some((sig.ret, invoke))
+        };
+
+        if let Some((ty, val)) = ret {
+            let val = match self.model.conv(ty, obj) {
+                Conv::Str => format!("Marshal.PtrToStringUTF8({})", val),
+                Conv::Slice(_) => {
+                    push(indent, &format!("var __slice = {};", val));
+                    "Runtime.ToArray(__slice.data, __slice.len)".into()
+                }
+                Conv::Object(o) => format!("new {}({})", self.class(o), val),
+                Conv::ObjectRef(o) => format!("{}.Borrow({}, this)", self.class(o), val),
+                Conv::Container => {
+                    push(indent, "var __obj = *Get();");
+                    push(indent, &format!("__obj.container = {};", val));
+                    format!(
+                        "new {}(__obj)",
+                        obj.map(|o| self.class(o)).unwrap_or_default()
+                    )
+                }
+                _ => val,
+            };
+            push(indent, &format!("return {};", val));
+        }
+
+        if !call.post.is_empty() {
+            indent -= 1;
+            push(indent, "}");
+            push(indent, "finally");
+            push(indent, "{");
+            for post in &call.post {
+                push(indent + 1, post);
+            }
+            push(indent, "}");
+        }
+
+        for _ in &call.blocks {
+            indent -= 1;
+            push(indent, "}");
+        }
+
+        for line in lines {
+            writeln!(out, "{}", line)?;
+        }
+
+        Ok(())
+    }
+
+    fn create_wrapper(&self, obj: &Object) -> Result<String> {
+        let mut out = String::new();
+
+        let class = self.class(obj);
+        let raw = &obj.raw.name;
+        let cont = obj.container_ty();
+
+        write!(
+            out,
+            "\n    /// <summary>\n    /// Wrapper of {} {}.\n    /// </summary>\n    public sealed unsafe class {} : IDisposable",
+            if obj.is_group { "group" } else { "trait object" },
+            raw,
+            class
+        )?;
+
+        for (t, _, _) in &obj.vtbls {
+            write!(out, ", I{}", t)?;
+        }
+
+        write!(
+            out,
+            r#"
+    {{
+        private {raw}* raw;
+        private readonly object parent;
+
+        /// <summary>
+        /// Take ownership of the raw object.
+        /// </summary>
+        public {class}({raw} raw)
+        {{
+            this.raw = ({raw}*)Marshal.AllocHGlobal(sizeof({raw}));
+            *this.raw = raw;
+        }}
+
+        private {class}({raw}* raw, object parent)
+        {{
+            this.raw = raw;
+            this.parent = parent;
+            GC.SuppressFinalize(this);
+        }}
+
+        ~{class}()
+        {{
+            Dispose();
+        }}
+
+        internal static {class} Borrow({raw}* raw, object parent)
+        {{
+            return raw == null ? null : new {class}(raw, parent);
+        }}
+
+        public void Dispose()
+        {{
+            if (raw != null && parent == null)
+            {{
+                Drop(&raw->container);
+                Free();
+            }}
+            raw = null;
+            GC.SuppressFinalize(this);
+        }}
+
+        /// <summary>
+        /// Release ownership of the raw object.
+        /// </summary>
+        public {raw} IntoRaw()
+        {{
+            if (parent != null)
+                throw new InvalidOperationException("Borrowed objects can not be consumed");
+            var ret = *Get();
+            Free();
+            return ret;
+        }}
+
+        internal {raw}* AsPtr()
+        {{
+            return Get();
+        }}
+
+        private {raw}* Get()
+        {{
+            if (raw == null)
+                throw new ObjectDisposedException(nameof({class}));
+            return raw;
+        }}
+
+        private void Free()
+        {{
+            Marshal.FreeHGlobal((IntPtr)raw);
+            raw = null;
+            GC.SuppressFinalize(this);
+        }}
+
+        private static void Drop({cont}* cont)
+        {{
+"#,
+            raw = raw,
+            class = class,
+            cont = cont,
+        )?;
+
+        // Instance and context both carry their own drop functions.
+        if let Some((_, cont)) = self.model.ir.find_struct(&cont) {
+            for f in cont.fields() {
+                let ty = self.model.resolve(&split_type(&f.ty).0);
+                let drop =
+                    self.model.ir.find_struct(&ty).filter(|(_, s)| {
+                        s.field("drop_fn").is_some() && s.field("instance").is_some()
+                    });
+
+                if drop.is_some() && split_type(&f.ty).1 == 0 {
+                    write!(
+                        out,
+                        "            if (cont->{f}.drop_fn != IntPtr.Zero)\n                Marshal.GetDelegateForFunctionPointer<{ty}.drop_fn_delegate>(cont->{f}.drop_fn)(cont->{f}.instance);\n",
+                        f = f.name,
+                        ty = ty
+                    )?;
+                }
+            }
+        }
+
+        out += "        }\n";
+
+        out += "\n        /// <summary>\n        /// Get the object as given trait interface, if the trait is implemented.\n        /// </summary>\n        public T TryAs<T>() where T : class\n        {\n";
+
+        if obj.is_group {
+            out += "            var raw = Get();\n";
+            for (t, field, _) in &obj.vtbls {
+                writeln!(
+                    out,
+                    "            if (typeof(T) == typeof(I{}) && raw->{} == null)\n                return null;",
+                    t, field
+                )?;
+            }
+        } else {
+            out += "            Get();\n";
+        }
+
+        out += "            return this as T;\n        }\n";
+
+        for method in obj.methods() {
+            let (sig, call) = self.method(obj, &method);
+            let vtbl = obj
+                .raw
+                .field(method.vtbl)
+                .map(|f| split_type(&f.ty).0)
+                .unwrap_or_default();
+
+            write!(
+                out,
+                "\n        public {}\n        {{\n            var __vtbl = Runtime.Check(Get()->{}, \"{}\");\n            var __func = Marshal.GetDelegateForFunctionPointer<{}.{}_delegate>(__vtbl->{});\n",
+                sig, method.vtbl, method.trait_name, vtbl, method.func.name, method.func.name
+            )?;
+
+            self.write_call(&mut out, "__func", &call, &method.sig, Some(obj))?;
+
+            out += "        }\n";
+        }
+
+        out += "    }\n";
+
+        Ok(out)
+    }
+
+    fn create_library(&self, config: &Config) -> Result<String> {
+        let mut out = String::new();
+
+        write!(
+            out,
+            "\n    /// <summary>\n    /// Functions exported by the library.\n    /// </summary>\n    public static unsafe class Library\n    {{\n        public const string Name = \"{}\";\n",
+            config.library_name.as_deref().unwrap_or("cglue")
+        )?;
+
+        for (f, sig) in self.model.functions() {
+            write!(
+                out,
+                "\n        [DllImport(Name, CallingConvention = CallingConvention.Cdecl, EntryPoint = \"{}\")]\n        {}private static extern {} {}_raw(",
+                f.name,
+                self.cs_ret(&f.ret),
+                self.cs_type(&f.ret),
+                f.name
+            )?;
+
+            for (i, (ty, name)) in f.args.iter().enumerate() {
+                if i > 0 {
+                    out += ", ";
+                }
+                out += &self.cs_param(ty, &cs_ident(name));
+            }
+
+            out += ");\n";
+
+            let mut call = Call::default();
+            let ret = self.convert_args(&mut call, &sig, None);
+
+            write!(
+                out,
+                "\n        public static {} {}({})\n        {{\n",
+                ret,
+                pascal_case(&f.name),
+                call.params.join(", ")
+            )?;
+
+            let mut body = String::new();
+            self.write_call(&mut body, &format!("{}_raw", f.name), &call, &sig, None)?;
+
+            // Static functions have no parent to borrow from.
+            out += &body.replace(", this)", ", null)");
+
+            out += "        }\n";
+        }
+
+        out += "    }\n";
+
+        Ok(out)
+    }
+}
+
+fn primitive(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "bool" => "bool",
+        "char" | "unsigned char" | "uint8_t" => "byte",
+        "signed char" | "int8_t" => "sbyte",
+        "short" | "int16_t" => "short",
+        "unsigned short" | "uint16_t" => "ushort",
+        "int" | "int32_t" => "int",
+        "unsigned" | "unsigned int" | "uint32_t" => "uint",
+        "long long" | "int64_t" => "long",
+        "unsigned long long" | "uint64_t" => "ulong",
+        "size_t" | "uintptr_t" => "UIntPtr",
+        "ssize_t" | "intptr_t" | "ptrdiff_t" => "IntPtr",
+        "float" => "float",
+        "double" => "double",
+        _ => return None,
+    })
+}
+
+/// Whether the C# type can be used in a fixed size buffer.
+fn primitive_name(ty: &str) -> bool {
+    matches!(
+        ty,
+        "bool"
+            | "byte"
+            | "sbyte"
+            | "short"
+            | "ushort"
+            | "int"
+            | "uint"
+            | "long"
+            | "ulong"
+            | "float"
+            | "double"
+    )
+}
+
+fn pascal_case(name: &str) -> String {
+    name.split('_')
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let mut c = w.chars();
+            c.next()
+                .map(|f| f.to_uppercase().chain(c).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Escape names that are reserved in C#.
+fn cs_ident(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "abstract",
+        "as",
+        "base",
+        "bool",
+        "break",
+        "byte",
+        "case",
+        "catch",
+        "char",
+        "checked",
+        "class",
+        "const",
+        "continue",
+        "decimal",
+        "default",
+        "delegate",
+        "do",
+        "double",
+        "else",
+        "enum",
+        "event",
+        "explicit",
+        "extern",
+        "false",
+        "finally",
+        "fixed",
+        "float",
+        "for",
+        "foreach",
+        "goto",
+        "if",
+        "implicit",
+        "in",
+        "int",
+        "interface",
+        "internal",
+        "is",
+        "lock",
+        "long",
+        "namespace",
+        "new",
+        "null",
+        "object",
+        "operator",
+        "out",
+        "override",
+        "params",
+        "private",
+        "protected",
+        "public",
+        "readonly",
+        "ref",
+        "return",
+        "sbyte",
+        "sealed",
+        "short",
+        "sizeof",
+        "stackalloc",
+        "static",
+        "string",
+        "struct",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "typeof",
+        "uint",
+        "ulong",
+        "unchecked",
+        "unsafe",
+        "ushort",
+        "using",
+        "virtual",
+        "void",
+        "volatile",
+        "while",
+    ];
+
+    if KEYWORDS.contains(&name) {
+        format!("@{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+const PREAMBLE: &str = r#"// CGlue bindings.
+//
+// Generated by cglue-bindgen, do not edit.
+
+using System;
+using System.Collections.Generic;
+using System.Runtime.InteropServices;
+
+namespace CGlue
+{
+    /// <summary>
+    /// Error code returned by a CGlue function.
+    /// </summary>
+    public class CGlueException : Exception
+    {
+        public int Code { get; }
+
+        public CGlueException(int code) : base("CGlue function failed with code " + code)
+        {
+            Code = code;
+        }
+    }
+
+    internal static unsafe class Runtime
+    {
+        internal static T* Check<T>(T* vtbl, string name) where T : unmanaged
+        {
+            if (vtbl == null)
+                throw new NotSupportedException(name + " is not implemented");
+            return vtbl;
+        }
+
+        // Slices may not point to null. Use a dangling, but aligned pointer for empty ones, just
+        // like Rust does.
+        internal static T* NonNull<T>(T* ptr) where T : unmanaged
+        {
+            return ptr != null ? ptr : (T*)new IntPtr(16);
+        }
+
+        internal static T[] ToArray<T>(T* data, UIntPtr len) where T : unmanaged
+        {
+            var ret = new T[(int)len];
+            for (var i = 0; i < ret.Length; i++)
+                ret[i] = data[i];
+            return ret;
+        }
+    }
+"#;
@@ -0,0 +1,932 @@
+//! # Go code generator.
+//!
+//! This generator builds a cgo package out of the C header. The C header is cleaned up by the
+//! [C generator](super::c) first, so the same monomorphized types are used in both languages.
+//!
+//! Pass `-t go` to `cglue-bindgen` before the `--` (and `-l C` to cbindgen), or use
+//! `Builder::with_target(Target::Go)` when generating bindings in-process. The package is named
+//! `cglue`, and links against the library set in the `library_name` option, if any.
+//!
+//! ## Structures
+//!
+//! The cgo preamble declares all structs, typedefs, and exported functions of the header. Every
+//! struct is available as a Go alias of the same name, for instance `KeyValue = C.struct_KeyValue`.
+//!
+//! Go can not call C function pointers, so the preamble also defines a trampoline for every vtable
+//! function, named `cglue_{vtable}_{function}`. The trampoline takes the vtable, followed by the
+//! function's arguments.
+//!
+//! ## Wrapper types
+//!
+//! For every trait object, and group, a wrapper type is generated. The type is named after the
+//! trait, or the group, if the object uses the default container and context types (see
+//! `default_container`, and `default_context` options). Objects with other container types are
+//! named after their structs, with an `Object` suffix.
+//!
+//! Trait functions are available as exported methods that call through the trampolines. In groups,
+//! if 2 traits have the same function name, all such functions will be prefixed with each trait's
+//! name. Calling a function of an optional trait that is not implemented panics, use `Has{Trait}`
+//! methods to check for optional traits beforehand.
+//!
+//! `Close` drops the container (`CBox` instance, and `CArc` context) of owned objects, it is also
+//! called by the finalizer. Functions that consume the object leave the wrapper closed. References
+//! returned by the object are wrapped as borrowed objects that keep their parent alive, and never
+//! drop anything.
+//!
+//! ## Conversions
+//!
+//! * `CSliceRef`, and `CSliceMut` are passed in, and returned as Go slices. Returned slices are
+//!   copied.
+//!
+//! * `ReprCStr` is passed in, and returned as `string`.
+//!
+//! * `Callback` (`OpaqueCallback`) is built out of a Go function. Returning `false` stops the
+//!   iteration. The function is passed to C through a `cgo.Handle`, and called by an exported
+//!   trampoline.
+//!
+//! * `CIterator` is built out of a `func() (T, bool)` function that returns `false` once there are
+//!   no more elements.
+//!
+//! * Functions that return a result through `ok_out` return the value alongside an `error`, which
+//!   is `*Error` with the error code on failure.
+//!
+//! ## Exported functions
+//!
+//! Functions exported by the library are available as package functions:
+//!
+//! ```ignore
+//! plugin, err := cglue.LoadPlugin("plugin_lib")
+//! if err != nil {
+//!     panic(err)
+//! }
+//! defer plugin.Close()
+//!
+//! features := plugin.BorrowFeatures()
+//! defer features.Close()
+//! features.WriteKeyValue([]byte("hello"), 42)
+//! ```
+//!
+
+use super::c;
+use super::model::*;
+use crate::config::*;
+use crate::ir::*;
+use crate::types::Result;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+pub fn parse_header(header: &str, config: &Config) -> Result<String> {
+    let header = c::parse_header(header, config)?;
+    let ir = Header::parse(&header)?;
+    let model = Model::new(&ir);
+
+    let gen = Generator { model: &model };
+
+    let mut preamble = String::new();
+    let mut body = String::new();
+
+    if let Some(lib) = &config.library_name {
+        writeln!(preamble, "#cgo LDFLAGS: -l{}", lib)?;
+    }
+
+    // The header defines functions, which must not be in the preamble of a file that uses
+    // `//export`. Only the declarations are taken over, helpers are defined as `static inline`.
+    for item in &ir.items {
+        let text = ir.text(item.span.clone()).trim();
+        match &item.kind {
+            ItemKind::Directive(_) if text.starts_with("#include") => {}
+            ItemKind::Struct(_) | ItemKind::Typedef(_) | ItemKind::Function(_) => {}
+            _ => continue,
+        }
+        writeln!(preamble, "{}", text)?;
+    }
+
+    body += "\n// Structures\n\n";
+
+    for name in model.structs() {
+        writeln!(body, "type {} = C.struct_{}", name, name)?;
+    }
+
+    body += "\n// Wrappers\n";
+
+    let mut vtbls = HashSet::new();
+    let mut helpers = HashSet::new();
+
+    for obj in &model.objects {
+        for (_, _, vtbl) in &obj.vtbls {
+            if vtbls.insert(vtbl.name.as_str()) {
+                preamble += &gen.create_trampolines(vtbl)?;
+            }
+        }
+
+        for (_, ty, _) in gen.container_fields(obj) {
+            if helpers.insert(ty.clone()) {
+                preamble += &gen.create_helpers(&ty)?;
+            }
+        }
+
+        body += &gen.create_wrapper(obj)?;
+    }
+
+    body += "\n// Exported functions\n";
+
+    for (f, sig) in model.functions() {
+        let mut call = Call::default();
+        let ret = gen.convert_args(&mut call, &sig, None);
+
+        write!(
+            body,
+            "\nfunc {}({}) {}{{\n",
+            pascal_case(&f.name),
+            call.params.join(", "),
+            ret_decl(&ret)
+        )?;
+        gen.write_call(&mut body, &format!("C.{}", f.name), &call, &sig, None)?;
+        body += "}\n";
+    }
+
+    // Callbacks, and iterators are called from C through exported functions.
+
+    let mut exports = HashSet::new();
+
+    let mut sigs = model
+        .objects
+        .iter()
+        .flat_map(|o| o.methods().into_iter().map(|m| m.sig))
+        .collect::<Vec<_>>();
+    sigs.extend(model.functions().map(|(_, sig)| sig));
+
+    for sig in &sigs {
+        for (ty, _) in sig.args {
+            if let Conv::Callback(t) | Conv::Iter(t) = model.conv(ty, None) {
+                if exports.insert(t.clone()) {
+                    let (decl, export) = gen.create_export(&t)?;
+                    preamble += &decl;
+                    body += &export;
+                }
+            }
+        }
+    }
+
+    let mut out = String::from("// Code generated by cglue-bindgen. DO NOT EDIT.\n\n");
+    out += "// Package cglue contains CGlue bindings.\npackage cglue\n\n";
+
+    for line in preamble.lines() {
+        if line.is_empty() {
+            out += "//\n";
+        } else {
+            writeln!(out, "// {}", line)?;
+        }
+    }
+
+    out += "import \"C\"\n\nimport (\n\t\"fmt\"\n";
+
+    for (import, used) in [("runtime", "runtime."), ("runtime/cgo", "cgo.")] {
+        if body.contains(used) {
+            writeln!(out, "\t\"{}\"", import)?;
+        }
+    }
+
+    out += "\t\"unsafe\"\n)\n";
+    out += RUNTIME;
+    out += &body;
+
+    Ok(out)
+}
+
+struct Generator<'a> {
+    model: &'a Model<'a>,
+}
+
+/// Statements needed to call a function.
+#[derive(Default)]
+struct Call {
+    params: Vec<String>,
+    args: Vec<String>,
+    pre: Vec<String>,
+}
+
+impl<'a> Generator<'a> {
+    /// Go type of the given C type, as used by the wrappers.
+    fn go_type(&self, ty: &str) -> String {
+        let (name, ptrs, array) = split_type(&self.model.expand(ty));
+
+        let ret = match (name.as_str(), ptrs) {
+            ("void", 0) => String::new(),
+            ("void", _) => format!("{}unsafe.Pointer", "*".repeat(ptrs - 1)),
+            (name, 0) if primitive(name).is_some() => primitive(name).unwrap_or_default().into(),
+            (name, _) if primitive(name).is_some() || name == "char" => {
+                format!("{}{}", "*".repeat(ptrs), cgo_primitive(name))
+            }
+            (name, _) if self.model.ir.has_type(name) => format!("{}{}", "*".repeat(ptrs), name),
+            (name, _) => format!("{}C.{}", "*".repeat(ptrs), name),
+        };
+
+        match array {
+            Some(len) => format!("[{}]{}", len, ret),
+            None => ret,
+        }
+    }
+
+    /// Go type of the given C type, as seen by cgo.
+    fn cgo_type(&self, ty: &str) -> String {
+        let (name, ptrs, _) = split_type(&self.model.expand(ty));
+
+        match (name.as_str(), ptrs) {
+            ("void", 0) => String::new(),
+            ("void", _) => format!("{}unsafe.Pointer", "*".repeat(ptrs - 1)),
+            (name, _) if primitive(name).is_some() || name == "char" => {
+                format!("{}{}", "*".repeat(ptrs), cgo_primitive(name))
+            }
+            (name, _) if self.model.ir.find_struct(name).is_some() => {
+                format!("{}C.struct_{}", "*".repeat(ptrs), name)
+            }
+            (name, _) => format!("{}C.{}", "*".repeat(ptrs), name),
+        }
+    }
+
+    /// Whether the type is a primitive value, that needs conversion between Go, and C.
+    fn is_primitive(&self, ty: &str) -> bool {
+        let (name, ptrs, array) = split_type(&self.model.expand(ty));
+        ptrs == 0 && array.is_none() && primitive(&name).is_some()
+    }
+
+    fn to_c(&self, ty: &str, expr: &str) -> String {
+        if self.is_primitive(ty) {
+            format!("{}({})", self.cgo_type(ty), expr)
+        } else {
+            expr.to_string()
+        }
+    }
+
+    fn to_go(&self, ty: &str, expr: &str) -> String {
+        if self.is_primitive(ty) {
+            format!("{}({})", self.go_type(ty), expr)
+        } else {
+            expr.to_string()
+        }
+    }
+
+    /// Type the given C pointer type points to.
+    fn pointee(ty: &str) -> String {
+        let (name, ptrs, _) = split_type(ty);
+        format!("{}{}", name, " *".repeat(ptrs.saturating_sub(1)))
+    }
+
+    /// Go element type of a slice struct, and the cgo type of its `data` field.
+    fn slice_elem(&self, slice: &str) -> (String, String) {
+        let data = self
+            .model
+            .ir
+            .find_struct(slice)
+            .and_then(|(_, s)| s.field("data"))
+            .map(|f| f.ty.as_str())
+            .unwrap_or("void *");
+
+        (self.go_type(&Self::pointee(data)), self.cgo_type(data))
+    }
+
+    /// Function pointer field of a callback, or iterator struct.
+    fn callback_func(&self, s: &str) -> Option<(&'a str, &'a [(String, String)])> {
+        let (_, s) = self.model.ir.find_struct(s)?;
+        let func = s.field("func")?;
+        match func.args.as_deref() {
+            Some(args) if args.len() == 2 => Some((func.ty.as_str(), args)),
+            _ => None,
+        }
+    }
+
+    fn wrapper(&self, obj: &Object) -> String {
+        self.model.wrapper_name(obj)
+    }
+
+    /// Container fields that can be dropped, their types, and whether they can be cloned.
+    fn container_fields(&self, obj: &Object) -> Vec<(&'a str, String, bool)> {
+        let cont = match self.model.ir.find_struct(&obj.container_ty()) {
+            Some((_, cont)) => cont,
+            None => return vec![],
+        };
+
+        cont.fields()
+            .iter()
+            .filter(|f| split_type(&f.ty).1 == 0)
+            .filter_map(|f| {
+                let ty = self.model.resolve(&split_type(&f.ty).0);
+                let (_, s) = self.model.ir.find_struct(&ty)?;
+                if s.field("instance").is_some() && s.field("drop_fn").is_some() {
+                    Some((f.name.as_str(), ty, s.field("clone_fn").is_some()))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn create_trampolines(&self, vtbl: &Struct) -> Result<String> {
+        let mut out = String::new();
+
+        for f in vtbl.fields() {
+            let args = match &f.args {
+                Some(args) => args,
+                None => continue,
+            };
+
+            write!(
+                out,
+                "\nstatic inline {} cglue_{}_{}(const struct {} *vtbl",
+                f.ty.trim(),
+                vtbl.name,
+                f.name,
+                vtbl.name
+            )?;
+
+            for (i, (ty, _)) in args.iter().enumerate() {
+                write!(out, ", {} arg{}", ty.trim(), i)?;
+            }
+
+            let call = format!(
+                "vtbl->{}({})",
+                f.name,
+                (0..args.len())
+                    .map(|i| format!("arg{}", i))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+
+            if split_type(&f.ty) == ("void".to_string(), 0, None) {
+                writeln!(out, ") {{\n    {};\n}}", call)?;
+            } else {
+                writeln!(out, ") {{\n    return {};\n}}", call)?;
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Drop, and clone helpers of a container field type, like `CBox`, or `CArc`.
+    fn create_helpers(&self, ty: &str) -> Result<String> {
+        let mut out = String::new();
+
+        write!(
+            out,
+            "\nstatic inline void cglue_{ty}_drop(struct {ty} *self) {{\n    if (self->drop_fn && self->instance) self->drop_fn(self->instance);\n}}\n",
+            ty = ty
+        )?;
+
+        if self
+            .model
+            .ir
+            .find_struct(ty)
+            .and_then(|(_, s)| s.field("clone_fn"))
+            .is_some()
+        {
+            write!(
+                out,
+                "\nstatic inline struct {ty} cglue_{ty}_clone(const struct {ty} *self) {{\n    struct {ty} ret = *self;\n    if (self->clone_fn && self->instance) ret.instance = self->clone_fn(self->instance);\n    return ret;\n}}\n",
+                ty = ty
+            )?;
+        }
+
+        Ok(out)
+    }
+
+    /// Declaration, and definition of the exported function called by a callback, or iterator.
+    fn create_export(&self, s: &str) -> Result<(String, String)> {
+        let (ret, args) = match self.callback_func(s) {
+            Some(func) => func,
+            None => return Ok((String::new(), String::new())),
+        };
+
+        let decl = format!(
+            "\nextern {} cglue_go_{}({}, {});\n",
+            ret.trim(),
+            s,
+            args[0].0.trim(),
+            args[1].0.trim()
+        );
+
+        let mut out = String::new();
+
+        let value_ty = &args[1].0;
+
+        if s.starts_with("CIterator") {
+            let elem = Self::pointee(value_ty);
+            write!(
+                out,
+                r#"
+//export cglue_go_{s}
+func cglue_go_{s}(iter unsafe.Pointer, out {out_ty}) {ret} {{
+	next := (*(*cgo.Handle)(iter)).Value().(func() ({elem}, bool))
+	value, ok := next()
+	if !ok {{
+		return 1
+	}}
+	*out = {value}
+	return 0
+}}
+"#,
+                s = s,
+                out_ty = self.cgo_type(value_ty),
+                ret = self.cgo_type(ret),
+                elem = self.go_type(&elem),
+                value = self.to_c(&elem, "value"),
+            )?;
+        } else {
+            write!(
+                out,
+                r#"
+//export cglue_go_{s}
+func cglue_go_{s}(ctx unsafe.Pointer, value {value_ty}) {ret} {{
+	f := (*(*cgo.Handle)(ctx)).Value().(func({go_ty}) bool)
+	return {call}
+}}
+"#,
+                s = s,
+                value_ty = self.cgo_type(value_ty),
+                ret = self.cgo_type(ret),
+                go_ty = self.go_type(value_ty),
+                call = self.to_c(ret, &format!("f({})", self.to_go(value_ty, "value"))),
+            )?;
+        }
+
+        Ok((decl, out))
+    }
+
+    fn create_wrapper(&self, obj: &Object) -> Result<String> {
+        let mut out = String::new();
+
+        let name = self.wrapper(obj);
+        let raw = format!("C.struct_{}", obj.raw.name);
+        let fields = self.container_fields(obj);
+
+        write!(
+            out,
+            r#"
+// {name} wraps {raw_name} {kind}.
+type {name} struct {{
+	raw      *{raw}
+	parent   any
+	borrowed bool
+}}
+
+func new{name}(raw {raw}) *{name} {{
+	w := &{name}{{raw: (*{raw})(C.malloc(C.sizeof_struct_{raw_name}))}}
+	*w.raw = raw
+	runtime.SetFinalizer(w, (*{name}).Close)
+	return w
+}}
+
+func borrow{name}(raw *{raw}, parent any) *{name} {{
+	if raw == nil {{
+		return nil
+	}}
+	return &{name}{{raw: raw, parent: parent, borrowed: true}}
+}}
+
+// Close drops the object. Borrowed objects are only invalidated.
+func (w *{name}) Close() {{
+	if w.raw != nil && !w.borrowed {{
+"#,
+            name = name,
+            raw = raw,
+            raw_name = obj.raw.name,
+            kind = if obj.is_group {
+                "group objects"
+            } else {
+                "trait objects"
+            },
+        )?;
+
+        for (field, ty, _) in &fields {
+            writeln!(out, "\t\tC.cglue_{}_drop(&w.raw.container.{})", ty, field)?;
+        }
+
+        write!(
+            out,
+            r#"		C.free(unsafe.Pointer(w.raw))
+	}}
+	w.raw = nil
+	w.parent = nil
+	runtime.SetFinalizer(w, nil)
+}}
+
+func (w *{name}) get() *{raw} {{
+	if w.raw == nil {{
+		panic("cglue: {name} is closed")
+	}}
+	return w.raw
+}}
+
+func (w *{name}) intoRaw() {raw} {{
+	if w.borrowed {{
+		panic("cglue: borrowed {name} can not be consumed")
+	}}
+	raw := *w.get()
+	C.free(unsafe.Pointer(w.raw))
+	w.raw = nil
+	runtime.SetFinalizer(w, nil)
+	return raw
+}}
+"#,
+            name = name,
+            raw = raw,
+        )?;
+
+        if obj.is_group {
+            for (t, field, _) in &obj.vtbls {
+                write!(
+                    out,
+                    "\n// Has{t} checks whether the object implements {t}.\nfunc (w *{name}) Has{t}() bool {{\n\treturn w.get().{field} != nil\n}}\n",
+                    t = t,
+                    name = name,
+                    field = field
+                )?;
+            }
+        }
+
+        let ctx = fields.iter().find(|(_, _, clone)| *clone);
+
+        for method in obj.methods() {
+            let vtbl = obj
+                .vtbls
+                .iter()
+                .find(|(_, field, _)| *field == method.vtbl)
+                .map(|(_, _, vtbl)| vtbl.name.as_str())
+                .unwrap_or_default();
+
+            let mut call = Call::default();
+
+            call.pre.push(format!("vtbl := w.get().{}", method.vtbl));
+            call.pre.push(format!(
+                "if vtbl == nil {{\n\tpanic(\"cglue: {} is not implemented\")\n}}",
+                method.trait_name
+            ));
+            call.args.push("vtbl".into());
+
+            if method.consumes {
+                call.pre.push("raw := w.intoRaw()".into());
+                // Keep the context alive until the call returns.
+                if let Some((field, ty, _)) = ctx {
+                    call.pre.push(format!(
+                        "ctx := C.cglue_{}_clone(&raw.container.{})",
+                        ty, field
+                    ));
+                    call.pre.push(format!("defer C.cglue_{}_drop(&ctx)", ty));
+                }
+                call.args.push("raw.container".into());
+            } else {
+                call.args.push("&w.raw.container".into());
+            }
+
+            let ret = self.convert_args(&mut call, &method.sig, Some(obj));
+
+            let method_name = if method.name == method.func.name {
+                pascal_case(&method.name)
+            } else {
+                format!("{}{}", method.trait_name, pascal_case(&method.func.name))
+            };
+
+            write!(
+                out,
+                "\nfunc (w *{}) {}({}) {}{{\n",
+                name,
+                method_name,
+                call.params.join(", "),
+                ret_decl(&ret)
+            )?;
+            self.write_call(
+                &mut out,
+                &format!("C.cglue_{}_{}", vtbl, method.func.name),
+                &call,
+                &method.sig,
+                Some(obj),
+            )?;
+            out += "}\n";
+        }
+
+        Ok(out)
+    }
+
+    /// Go type returned to the caller.
+    fn ret_type(&self, ty: &str, obj: Option<&Object>) -> String {
+        match self.model.conv(ty, obj) {
+            Conv::Str => "string".into(),
+            Conv::Slice(t) => format!("[]{}", self.slice_elem(&t).0),
+            Conv::Object(o) | Conv::ObjectRef(o) => format!("*{}", self.wrapper(o)),
+            Conv::Container => format!("*{}", obj.map(|o| self.wrapper(o)).unwrap_or_default()),
+            _ => self.go_type(ty),
+        }
+    }
+
+    /// Convert the arguments of the call, and return the Go return type.
+    fn convert_args(&self, call: &mut Call, sig: &Signature, obj: Option<&Object>) -> String {
+        for (i, (ty, name)) in sig.args.iter().enumerate() {
+            let name = if name.is_empty() {
+                format!("arg{}", i)
+            } else {
+                go_ident(name)
+            };
+
+            match self.model.conv(ty, obj) {
+                Conv::Str => {
+                    call.params.push(format!("{} string", name));
+                    call.pre.push(format!("{}Str := C.CString({})", name, name));
+                    call.pre
+                        .push(format!("defer C.free(unsafe.Pointer({}Str))", name));
+                    call.args.push(format!("{}Str", name));
+                }
+                Conv::Slice(t) => {
+                    let (elem, data) = self.slice_elem(&t);
+                    call.params.push(format!("{} []{}", name, elem));
+                    call.args.push(format!(
+                        "{}{{data: ({})(cglueSliceData({})), len: C.uintptr_t(len({}))}}",
+                        t, data, name, name
+                    ));
+                }
+                Conv::Callback(t) | Conv::Iter(t) => {
+                    let is_iter = t.starts_with("CIterator");
+                    let value_ty = self
+                        .callback_func(&t)
+                        .map(|(_, args)| args[1].0.clone())
+                        .unwrap_or_default();
+
+                    if is_iter {
+                        let elem = self.go_type(&Self::pointee(&value_ty));
+                        call.params
+                            .push(format!("{} func() ({}, bool)", name, elem));
+                    } else {
+                        call.params.push(format!(
+                            "{} func({}) bool",
+                            name,
+                            self.go_type(&value_ty)
+                        ));
+                    }
+
+                    call.pre
+                        .push(format!("{}Handle := cgo.NewHandle({})", name, name));
+                    call.pre.push(format!("defer {}Handle.Delete()", name));
+                    call.args.push(format!(
+                        "{}{{{}: unsafe.Pointer(&{}Handle), _func: (*[0]byte)(unsafe.Pointer(C.cglue_go_{}))}}",
+                        t,
+                        if is_iter { "iter" } else { "context" },
+                        name,
+                        t
+                    ));
+                }
+                Conv::Object(o) => {
+                    call.params.push(format!("{} *{}", name, self.wrapper(o)));
+                    call.args.push(format!("{}.intoRaw()", name));
+                }
+                Conv::ObjectRef(o) => {
+                    call.params.push(format!("{} *{}", name, self.wrapper(o)));
+                    call.args.push(format!("{}.get()", name));
+                }
+                Conv::Plain | Conv::Container => {
+                    call.params.push(format!("{} {}", name, self.go_type(ty)));
+                    call.args.push(self.to_c(ty, &name));
+                }
+            }
+        }
+
+        let ret = self.ret_type(sig.ret_ty(), obj);
+
+        match sig.ok_out {
+            Some(ok_ty) => {
+                call.pre.push(format!("var ok {}", self.cgo_type(ok_ty)));
+                call.args.push("&ok".into());
+                format!("({}, error)", ret)
+            }
+            None => ret,
+        }
+    }
+
+    /// Convert the value returned by C.
+    fn convert_ret(
+        &self,
+        pre: &mut Vec<String>,
+        ty: &str,
+        expr: &str,
+        obj: Option<&Object>,
+    ) -> String {
+        match self.model.conv(ty, obj) {
+            Conv::Str => format!("C.GoString({})", expr),
+            Conv::Slice(t) => {
+                pre.push(format!("ret := {}", expr));
+                format!(
+                    "cglueToSlice[{}](unsafe.Pointer(ret.data), int(ret.len))",
+                    self.slice_elem(&t).0
+                )
+            }
+            Conv::Object(o) => format!("new{}({})", self.wrapper(o), expr),
+            Conv::ObjectRef(o) => format!(
+                "borrow{}({}, {})",
+                self.wrapper(o),
+                expr,
+                if obj.is_some() { "w" } else { "nil" }
+            ),
+            Conv::Container => {
+                pre.push("obj := *w.get()".into());
+                pre.push(format!("obj.container = {}", expr));
+                format!(
+                    "new{}(obj)",
+                    obj.map(|o| self.wrapper(o)).unwrap_or_default()
+                )
+            }
+            _ => self.to_go(ty, expr),
+        }
+    }
+
+    /// Write the body of a function that calls `func`.
+    fn write_call(
+        &self,
+        out: &mut String,
+        func: &str,
+        call: &Call,
+        sig: &Signature,
+        obj: Option<&Object>,
+    ) -> Result<()> {
+        let mut lines = call.pre.clone();
+
+        let invoke = format!("{}({})", func, call.args.join(", "));
+
+        if let Some(ok_ty) = sig.ok_out {
+            let ret = self.ret_type(ok_ty, obj);
+            lines.push(format!(
+                "if code := {}; code != 0 {{\n\treturn {}, &Error{{Code: int32(code)}}\n}}",
+                invoke,
+                zero_value(&ret)
+            ));
+            let val = self.convert_ret(&mut lines, ok_ty, "ok", obj);
+            lines.push(format!("return {}, nil", val));
+        } else if self.go_type(sig.ret).is_empty() {
+            lines.push(invoke);
+        } else {
+            let val = self.convert_ret(&mut lines, sig.ret, &invoke, obj);
+            lines.push(format!("return {}", val));
+        }
+
+        for line in lines.iter().flat_map(|l| l.lines()) {
+            writeln!(out, "\t{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn primitive(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "bool" => "bool",
+        "char" | "unsigned char" | "uint8_t" => "byte",
+        "signed char" | "int8_t" => "int8",
+        "short" | "int16_t" => "int16",
+        "unsigned short" | "uint16_t" => "uint16",
+        "int" | "int32_t" => "int32",
+        "unsigned" | "unsigned int" | "uint32_t" => "uint32",
+        "long long" | "int64_t" => "int64",
+        "unsigned long long" | "uint64_t" => "uint64",
+        "size_t" | "uintptr_t" => "uintptr",
+        "ssize_t" | "intptr_t" | "ptrdiff_t" => "int",
+        "float" => "float32",
+        "double" => "float64",
+        _ => return None,
+    })
+}
+
+/// Name of a C primitive type in cgo, such as `C.uint`, or `C.int32_t`.
+fn cgo_primitive(name: &str) -> String {
+    let name = match name {
+        "unsigned" => "unsigned int",
+        "signed char" => "schar",
+        name => name,
+    };
+    format!("C.{}", name.replace("unsigned ", "u").replace(' ', ""))
+}
+
+fn ret_decl(ret: &str) -> String {
+    if ret.is_empty() {
+        String::new()
+    } else {
+        format!("{} ", ret)
+    }
+}
+
+fn zero_value(ty: &str) -> String {
+    match ty {
+        "string" => "\"\"".into(),
+        "bool" => "false".into(),
+        "unsafe.Pointer" => "nil".into(),
+        ty if ty.starts_with('*') || ty.starts_with("[]") => "nil".into(),
+        ty if primitive_go(ty) => "0".into(),
+        ty => format!("{}{{}}", ty),
+    }
+}
+
+fn primitive_go(ty: &str) -> bool {
+    matches!(
+        ty,
+        "byte"
+            | "int"
+            | "int8"
+            | "int16"
+            | "int32"
+            | "int64"
+            | "uint8"
+            | "uint16"
+            | "uint32"
+            | "uint64"
+            | "uintptr"
+            | "float32"
+            | "float64"
+    )
+}
+
+fn pascal_case(name: &str) -> String {
+    name.split('_')
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let mut c = w.chars();
+            c.next()
+                .map(|f| f.to_uppercase().chain(c).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Escape names that are reserved in Go, or used by the generated code.
+fn go_ident(name: &str) -> String {
+    const RESERVED: &[&str] = &[
+        "break",
+        "case",
+        "chan",
+        "const",
+        "continue",
+        "default",
+        "defer",
+        "else",
+        "fallthrough",
+        "for",
+        "func",
+        "go",
+        "goto",
+        "if",
+        "import",
+        "interface",
+        "map",
+        "package",
+        "range",
+        "return",
+        "select",
+        "struct",
+        "switch",
+        "type",
+        "var",
+        "len",
+        "string",
+        "w",
+        "vtbl",
+        "raw",
+        "ctx",
+        "ok",
+        "code",
+        "ret",
+        "obj",
+        "unsafe",
+        "cgo",
+        "runtime",
+        "fmt",
+    ];
+
+    if RESERVED.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+const RUNTIME: &str = r#"
+// Error is returned by functions that fail with an error code.
+type Error struct {
+	Code int32
+}
+
+func (e *Error) Error() string {
+	return fmt.Sprintf("cglue: function failed with code %d", e.Code)
+}
+
+// Slices passed to Rust must not be null, even if they are empty.
+var cglueEmptySlice [1]uint64
+
+func cglueSliceData[T any](s []T) unsafe.Pointer {
+	if len(s) == 0 {
+		return unsafe.Pointer(&cglueEmptySlice[0])
+	}
+	return unsafe.Pointer(&s[0])
+}
+
+func cglueToSlice[T any](data unsafe.Pointer, length int) []T {
+	if data == nil || length == 0 {
+		return nil
+	}
+	return append([]T(nil), unsafe.Slice((*T)(data), length)...)
+}
+"#;
@@ -1,2 +1,54 @@
+use crate::types::Result;
+use std::fmt;
+use std::str::FromStr;
+
 pub mod c;
 pub mod cpp;
+pub mod csharp;
+pub mod go;
+pub mod model;
+pub mod python;
+pub mod zig;
+
+/// Kind of bindings to generate out of cbindgen output.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// C, or C++ header, depending on the language cbindgen was run with.
+    #[default]
+    Header,
+    /// Python `ctypes` module. Requires C output from cbindgen.
+    Python,
+    /// C# P/Invoke bindings. Requires C output from cbindgen.
+    CSharp,
+    /// Zig bindings. Requires C output from cbindgen.
+    Zig,
+    /// Go (cgo) package. Requires C output from cbindgen.
+    Go,
+}
+
+impl FromStr for Target {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "header" | "c" | "c++" | "cpp" => Ok(Self::Header),
+            "python" | "py" => Ok(Self::Python),
+            "csharp" | "cs" | "c#" => Ok(Self::CSharp),
+            "zig" => Ok(Self::Zig),
+            "go" | "golang" => Ok(Self::Go),
+            _ => Err(format!("Unknown target: {}", s).into()),
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Header => f.write_str("header"),
+            Self::Python => f.write_str("python"),
+            Self::CSharp => f.write_str("csharp"),
+            Self::Zig => f.write_str("zig"),
+            Self::Go => f.write_str("go"),
+        }
+    }
+}
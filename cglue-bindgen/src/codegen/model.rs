@@ -0,0 +1,368 @@
+//! Language independent model of CGlue objects within a C header.
+//!
+//! Generators of languages that consume the C ABI directly, rather than including the header,
+//! use this to find trait objects, groups, their methods, and conversions the methods need.
+
+use super::c::split_vtbl_name;
+use crate::ir::*;
+use std::collections::{HashMap, HashSet};
+
+pub struct Model<'a> {
+    pub ir: &'a Header<'a>,
+    aliases: HashMap<&'a str, &'a str>,
+    /// Struct name to index of its object.
+    wrapped: HashMap<&'a str, usize>,
+    /// All trait objects, and groups, one per monomorphized struct.
+    pub objects: Vec<Object<'a>>,
+}
+
+/// Trait object, or group.
+pub struct Object<'a> {
+    /// Name of the trait, or the group.
+    pub name: String,
+    pub raw: &'a Struct,
+    /// Vtables of the object as `(trait, field, vtable)`.
+    pub vtbls: Vec<(String, &'a str, &'a Struct)>,
+    pub is_group: bool,
+}
+
+/// Function callable on an object.
+pub struct Method<'a> {
+    /// Name of the method, prefixed with the trait name if it is ambiguous within the object.
+    pub name: String,
+    pub trait_name: &'a str,
+    /// Name of the vtable field within the object.
+    pub vtbl: &'a str,
+    pub func: &'a Field,
+    /// Whether the call consumes the object.
+    pub consumes: bool,
+    pub sig: Signature<'a>,
+}
+
+/// Signature of a function, as seen by the caller.
+pub struct Signature<'a> {
+    /// Arguments, excluding the container, and `ok_out`.
+    pub args: &'a [(String, String)],
+    pub ret: &'a str,
+    /// Type of the `ok_out` argument, if the result is returned through it, alongside an integer
+    /// error code.
+    pub ok_out: Option<&'a str>,
+}
+
+/// Kind of conversion between native and C values.
+pub enum Conv<'a> {
+    Plain,
+    /// `ReprCStr`, or a `char` pointer.
+    Str,
+    /// `CSliceRef`, or `CSliceMut` struct.
+    Slice(String),
+    Callback(String),
+    Iter(String),
+    /// Owned object.
+    Object(&'a Object<'a>),
+    /// Pointer to an object.
+    ObjectRef(&'a Object<'a>),
+    /// Container of the object the function is called on.
+    Container,
+}
+
+impl<'a> Model<'a> {
+    pub fn new(ir: &'a Header<'a>) -> Self {
+        let aliases = ir
+            .typedefs()
+            .map(|(_, t)| (t.name.as_str(), t.target.as_str()))
+            .collect::<HashMap<_, _>>();
+
+        let mut objects = vec![];
+
+        for (_, s) in ir.structs() {
+            if s.field("container").is_none() {
+                continue;
+            }
+
+            if let Some(vtbl) = s.field("vtbl") {
+                let vtbl_ty = split_type(&vtbl.ty).0;
+                let name = match split_vtbl_name(&vtbl_ty) {
+                    Some((name, _, _)) => name,
+                    None => continue,
+                };
+                let vtbl = match ir.find_struct(&vtbl_ty) {
+                    Some((_, vtbl)) => vtbl,
+                    None => continue,
+                };
+                objects.push(Object {
+                    name: name.to_string(),
+                    raw: s,
+                    vtbls: vec![(name.to_string(), "vtbl", vtbl)],
+                    is_group: false,
+                });
+            } else {
+                let vtbls = s
+                    .fields()
+                    .iter()
+                    .filter(|f| f.name.starts_with("vtbl_"))
+                    .filter_map(|f| {
+                        let vtbl_ty = split_type(&f.ty).0;
+                        let (name, _, _) = split_vtbl_name(&vtbl_ty)?;
+                        let (_, vtbl) = ir.find_struct(&vtbl_ty)?;
+                        Some((name.to_string(), f.name.as_str(), vtbl))
+                    })
+                    .collect::<Vec<_>>();
+
+                if vtbls.is_empty() {
+                    continue;
+                }
+
+                objects.push(Object {
+                    name: s.name.split('_').next().unwrap_or_default().to_string(),
+                    raw: s,
+                    vtbls,
+                    is_group: true,
+                });
+            }
+        }
+
+        Self {
+            ir,
+            aliases,
+            wrapped: objects
+                .iter()
+                .enumerate()
+                .map(|(i, o)| (o.raw.name.as_str(), i))
+                .collect(),
+            objects,
+        }
+    }
+
+    /// Names of all declared structs, in order of their first declaration.
+    pub fn structs(&self) -> Vec<&'a str> {
+        let mut declared = HashSet::new();
+
+        self.ir
+            .items
+            .iter()
+            .filter_map(|item| match &item.kind {
+                ItemKind::Struct(s) => Some(s.name.as_str()),
+                ItemKind::Typedef(t) => t.target.strip_prefix("struct "),
+                _ => None,
+            })
+            .filter(|name| declared.insert(*name))
+            .collect()
+    }
+
+    /// Typedefs that are not merely aliasing a struct of the same name.
+    pub fn aliases(&self) -> impl Iterator<Item = &'a Typedef> {
+        self.ir
+            .typedefs()
+            .map(|(_, t)| t)
+            .filter(|t| t.target != format!("struct {}", t.name))
+    }
+
+    /// Find the object built out of the given struct.
+    pub fn object(&self, name: &str) -> Option<&Object<'a>> {
+        self.wrapped.get(name).map(|&i| &self.objects[i])
+    }
+
+    /// Find the typedef-ed alias of the object, such as `FeaturesGroup` for the default container
+    /// and context types.
+    pub fn object_alias(&self, obj: &Object) -> Option<&'a str> {
+        self.aliases()
+            .find(|t| t.name == obj.name && self.resolve(&t.name) == obj.raw.name)
+            .map(|t| t.name.as_str())
+    }
+
+    /// Name of the object's wrapper, in languages that wrap objects in classes.
+    ///
+    /// Objects with the default container and context types are named after the trait, or the
+    /// group. So is the first object of each name, if there are no defaults. Other objects are named
+    /// after their structs, with an `Object` suffix.
+    pub fn wrapper_name(&self, obj: &Object) -> String {
+        let default = match self.object_alias(obj) {
+            Some(_) => true,
+            None => {
+                self.objects.iter().all(|o| self.object_alias(o).is_none())
+                    && self
+                        .objects
+                        .iter()
+                        .find(|o| o.name == obj.name)
+                        .map(|o| o.raw.name.as_str())
+                        == Some(obj.raw.name.as_str())
+            }
+        };
+
+        if default {
+            obj.name.clone()
+        } else {
+            format!("{}Object", obj.raw.name)
+        }
+    }
+
+    /// Follow typedefs to the underlying type name, as long as no pointers are involved.
+    pub fn resolve(&self, name: &str) -> String {
+        let mut name = name.to_string();
+        while let Some(target) = self.aliases.get(name.as_str()) {
+            let target = target.strip_prefix("struct ").unwrap_or(target);
+            if target == name || !is_ident(target) {
+                break;
+            }
+            name = target.to_string();
+        }
+        name
+    }
+
+    /// Replace all typedef-ed names within the type with their targets.
+    pub fn expand(&self, ty: &str) -> String {
+        let (name, ptrs, array) = split_type(ty);
+
+        let base = match self.aliases.get(name.as_str()) {
+            Some(target) if target.strip_prefix("struct ") != Some(&name) => self.expand(target),
+            _ => name,
+        };
+
+        let mut ret = format!("{}{}", base, " *".repeat(ptrs));
+
+        if let Some(len) = array {
+            ret = format!("{}[{}]", ret, len);
+        }
+
+        ret
+    }
+
+    /// Determine how a value of given type is to be converted.
+    ///
+    /// `obj` is the object the function is called on, if any.
+    pub fn conv(&self, ty: &str, obj: Option<&Object>) -> Conv<'_> {
+        let (name, ptrs, _) = split_type(ty);
+        let name = self.resolve(&name);
+
+        let cont = obj
+            .and_then(|o| o.raw.field("container"))
+            .map(|f| split_type(&f.ty).0);
+
+        match (name.as_str(), ptrs) {
+            ("ReprCStr", 0) | ("char", 1) => Conv::Str,
+            (n, 0) if n.starts_with("CSliceRef_") || n.starts_with("CSliceMut_") => {
+                Conv::Slice(name)
+            }
+            (n, 0) if n.starts_with("Callback_") => Conv::Callback(name),
+            (n, 0) if n.starts_with("CIterator_") => Conv::Iter(name),
+            (n, 0) if Some(n) == cont.as_deref() => Conv::Container,
+            (n, p) => match (self.object(n), p) {
+                (Some(o), 0) => Conv::Object(o),
+                (Some(o), 1) => Conv::ObjectRef(o),
+                _ => Conv::Plain,
+            },
+        }
+    }
+
+    /// Exported functions of the library.
+    pub fn functions(&self) -> impl Iterator<Item = (&'a Function, Signature<'a>)> {
+        self.ir
+            .functions()
+            .map(|(_, f)| (f, Signature::new(&f.args, &f.ret)))
+    }
+}
+
+impl<'a> Object<'a> {
+    /// Type of the object's container.
+    pub fn container_ty(&self) -> String {
+        self.raw
+            .field("container")
+            .map(|f| split_type(&f.ty).0)
+            .unwrap_or_default()
+    }
+
+    pub fn methods(&self) -> Vec<Method<'_>> {
+        let mut counts = HashMap::new();
+
+        for (_, _, vtbl) in &self.vtbls {
+            for f in vtbl.fields() {
+                *counts.entry(f.name.as_str()).or_insert(0) += 1;
+            }
+        }
+
+        let mut ret = vec![];
+
+        for (t, field, vtbl) in &self.vtbls {
+            for f in vtbl.fields() {
+                let args = match &f.args {
+                    Some(args) if args.first().map(|(_, n)| n.as_str()) == Some("cont") => args,
+                    _ => continue,
+                };
+
+                let name = if counts[f.name.as_str()] > 1 {
+                    format!("{}_{}", t.to_lowercase(), f.name)
+                } else {
+                    f.name.clone()
+                };
+
+                ret.push(Method {
+                    name,
+                    trait_name: t,
+                    vtbl: field,
+                    func: f,
+                    consumes: split_type(&args[0].0).1 == 0,
+                    sig: Signature::new(&args[1..], &f.ty),
+                });
+            }
+        }
+
+        ret
+    }
+}
+
+impl<'a> Signature<'a> {
+    pub fn new(args: &'a [(String, String)], ret: &'a str) -> Self {
+        let ok_out = match args.last() {
+            Some((ty, name)) if name == "ok_out" && split_type(ret).0 == "int32_t" => {
+                ty.trim_end().strip_suffix('*')
+            }
+            _ => None,
+        };
+
+        Self {
+            args: if ok_out.is_some() {
+                &args[..(args.len() - 1)]
+            } else {
+                args
+            },
+            ret,
+            ok_out,
+        }
+    }
+
+    /// Type of the value returned to the caller.
+    pub fn ret_ty(&self) -> &'a str {
+        self.ok_out.unwrap_or(self.ret)
+    }
+}
+
+/// Split C type into its base name, pointer depth, and array length.
+pub fn split_type(ty: &str) -> (String, usize, Option<String>) {
+    let (ty, array) = match ty.rfind('[') {
+        Some(idx) if ty.ends_with(']') => (
+            &ty[..idx],
+            Some(ty[(idx + 1)..(ty.len() - 1)].trim().to_string()),
+        ),
+        _ => (ty, None),
+    };
+
+    let ty = ty.replace('*', " * ");
+
+    let mut ptrs = 0;
+    let mut name = vec![];
+
+    for word in ty.split_whitespace() {
+        match word {
+            "*" => ptrs += 1,
+            "const" | "struct" | "enum" | "union" | "volatile" => {}
+            w => name.push(w),
+        }
+    }
+
+    (name.join(" "), ptrs, array)
+}
+
+pub fn is_ident(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
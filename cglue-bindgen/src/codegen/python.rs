@@ -0,0 +1,554 @@
+//! # Python code generator.
+//!
+//! This generator builds a `ctypes` module out of the C header. The C header is cleaned up by the
+//! [C generator](super::c) first, so the same monomorphized types are used in both languages.
+//!
+//! Pass `-t python` to `cglue-bindgen` before the `--` (and `-l C` to cbindgen), or use
+//! `Builder::with_target(Target::Python)` when generating bindings in-process.
+//!
+//! ## Structures
+//!
+//! Every struct becomes a `ctypes.Structure` of the same name, and every typedef becomes an alias
+//! of its target. Structures that are only declared (like `TypeLayout`) stay opaque.
+//!
+//! ## Wrapper classes
+//!
+//! For every trait object, and group, a wrapper class is generated, named after the trait, or the
+//! group. Trait functions are available as methods that call through the vtable. In groups, if 2
+//! traits have the same function name, all such functions will be prefixed with each trait's name.
+//! Calling a function of an optional trait that is not implemented raises `NotImplementedError`,
+//! use `implements` to check for optional traits beforehand.
+//!
+//! Owned wrappers drop their container (`CBox` instance and `CArc` context) in `__del__`.
+//! Functions that consume the object (take `self` by value) leave the wrapper empty. References
+//! returned by the object are wrapped as borrowed objects that keep their parent alive and never
+//! drop anything. The same goes for objects returned by value from functions that do not consume
+//! the object, since they may borrow from it.
+//!
+//! ## Conversions
+//!
+//! Arguments and return values are converted where it makes sense:
+//!
+//! * `CSliceRef`, and `CSliceMut` are built out of `bytes`, `bytearray`, `str`, or lists. `u8`
+//!   slices are returned as `bytes`, other slices as lists.
+//!
+//! * `ReprCStr` accepts `str`, and is returned as `str`.
+//!
+//! * `Callback` is built out of any Python callable. Returning `False` from the callable stops the
+//!   iteration.
+//!
+//! * `CIterator` is built out of any Python iterable.
+//!
+//! * Trait objects, and groups are accepted (and returned) as wrapper classes.
+//!
+//! * Functions that return a result through `ok_out` return the value directly, and raise
+//!   `CGlueError` with the error code on failure.
+//!
+//! ## Loading the library
+//!
+//! Exported functions are available through the `Library` class:
+//!
+//! ```ignore
+//! import bindings
+//!
+//! lib = bindings.Library("./libplugin_api.so")
+//! plugin = lib.load_plugin("plugin_lib")
+//! features = plugin.borrow_features()
+//! features.write_key_value("hello", 42)
+//! print(features.get_key_value("hello"))
+//! ```
+//!
+
+use super::c;
+use super::model::*;
+use crate::config::*;
+use crate::ir::*;
+use crate::types::*;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+pub fn parse_header(header: &str, config: &Config) -> Result<String> {
+    let header = c::parse_header(header, config)?;
+    let ir = Header::parse(&header)?;
+    let model = Model::new(&ir);
+
+    // Objects of the same trait, or group share the wrapper class.
+    let mut names = HashSet::new();
+    let objects = model
+        .objects
+        .iter()
+        .filter(|o| names.insert(o.name.as_str()))
+        .collect::<Vec<_>>();
+
+    let gen = Generator { model: &model };
+
+    let mut out = String::from(PREAMBLE);
+
+    // Declare all structures first, so that they can reference each other by pointer.
+
+    out += "\n\n# Structures\n";
+
+    for name in model.structs() {
+        write!(out, "\n\nclass {}(ctypes.Structure):\n    pass\n", name)?;
+    }
+
+    out += "\n\n# Type aliases\n\n";
+
+    for t in model.aliases() {
+        if !names.contains(t.name.as_str()) {
+            writeln!(out, "{} = {}", t.name, gen.py_type(&t.target))?;
+        }
+    }
+
+    out += "\n\n# Layouts\n";
+
+    for (_, s) in ir.structs() {
+        let body = match &s.body {
+            Some(body) => body,
+            None => continue,
+        };
+
+        if body
+            .fields
+            .iter()
+            .any(|f| !is_ident(&f.name) || f.ty.contains('}'))
+        {
+            write!(
+                out,
+                "\n# {} has unsupported layout, and stays opaque.\n",
+                s.name
+            )?;
+            continue;
+        }
+
+        write!(out, "\n{}._fields_ = [\n", s.name)?;
+
+        for f in &body.fields {
+            writeln!(out, "    (\"{}\", {}),", f.name, gen.field_type(f))?;
+        }
+
+        out += "]\n";
+    }
+
+    out += "\n\n# Wrappers\n";
+
+    for obj in objects {
+        out += &gen.create_wrapper(obj)?;
+    }
+
+    out += "\n\n# Exported functions\n";
+    out += &gen.create_library()?;
+
+    Ok(out)
+}
+
+struct Generator<'a> {
+    model: &'a Model<'a>,
+}
+
+impl<'a> Generator<'a> {
+    /// Python expression of the given C type.
+    fn py_type(&self, ty: &str) -> String {
+        let (name, mut ptrs, array) = split_type(ty);
+
+        let mut ret = match (name.as_str(), ptrs) {
+            ("void", 0) => return "None".into(),
+            ("void", _) | ("char", 1..) => {
+                ptrs -= 1;
+                if name == "void" {
+                    "ctypes.c_void_p"
+                } else {
+                    "ctypes.c_char_p"
+                }
+                .to_string()
+            }
+            (name, _) => match primitive(name) {
+                Some(p) => p.to_string(),
+                // Aliases shadowed by wrapper classes are referred to by their target.
+                None if self.model.objects.iter().any(|o| o.name == name) => {
+                    self.model.resolve(name)
+                }
+                None => name.to_string(),
+            },
+        };
+
+        for _ in 0..ptrs {
+            ret = format!("ctypes.POINTER({})", ret);
+        }
+
+        match array {
+            Some(len) => format!("({} * {})", ret, len),
+            None => ret,
+        }
+    }
+
+    fn field_type(&self, field: &Field) -> String {
+        match &field.args {
+            Some(args) => {
+                let mut ret = format!("ctypes.CFUNCTYPE({}", self.py_type(&field.ty));
+                for (ty, _) in args {
+                    ret += ", ";
+                    ret += &self.py_type(ty);
+                }
+                ret + ")"
+            }
+            None => self.py_type(&field.ty),
+        }
+    }
+
+    fn create_wrapper(&self, obj: &Object) -> Result<String> {
+        let mut out = String::new();
+
+        write!(out, "\n\nclass {}(CGlueObject):\n", obj.name)?;
+
+        if obj.is_group {
+            write!(
+                out,
+                "    \"\"\"Wrapper of {} group objects.\"\"\"\n\n    _traits = {{\n",
+                obj.name
+            )?;
+            for (t, field, _) in &obj.vtbls {
+                writeln!(out, "        \"{}\": \"{}\",", t, field)?;
+            }
+            out += "    }\n";
+        } else {
+            write!(
+                out,
+                "    \"\"\"Wrapper of {} trait objects.\"\"\"\n\n    _traits = {{\"{}\": \"vtbl\"}}\n",
+                obj.name, obj.name
+            )?;
+        }
+
+        for method in obj.methods() {
+            let cont = if method.consumes {
+                "self._take().container"
+            } else {
+                "self._cont()"
+            };
+
+            write!(out, "\n    def {}(self", method.name)?;
+            let call = self.create_call(
+                &mut out,
+                &format!("self._vtbl(\"{}\").{}", method.vtbl, method.func.name),
+                Some(cont),
+                &method.sig,
+                Some(obj),
+                !method.consumes,
+            )?;
+            out += &call;
+        }
+
+        Ok(out)
+    }
+
+    fn create_library(&self) -> Result<String> {
+        let mut out = String::new();
+
+        out += "\n\nclass Library:\n";
+        out += "    \"\"\"Functions exported by the library.\"\"\"\n\n";
+        out += "    def __init__(self, path):\n";
+        out += "        self._lib = ctypes.CDLL(path)\n";
+
+        for (f, _) in self.model.functions() {
+            write!(
+                out,
+                "        _bind(self._lib, \"{}\", {}, [",
+                f.name,
+                self.py_type(&f.ret)
+            )?;
+            for (i, (ty, _)) in f.args.iter().enumerate() {
+                if i > 0 {
+                    out += ", ";
+                }
+                out += &self.py_type(ty);
+            }
+            out += "])\n";
+        }
+
+        for (f, sig) in self.model.functions() {
+            write!(out, "\n    def {}(self", f.name)?;
+            let call = self.create_call(
+                &mut out,
+                &format!("self._lib.{}", f.name),
+                None,
+                &sig,
+                None,
+                false,
+            )?;
+            out += &call;
+        }
+
+        Ok(out)
+    }
+
+    /// Finish the method signature, and create its body calling `func`.
+    ///
+    /// If `borrows` is set, returned objects are wrapped as borrowed from `self`.
+    fn create_call(
+        &self,
+        sig_out: &mut String,
+        func: &str,
+        cont: Option<&str>,
+        sig: &Signature,
+        obj: Option<&Object>,
+        borrows: bool,
+    ) -> Result<String> {
+        let mut body = String::new();
+        let mut call_args = cont.into_iter().map(str::to_string).collect::<Vec<_>>();
+
+        for (ty, name) in sig.args {
+            let name = py_ident(name);
+            write!(sig_out, ", {}", name)?;
+            call_args.push(match self.model.conv(ty, obj) {
+                Conv::Str => format!("_str({})", name),
+                Conv::Slice(t) => format!("_slice({}, {})", t, name),
+                Conv::Callback(t) => format!("_callback({}, {})", t, name),
+                Conv::Iter(t) => format!("_iter({}, {})", t, name),
+                Conv::Object(_) => format!("_into_raw({})", name),
+                Conv::ObjectRef(_) => format!("_as_ptr({})", name),
+                Conv::Plain | Conv::Container => name,
+            });
+        }
+
+        *sig_out += "):\n";
+
+        if sig.ok_out.is_some() {
+            call_args.push("ctypes.byref(ok_out)".into());
+        }
+
+        let call = format!("{}({})", func, call_args.join(", "));
+
+        let ret_val = if let Some(ok_ty) = sig.ok_out {
+            writeln!(body, "        ok_out = {}()", self.py_type(ok_ty))?;
+            writeln!(body, "        ret = {}", call)?;
+            body += "        if ret != 0:\n            raise CGlueError(ret)\n";
+            "ok_out".to_string()
+        } else if self.py_type(sig.ret) == "None" {
+            writeln!(body, "        {}", call)?;
+            return Ok(body);
+        } else {
+            call
+        };
+
+        let ret_val = match self.model.conv(sig.ret_ty(), obj) {
+            Conv::Str => format!("_decode({})", ret_val),
+            Conv::Slice(_) => format!("_from_slice({})", ret_val),
+            Conv::Object(o) if borrows => format!("{}({}, self)", o.name, ret_val),
+            Conv::Object(o) => format!("{}({})", o.name, ret_val),
+            Conv::ObjectRef(o) => format!("_borrow({}, {}, self)", o.name, ret_val),
+            Conv::Container => format!("self._with_container({})", ret_val),
+            Conv::Plain | Conv::Callback(_) | Conv::Iter(_) => ret_val,
+        };
+
+        writeln!(body, "        return {}", ret_val)?;
+
+        Ok(body)
+    }
+}
+
+fn primitive(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "bool" => "ctypes.c_bool",
+        "char" => "ctypes.c_char",
+        "signed char" | "int8_t" => "ctypes.c_int8",
+        "unsigned char" | "uint8_t" => "ctypes.c_uint8",
+        "short" | "int16_t" => "ctypes.c_int16",
+        "unsigned short" | "uint16_t" => "ctypes.c_uint16",
+        "int" => "ctypes.c_int",
+        "unsigned" | "unsigned int" => "ctypes.c_uint",
+        "int32_t" => "ctypes.c_int32",
+        "uint32_t" => "ctypes.c_uint32",
+        "long" => "ctypes.c_long",
+        "unsigned long" => "ctypes.c_ulong",
+        "long long" | "int64_t" => "ctypes.c_int64",
+        "unsigned long long" | "uint64_t" => "ctypes.c_uint64",
+        "size_t" | "uintptr_t" => "ctypes.c_size_t",
+        "ssize_t" | "intptr_t" | "ptrdiff_t" => "ctypes.c_ssize_t",
+        "float" => "ctypes.c_float",
+        "double" => "ctypes.c_double",
+        _ => return None,
+    })
+}
+
+/// Escape names that are reserved in Python.
+fn py_ident(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
+        "continue", "def", "del", "elif", "else", "except", "finally", "for", "from", "global",
+        "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return",
+        "try", "while", "with", "yield", "self", "ctypes",
+    ];
+
+    if KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+const PREAMBLE: &str = r#""""CGlue bindings.
+
+Generated by cglue-bindgen, do not edit.
+"""
+
+import ctypes
+
+
+class CGlueError(Exception):
+    """Error code returned by a CGlue function."""
+
+    def __init__(self, code):
+        super().__init__("CGlue function failed with code {}".format(code))
+        self.code = code
+
+
+class CGlueObject:
+    """Base class of trait object, and group wrappers.
+
+    Owned objects drop their container once garbage collected. Borrowed objects keep their parent
+    alive instead.
+    """
+
+    _traits = {}
+
+    def __init__(self, raw, parent=None):
+        self._raw = raw
+        self._parent = parent
+
+    def __del__(self):
+        raw = getattr(self, "_raw", None)
+        if raw is not None and self._parent is None:
+            self._raw = None
+            _drop(raw.container)
+
+    def implements(self, *traits):
+        """Check whether the object implements all given traits."""
+        raw = self._get()
+        return all(bool(getattr(raw, self._traits[t])) for t in traits)
+
+    def into_raw(self):
+        """Release ownership of the underlying structure."""
+        return self._take()
+
+    def _get(self):
+        if self._raw is None:
+            raise ValueError("object has been consumed")
+        return self._raw
+
+    def _take(self):
+        if self._parent is not None:
+            raise ValueError("borrowed objects can not be consumed")
+        raw = self._get()
+        self._raw = None
+        return raw
+
+    def _vtbl(self, field):
+        vtbl = getattr(self._get(), field)
+        if not vtbl:
+            raise NotImplementedError("{} is not implemented".format(field))
+        return vtbl.contents
+
+    def _cont(self):
+        return ctypes.byref(self._get().container)
+
+    def _with_container(self, cont):
+        raw = type(self._get())()
+        ctypes.pointer(raw)[0] = self._raw
+        raw.container = cont
+        return type(self)(raw)
+
+
+def _drop(cont):
+    # Instance and context both carry their own drop functions.
+    for name, _ in cont._fields_:
+        field = getattr(cont, name)
+        drop_fn = getattr(field, "drop_fn", None)
+        if drop_fn:
+            drop_fn(field.instance)
+
+
+def _bind(lib, name, restype, argtypes):
+    func = getattr(lib, name, None)
+    if func is not None:
+        func.restype = restype
+        func.argtypes = argtypes
+
+
+def _str(value):
+    if isinstance(value, str):
+        return value.encode()
+    return value
+
+
+def _decode(value):
+    if isinstance(value, bytes):
+        return value.decode()
+    return value
+
+
+def _slice(ty, value):
+    if isinstance(value, ty):
+        return value
+    if isinstance(value, str):
+        value = value.encode()
+    data_ty = dict(ty._fields_)["data"]
+    if isinstance(value, bytearray):
+        buf = (ctypes.c_char * len(value)).from_buffer(value)
+    elif isinstance(value, bytes):
+        buf = ctypes.create_string_buffer(value, len(value))
+    else:
+        buf = (data_ty._type_ * len(value))(*value)
+    ret = ty(ctypes.cast(buf, data_ty), len(value))
+    ret._buf = buf
+    return ret
+
+
+def _from_slice(value):
+    if dict(type(value)._fields_)["data"]._type_ is ctypes.c_uint8:
+        return ctypes.string_at(value.data, value.len) if value.len else b""
+    return value.data[:value.len]
+
+
+def _callback(ty, func):
+    if isinstance(func, ty):
+        return func
+    func_ty = dict(ty._fields_)["func"]
+
+    def call(_context, value):
+        return func(value) is not False
+
+    return ty(None, func_ty(call))
+
+
+def _iter(ty, iterable):
+    if isinstance(iterable, ty):
+        return iterable
+    func_ty = dict(ty._fields_)["func"]
+    values = iter(iterable)
+
+    def next_value(_iter, out):
+        try:
+            out[0] = next(values)
+        except StopIteration:
+            return 1
+        return 0
+
+    return ty(None, func_ty(next_value))
+
+
+def _into_raw(obj):
+    if isinstance(obj, CGlueObject):
+        return obj._take()
+    return obj
+
+
+def _as_ptr(obj):
+    if isinstance(obj, CGlueObject):
+        return ctypes.byref(obj._get())
+    return obj
+
+
+def _borrow(cls, ptr, parent):
+    if not ptr:
+        return None
+    return cls(ptr.contents, parent)"#;
@@ -0,0 +1,761 @@
+//! # Zig code generator.
+//!
+//! This generator builds a Zig source file out of the C header, so that the header (and its
+//! `COLLECT_CB`, `BUF_ITER` macros) never needs to go through `@cImport`. The C header is cleaned
+//! up by the [C generator](super::c) first, so the same monomorphized types are used in both
+//! languages.
+//!
+//! Pass `-t zig` to `cglue-bindgen` before the `--` (and `-l C` to cbindgen), or use
+//! `Builder::with_target(Target::Zig)` when generating bindings in-process.
+//!
+//! ## Structures
+//!
+//! Every struct becomes an `extern struct` of the same name, and every typedef becomes an alias
+//! of its target. Structures that are only declared (like `TypeLayout`) are `opaque`.
+//!
+//! Common CGlue types are comptime-generic, and the monomorphized structs are their
+//! instantiations, for instance `CSliceRef_u8 = CSliceRef(u8)`:
+//!
+//! * `CBox(T)`, and `CArc(T)` have `deinit` (and `clone` for `CArc`).
+//!
+//! * `CSliceRef(T)`, and `CSliceMut(T)` convert from, and to Zig slices with `init`, and `slice`.
+//!
+//! * `Callback(T)` is built with `init` out of a context pointer, and a function that gets called
+//!   with it. `collect` builds a callback that appends to a managed array list.
+//!
+//! * `CIterator(T)` is built with `init` out of a pointer to anything with a `next() ?T` function.
+//!   `CIterator(T).Slice` iterates over a slice.
+//!
+//! ## Wrapper methods
+//!
+//! Trait objects, and groups get methods calling through their vtables. Method names are
+//! camelCase. In groups, if 2 traits have the same function name, all such functions will be
+//! prefixed with each trait's name. Calling a function of an optional trait that is not implemented
+//! panics, use `has{Trait}` functions to check for optional traits beforehand.
+//!
+//! `deinit` drops the object's container (`CBox` instance, and `CArc` context). Functions that
+//! consume the object take it by value, and the object must not be used, or deinitialized
+//! afterwards.
+//!
+//! Arguments are converted where it makes sense:
+//!
+//! * `CSliceRef`, and `CSliceMut` are passed in as Zig slices, and returned as Zig slices.
+//!
+//! * `ReprCStr` accepts null-terminated strings.
+//!
+//! * `Callback` is passed in as a context pointer, and a comptime function.
+//!
+//! * `CIterator` is passed in as a pointer to anything with a `next() ?T` function.
+//!
+//! * Functions that return a result through `ok_out` return `Error!T`.
+//!
+//! ## Exported functions
+//!
+//! Functions exported by the library are declared `extern`, link against the library to use them.
+//! Functions that need conversions get camelCase wrappers:
+//!
+//! ```ignore
+//! const cglue = @import("bindings.zig");
+//!
+//! var plugin = try cglue.loadPlugin("plugin_lib");
+//! var features = plugin.borrowFeatures();
+//! defer features.deinit();
+//! features.writeKeyValue("hello", 42);
+//! ```
+//!
+
+use super::c;
+use super::model::*;
+use crate::config::*;
+use crate::ir::*;
+use crate::types::Result;
+use std::fmt::Write;
+
+pub fn parse_header(header: &str, config: &Config) -> Result<String> {
+    let header = c::parse_header(header, config)?;
+    let ir = Header::parse(&header)?;
+    let model = Model::new(&ir);
+
+    let gen = Generator { model: &model };
+
+    let mut out = String::from(PREAMBLE);
+
+    // Declaration order does not matter in Zig.
+
+    out += "\n// Type aliases\n\n";
+
+    for t in model.aliases() {
+        if t.target.contains('(') {
+            writeln!(out, "// {} is not supported.", t.name)?;
+        } else {
+            writeln!(out, "pub const {} = {};", t.name, gen.zig_type(&t.target))?;
+        }
+    }
+
+    out += "\n// Structures\n";
+
+    for name in model.structs() {
+        out += &gen.create_struct(name)?;
+    }
+
+    out += "\n// Exported functions\n";
+
+    for (f, sig) in model.functions() {
+        out += &gen.create_function(f, &sig)?;
+    }
+
+    Ok(out)
+}
+
+struct Generator<'a> {
+    model: &'a Model<'a>,
+}
+
+/// Generic type of a monomorphized struct, and the type it is instantiated with.
+struct Generic {
+    name: &'static str,
+    arg: String,
+}
+
+impl<'a> Generator<'a> {
+    /// Zig type of the given C type.
+    fn zig_type(&self, ty: &str) -> String {
+        let (name, ptrs, array) = split_type(ty);
+
+        // Constness applies to the innermost pointee.
+        let is_const = ty
+            .split('*')
+            .next()
+            .map(|s| s.split_whitespace().any(|w| w == "const"))
+            .unwrap_or_default();
+
+        let mut ret = match (name.as_str(), ptrs) {
+            ("char", 1..) if is_const => "[*c]const u8".to_string(),
+            ("char", 1..) => "[*c]u8".to_string(),
+            ("void", 0) => return "void".into(),
+            ("void", _) if is_const => "?*const anyopaque".to_string(),
+            ("void", _) => "?*anyopaque".to_string(),
+            (name, 0) => primitive(name).unwrap_or(name).to_string(),
+            (name, _) if is_const => format!("?*const {}", primitive(name).unwrap_or(name)),
+            (name, _) => format!("?*{}", primitive(name).unwrap_or(name)),
+        };
+
+        for _ in 1..ptrs {
+            ret = format!("?*{}", ret);
+        }
+
+        match array {
+            Some(len) => format!("[{}]{}", len, ret),
+            None => ret,
+        }
+    }
+
+    /// Zig type of the value the C pointer type points to.
+    fn pointee(&self, ty: &str) -> String {
+        let (name, ptrs, _) = split_type(ty);
+        match (name.as_str(), ptrs) {
+            ("void", 1) => "anyopaque".into(),
+            (_, 0) => self.zig_type(ty),
+            (name, _) => self.zig_type(&format!("{}{}", name, " *".repeat(ptrs - 1))),
+        }
+    }
+
+    fn field_type(&self, field: &Field) -> String {
+        match &field.args {
+            Some(args) => format!(
+                "?*const fn ({}) callconv(callconv_c) {}",
+                args.iter()
+                    .map(|(ty, _)| self.zig_type(ty))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                self.zig_type(&field.ty)
+            ),
+            None => self.zig_type(&field.ty),
+        }
+    }
+
+    /// Find the generic type the struct is an instance of.
+    fn generic(&self, s: &Struct) -> Option<Generic> {
+        let fields = s.fields();
+        let names = fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>();
+        let fn_arg = |idx: usize| {
+            fields
+                .last()
+                .and_then(|f| f.args.as_ref())
+                .filter(|args| args.len() == 2)
+                .map(|args| args[idx].0.as_str())
+        };
+
+        let (name, arg) = match (s.name.split('_').next()?, names.as_slice()) {
+            ("CBox", ["instance", "drop_fn", "free_fn"]) => ("CBox", self.pointee(&fields[0].ty)),
+            ("CArc", ["instance", "clone_fn", "drop_fn"]) => ("CArc", self.pointee(&fields[0].ty)),
+            ("CSliceRef", ["data", "len"]) => ("CSliceRef", self.pointee(&fields[0].ty)),
+            ("CSliceMut", ["data", "len"]) => ("CSliceMut", self.pointee(&fields[0].ty)),
+            ("Callback", ["context", "func"]) => ("Callback", self.zig_type(fn_arg(1)?)),
+            ("CIterator", ["iter", "func"]) => ("CIterator", self.pointee(fn_arg(1)?)),
+            _ => return None,
+        };
+
+        Some(Generic { name, arg })
+    }
+
+    /// Generic type of the struct with the given (possibly aliased) name.
+    fn generic_of(&self, name: &str) -> Option<Generic> {
+        let (_, s) = self.model.ir.find_struct(&self.model.resolve(name))?;
+        self.generic(s)
+    }
+
+    fn create_struct(&self, name: &str) -> Result<String> {
+        let mut out = String::new();
+
+        let s = match self.model.ir.find_struct(name) {
+            Some((_, s)) => s,
+            None => {
+                writeln!(out, "\npub const {} = opaque {{}};", name)?;
+                return Ok(out);
+            }
+        };
+
+        if s.fields()
+            .iter()
+            .any(|f| !is_ident(&f.name) || f.ty.contains('}'))
+        {
+            writeln!(
+                out,
+                "\n// {} has unsupported layout, and stays opaque.\npub const {} = opaque {{}};",
+                name, name
+            )?;
+            return Ok(out);
+        }
+
+        if let Some(Generic { name: generic, arg }) = self.generic(s) {
+            writeln!(out, "\npub const {} = {}({});", name, generic, arg)?;
+            return Ok(out);
+        }
+
+        write!(out, "\npub const {} = extern struct {{\n", name)?;
+
+        for f in s.fields() {
+            writeln!(out, "    {}: {},", zig_ident(&f.name), self.field_type(f))?;
+        }
+
+        if let Some(obj) = self.model.object(name) {
+            out += &self.create_methods(obj)?;
+        }
+
+        out += "};\n";
+
+        Ok(out)
+    }
+
+    /// Container fields that can be dropped, and the generic type of each of them.
+    fn container_fields(&self, obj: &Object) -> Vec<(&'a str, &'static str)> {
+        let cont = match self.model.ir.find_struct(&obj.container_ty()) {
+            Some((_, cont)) => cont,
+            None => return vec![],
+        };
+
+        cont.fields()
+            .iter()
+            .filter(|f| split_type(&f.ty).1 == 0)
+            .filter_map(|f| {
+                let generic = self.generic_of(&split_type(&f.ty).0)?;
+                match generic.name {
+                    "CBox" | "CArc" => Some((f.name.as_str(), generic.name)),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    fn create_methods(&self, obj: &Object) -> Result<String> {
+        let mut out = String::new();
+
+        let drops = self.container_fields(obj);
+
+        out += "\n    /// Drop the container of the object.\n";
+        out += "    pub fn deinit(self: *@This()) void {\n";
+        for (field, _) in &drops {
+            writeln!(out, "        self.container.{}.deinit();", field)?;
+        }
+        if drops.is_empty() {
+            out += "        _ = self;\n";
+        }
+        out += "    }\n";
+
+        if obj.is_group {
+            for (t, field, _) in &obj.vtbls {
+                write!(
+                    out,
+                    "\n    pub fn has{}(self: *const @This()) bool {{\n        return self.{} != null;\n    }}\n",
+                    t, field
+                )?;
+            }
+        }
+
+        for method in obj.methods() {
+            let (receiver, cont) = if method.consumes {
+                ("@This()", "self.container")
+            } else if method
+                .func
+                .args
+                .as_ref()
+                .and_then(|args| args.first())
+                .map(|(ty, _)| self.zig_type(ty).starts_with("?*const "))
+                .unwrap_or_default()
+            {
+                ("*const @This()", "&self.container")
+            } else {
+                ("*@This()", "&self.container")
+            };
+
+            let mut params = vec![format!("self: {}", receiver)];
+            let mut pre = vec![format!(
+                "const vtbl = self.{} orelse @panic(\"{} is not implemented\");",
+                method.vtbl, method.trait_name
+            )];
+
+            // Keep the context alive until the consuming call returns.
+            if method.consumes {
+                if let Some((field, _)) = drops.iter().find(|(_, g)| *g == "CArc") {
+                    pre.push(format!("var ctx = self.container.{}.clone();", field));
+                    pre.push("defer ctx.deinit();".into());
+                }
+            }
+
+            let func = format!("vtbl.{}.?", zig_ident(&method.func.name));
+
+            out += &self.create_call(
+                &camel_case(&method.name),
+                &func,
+                Some(cont),
+                &mut params,
+                pre,
+                &method.sig,
+                Some(obj),
+            )?;
+        }
+
+        Ok(out)
+    }
+
+    fn create_function(&self, f: &Function, sig: &Signature) -> Result<String> {
+        let mut out = String::new();
+
+        write!(out, "\npub extern fn {}(", f.name)?;
+
+        for (i, (ty, name)) in f.args.iter().enumerate() {
+            if i > 0 {
+                out += ", ";
+            }
+            write!(out, "{}: {}", arg_name(name, i), self.zig_type(ty))?;
+        }
+
+        writeln!(out, ") {};", self.zig_type(&f.ret))?;
+
+        let name = camel_case(&f.name);
+
+        if name != f.name {
+            let call = self.create_call(&name, &f.name, None, &mut vec![], vec![], sig, None)?;
+
+            // Free functions live at the top level.
+            for line in call.lines() {
+                writeln!(out, "{}", line.strip_prefix("    ").unwrap_or(line))?;
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Create a function named `name` calling `func`.
+    #[allow(clippy::too_many_arguments)]
+    fn create_call(
+        &self,
+        name: &str,
+        func: &str,
+        cont: Option<&str>,
+        params: &mut Vec<String>,
+        mut pre: Vec<String>,
+        sig: &Signature,
+        obj: Option<&Object>,
+    ) -> Result<String> {
+        let mut out = String::new();
+
+        let mut args = cont.into_iter().map(str::to_string).collect::<Vec<_>>();
+
+        for (i, (ty, name)) in sig.args.iter().enumerate() {
+            let name = arg_name(name, i);
+
+            let arg = match self.model.conv(ty, obj) {
+                Conv::Str => {
+                    params.push(format!("{}: [*:0]const u8", name));
+                    name
+                }
+                Conv::Slice(t) => {
+                    let elem = self.generic_of(&t).map(|g| g.arg).unwrap_or_default();
+                    let constness = if t.starts_with("CSliceRef") {
+                        "const "
+                    } else {
+                        ""
+                    };
+                    params.push(format!("{}: []{}{}", name, constness, elem));
+                    format!("{}.init({})", t, name)
+                }
+                Conv::Callback(t) => {
+                    let elem = self.generic_of(&t).map(|g| g.arg).unwrap_or_default();
+                    params.push(format!("{}_ctx: anytype", name));
+                    params.push(format!(
+                        "comptime {}: fn (@TypeOf({}_ctx), {}) bool",
+                        name, name, elem
+                    ));
+                    format!("{}.init({}_ctx, {})", t, name, name)
+                }
+                Conv::Iter(t) => {
+                    params.push(format!("{}: anytype", name));
+                    format!("{}.init({})", t, name)
+                }
+                _ => {
+                    params.push(format!("{}: {}", name, self.zig_type(ty)));
+                    name
+                }
+            };
+
+            args.push(arg);
+        }
+
+        if sig.ok_out.is_some() {
+            args.push("&ok".into());
+        }
+
+        let call = format!("{}({})", func, args.join(", "));
+
+        let ret_ty = match self.model.conv(sig.ret_ty(), obj) {
+            Conv::Slice(t) => {
+                let elem = self.generic_of(&t).map(|g| g.arg).unwrap_or_default();
+                if t.starts_with("CSliceRef") {
+                    format!("[]const {}", elem)
+                } else {
+                    format!("[]{}", elem)
+                }
+            }
+            Conv::Container => "@This()".into(),
+            _ => self.zig_type(sig.ret_ty()),
+        };
+
+        let body = if let Some(ok_ty) = sig.ok_out {
+            pre.push(format!("var ok: {} = undefined;", self.zig_type(ok_ty)));
+            pre.push(format!("if ({} != 0) return error.CGlueFailure;", call));
+            "return ok;".to_string()
+        } else if ret_ty == "void" {
+            format!("{};", call)
+        } else {
+            match self.model.conv(sig.ret, obj) {
+                Conv::Slice(_) => format!("return {}.slice();", call),
+                Conv::Container => {
+                    pre.push("var ret = self.*;".into());
+                    pre.push(format!("ret.container = {};", call));
+                    "return ret;".into()
+                }
+                _ => format!("return {};", call),
+            }
+        };
+
+        let ret_ty = if sig.ok_out.is_some() {
+            format!("Error!{}", ret_ty)
+        } else {
+            ret_ty
+        };
+
+        write!(
+            out,
+            "\n    pub fn {}({}) {} {{\n",
+            name,
+            params.join(", "),
+            ret_ty
+        )?;
+
+        for line in pre {
+            writeln!(out, "        {}", line)?;
+        }
+
+        writeln!(out, "        {}", body)?;
+
+        out += "    }\n";
+
+        Ok(out)
+    }
+}
+
+fn primitive(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "bool" => "bool",
+        "char" | "unsigned char" | "uint8_t" => "u8",
+        "signed char" | "int8_t" => "i8",
+        "short" => "c_short",
+        "unsigned short" => "c_ushort",
+        "int" => "c_int",
+        "unsigned" | "unsigned int" => "c_uint",
+        "long" => "c_long",
+        "unsigned long" => "c_ulong",
+        "long long" => "c_longlong",
+        "unsigned long long" => "c_ulonglong",
+        "int16_t" => "i16",
+        "uint16_t" => "u16",
+        "int32_t" => "i32",
+        "uint32_t" => "u32",
+        "int64_t" => "i64",
+        "uint64_t" => "u64",
+        "size_t" | "uintptr_t" => "usize",
+        "ssize_t" | "intptr_t" | "ptrdiff_t" => "isize",
+        "float" => "f32",
+        "double" => "f64",
+        _ => return None,
+    })
+}
+
+fn arg_name(name: &str, idx: usize) -> String {
+    if name.is_empty() {
+        format!("arg{}", idx)
+    } else {
+        zig_ident(name)
+    }
+}
+
+fn camel_case(name: &str) -> String {
+    let mut ret = String::new();
+    let mut upper = false;
+
+    for c in name.chars() {
+        match c {
+            '_' if !ret.is_empty() => upper = true,
+            c if upper => {
+                ret.extend(c.to_uppercase());
+                upper = false;
+            }
+            c => ret.push(c),
+        }
+    }
+
+    ret
+}
+
+/// Escape names that are reserved in Zig.
+fn zig_ident(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "addrspace",
+        "align",
+        "allowzero",
+        "and",
+        "anyframe",
+        "anytype",
+        "asm",
+        "async",
+        "await",
+        "break",
+        "callconv",
+        "catch",
+        "comptime",
+        "const",
+        "continue",
+        "defer",
+        "else",
+        "enum",
+        "errdefer",
+        "error",
+        "export",
+        "extern",
+        "fn",
+        "for",
+        "if",
+        "inline",
+        "linksection",
+        "noalias",
+        "noinline",
+        "nosuspend",
+        "opaque",
+        "or",
+        "orelse",
+        "packed",
+        "pub",
+        "resume",
+        "return",
+        "struct",
+        "suspend",
+        "switch",
+        "test",
+        "threadlocal",
+        "try",
+        "union",
+        "unreachable",
+        "usingnamespace",
+        "var",
+        "volatile",
+        "while",
+        "bool",
+        "type",
+        "void",
+        "anyopaque",
+        "null",
+        "undefined",
+        "true",
+        "false",
+        "isize",
+        "usize",
+    ];
+
+    let is_int = name.len() > 1
+        && (name.starts_with('u') || name.starts_with('i'))
+        && name[1..].chars().all(|c| c.is_ascii_digit());
+
+    if KEYWORDS.contains(&name) || is_int {
+        format!("@\"{}\"", name)
+    } else {
+        name.to_string()
+    }
+}
+
+const PREAMBLE: &str = r#"// CGlue bindings.
+//
+// Generated by cglue-bindgen, do not edit.
+
+const std = @import("std");
+
+/// Error of functions that return their result through `ok_out`.
+pub const Error = error{CGlueFailure};
+
+/// C calling convention, spelled `.c` since Zig 0.14, and `.C` before.
+pub const callconv_c: std.builtin.CallingConvention = if (@hasDecl(std.builtin.CallingConvention, "c")) .c else .C;
+
+pub fn CBox(comptime T: type) type {
+    return extern struct {
+        instance: ?*T,
+        drop_fn: ?*const fn (?*T) callconv(callconv_c) void,
+        free_fn: ?*const fn (?*T) callconv(callconv_c) void,
+
+        pub fn deinit(self: *@This()) void {
+            if (self.drop_fn) |drop| {
+                if (self.instance) |instance| drop(instance);
+            }
+            self.instance = null;
+        }
+    };
+}
+
+pub fn CArc(comptime T: type) type {
+    return extern struct {
+        instance: ?*const T,
+        clone_fn: ?*const fn (?*const T) callconv(callconv_c) ?*const T,
+        drop_fn: ?*const fn (?*const T) callconv(callconv_c) void,
+
+        pub fn clone(self: *const @This()) @This() {
+            var ret = self.*;
+            if (self.clone_fn) |clone_fn| ret.instance = clone_fn(self.instance);
+            return ret;
+        }
+
+        pub fn deinit(self: *@This()) void {
+            if (self.drop_fn) |drop| {
+                if (self.instance) |instance| drop(instance);
+            }
+            self.instance = null;
+        }
+    };
+}
+
+pub fn CSliceRef(comptime T: type) type {
+    return extern struct {
+        data: ?[*]const T,
+        len: usize,
+
+        pub fn init(items: []const T) @This() {
+            return .{ .data = items.ptr, .len = items.len };
+        }
+
+        pub fn slice(self: @This()) []const T {
+            return if (self.data) |data| data[0..self.len] else &[_]T{};
+        }
+    };
+}
+
+pub fn CSliceMut(comptime T: type) type {
+    return extern struct {
+        data: ?[*]T,
+        len: usize,
+
+        pub fn init(items: []T) @This() {
+            return .{ .data = items.ptr, .len = items.len };
+        }
+
+        pub fn slice(self: @This()) []T {
+            return if (self.data) |data| data[0..self.len] else &[_]T{};
+        }
+    };
+}
+
+pub fn Callback(comptime T: type) type {
+    return extern struct {
+        context: ?*anyopaque,
+        func: ?*const fn (?*anyopaque, T) callconv(callconv_c) bool,
+
+        /// Build a callback calling `func` with the `context` pointer. Returning `false` from
+        /// `func` stops the iteration.
+        pub fn init(context: anytype, comptime func: fn (@TypeOf(context), T) bool) @This() {
+            const Context = @TypeOf(context);
+            const Trampoline = struct {
+                fn call(ctx: ?*anyopaque, value: T) callconv(callconv_c) bool {
+                    const c: Context = @ptrCast(@alignCast(ctx));
+                    return func(c, value);
+                }
+            };
+            return .{ .context = @ptrCast(@constCast(context)), .func = &Trampoline.call };
+        }
+
+        /// Build a callback that appends all values to a managed list (`std.ArrayList(T)` before
+        /// Zig 0.15, `std.array_list.Managed(T)` since). Stops on allocation failure.
+        pub fn collect(list: anytype) @This() {
+            const List = @TypeOf(list);
+            return init(list, struct {
+                fn push(l: List, value: T) bool {
+                    l.append(value) catch return false;
+                    return true;
+                }
+            }.push);
+        }
+    };
+}
+
+pub fn CIterator(comptime T: type) type {
+    return extern struct {
+        iter: ?*anyopaque,
+        func: ?*const fn (?*anyopaque, *T) callconv(callconv_c) i32,
+
+        /// Build an iterator out of a pointer to anything with a `next() ?T` function.
+        pub fn init(iter: anytype) @This() {
+            const Iter = @TypeOf(iter);
+            const Trampoline = struct {
+                fn call(it: ?*anyopaque, out: *T) callconv(callconv_c) i32 {
+                    const i: Iter = @ptrCast(@alignCast(it));
+                    if (i.next()) |value| {
+                        out.* = value;
+                        return 0;
+                    }
+                    return 1;
+                }
+            };
+            return .{ .iter = @ptrCast(iter), .func = &Trampoline.call };
+        }
+
+        /// Iterator over the values of a slice.
+        pub const Slice = struct {
+            items: []const T,
+            pos: usize = 0,
+
+            pub fn next(self: *@This()) ?T {
+                if (self.pos >= self.items.len) return null;
+                self.pos += 1;
+                return self.items[self.pos - 1];
+            }
+        };
+    };
+}
+"#;
@@ -1,8 +1,23 @@
+use crate::types::Result;
 use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub default_container: Option<String>,
     pub default_context: Option<String>,
     pub function_prefix: Option<String>,
+    pub library_name: Option<String>,
+}
+
+impl Config {
+    /// Load the configuration from a toml file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut f = File::open(path)?;
+        let mut val = vec![];
+        f.read_to_end(&mut val)?;
+        Ok(toml::from_str(std::str::from_utf8(&val)?)?)
+    }
 }
@@ -0,0 +1,1083 @@
+//! Structured model of cbindgen headers.
+//!
+//! Instead of matching the exact text cbindgen emits, C and C++ transformations look up structs,
+//! typedefs and their fields in this model. Every item remembers its location in the source header,
+//! so that it can then be removed, replaced or extended through [`Edits`], regardless of how the
+//! surrounding code (or its documentation) is formatted.
+
+use crate::types::{parse_arguments, Result};
+use std::ops::Range;
+
+/// Parsed header.
+#[derive(Debug, Clone)]
+pub struct Header<'a> {
+    pub source: &'a str,
+    pub items: Vec<Item>,
+}
+
+/// Top level item of the header.
+#[derive(Debug, Clone)]
+pub struct Item {
+    /// Span of the whole item, including its documentation comment.
+    pub span: Range<usize>,
+    /// Span of the declaration, excluding the documentation comment.
+    pub decl: Range<usize>,
+    pub template: Option<Template>,
+    pub kind: ItemKind,
+}
+
+/// C++ template parameters of an item.
+#[derive(Debug, Clone)]
+pub struct Template {
+    /// Span of the parameter list, excluding the angle brackets.
+    pub span: Range<usize>,
+    pub params: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ItemKind {
+    Struct(Struct),
+    Typedef(Typedef),
+    /// Preprocessor directive, such as `#include <stdint.h>`.
+    Directive(String),
+    /// Opening of `extern "C"`, or `namespace` block.
+    ScopeStart,
+    /// Closing of `extern "C"`, or `namespace` block.
+    ScopeEnd,
+    /// Function declaration, such as `void do_thing(const char *name);`.
+    Function(Function),
+    /// Any other declaration (function definitions, enums, unions, constants).
+    Other,
+}
+
+/// Struct declaration, or definition.
+///
+/// Covers `struct A { ... };`, `typedef struct A { ... } B;`, and `struct A;`.
+#[derive(Debug, Clone)]
+pub struct Struct {
+    /// Name of the struct, including specialization arguments, if any.
+    pub name: String,
+    /// Name given by the typedef.
+    pub alias: Option<String>,
+    pub body: Option<Body>,
+}
+
+/// Typedef, or `using` type alias.
+#[derive(Debug, Clone)]
+pub struct Typedef {
+    pub name: String,
+    pub target: String,
+}
+
+/// Function declaration.
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub name: String,
+    pub ret: String,
+    /// Arguments of the function, as `(type, name)` pairs.
+    pub args: Vec<(String, String)>,
+}
+
+/// Body of a struct definition.
+#[derive(Debug, Clone)]
+pub struct Body {
+    /// Span between the curly braces.
+    pub span: Range<usize>,
+    pub fields: Vec<Field>,
+}
+
+/// Single field of a struct.
+#[derive(Debug, Clone)]
+pub struct Field {
+    /// Span from the end of the previous field to the semicolon of this one.
+    ///
+    /// This includes the field's indentation and documentation, so that removing the span removes
+    /// the field cleanly.
+    pub span: Range<usize>,
+    pub name: String,
+    /// Type of the field, or return type for function pointers.
+    pub ty: String,
+    /// Arguments of a function pointer field, as `(type, name)` pairs.
+    pub args: Option<Vec<(String, String)>>,
+}
+
+impl Item {
+    pub fn as_struct(&self) -> Option<&Struct> {
+        match &self.kind {
+            ItemKind::Struct(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_typedef(&self) -> Option<&Typedef> {
+        match &self.kind {
+            ItemKind::Typedef(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    pub fn as_function(&self) -> Option<&Function> {
+        match &self.kind {
+            ItemKind::Function(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    pub fn template_params(&self) -> &[String] {
+        self.template
+            .as_ref()
+            .map(|t| t.params.as_slice())
+            .unwrap_or(&[])
+    }
+}
+
+impl Struct {
+    pub fn fields(&self) -> &[Field] {
+        self.body
+            .as_ref()
+            .map(|b| b.fields.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields().iter().find(|f| f.name == name)
+    }
+
+    /// Position right after the last field, or the opening brace, if there are no fields.
+    pub fn fields_end(&self) -> Option<usize> {
+        let body = self.body.as_ref()?;
+        Some(
+            body.fields
+                .last()
+                .map(|f| f.span.end)
+                .unwrap_or(body.span.start),
+        )
+    }
+
+    /// Whether all fields of the struct are function pointers.
+    ///
+    /// The `descriptor` field of vtables, pointing to the trait's descriptor, is not taken into
+    /// account.
+    pub fn is_fn_table(&self) -> bool {
+        self.body.is_some()
+            && self
+                .fields()
+                .iter()
+                .all(|f| f.args.is_some() || f.name == "descriptor")
+    }
+}
+
+impl Field {
+    pub fn is_fn_ptr(&self) -> bool {
+        self.args.is_some()
+    }
+}
+
+impl<'a> Header<'a> {
+    pub fn parse(source: &'a str) -> Result<Self> {
+        let src = source.as_bytes();
+        let mut items = vec![];
+        let mut comment: Option<Range<usize>> = None;
+        let mut pos = 0;
+
+        while pos < src.len() {
+            let c = src[pos];
+
+            if c.is_ascii_whitespace() {
+                pos += 1;
+            } else if let Some(end) = comment_end(src, pos) {
+                comment = match comment {
+                    Some(c) if is_adjacent(source, c.end, pos) => Some(c.start..end),
+                    _ => Some(pos..end),
+                };
+                pos = end;
+            } else if c == b'#' {
+                let end = directive_end(src, pos);
+                items.push(Item::new(
+                    pos..end,
+                    None,
+                    ItemKind::Directive(normalize(&source[pos..end])),
+                ));
+                comment = None;
+                pos = end;
+            } else if c == b'}' {
+                let mut end = pos + 1;
+                if src.get(end) == Some(&b';') {
+                    end += 1;
+                }
+                items.push(Item::new(pos..end, None, ItemKind::ScopeEnd));
+                comment = None;
+                pos = end;
+            } else {
+                let (end, opens_scope) = statement_end(source, pos)?;
+
+                let comment = comment
+                    .take()
+                    .filter(|c| is_adjacent(source, c.end, pos))
+                    .map(|c| c.start);
+
+                if opens_scope {
+                    items.push(Item::new(pos..end, None, ItemKind::ScopeStart));
+                } else {
+                    items.push(parse_item(source, comment, pos..end)?);
+                }
+
+                pos = end;
+            }
+        }
+
+        Ok(Self { source, items })
+    }
+
+    pub fn text(&self, span: Range<usize>) -> &'a str {
+        &self.source[span]
+    }
+
+    pub fn structs(&self) -> impl Iterator<Item = (&Item, &Struct)> {
+        self.items
+            .iter()
+            .filter_map(|i| i.as_struct().map(|s| (i, s)))
+    }
+
+    pub fn typedefs(&self) -> impl Iterator<Item = (&Item, &Typedef)> {
+        self.items
+            .iter()
+            .filter_map(|i| i.as_typedef().map(|t| (i, t)))
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = (&Item, &Function)> {
+        self.items
+            .iter()
+            .filter_map(|i| i.as_function().map(|f| (i, f)))
+    }
+
+    pub fn directives(&self) -> impl Iterator<Item = (&Item, &str)> {
+        self.items.iter().filter_map(|i| match &i.kind {
+            ItemKind::Directive(d) => Some((i, d.as_str())),
+            _ => None,
+        })
+    }
+
+    /// Find struct definition (one with a body) by its name.
+    pub fn find_struct(&self, name: &str) -> Option<(&Item, &Struct)> {
+        self.structs()
+            .find(|(_, s)| s.name == name && s.body.is_some())
+    }
+
+    /// Check whether a type with given name is declared in any way.
+    pub fn has_type(&self, name: &str) -> bool {
+        self.items.iter().any(|i| match &i.kind {
+            ItemKind::Struct(s) => s.name == name || s.alias.as_deref() == Some(name),
+            ItemKind::Typedef(t) => t.name == name,
+            _ => false,
+        })
+    }
+
+    /// Span of the item, including the line breaks surrounding it.
+    ///
+    /// Removing this span does not leave an extra blank line in place of the item.
+    pub fn line_span(&self, item: &Item) -> Range<usize> {
+        let src = self.source.as_bytes();
+
+        let start = match item.span.start.checked_sub(1) {
+            Some(start) if src[start] == b'\n' => start,
+            _ => item.span.start,
+        };
+
+        let end = match src.get(item.span.end) {
+            Some(b'\n') => item.span.end + 1,
+            _ => item.span.end,
+        };
+
+        start..end
+    }
+
+    /// Whether this is a C++ header.
+    ///
+    /// C++ headers contain templates, `using` aliases, or namespaces, or include the C++ versions
+    /// of the C standard headers, such as `<cstdint>`.
+    pub fn is_cpp(&self) -> bool {
+        self.items.iter().any(|i| {
+            let decl = self.text(i.decl.clone());
+            i.template.is_some()
+                || match &i.kind {
+                    ItemKind::Typedef(_) => decl.starts_with("using"),
+                    ItemKind::ScopeStart => decl.starts_with("namespace"),
+                    ItemKind::Directive(d) => d.starts_with("#include <c") && !d.ends_with(".h>"),
+                    _ => false,
+                }
+        })
+    }
+
+    /// Start of the line after the leading block of preprocessor directives (include guard,
+    /// includes).
+    pub fn preamble_end(&self) -> usize {
+        self.items
+            .iter()
+            .take_while(|i| matches!(i.kind, ItemKind::Directive(_)))
+            .last()
+            .map(|i| self.line_span(i).end)
+            .unwrap_or(0)
+    }
+}
+
+impl Item {
+    fn new(span: Range<usize>, template: Option<Template>, kind: ItemKind) -> Self {
+        Self {
+            decl: span.clone(),
+            span,
+            template,
+            kind,
+        }
+    }
+}
+
+/// Set of pending modifications to the header source.
+#[derive(Debug, Default)]
+pub struct Edits {
+    edits: Vec<(Range<usize>, String)>,
+}
+
+impl Edits {
+    pub fn replace(&mut self, span: Range<usize>, text: impl Into<String>) {
+        self.edits.push((span, text.into()));
+    }
+
+    pub fn remove(&mut self, span: Range<usize>) {
+        self.replace(span, "");
+    }
+
+    /// Insert text at given position.
+    ///
+    /// Multiple insertions at the same position are applied in the order they were made.
+    pub fn insert(&mut self, pos: usize, text: impl Into<String>) {
+        self.replace(pos..pos, text);
+    }
+
+    /// Apply all edits to the source.
+    ///
+    /// Edits that overlap an already applied edit are an error.
+    pub fn apply(mut self, source: &str) -> Result<String> {
+        self.edits.sort_by_key(|(span, _)| span.start);
+
+        let mut out = String::with_capacity(source.len());
+        let mut pos = 0;
+
+        for (span, text) in self.edits {
+            if span.start < pos {
+                return Err(format!("Overlapping header edits at {}", span.start).into());
+            }
+            out.push_str(&source[pos..span.start]);
+            out.push_str(&text);
+            pos = span.end;
+        }
+
+        out.push_str(&source[pos..]);
+
+        Ok(out)
+    }
+}
+
+/// Map every identifier of the text through `f`.
+///
+/// Identifiers for which `f` returns `None`, as well as comments, are left unchanged.
+pub fn map_idents(text: &str, mut f: impl FnMut(&str) -> Option<String>) -> String {
+    let src = text.as_bytes();
+    let is_ident = |c: u8| c.is_ascii_alphanumeric() || c == b'_';
+    let mut out = String::with_capacity(text.len());
+    let mut pos = 0;
+
+    while pos < src.len() {
+        if let Some(end) = comment_end(src, pos) {
+            out.push_str(&text[pos..end]);
+            pos = end;
+        } else if is_ident(src[pos]) {
+            let end = src[pos..]
+                .iter()
+                .position(|&c| !is_ident(c))
+                .map(|p| pos + p)
+                .unwrap_or(src.len());
+            let ident = &text[pos..end];
+            match f(ident) {
+                Some(mapped) => out.push_str(&mapped),
+                None => out.push_str(ident),
+            }
+            pos = end;
+        } else {
+            let c = text[pos..].chars().next().unwrap();
+            out.push(c);
+            pos += c.len_utf8();
+        }
+    }
+
+    out
+}
+
+/// Collapse all whitespace sequences into single spaces.
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Remove comments and preprocessor lines from a declaration.
+fn strip_comments(text: &str) -> String {
+    let src = text.as_bytes();
+    let mut out = String::new();
+    let mut pos = 0;
+    let mut line_start = true;
+
+    while pos < src.len() {
+        if let Some(end) = comment_end(src, pos) {
+            out.push(' ');
+            pos = end;
+        } else if line_start && src[pos] == b'#' {
+            pos = directive_end(src, pos);
+        } else {
+            let c = text[pos..].chars().next().unwrap();
+            if c == '\n' {
+                line_start = true;
+            } else if !c.is_whitespace() {
+                line_start = false;
+            }
+            out.push(c);
+            pos += c.len_utf8();
+        }
+    }
+
+    normalize(&out)
+}
+
+/// Whether the text between 2 positions contains no blank lines.
+fn is_adjacent(source: &str, start: usize, end: usize) -> bool {
+    source[start..end].matches('\n').count() <= 1
+}
+
+/// End of a comment starting at `pos`, if there is one.
+fn comment_end(src: &[u8], pos: usize) -> Option<usize> {
+    match src.get(pos..pos + 2)? {
+        b"/*" => Some(
+            src[pos + 2..]
+                .windows(2)
+                .position(|w| w == b"*/")
+                .map(|p| pos + 2 + p + 2)
+                .unwrap_or(src.len()),
+        ),
+        b"//" => Some(line_end(src, pos)),
+        _ => None,
+    }
+}
+
+/// Position of the newline ending the line at `pos`, or end of source.
+fn line_end(src: &[u8], pos: usize) -> usize {
+    src[pos..]
+        .iter()
+        .position(|&c| c == b'\n')
+        .map(|p| pos + p)
+        .unwrap_or(src.len())
+}
+
+/// End of a preprocessor directive, taking line continuations into account.
+fn directive_end(src: &[u8], mut pos: usize) -> usize {
+    loop {
+        let end = line_end(src, pos);
+        if end > 0 && end < src.len() && src[end - 1] == b'\\' {
+            pos = end + 1;
+        } else {
+            break end;
+        }
+    }
+}
+
+/// End of a string, or character literal starting at `pos`.
+fn literal_end(src: &[u8], pos: usize) -> usize {
+    let quote = src[pos];
+    let mut i = pos + 1;
+    while i < src.len() && src[i] != quote {
+        if src[i] == b'\\' {
+            i += 1;
+        }
+        i += 1;
+    }
+    (i + 1).min(src.len())
+}
+
+/// Find the end of the statement starting at `start`.
+///
+/// Returns the position right after the terminating semicolon, and whether the statement instead
+/// opens a scope (`extern "C" {`, `namespace a {`).
+fn statement_end(source: &str, start: usize) -> Result<(usize, bool)> {
+    let src = source.as_bytes();
+    let mut depth = 0i32;
+    let mut pos = start;
+    let mut line_start = false;
+    let mut is_function = false;
+
+    while pos < src.len() {
+        let c = src[pos];
+
+        if let Some(end) = comment_end(src, pos) {
+            pos = end;
+            continue;
+        }
+
+        match c {
+            b'#' if line_start => {
+                pos = directive_end(src, pos);
+                continue;
+            }
+            b'"' | b'\'' => {
+                pos = literal_end(src, pos);
+                line_start = false;
+                continue;
+            }
+            b'{' => {
+                if depth == 0 {
+                    let head = strip_comments(&source[start..pos]);
+                    if head.starts_with("extern") || head.starts_with("namespace") {
+                        return Ok((pos + 1, true));
+                    }
+                    is_function = is_function_head(&head);
+                }
+                depth += 1;
+            }
+            b'(' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                // Function definitions are not terminated by a semicolon.
+                if depth == 0 && is_function {
+                    return Ok((pos + 1, false));
+                }
+            }
+            b')' => depth -= 1,
+            b';' if depth == 0 => return Ok((pos + 1, false)),
+            _ => {}
+        }
+
+        if c == b'\n' {
+            line_start = true;
+        } else if !c.is_ascii_whitespace() {
+            line_start = false;
+        }
+
+        pos += 1;
+    }
+
+    Err(format!(
+        "Unterminated declaration: {}",
+        normalize(&source[start..(start + 64).min(source.len())])
+    )
+    .into())
+}
+
+/// Whether the declaration before the opening brace is that of a function.
+fn is_function_head(head: &str) -> bool {
+    let head = match head.strip_prefix("template") {
+        Some(rest) => {
+            let mut depth = 0;
+            let end = rest.find(|c| {
+                match c {
+                    '<' => depth += 1,
+                    '>' => depth -= 1,
+                    _ => return false,
+                }
+                depth == 0
+            });
+            end.map(|e| rest[(e + 1)..].trim()).unwrap_or(rest)
+        }
+        None => head,
+    };
+
+    let is_type = head
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .map(|kw| ["struct", "class", "union", "enum", "typedef"].contains(&kw))
+        .unwrap_or(false);
+
+    !is_type && head.contains('(')
+}
+
+/// Simple cursor over a declaration.
+struct Cursor<'a> {
+    source: &'a str,
+    pos: usize,
+    end: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn skip_ws(&mut self) {
+        let src = self.source.as_bytes();
+        while self.pos < self.end {
+            if src[self.pos].is_ascii_whitespace() {
+                self.pos += 1;
+            } else if let Some(end) = comment_end(src, self.pos) {
+                self.pos = end.min(self.end);
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_ws();
+        if self.pos < self.end {
+            Some(self.source.as_bytes()[self.pos])
+        } else {
+            None
+        }
+    }
+
+    fn ident(&mut self) -> Option<&'a str> {
+        self.skip_ws();
+        let start = self.pos;
+        let src = self.source.as_bytes();
+        while self.pos < self.end
+            && (src[self.pos].is_ascii_alphanumeric() || src[self.pos] == b'_')
+        {
+            self.pos += 1;
+        }
+        if self.pos > start {
+            Some(&self.source[start..self.pos])
+        } else {
+            None
+        }
+    }
+
+    /// Skip a bracketed group starting at the current position, returning the span of its contents.
+    fn group(&mut self, open: u8, close: u8) -> Result<Range<usize>> {
+        let src = self.source.as_bytes();
+
+        if self.peek() != Some(open) {
+            return Err(format!("Expected '{}'", open as char).into());
+        }
+
+        let start = self.pos + 1;
+        let mut depth = 0;
+
+        while self.pos < self.end {
+            if let Some(end) = comment_end(src, self.pos) {
+                self.pos = end;
+                continue;
+            }
+
+            match src[self.pos] {
+                b'"' | b'\'' => {
+                    self.pos = literal_end(src, self.pos);
+                    continue;
+                }
+                c if c == open => depth += 1,
+                c if c == close => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.pos += 1;
+                        return Ok(start..(self.pos - 1));
+                    }
+                }
+                _ => {}
+            }
+
+            self.pos += 1;
+        }
+
+        Err(format!("Unbalanced '{}'", open as char).into())
+    }
+
+    /// Remaining text of the declaration, without the final semicolon.
+    fn rest(&mut self) -> String {
+        let text = strip_comments(&self.source[self.pos..self.end]);
+        text.trim_end_matches(';').trim().to_string()
+    }
+}
+
+fn parse_item(source: &str, comment: Option<usize>, decl: Range<usize>) -> Result<Item> {
+    let mut cursor = Cursor {
+        source,
+        pos: decl.start,
+        end: decl.end,
+    };
+
+    let mut template = None;
+    let mut word = cursor.ident();
+
+    if word == Some("template") {
+        let span = cursor.group(b'<', b'>')?;
+        template = Some(Template {
+            params: split_params(&source[span.clone()]),
+            span,
+        });
+        word = cursor.ident();
+    }
+
+    let kind = match word {
+        Some("typedef") => {
+            let checkpoint = cursor.pos;
+            if cursor.ident() == Some("struct") {
+                parse_struct(&mut cursor, true)?
+            } else {
+                cursor.pos = checkpoint;
+                let rest = cursor.rest();
+                match rest.rsplit(&[' ', '*'][..]).next() {
+                    Some(name) if !rest.contains('(') && !rest.contains('{') => {
+                        ItemKind::Typedef(Typedef {
+                            name: name.to_string(),
+                            target: rest[..(rest.len() - name.len())].trim().to_string(),
+                        })
+                    }
+                    _ => ItemKind::Other,
+                }
+            }
+        }
+        Some("struct") => parse_struct(&mut cursor, false)?,
+        Some("using") => {
+            let name = cursor.ident().unwrap_or_default().to_string();
+            if cursor.peek() == Some(b'=') {
+                cursor.pos += 1;
+                ItemKind::Typedef(Typedef {
+                    name,
+                    target: cursor.rest(),
+                })
+            } else {
+                ItemKind::Other
+            }
+        }
+        Some(_) => {
+            cursor.pos = decl.start;
+            parse_function(&cursor.rest()).unwrap_or(ItemKind::Other)
+        }
+        None => ItemKind::Other,
+    };
+
+    Ok(Item {
+        span: comment.unwrap_or(decl.start)..decl.end,
+        decl,
+        template,
+        kind,
+    })
+}
+
+fn parse_struct(cursor: &mut Cursor, typedef: bool) -> Result<ItemKind> {
+    let mut name = cursor.ident().ok_or("Expected struct name")?.to_string();
+
+    // Skip attributes, such as `alignas(8)`.
+    while cursor.peek() == Some(b'(') {
+        cursor.group(b'(', b')')?;
+        name = cursor.ident().ok_or("Expected struct name")?.to_string();
+    }
+
+    if cursor.peek() == Some(b'<') {
+        let span = cursor.group(b'<', b'>')?;
+        name = format!("{}<{}>", name, normalize(&cursor.source[span]));
+    }
+
+    let body = if cursor.peek() == Some(b'{') {
+        let span = cursor.group(b'{', b'}')?;
+        Some(Body {
+            fields: parse_fields(cursor.source, span.clone())?,
+            span,
+        })
+    } else {
+        None
+    };
+
+    let rest = cursor.rest();
+
+    Ok(match (typedef, body) {
+        (true, None) => ItemKind::Typedef(Typedef {
+            name: rest,
+            target: format!("struct {}", name),
+        }),
+        (_, body) => ItemKind::Struct(Struct {
+            name,
+            alias: Some(rest).filter(|r| !r.is_empty()),
+            body,
+        }),
+    })
+}
+
+fn parse_function(decl: &str) -> Option<ItemKind> {
+    if decl.contains('{') || decl.starts_with("static ") {
+        return None;
+    }
+
+    let decl = decl.strip_prefix("extern ").unwrap_or(decl);
+
+    let args_start = decl.find('(')?;
+    let head = decl[..args_start].trim();
+    let args = decl[(args_start + 1)..].strip_suffix(')')?.trim();
+
+    if args.contains('(') {
+        return None;
+    }
+
+    let name = head.rsplit(&[' ', '*'][..]).next()?;
+
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+
+    let args = if args.is_empty() || args == "void" {
+        vec![]
+    } else {
+        parse_arguments(args)
+            .map(|(ty, name)| (ty.to_string(), name.to_string()))
+            .collect()
+    };
+
+    Some(ItemKind::Function(Function {
+        name: name.to_string(),
+        ret: head[..(head.len() - name.len())].trim().to_string(),
+        args,
+    }))
+}
+
+/// Split comma separated parameters, ignoring commas within nested brackets.
+fn split_params(params: &str) -> Vec<String> {
+    let mut ret = vec![];
+    let mut depth = 0;
+    let mut cur = String::new();
+
+    for c in params.chars() {
+        match c {
+            '<' | '(' => depth += 1,
+            '>' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                ret.push(normalize(&cur));
+                cur.clear();
+                continue;
+            }
+            _ => {}
+        }
+        cur.push(c);
+    }
+
+    if !cur.trim().is_empty() {
+        ret.push(normalize(&cur));
+    }
+
+    ret
+}
+
+fn parse_fields(source: &str, body: Range<usize>) -> Result<Vec<Field>> {
+    let mut fields = vec![];
+    let mut field_start = body.start;
+
+    loop {
+        let (end, _) = match statement_end(&source[..body.end], field_start) {
+            Ok(v) => v,
+            // No more terminated fields in the body.
+            Err(_) => break,
+        };
+
+        let decl = strip_comments(&source[field_start..(end - 1)]);
+
+        if let Some(field) = parse_field(&decl, field_start..end) {
+            fields.push(field);
+        }
+
+        field_start = end;
+    }
+
+    Ok(fields)
+}
+
+fn parse_field(decl: &str, span: Range<usize>) -> Option<Field> {
+    if let Some(idx) = decl.find("(*") {
+        let ret = decl[..idx].trim();
+        let rest = &decl[(idx + 2)..];
+        let name_end = rest.find(')')?;
+        let name = rest[..name_end].trim();
+        let args = rest[(name_end + 1)..].trim();
+        let args = args.strip_prefix('(')?.strip_suffix(')')?.trim();
+
+        let args = if args == "void" {
+            vec![]
+        } else {
+            parse_arguments(args)
+                .map(|(ty, name)| (ty.to_string(), name.to_string()))
+                .collect()
+        };
+
+        Some(Field {
+            span,
+            name: name.to_string(),
+            ty: ret.to_string(),
+            args: Some(args),
+        })
+    } else {
+        let (decl, array) = match decl.rfind('[') {
+            Some(idx) if decl.ends_with(']') => (decl[..idx].trim(), &decl[idx..]),
+            _ => (decl, ""),
+        };
+
+        let name = decl.rsplit(&[' ', '*', '&'][..]).next()?;
+
+        if name.is_empty() {
+            return None;
+        }
+
+        Some(Field {
+            span,
+            name: name.to_string(),
+            ty: format!("{}{}", decl[..(decl.len() - name.len())].trim(), array),
+            args: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const C_HEADER: &str = r#"#ifndef PLUGIN_H
+#define PLUGIN_H
+
+#include <stdint.h>
+
+/**
+ * Type definition for temporary return value wrapping storage.
+ */
+typedef struct MainFeatureRetTmp_CArc_c_void MainFeatureRetTmp_CArc_c_void;
+
+// CGlue vtable for trait MainFeature.
+typedef struct MainFeatureVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void {
+    void (*print_self)(const struct CGlueObjContainer_CBox_c_void_____CArc_c_void *cont);
+    /* Comments get stripped */
+    int32_t (*add)(struct CGlueObjContainer_CBox_c_void_____CArc_c_void *cont, int32_t a, int32_t b);
+    const struct TraitDescriptor *descriptor;
+} MainFeatureVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void;
+
+typedef struct Bytes {
+    uint8_t data[16];
+    const char *name;
+} Bytes;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+void do_thing(const char *name);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* PLUGIN_H */
+"#;
+
+    const CPP_HEADER: &str = r"#include <cstdint>
+
+template<typename T>
+struct MaybeUninit;
+
+template<typename CGlueC>
+struct MainFeatureVtbl {
+    void (*print_self)(const CGlueC *cont);
+};
+
+template<typename CGlueInst, typename CGlueCtx>
+using FeaturesGroupBox = FeaturesGroup<CBox<void>, CGlueCtx>;
+";
+
+    #[test]
+    fn parse_c() {
+        let header = Header::parse(C_HEADER).unwrap();
+
+        let (item, zst) = header.typedefs().next().unwrap();
+        assert_eq!(zst.name, "MainFeatureRetTmp_CArc_c_void");
+        assert_eq!(zst.target, "struct MainFeatureRetTmp_CArc_c_void");
+        assert!(header.text(item.span.clone()).starts_with("/**"));
+
+        let (item, vtbl) = header
+            .find_struct("MainFeatureVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void")
+            .unwrap();
+        assert!(header
+            .text(item.span.clone())
+            .starts_with("// CGlue vtable"));
+        assert!(vtbl.is_fn_table());
+        assert!(!vtbl.fields()[2].is_fn_ptr());
+        assert_eq!(vtbl.fields()[0].name, "print_self");
+        assert_eq!(vtbl.fields()[1].ty, "int32_t");
+        assert_eq!(
+            vtbl.fields()[1].args.as_ref().unwrap()[1],
+            ("int32_t".to_string(), "a".to_string())
+        );
+
+        let (_, bytes) = header.find_struct("Bytes").unwrap();
+        assert_eq!(bytes.alias.as_deref(), Some("Bytes"));
+        assert_eq!(bytes.field("data").unwrap().ty, "uint8_t[16]");
+        assert_eq!(bytes.field("name").unwrap().ty, "const char *");
+
+        let (_, func) = header.functions().next().unwrap();
+        assert_eq!(func.name, "do_thing");
+        assert_eq!(func.ret, "void");
+        assert_eq!(
+            func.args,
+            [("const char *".to_string(), "name".to_string())]
+        );
+
+        assert!(header
+            .items
+            .iter()
+            .any(|i| matches!(i.kind, ItemKind::ScopeStart)));
+        assert!(header
+            .items
+            .iter()
+            .any(|i| matches!(i.kind, ItemKind::ScopeEnd)));
+        assert_eq!(
+            header.directives().last().unwrap().1,
+            "#endif /* PLUGIN_H */"
+        );
+        assert_eq!(
+            &C_HEADER[..header.preamble_end()],
+            "#ifndef PLUGIN_H\n#define PLUGIN_H\n\n#include <stdint.h>\n"
+        );
+        assert!(!header.is_cpp());
+    }
+
+    #[test]
+    fn parse_cpp() {
+        let header = Header::parse(CPP_HEADER).unwrap();
+
+        let (item, uninit) = header.structs().next().unwrap();
+        assert_eq!(uninit.name, "MaybeUninit");
+        assert!(uninit.body.is_none());
+        assert_eq!(item.template_params(), ["typename T"]);
+
+        let (item, vtbl) = header.find_struct("MainFeatureVtbl").unwrap();
+        assert_eq!(item.template_params(), ["typename CGlueC"]);
+        assert_eq!(
+            vtbl.fields()[0].args.as_ref().unwrap()[0].0,
+            "const CGlueC *"
+        );
+
+        let (item, using) = header.typedefs().next().unwrap();
+        assert_eq!(using.name, "FeaturesGroupBox");
+        assert_eq!(using.target, "FeaturesGroup<CBox<void>, CGlueCtx>");
+        assert_eq!(item.template_params().len(), 2);
+
+        assert!(header.is_cpp());
+    }
+
+    #[test]
+    fn idents() {
+        let text = "struct A_Context a; /* A_Context */ Context context;";
+        let out = map_idents(text, |i| {
+            i.strip_suffix("Context").map(|i| format!("{}Ctx", i))
+        });
+        assert_eq!(out, "struct A_Ctx a; /* A_Context */ Ctx context;");
+    }
+
+    #[test]
+    fn edits() {
+        let header = Header::parse(C_HEADER).unwrap();
+        let (_, bytes) = header.find_struct("Bytes").unwrap();
+
+        let mut edits = Edits::default();
+        edits.remove(bytes.field("name").unwrap().span.clone());
+        edits.insert(bytes.fields_end().unwrap(), "\n    int b;");
+        edits.insert(bytes.fields_end().unwrap(), "\n    int c;");
+
+        let out = edits.apply(C_HEADER).unwrap();
+        assert!(out.contains(
+            "typedef struct Bytes {\n    uint8_t data[16];\n    int b;\n    int c;\n} Bytes;"
+        ));
+    }
+}
@@ -0,0 +1,310 @@
+//! # cglue-bindgen
+//!
+//! Cleanup cbindgen output for CGlue.
+//!
+//! This crate essentially wraps cbindgen and performs additional header cleanup steps on top for
+//! good out-of-the-box usage. Note that the program expects standard naming convention, and will
+//! likely break if there is any renaming happening in cbindgen config.
+//!
+//! ## Install
+//!
+//! ```sh
+//! cargo install cglue-bindgen
+//! ```
+//!
+//! Also make sure cbindgen is installed (not needed when using the crate as a library):
+//!
+//! ```sh
+//! cargo install cbindgen
+//! ```
+//!
+//! ## Running
+//!
+//! Run similarly to cbindgen:
+//!
+//! ```sh
+//! cglue-bindgen +nightly -- --config cbindgen.toml --crate your_crate --output output_header.h
+//! ```
+//!
+//! ### Other languages
+//!
+//! Bindings for languages other than C and C++ are built on top of the C header. Pass the target
+//! with `-t` before the `--`, and make cbindgen output C:
+//!
+//! ```sh
+//! cglue-bindgen +nightly -t python -- --config cbindgen.toml --crate your_crate --output bindings.py -l C
+//! ```
+//!
+//! Supported targets: `python`, `csharp`, `zig`, `go`.
+//!
+//! ## Using as a library
+//!
+//! Headers can also be generated from a `build.rs` script, without any external tools. Add
+//! `cglue-bindgen` to `[build-dependencies]`, and drive it similarly to `cbindgen::Builder`:
+//!
+//! ```ignore
+//! fn main() {
+//!     let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+//!
+//!     let config = cglue_bindgen::Config {
+//!         default_container: Some("Box".into()),
+//!         default_context: Some("Arc".into()),
+//!         ..Default::default()
+//!     };
+//!
+//!     cglue_bindgen::Builder::new(&crate_dir)
+//!         .with_cbindgen_config(
+//!             cglue_bindgen::cbindgen::Config::from_file("cbindgen.toml").unwrap(),
+//!         )
+//!         .with_config(config)
+//!         .generate()
+//!         .expect("Unable to generate bindings")
+//!         .write_to_file("bindings.h")
+//!         .unwrap();
+//! }
+//! ```
+//!
+//! Note that the `[parse.expand]` option requires a nightly compiler, just like it does with the
+//! cbindgen binary.
+//!
+//! Other languages are selected with `with_target`, for instance `.with_target(Target::Python)`.
+//!
+//! ## Configuring
+//!
+//! Create a `cglue.toml`, and pass `-c cglue.toml` to `cglue-bindgen` before the `--`. When using
+//! the library, load it with `Config::from_file`, or fill in `Config` directly.
+//!
+//! Several values can be set:
+//!
+//! `default_container` - set the default container type. This will make C/C++ code less verbose
+//! for objects that match the container and context types. Supports out-of-the-box:
+//! `Box`, `Mut`, `Ref`.
+//!
+//! `default_context` - set the default context type. This will make C/C++ code less verbose for
+//! objects that match the container and context types. Supports out-of-the-box: `Arc`,
+//! `NoContext`.
+//!
+//! `library_name` - name of the shared library to import functions from, in bindings that
+//! load the library by name (C#), or link against it (Go). Defaults to `cglue` in C#.
+//!
+//! ## Using the bindings
+//!
+//! Check the documentation for the respective language:
+//!
+//! * [C](self::codegen::c)
+//!
+//! * [C++](self::codegen::cpp)
+//!
+//! * [Python](self::codegen::python)
+//!
+//! * [C#](self::codegen::csharp)
+//!
+//! * [Zig](self::codegen::zig)
+//!
+//! * [Go](self::codegen::go)
+//!
+//! You can also check the [code examples](https://github.com/h33p/cglue/tree/main/examples).
+//!
+//! ## In case of an issue
+//!
+//! Please check if any custom cbindgen options are influencing the way the code is generated in
+//! any way. The header is parsed into a structured model of structs, typedefs and their fields,
+//! which relies on the names cbindgen gives to the types. Renaming types will break the code
+//! generation.
+//!
+//! If you still have issues without any custom parameters, please report an issue, because then it
+//! is likely my fault or cbindgen update broke the binding generation.
+//!
+//! Verified to work cbindgen version: `v0.24.5`.
+//!
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+pub mod types;
+use types::Result;
+
+pub mod ir;
+
+pub mod codegen;
+pub use codegen::Target;
+use codegen::{c, cpp, csharp, go, python, zig};
+
+pub mod config;
+pub use config::Config;
+
+pub use cbindgen;
+use cbindgen::Language;
+
+/// Apply CGlue transformations to a header generated by cbindgen.
+///
+/// The language is detected from the contents of the header.
+pub fn process_header(header: &str, config: &Config) -> Result<String> {
+    if cpp::is_cpp(header)? {
+        cpp::parse_header(header, config)
+    } else if c::is_c(header)? {
+        c::parse_header(header, config)
+    } else {
+        Err("Unsupported header format!".into())
+    }
+}
+
+/// Generate bindings for the given target out of a header generated by cbindgen.
+///
+/// Targets other than [`Target::Header`] are built on top of a C header.
+pub fn process_header_for(header: &str, config: &Config, target: Target) -> Result<String> {
+    match target {
+        Target::Header => process_header(header, config),
+        _ if cpp::is_cpp(header)? || !c::is_c(header)? => {
+            Err(format!("{} bindings require a C header", target).into())
+        }
+        Target::Python => python::parse_header(header, config),
+        Target::CSharp => csharp::parse_header(header, config),
+        Target::Zig => zig::parse_header(header, config),
+        Target::Go => go::parse_header(header, config),
+    }
+}
+
+/// Generates CGlue bindings in-process.
+///
+/// This drives a [`cbindgen::Builder`], and applies CGlue transformations on top of its output.
+pub struct Builder {
+    cbindgen: cbindgen::Builder,
+    config: Config,
+    target: Target,
+}
+
+impl Builder {
+    /// Create a builder for the crate located in `crate_dir`.
+    pub fn new<P: AsRef<Path>>(crate_dir: P) -> Self {
+        Self::from_cbindgen(cbindgen::Builder::new().with_crate(crate_dir))
+    }
+
+    /// Create a builder out of a preconfigured cbindgen builder.
+    pub fn from_cbindgen(cbindgen: cbindgen::Builder) -> Self {
+        Self {
+            cbindgen,
+            config: Config::default(),
+            target: Target::default(),
+        }
+    }
+
+    /// Set the CGlue specific configuration.
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Set the cbindgen configuration.
+    ///
+    /// This overrides any cbindgen options that have been set before.
+    pub fn with_cbindgen_config(mut self, config: cbindgen::Config) -> Self {
+        self.cbindgen = self.cbindgen.with_config(config);
+        self
+    }
+
+    /// Set the output language.
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.cbindgen = self.cbindgen.with_language(language);
+        self
+    }
+
+    /// Set the kind of bindings to generate.
+    ///
+    /// Targets other than [`Target::Header`] always run cbindgen with C output.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Modify the underlying cbindgen builder.
+    pub fn with_cbindgen(
+        mut self,
+        func: impl FnOnce(cbindgen::Builder) -> cbindgen::Builder,
+    ) -> Self {
+        self.cbindgen = func(self.cbindgen);
+        self
+    }
+
+    /// Generate the bindings.
+    ///
+    /// Errors produced by cbindgen are returned as [`cbindgen::Error`].
+    pub fn generate(self) -> Result<Bindings> {
+        let cbindgen = match self.target {
+            Target::Header => self.cbindgen,
+            _ => self.cbindgen.with_language(Language::C),
+        };
+
+        let bindings = cbindgen.generate()?;
+
+        let mut out = vec![];
+        bindings.write(&mut out);
+
+        // cbindgen produces no output when it is invoked recursively from a macro expansion build.
+        if out.is_empty() {
+            return Ok(Bindings {
+                header: None,
+                cbindgen_header: None,
+            });
+        }
+
+        let raw = std::str::from_utf8(&out)?;
+
+        let header = match (self.target, bindings.config.language) {
+            (Target::Header, Language::Cxx) => cpp::parse_header(raw, &self.config)?,
+            (Target::Header, Language::C) => c::parse_header(raw, &self.config)?,
+            (Target::Header, Language::Cython) => return Err("Cython is not supported".into()),
+            (target, _) => process_header_for(raw, &self.config, target)?,
+        };
+
+        Ok(Bindings {
+            header: Some(header),
+            cbindgen_header: Some(raw.into()),
+        })
+    }
+}
+
+/// Generated CGlue bindings.
+pub struct Bindings {
+    header: Option<String>,
+    cbindgen_header: Option<String>,
+}
+
+impl Bindings {
+    /// Get the generated header.
+    ///
+    /// This is `None` when generation was skipped, because cbindgen is already running further up
+    /// the stack (when expanding macros).
+    pub fn header(&self) -> Option<&str> {
+        self.header.as_deref()
+    }
+
+    /// Get the header as it was generated by cbindgen, before CGlue transformations.
+    pub fn cbindgen_header(&self) -> Option<&str> {
+        self.cbindgen_header.as_deref()
+    }
+
+    /// Write the bindings to a file.
+    ///
+    /// The file is only written if its contents change, so that the build is not needlessly
+    /// retriggered. Returns whether the file was written.
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<bool> {
+        let header = match &self.header {
+            Some(header) => header,
+            None => return Ok(false),
+        };
+
+        if let Ok(mut file) = File::open(path.as_ref()) {
+            let mut old = vec![];
+            file.read_to_end(&mut old)?;
+            if old == header.as_bytes() {
+                return Ok(false);
+            }
+        }
+
+        File::create(path)?.write_all(header.as_bytes())?;
+
+        Ok(true)
+    }
+}
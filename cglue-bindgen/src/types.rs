@@ -1,4 +1,5 @@
 use crate::config::Config;
+use crate::ir::Field;
 use itertools::Itertools;
 use regex::*;
 use std::collections::{HashMap, HashSet};
@@ -94,6 +95,17 @@ impl<'a> ContextType<'a> {
                     ),
                 },
             ),
+            (
+                "CRc_c_void",
+                ContextType {
+                    ty_prefix: "Rc",
+                    cpp_type: "CRc<void>",
+                    clone_impl: Some("ret.instance = self->clone_fn(self->instance);"),
+                    drop_impl: Some(
+                        "if (self->drop_fn && self->instance) self->drop_fn(self->instance);",
+                    ),
+                },
+            ),
         ]
         .iter()
         .cloned()
@@ -379,54 +391,64 @@ impl<'a> Iterator for ArgsParser<'a> {
             return None;
         }
 
+        let ret = ret.trim();
         let name = ret.rsplit(&['&', '*', ' '][..]).next()?;
+        let ty = ret[..(ret.len() - name.len())].trim();
 
-        Some((ret[..(ret.len() - name.len())].trim(), name.trim()))
+        // Unnamed arguments, such as `struct KeyValue`, consist of the type only.
+        if ty.is_empty() || ty == "const" || ty == "struct" || ty == "const struct" {
+            Some((ret, ""))
+        } else {
+            Some((ty, name))
+        }
     }
 }
 
-fn parse_arguments(args: &str) -> impl Iterator<Item = (&str, &str)> {
+pub fn parse_arguments(args: &str) -> impl Iterator<Item = (&str, &str)> {
     ArgsParser { args }
 }
 
 impl Vtable {
-    pub fn new(name: String, functions_str: &str, container_ty: &str) -> Result<Self> {
+    /// Build the vtable out of function pointer fields that take `container_ty` as first argument.
+    pub fn new(name: String, fields: &[Field], container_ty: &str) -> Self {
         let mut functions = vec![];
 
-        let reg = Regex::new(&format!(
-            r"(?P<ret_type>[^;]+)\(\*(?P<name>\w+)\)\((?P<cont>({cont_ty} \*|const {cont_ty} \*|{cont_ty} ))cont(?P<args>.*)\)",
-            cont_ty = container_ty
-        ))?;
-
-        for func in functions_str.split(';').filter(|s| !s.is_empty()) {
-            if let Some(cap) = reg.captures(func) {
-                let cont = &cap["cont"];
-
-                let mut arguments = vec![];
+        for f in fields {
+            let mut args = match &f.args {
+                Some(args) => args.iter(),
+                None => continue,
+            };
 
-                let args = &cap["args"];
+            let cont = match args.next() {
+                Some((cont, _)) => cont,
+                None => continue,
+            };
 
-                if !args.is_empty() {
-                    for (ty, name) in parse_arguments(&args[1..]) {
-                        arguments.push(FunctionArg {
-                            ty: ty.into(),
-                            name: name.into(),
-                        });
-                    }
-                }
+            let cont_base = cont
+                .trim_start_matches("const ")
+                .trim_end_matches('*')
+                .trim();
 
-                functions.push(Function {
-                    name: cap["name"].to_string(),
-                    return_type: cap["ret_type"].to_string(),
-                    arguments,
-                    is_const: cont.contains("const"),
-                    moves_self: !cont.contains('*'),
-                    calls_vtbl: true,
-                });
+            if cont_base != container_ty {
+                continue;
             }
+
+            functions.push(Function {
+                name: f.name.clone(),
+                return_type: f.ty.clone(),
+                arguments: args
+                    .map(|(ty, name)| FunctionArg {
+                        ty: ty.clone(),
+                        name: name.clone(),
+                    })
+                    .collect(),
+                is_const: cont.starts_with("const "),
+                moves_self: !cont.contains('*'),
+                calls_vtbl: true,
+            });
         }
 
-        Ok(Self { name, functions })
+        Self { name, functions }
     }
 
     pub fn create_wrappers(
@@ -552,19 +574,69 @@ pub struct Group {
 }
 
 impl Group {
-    pub fn new(name: String, vtable_defs: &str) -> Result<Self> {
-        let mut vtables = vec![];
+    /// Build the group out of its `const {Trait}Vtbl<...> *vtbl_{trait}` fields.
+    pub fn new(name: String, fields: &[Field]) -> Self {
+        let vtables = fields
+            .iter()
+            .filter(|f| f.name.starts_with("vtbl_"))
+            .filter_map(|f| {
+                let ty = f.ty.trim_start_matches("const ");
+                ty.find("Vtbl<")
+                    .map(|idx| (ty[..idx].to_string(), f.name.clone()))
+            })
+            .collect();
+
+        Self { name, vtables }
+    }
+
+    /// Create member functions for retrieving the set of implemented traits.
+    ///
+    /// Bit order matches the order of vtables within the group, which is the same order that
+    /// `CGLUE_CAPABILITY_NAMES` use on Rust side.
+    pub fn create_capabilities(&self) -> String {
+        let mut checks = String::new();
+        let mut names = String::new();
+
+        for (i, (ty, get)) in self.vtables.iter().enumerate() {
+            checks += &format!("\n        if ({}) ret |= 1ull << {};", get, i);
+            names += &format!("\n            \"{}\",", ty);
+        }
+
+        format!(
+            r"
+    inline uint64_t cglue_capabilities() const noexcept {{
+        uint64_t ret = 0;{checks}
+        return ret;
+    }}
+
+    static inline const char * const *cglue_capability_names() noexcept {{
+        static const char * const names[] = {{{names}
+            nullptr
+        }};
+        return names;
+    }}
+",
+            checks = checks,
+            names = names
+        )
+    }
 
-        let reg = Regex::new(r"const (?P<vtbl_type>[^;]+)Vtbl.*;")?;
+    /// Create member functions for setting vtables by their type.
+    pub fn create_vtbl_setters(&self) -> String {
+        let mut ret = String::new();
 
-        for cap in reg.captures_iter(vtable_defs) {
-            vtables.push((
-                cap["vtbl_type"].to_string(),
-                format!("vtbl_{}", cap["vtbl_type"].to_string().to_lowercase()),
-            ));
+        for (_, get) in &self.vtables {
+            ret += &format!(
+                r"
+    inline void set_vtbl(decltype({get}) vtbl) noexcept {{
+        {get} = vtbl;
+    }}
+",
+                get = get
+            );
         }
 
-        Ok(Self { name, vtables })
+        ret
     }
 
     pub fn create_wrappers(&self, vtables: &HashMap<&str, &Vtable>, container: &str) -> String {
@@ -0,0 +1,40 @@
+//! Generated binding snapshots.
+//!
+//! Every test here runs one of the language backends over the sample plugin API header, and
+//! compares the output against a stored snapshot in `tests/snapshots`. Changes to the generated
+//! bindings show up as snapshot diffs that need to be reviewed and accepted explicitly
+//! (`cargo insta review`, or `INSTA_UPDATE=always cargo test -p cglue-bindgen`).
+
+use cglue_bindgen::{process_header_for, Config, Target};
+
+const HEADER: &str = include_str!("data/plugin_api.h");
+
+fn generate(target: Target) -> String {
+    process_header_for(HEADER, &Config::default(), target).unwrap()
+}
+
+#[test]
+fn python() {
+    insta::assert_snapshot!(generate(Target::Python));
+}
+
+#[test]
+fn csharp() {
+    insta::assert_snapshot!(generate(Target::CSharp));
+}
+
+#[test]
+fn zig() {
+    insta::assert_snapshot!(generate(Target::Zig));
+}
+
+#[test]
+fn go() {
+    insta::assert_snapshot!(generate(Target::Go));
+}
+
+#[test]
+fn cpp_header_rejected() {
+    let header = include_str!("../../examples/pregen-headers/bindings.hpp");
+    assert!(process_header_for(header, &Config::default(), Target::Python).is_err());
+}
@@ -0,0 +1,33 @@
+//! Builds objects through the vtable macros, and object builders of the generated C header.
+//!
+//! The test is skipped if a C compiler (`$CC`, or `cc`) is not available.
+
+mod common;
+
+use cglue_bindgen::{process_header_for, Config, Target};
+use common::*;
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn build_objects() {
+    let dir = out_dir("c");
+
+    let header = process_header_for(HEADER, &Config::default(), Target::Header).unwrap();
+    fs::write(dir.join("bindings.h"), header).unwrap();
+
+    let bin = dir.join("builder");
+
+    if run(cc()
+        .args(["-std=c99", "-Werror=incompatible-pointer-types", "-I"])
+        .arg(&dir)
+        .arg(data("builder.c"))
+        .arg("-o")
+        .arg(&bin))
+    .is_none()
+    {
+        return;
+    }
+
+    run(&mut Command::new(&bin));
+}
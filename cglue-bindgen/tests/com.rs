@@ -0,0 +1,33 @@
+//! Calls the COM interface of the sample plugin (`tests/plugin`) from C, through the raw `lpVtbl`.
+//!
+//! The test is skipped if a C compiler (`$CC`, or `cc`) is not available.
+
+mod common;
+
+use common::*;
+use std::process::Command;
+
+#[test]
+fn call_through_vtbl() {
+    let dir = out_dir("com");
+
+    let lib = plugin_lib();
+    let lib_dir = lib.parent().unwrap();
+
+    let bin = dir.join("com");
+
+    if run(cc()
+        .args(["-std=c99", "-Werror=incompatible-pointer-types"])
+        .arg(data("com.c"))
+        .arg("-L")
+        .arg(lib_dir)
+        .arg(format!("-l{}", PLUGIN_LIB))
+        .arg("-o")
+        .arg(&bin))
+    .is_none()
+    {
+        return;
+    }
+
+    run(lib_path(&mut Command::new(&bin), lib_dir));
+}
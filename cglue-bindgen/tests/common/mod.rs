@@ -0,0 +1,90 @@
+// Not every test binary uses all of the helpers.
+#![allow(dead_code)]
+
+use std::env;
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Sample plugin API header, as generated by cbindgen out of `tests/plugin` (see `tests/fixture.rs`).
+pub const HEADER: &str = include_str!("../data/plugin_api.h");
+
+/// Name of the sample plugin library, as passed to the linker.
+pub const PLUGIN_LIB: &str = "cglue_bindgen_test_plugin";
+
+/// Path to a file in `tests/data`.
+pub fn data(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data")
+        .join(name)
+}
+
+/// Temporary directory for build outputs of the given test.
+pub fn out_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Build the sample plugin (`tests/plugin`) as a shared library, and return its path.
+pub fn plugin_lib() -> PathBuf {
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("plugin");
+
+    run(Command::new(env!("CARGO"))
+        .args(["build", "--manifest-path"])
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/plugin/Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target_dir))
+    .unwrap();
+
+    target_dir
+        .join("debug")
+        .join(format!("{}{}{}", DLL_PREFIX, PLUGIN_LIB, DLL_SUFFIX))
+}
+
+/// Let the dynamic loader find libraries in `dir` when running the command.
+pub fn lib_path<'a>(cmd: &'a mut Command, dir: &Path) -> &'a mut Command {
+    let var = if cfg!(windows) {
+        "PATH"
+    } else if cfg!(target_os = "macos") {
+        "DYLD_LIBRARY_PATH"
+    } else {
+        "LD_LIBRARY_PATH"
+    };
+
+    let mut paths = vec![dir.to_path_buf()];
+    if let Some(old) = env::var_os(var) {
+        paths.extend(env::split_paths(&old));
+    }
+
+    cmd.env(var, env::join_paths(paths).unwrap())
+}
+
+/// C compiler command (`$CC`, or `cc`).
+pub fn cc() -> Command {
+    Command::new(env::var("CC").unwrap_or_else(|_| "cc".into()))
+}
+
+/// Run the command, and assert that it succeeds.
+///
+/// Returns `None` if the command could not be started at all, in which case the test should be
+/// skipped.
+pub fn run(cmd: &mut Command) -> Option<()> {
+    let output = match cmd.output() {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("skipping, unable to run {:?}: {}", cmd.get_program(), e);
+            return None;
+        }
+    };
+
+    assert!(
+        output.status.success(),
+        "{:?} failed:\n{}{}",
+        cmd,
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    Some(())
+}
@@ -0,0 +1,58 @@
+//! Loads the sample plugin (`tests/plugin`) through the generated C# bindings.
+//!
+//! The bindings are built with `mcs`, and run with `mono`, or built, and run with `dotnet`, if
+//! mono is not available. The test is skipped if neither of them is available.
+
+mod common;
+
+use cglue_bindgen::{process_header_for, Config, Target};
+use common::*;
+use std::fs;
+use std::process::Command;
+
+const PROJECT: &str = r#"<Project Sdk="Microsoft.NET.Sdk">
+  <PropertyGroup>
+    <OutputType>Exe</OutputType>
+    <TargetFramework>$(BundledNETCoreAppTargetFramework)</TargetFramework>
+    <AllowUnsafeBlocks>true</AllowUnsafeBlocks>
+  </PropertyGroup>
+</Project>
+"#;
+
+#[test]
+fn load_and_call() {
+    let dir = out_dir("csharp");
+
+    let config = Config {
+        library_name: Some(PLUGIN_LIB.into()),
+        ..Default::default()
+    };
+    let bindings = process_header_for(HEADER, &config, Target::CSharp).unwrap();
+    fs::write(dir.join("bindings.cs"), bindings).unwrap();
+    fs::copy(data("plugin.cs"), dir.join("plugin.cs")).unwrap();
+
+    let lib = plugin_lib();
+    let lib_dir = lib.parent().unwrap();
+
+    let exe = dir.join("plugin.exe");
+
+    if run(Command::new("mcs")
+        .args(["-unsafe", "-nologo"])
+        .arg(format!("-out:{}", exe.display()))
+        .arg(dir.join("bindings.cs"))
+        .arg(dir.join("plugin.cs")))
+    .is_some()
+    {
+        run(lib_path(Command::new("mono").arg(&exe), lib_dir));
+        return;
+    }
+
+    fs::write(dir.join("plugin.csproj"), PROJECT).unwrap();
+
+    run(lib_path(
+        Command::new("dotnet")
+            .args(["run", "--project"])
+            .arg(dir.join("plugin.csproj")),
+        lib_dir,
+    ));
+}
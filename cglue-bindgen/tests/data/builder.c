@@ -0,0 +1,124 @@
+// Builds objects of the sample plugin API (`plugin_api.h`) through the generated vtable macros,
+// and object builders. Run by `c.rs`.
+
+#include "bindings.h"
+
+#include <stdio.h>
+
+#define CHECK(cond) \
+    if (!(cond)) { \
+        fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); \
+        return 1; \
+    }
+
+typedef struct Store {
+    uintptr_t values[4];
+} Store;
+
+static int drops = 0;
+static int ctx_refs = 0;
+
+static const void *ctx_clone(const void *ctx) {
+    ctx_refs++;
+    return ctx;
+}
+
+static void ctx_drop(const void *ctx) {
+    (void)ctx;
+    ctx_refs--;
+}
+
+static CArc_c_void ctx_new(void) {
+    ctx_refs++;
+    CArc_c_void ctx = {&ctx_refs, ctx_clone, ctx_drop};
+    return ctx;
+}
+
+static void store_drop(void *store) {
+    free(store);
+    drops++;
+}
+
+static void store_free(void *store) {
+    free(store);
+}
+
+static Store *store_new(void) {
+    return (Store *)calloc(1, sizeof(Store));
+}
+
+static void kv_print_self(const struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void *cont) {
+    printf("store %p\n", cont->instance.instance);
+}
+
+static void kv_write_key_value(struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void *cont,
+                               struct CSliceRef_u8 name, uintptr_t val) {
+    ((Store *)cont->instance.instance)->values[name.len % 4] = val;
+}
+
+static uintptr_t kv_get_key_value(const struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void *cont,
+                                  struct CSliceRef_u8 name) {
+    return ((const Store *)cont->instance.instance)->values[name.len % 4];
+}
+
+static const struct MainFeatureVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void kv_main_vtbl =
+    MAINFEATURE_VTBL(kv);
+
+static const struct KeyValueStoreVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void kv_store_vtbl =
+    KEYVALUESTORE_VTBL(kv);
+
+static struct FeaturesGroup_CBox_c_void_____CArc_c_void plg_features(void *instance, void (*drop_fn)(void *),
+                                                                     void (*free_fn)(void *),
+                                                                     CArc_c_void context) {
+    return featuresgroup_arc_box_new(instance, drop_fn, free_fn, context, &kv_main_vtbl, NULL, NULL, &kv_store_vtbl);
+}
+
+static struct FeaturesGroup_CBox_c_void_____CArc_c_void plg_borrow_features(
+    struct CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void *cont) {
+    return plg_features(cont->instance.instance, NULL, NULL, ctx_arc_clone(&cont->context));
+}
+
+static struct FeaturesGroup_CBox_c_void_____CArc_c_void plg_into_features(
+    struct CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void cont) {
+    return plg_features(cont.instance.instance, cont.instance.drop_fn, cont.instance.free_fn, cont.context);
+}
+
+static struct FeaturesGroup_____c_void__CArc_c_void *plg_mut_features(
+    struct CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void *cont) {
+    (void)cont;
+    return NULL;
+}
+
+static const struct PluginInnerVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void plg_vtbl =
+    PLUGININNER_VTBL(plg);
+
+int main(void) {
+    struct FeaturesGroup_CBox_c_void_____CArc_c_void group =
+        plg_features(store_new(), store_drop, store_free, ctx_new());
+    CHECK(group.vtbl_clone == NULL && group.vtbl_keyvaluedumper == NULL);
+    CHECK(group.container.instance.free_fn == store_free);
+    featuresgroup_print_self(&group);
+    featuresgroup_write_key_value(&group, STR("a"), 5);
+    CHECK(featuresgroup_get_key_value(&group, STR("b")) == 5);
+    CHECK(featuresgroup_get_key_value(&group, STR("ab")) == 0);
+    featuresgroup_arc_box_drop(group);
+    CHECK(drops == 1 && ctx_refs == 0);
+
+    struct CGlueTraitObj_CBox_c_void_____PluginInnerVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void______________CArc_c_void_____PluginInnerRetTmp_CArc_c_void plugin =
+        plugininner_arc_box_new(store_new(), store_drop, store_free, ctx_new(), &plg_vtbl);
+    CHECK(plugin.vtbl == &plg_vtbl);
+    CHECK(plg_vtbl.descriptor == NULL && kv_main_vtbl.descriptor == NULL);
+
+    struct FeaturesGroup_CBox_c_void_____CArc_c_void borrowed = borrow_features(&plugin);
+    featuresgroup_write_key_value(&borrowed, STR("key"), 42);
+    featuresgroup_arc_box_drop(borrowed);
+    CHECK(drops == 1 && ctx_refs == 1);
+
+    group = arc_box_into_features(plugin);
+    CHECK(featuresgroup_get_key_value(&group, STR("abc")) == 42);
+    featuresgroup_arc_box_drop(group);
+    CHECK(drops == 2 && ctx_refs == 0);
+
+    printf("ok\n");
+    return 0;
+}
@@ -0,0 +1,85 @@
+// Calls into the `KeyCounter` COM interface of the sample plugin through the raw `lpVtbl`, just
+// like a COM client would. Run by `com.rs`.
+
+#include <stdint.h>
+#include <stdio.h>
+
+#if defined(_WIN32)
+#define STDMETHODCALLTYPE __stdcall
+#else
+#define STDMETHODCALLTYPE
+#endif
+
+#define CHECK(cond) \
+    if (!(cond)) { \
+        fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); \
+        return 1; \
+    }
+
+typedef int32_t HRESULT;
+
+#define S_OK ((HRESULT)0)
+#define E_NOINTERFACE ((HRESULT)0x80004002)
+
+typedef struct GUID {
+    uint32_t data1;
+    uint16_t data2;
+    uint16_t data3;
+    uint8_t data4[8];
+} GUID;
+
+static const GUID IID_IUnknown = {0x00000000, 0x0000, 0x0000, {0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46}};
+static const GUID IID_IKeyCounter = {0x0b3e4e3c, 0x95a4, 0x4c1d, {0x9b, 0xd2, 0x5e, 0x6a, 0x2f, 0x43, 0xc1, 0xa7}};
+static const GUID IID_Other = {0x00000001, 0x0000, 0x0000, {0}};
+
+typedef struct IKeyCounter IKeyCounter;
+
+typedef struct IKeyCounterVtbl {
+    HRESULT (STDMETHODCALLTYPE *QueryInterface)(IKeyCounter *This, const GUID *riid, void **ppvObject);
+    uint32_t (STDMETHODCALLTYPE *AddRef)(IKeyCounter *This);
+    uint32_t (STDMETHODCALLTYPE *Release)(IKeyCounter *This);
+    uintptr_t (STDMETHODCALLTYPE *KeyCount)(const IKeyCounter *This);
+    void (STDMETHODCALLTYPE *AddKeys)(IKeyCounter *This, uintptr_t count);
+} IKeyCounterVtbl;
+
+struct IKeyCounter {
+    const IKeyCounterVtbl *lpVtbl;
+};
+
+IKeyCounter *plugin_com_store(void);
+int32_t plugin_live_stores(void);
+
+int main(void) {
+    IKeyCounter *counter = plugin_com_store();
+    CHECK(counter != NULL);
+    CHECK(plugin_live_stores() == 1);
+
+    CHECK(counter->lpVtbl->KeyCount(counter) == 0);
+    counter->lpVtbl->AddKeys(counter, 3);
+    CHECK(counter->lpVtbl->KeyCount(counter) == 3);
+
+    void *out = NULL;
+    CHECK(counter->lpVtbl->QueryInterface(counter, &IID_IKeyCounter, &out) == S_OK);
+    CHECK(out == counter);
+    CHECK(counter->lpVtbl->QueryInterface(counter, &IID_IUnknown, &out) == S_OK);
+    CHECK(out == counter);
+    CHECK(counter->lpVtbl->QueryInterface(counter, &IID_Other, &out) == E_NOINTERFACE);
+    CHECK(out == NULL);
+
+    CHECK(counter->lpVtbl->AddRef(counter) == 4);
+    CHECK(counter->lpVtbl->Release(counter) == 3);
+    CHECK(counter->lpVtbl->Release(counter) == 2);
+    CHECK(counter->lpVtbl->Release(counter) == 1);
+
+    // The last reference keeps the store alive.
+    counter->lpVtbl->AddKeys(counter, 2);
+    CHECK(counter->lpVtbl->KeyCount(counter) == 5);
+    CHECK(plugin_live_stores() == 1);
+
+    CHECK(counter->lpVtbl->Release(counter) == 0);
+    CHECK(plugin_live_stores() == 0);
+
+    printf("ok\n");
+
+    return 0;
+}
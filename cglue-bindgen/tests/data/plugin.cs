@@ -0,0 +1,71 @@
+// Loads the sample plugin through the generated C# bindings.
+
+using System;
+using System.Text;
+using CGlue;
+
+static class Program
+{
+    static void Check(bool cond, string what)
+    {
+        if (!cond)
+            throw new Exception("check failed: " + what);
+    }
+
+    static int Main()
+    {
+        try
+        {
+            Library.LoadPlugin("missing");
+            throw new Exception("loading a missing plugin succeeded");
+        }
+        catch (CGlueException e)
+        {
+            Check(e.Code == -1, "error code");
+        }
+
+        using (var plugin = Library.LoadPlugin("kvstore"))
+        {
+            using (var features = plugin.BorrowFeatures())
+            {
+                Check(features.TryAs<IKeyValueStore>() != null, "KeyValueStore is implemented");
+                Check(features.TryAs<IClone>() == null, "Clone is not implemented");
+
+                features.WriteKeyValue(Encoding.UTF8.GetBytes("hello"), (UIntPtr)42);
+                features.WriteKeyValue(Encoding.UTF8.GetBytes("world"), (UIntPtr)7);
+                Check(features.GetKeyValue(Encoding.UTF8.GetBytes("hello")) == (UIntPtr)42, "stored value");
+
+                var count = 0;
+                features.DumpKeyValues(kv =>
+                {
+                    count++;
+                    return true;
+                });
+                Check(count == 2, "dumped values");
+
+                try
+                {
+                    features.Clone();
+                    throw new Exception("missing optional trait was called");
+                }
+                catch (NotSupportedException)
+                {
+                }
+            }
+
+            Check(Library.PluginLiveStores() == 1, "plugin keeps the store");
+        }
+
+        Check(Library.PluginLiveStores() == 0, "plugin drops the store");
+
+        var owned = Library.LoadPlugin("kvstore").IntoFeatures();
+        owned.WriteKeyValue(Encoding.UTF8.GetBytes("key"), (UIntPtr)1);
+        Check(owned.GetKeyValue(Encoding.UTF8.GetBytes("key")) == (UIntPtr)1, "owned value");
+        Check(Library.PluginLiveStores() == 1, "owned features keep the store");
+        owned.Dispose();
+        Check(Library.PluginLiveStores() == 0, "owned features drop the store");
+
+        Console.WriteLine("ok");
+        return 0;
+    }
+}
@@ -0,0 +1,52 @@
+// Loads the sample plugin through the generated Go bindings.
+
+package main
+
+import (
+	"errors"
+	"fmt"
+
+	"cgluetest/cglue"
+)
+
+func check(cond bool, what string) {
+	if !cond {
+		panic("check failed: " + what)
+	}
+}
+
+func main() {
+	_, err := cglue.LoadPlugin("missing")
+	var cerr *cglue.Error
+	check(errors.As(err, &cerr) && cerr.Code == -1, "error code")
+
+	plugin, err := cglue.LoadPlugin("kvstore")
+	check(err == nil, "plugin loads")
+
+	features := plugin.BorrowFeatures()
+	check(features.HasKeyValueStore(), "KeyValueStore is implemented")
+	check(!features.HasClone(), "Clone is not implemented")
+
+	features.WriteKeyValue([]byte("hello"), 42)
+	features.WriteKeyValue([]byte("world"), 7)
+	check(features.GetKeyValue([]byte("hello")) == 42, "stored value")
+
+	count := 0
+	features.DumpKeyValues(func(cglue.KeyValue) bool {
+		count++
+		return true
+	})
+	check(count == 2, "dumped values")
+
+	features.Close()
+	check(cglue.PluginLiveStores() == 1, "plugin keeps the store")
+
+	owned := plugin.IntoFeatures()
+	check(owned.GetKeyValue([]byte("world")) == 7, "owned value")
+	check(cglue.PluginLiveStores() == 1, "owned features keep the store")
+
+	owned.Close()
+	check(cglue.PluginLiveStores() == 0, "owned features drop the store")
+
+	fmt.Println("ok")
+}
@@ -0,0 +1,77 @@
+# Loads the sample plugin through the generated ctypes bindings.
+#
+# Usage: plugin.py <bindings directory> <plugin library>
+
+import gc
+import sys
+
+sys.path.insert(0, sys.argv[1])
+
+import bindings
+
+lib = bindings.Library(sys.argv[2])
+live_stores = lib.plugin_live_stores
+
+
+def collect():
+    gc.collect()
+    return live_stores()
+
+
+try:
+    lib.load_plugin("missing")
+    raise AssertionError("loading a missing plugin succeeded")
+except bindings.CGlueError as e:
+    assert e.code == -1
+
+# Borrowed features keep the plugin alive.
+plugin = lib.load_plugin("kvstore")
+features = plugin.borrow_features()
+assert features.implements("MainFeature", "KeyValueStore")
+assert not features.implements("Clone")
+features.write_key_value("hello", 42)
+features.write_key_value(b"world", 7)
+assert features.get_key_value("hello") == 42
+assert plugin.borrow_features().get_key_value("world") == 7
+del plugin
+assert collect() == 1
+assert features.get_key_value("world") == 7
+
+pairs = []
+features.dump_key_values(lambda kv: pairs.append((bytes(kv._0.data[:kv._0.len]), kv._1)))
+assert pairs == [(b"hello", 42), (b"world", 7)], pairs
+
+try:
+    features.into_raw()
+    raise AssertionError("borrowed features were consumed")
+except ValueError:
+    pass
+
+del features
+assert collect() == 0
+
+# Owned features drop the store.
+plugin = lib.load_plugin("kvstore")
+features = plugin.into_features()
+assert collect() == 1
+try:
+    plugin.borrow_features()
+    raise AssertionError("consumed plugin was used")
+except ValueError:
+    pass
+features.write_key_value("key", 1)
+assert features.get_key_value("key") == 1
+del plugin, features
+assert collect() == 0
+
+try:
+    features = lib.load_plugin("kvstore").into_features()
+    features.clone()
+    raise AssertionError("missing optional trait was called")
+except NotImplementedError:
+    pass
+
+del features
+assert collect() == 0
+
+print("ok")
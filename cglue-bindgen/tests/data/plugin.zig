@@ -0,0 +1,45 @@
+// Loads the sample plugin through the generated Zig bindings.
+
+const std = @import("std");
+const cglue = @import("bindings.zig");
+
+const expect = std.testing.expect;
+const expectEqual = std.testing.expectEqual;
+
+comptime {
+    std.testing.refAllDecls(cglue);
+}
+
+fn count(n: *usize, kv: cglue.KeyValue) bool {
+    _ = kv;
+    n.* += 1;
+    return true;
+}
+
+test "load and call" {
+    try std.testing.expectError(error.CGlueFailure, cglue.loadPlugin("missing"));
+
+    var plugin = try cglue.loadPlugin("kvstore");
+
+    var features = plugin.borrowFeatures();
+    try expect(features.hasKeyValueStore());
+    try expect(!features.hasClone());
+
+    features.writeKeyValue("hello", 42);
+    features.writeKeyValue("world", 7);
+    try expectEqual(@as(usize, 42), features.getKeyValue("hello"));
+
+    var n: usize = 0;
+    features.dumpKeyValues(&n, count);
+    try expectEqual(@as(usize, 2), n);
+
+    features.deinit();
+    try expectEqual(@as(i32, 1), cglue.pluginLiveStores());
+
+    var owned = plugin.intoFeatures();
+    try expectEqual(@as(usize, 7), owned.getKeyValue("world"));
+    try expectEqual(@as(i32, 1), cglue.pluginLiveStores());
+
+    owned.deinit();
+    try expectEqual(@as(i32, 0), cglue.pluginLiveStores());
+}
@@ -0,0 +1,623 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Type definition for temporary return value wrapping storage.
+ *
+ * The trait does not use return wrapping, thus is a typedef to `PhantomData`.
+ *
+ * Note that `cbindgen` will generate wrong structures for this type. It is important
+ * to go inside the generated headers and fix it - all RetTmp structures without a
+ * body should be completely deleted, both as types, and as fields in the
+ * groups/objects. If C++11 templates are generated, it is important to define a
+ * custom type for CGlueTraitObj that does not have `ret_tmp` defined, and change all
+ * type aliases of this trait to use that particular structure.
+ */
+typedef struct CloneRetTmp_CArc_c_void CloneRetTmp_CArc_c_void;
+
+/**
+ * Type definition for temporary return value wrapping storage.
+ *
+ * The trait does not use return wrapping, thus is a typedef to `PhantomData`.
+ *
+ * Note that `cbindgen` will generate wrong structures for this type. It is important
+ * to go inside the generated headers and fix it - all RetTmp structures without a
+ * body should be completely deleted, both as types, and as fields in the
+ * groups/objects. If C++11 templates are generated, it is important to define a
+ * custom type for CGlueTraitObj that does not have `ret_tmp` defined, and change all
+ * type aliases of this trait to use that particular structure.
+ */
+typedef struct CloneRetTmp_Context CloneRetTmp_Context;
+
+/**
+ * Type definition for temporary return value wrapping storage.
+ *
+ * The trait does not use return wrapping, thus is a typedef to `PhantomData`.
+ *
+ * Note that `cbindgen` will generate wrong structures for this type. It is important
+ * to go inside the generated headers and fix it - all RetTmp structures without a
+ * body should be completely deleted, both as types, and as fields in the
+ * groups/objects. If C++11 templates are generated, it is important to define a
+ * custom type for CGlueTraitObj that does not have `ret_tmp` defined, and change all
+ * type aliases of this trait to use that particular structure.
+ */
+typedef struct KeyValueDumperRetTmp_CArc_c_void KeyValueDumperRetTmp_CArc_c_void;
+
+/**
+ * Type definition for temporary return value wrapping storage.
+ *
+ * The trait does not use return wrapping, thus is a typedef to `PhantomData`.
+ *
+ * Note that `cbindgen` will generate wrong structures for this type. It is important
+ * to go inside the generated headers and fix it - all RetTmp structures without a
+ * body should be completely deleted, both as types, and as fields in the
+ * groups/objects. If C++11 templates are generated, it is important to define a
+ * custom type for CGlueTraitObj that does not have `ret_tmp` defined, and change all
+ * type aliases of this trait to use that particular structure.
+ */
+typedef struct KeyValueDumperRetTmp_Context KeyValueDumperRetTmp_Context;
+
+/**
+ * Type definition for temporary return value wrapping storage.
+ *
+ * The trait does not use return wrapping, thus is a typedef to `PhantomData`.
+ *
+ * Note that `cbindgen` will generate wrong structures for this type. It is important
+ * to go inside the generated headers and fix it - all RetTmp structures without a
+ * body should be completely deleted, both as types, and as fields in the
+ * groups/objects. If C++11 templates are generated, it is important to define a
+ * custom type for CGlueTraitObj that does not have `ret_tmp` defined, and change all
+ * type aliases of this trait to use that particular structure.
+ */
+typedef struct KeyValueStoreRetTmp_CArc_c_void KeyValueStoreRetTmp_CArc_c_void;
+
+/**
+ * Type definition for temporary return value wrapping storage.
+ *
+ * The trait does not use return wrapping, thus is a typedef to `PhantomData`.
+ *
+ * Note that `cbindgen` will generate wrong structures for this type. It is important
+ * to go inside the generated headers and fix it - all RetTmp structures without a
+ * body should be completely deleted, both as types, and as fields in the
+ * groups/objects. If C++11 templates are generated, it is important to define a
+ * custom type for CGlueTraitObj that does not have `ret_tmp` defined, and change all
+ * type aliases of this trait to use that particular structure.
+ */
+typedef struct KeyValueStoreRetTmp_Context KeyValueStoreRetTmp_Context;
+
+/**
+ * Type definition for temporary return value wrapping storage.
+ *
+ * The trait does not use return wrapping, thus is a typedef to `PhantomData`.
+ *
+ * Note that `cbindgen` will generate wrong structures for this type. It is important
+ * to go inside the generated headers and fix it - all RetTmp structures without a
+ * body should be completely deleted, both as types, and as fields in the
+ * groups/objects. If C++11 templates are generated, it is important to define a
+ * custom type for CGlueTraitObj that does not have `ret_tmp` defined, and change all
+ * type aliases of this trait to use that particular structure.
+ */
+typedef struct MainFeatureRetTmp_CArc_c_void MainFeatureRetTmp_CArc_c_void;
+
+/**
+ * Type definition for temporary return value wrapping storage.
+ *
+ * The trait does not use return wrapping, thus is a typedef to `PhantomData`.
+ *
+ * Note that `cbindgen` will generate wrong structures for this type. It is important
+ * to go inside the generated headers and fix it - all RetTmp structures without a
+ * body should be completely deleted, both as types, and as fields in the
+ * groups/objects. If C++11 templates are generated, it is important to define a
+ * custom type for CGlueTraitObj that does not have `ret_tmp` defined, and change all
+ * type aliases of this trait to use that particular structure.
+ */
+typedef struct MainFeatureRetTmp_Context MainFeatureRetTmp_Context;
+
+/**
+ * Wrapper around null-terminated C-style strings.
+ *
+ * Analog to Rust's `str`, [`ReprCStr`] borrows the underlying data.
+ */
+typedef const char *ReprCStr;
+
+/**
+ * FFI-safe box
+ *
+ * This box has a static self reference, alongside a custom drop function.
+ *
+ * The drop function can be called from anywhere, it will free on correct allocator internally.
+ * The free function releases the storage without dropping the value, and is used when the
+ * value gets moved out of the box.
+ */
+typedef struct CBox_c_void {
+    void *instance;
+    void (*drop_fn)(void*);
+    void (*free_fn)(void*);
+} CBox_c_void;
+
+typedef struct FeaturesGroupContainer_CBox_c_void_____Context {
+    struct CBox_c_void instance;
+    Context context;
+    struct MainFeatureRetTmp_Context ret_tmp_mainfeature;
+    struct CloneRetTmp_Context ret_tmp_clone;
+    struct KeyValueDumperRetTmp_Context ret_tmp_keyvaluedumper;
+    struct KeyValueStoreRetTmp_Context ret_tmp_keyvaluestore;
+} FeaturesGroupContainer_CBox_c_void_____Context;
+
+/**
+ * Wrapper around const slices.
+ *
+ * This is meant as a safe type to pass across the FFI boundary with similar semantics as regular
+ * slice. However, not all functionality is present, use the slice conversion functions.
+ *
+ * # Examples
+ *
+ * Simple conversion:
+ *
+ * ```
+ * use cglue::slice::CSliceRef;
+ *
+ * let arr = [0, 5, 3, 2];
+ *
+ * let cslice = CSliceRef::from(&arr[..]);
+ *
+ * let slice = cslice.as_slice();
+ *
+ * assert_eq!(&arr, slice);
+ * ```
+ */
+typedef struct CSliceRef_u8 {
+    const uint8_t *data;
+    uintptr_t len;
+} CSliceRef_u8;
+
+/**
+ * FFI-safe description of a trait method.
+ */
+typedef struct MethodDescriptor {
+    struct CSliceRef_u8 name;
+    /**
+     * Same as [`MethodInfo::signature`].
+     */
+    struct CSliceRef_u8 signature;
+} MethodDescriptor;
+
+/**
+ * Wrapper around const slices.
+ *
+ * This is meant as a safe type to pass across the FFI boundary with similar semantics as regular
+ * slice. However, not all functionality is present, use the slice conversion functions.
+ *
+ * # Examples
+ *
+ * Simple conversion:
+ *
+ * ```
+ * use cglue::slice::CSliceRef;
+ *
+ * let arr = [0, 5, 3, 2];
+ *
+ * let cslice = CSliceRef::from(&arr[..]);
+ *
+ * let slice = cslice.as_slice();
+ *
+ * assert_eq!(&arr, slice);
+ * ```
+ */
+typedef struct CSliceRef_MethodDescriptor {
+    const struct MethodDescriptor *data;
+    uintptr_t len;
+} CSliceRef_MethodDescriptor;
+
+/**
+ * FFI-safe description of a trait.
+ *
+ * Every CGlue vtable refers to a static instance of this structure.
+ */
+typedef struct TraitDescriptor {
+    struct CSliceRef_u8 name;
+    struct CSliceRef_MethodDescriptor methods;
+} TraitDescriptor;
+
+/**
+ * CGlue vtable for trait MainFeature.
+ *
+ * This virtual function table contains ABI-safe interface for the given trait.
+ */
+typedef struct MainFeatureVtbl_FeaturesGroupContainer_CBox_c_void_____Context {
+    void (*print_self)(const struct FeaturesGroupContainer_CBox_c_void_____Context *cont);
+    const struct TraitDescriptor *descriptor;
+} MainFeatureVtbl_FeaturesGroupContainer_CBox_c_void_____Context;
+
+/**
+ * CGlue vtable for trait Clone.
+ *
+ * This virtual function table contains ABI-safe interface for the given trait.
+ */
+typedef struct CloneVtbl_FeaturesGroupContainer_CBox_c_void_____Context {
+    struct FeaturesGroupContainer_CBox_c_void_____Context (*clone)(const struct FeaturesGroupContainer_CBox_c_void_____Context *cont);
+    const struct TraitDescriptor *descriptor;
+} CloneVtbl_FeaturesGroupContainer_CBox_c_void_____Context;
+
+typedef struct KeyValue {
+    struct CSliceRef_u8 _0;
+    uintptr_t _1;
+} KeyValue;
+
+typedef struct Callback_c_void__KeyValue {
+    void *context;
+    bool (*func)(void*, struct KeyValue);
+} Callback_c_void__KeyValue;
+
+typedef struct Callback_c_void__KeyValue OpaqueCallback_KeyValue;
+
+typedef OpaqueCallback_KeyValue KeyValueCallback;
+
+/**
+ * FFI compatible iterator.
+ *
+ * Any mutable reference to an iterator can be converted to a `CIterator`.
+ *
+ * `CIterator<T>` implements `Iterator<Item = T>`.
+ *
+ * # Examples
+ *
+ * Using [`AsCIterator`](AsCIterator) helper:
+ *
+ * ```
+ * use cglue::iter::{CIterator, AsCIterator};
+ *
+ * extern "C" fn sum_all(iter: CIterator<usize>) -> usize {
+ *     iter.sum()
+ * }
+ *
+ * let mut iter = (0..10).map(|v| v * v);
+ *
+ * assert_eq!(sum_all(iter.as_citer()), 285);
+ * ```
+ *
+ * Converting with `Into` trait:
+ *
+ * ```
+ * use cglue::iter::{CIterator, AsCIterator};
+ *
+ * extern "C" fn sum_all(iter: CIterator<usize>) -> usize {
+ *     iter.sum()
+ * }
+ *
+ * let mut iter = (0..=10).map(|v| v * v);
+ *
+ * assert_eq!(sum_all((&mut iter).into()), 385);
+ * ```
+ */
+typedef struct CIterator_i32 {
+    void *iter;
+    int32_t (*func)(void*, int32_t *out);
+} CIterator_i32;
+
+/**
+ * CGlue vtable for trait KeyValueDumper.
+ *
+ * This virtual function table contains ABI-safe interface for the given trait.
+ */
+typedef struct KeyValueDumperVtbl_FeaturesGroupContainer_CBox_c_void_____Context {
+    void (*dump_key_values)(const struct FeaturesGroupContainer_CBox_c_void_____Context *cont,
+                            KeyValueCallback callback);
+    void (*print_ints)(const struct FeaturesGroupContainer_CBox_c_void_____Context *cont,
+                       struct CIterator_i32 iter);
+    const struct TraitDescriptor *descriptor;
+} KeyValueDumperVtbl_FeaturesGroupContainer_CBox_c_void_____Context;
+
+/**
+ * CGlue vtable for trait KeyValueStore.
+ *
+ * This virtual function table contains ABI-safe interface for the given trait.
+ */
+typedef struct KeyValueStoreVtbl_FeaturesGroupContainer_CBox_c_void_____Context {
+    void (*write_key_value)(struct FeaturesGroupContainer_CBox_c_void_____Context *cont,
+                            struct CSliceRef_u8 name,
+                            uintptr_t val);
+    uintptr_t (*get_key_value)(const struct FeaturesGroupContainer_CBox_c_void_____Context *cont,
+                               struct CSliceRef_u8 name);
+    const struct TraitDescriptor *descriptor;
+} KeyValueStoreVtbl_FeaturesGroupContainer_CBox_c_void_____Context;
+
+/**
+ * Trait group potentially implementing `MainFeature < > + :: cglue :: ext :: core :: clone :: Clone < > + KeyValueDumper < > + KeyValueStore < >` traits.
+ *
+ * Optional traits are not implemented here, however. There are numerous conversion
+ * functions available for safely retrieving a concrete collection of traits.
+ *
+ * `check_impl_` functions allow to check if the object implements the wanted traits.
+ *
+ * `into_impl_` functions consume the object and produce a new final structure that
+ * keeps only the required information.
+ *
+ * `cast_impl_` functions merely check and transform the object into a type that can
+ *be transformed back into `FeaturesGroup` without losing data.
+ *
+ * `as_ref_`, and `as_mut_` functions obtain references to safe objects, but do not
+ * perform any memory transformations either. They are the safest to use, because
+ * there is no risk of accidentally consuming the whole object.
+ */
+typedef struct FeaturesGroup_CBox_c_void_____Context {
+    const struct MainFeatureVtbl_FeaturesGroupContainer_CBox_c_void_____Context *vtbl_mainfeature;
+    const struct CloneVtbl_FeaturesGroupContainer_CBox_c_void_____Context *vtbl_clone;
+    const struct KeyValueDumperVtbl_FeaturesGroupContainer_CBox_c_void_____Context *vtbl_keyvaluedumper;
+    const struct KeyValueStoreVtbl_FeaturesGroupContainer_CBox_c_void_____Context *vtbl_keyvaluestore;
+    struct FeaturesGroupContainer_CBox_c_void_____Context container;
+} FeaturesGroup_CBox_c_void_____Context;
+
+/**
+ * FFI-Safe Arc
+ *
+ * This is an FFI-Safe equivalent of Arc<T> and Option<Arc<T>>.
+ */
+typedef struct CArc_c_void {
+    const void *instance;
+    const void *(*clone_fn)(const void*);
+    void (*drop_fn)(const void*);
+} CArc_c_void;
+
+typedef struct FeaturesGroupContainer_____c_void__CArc_c_void {
+    void *instance;
+    struct CArc_c_void context;
+    struct MainFeatureRetTmp_CArc_c_void ret_tmp_mainfeature;
+    struct CloneRetTmp_CArc_c_void ret_tmp_clone;
+    struct KeyValueDumperRetTmp_CArc_c_void ret_tmp_keyvaluedumper;
+    struct KeyValueStoreRetTmp_CArc_c_void ret_tmp_keyvaluestore;
+} FeaturesGroupContainer_____c_void__CArc_c_void;
+
+/**
+ * CGlue vtable for trait MainFeature.
+ *
+ * This virtual function table contains ABI-safe interface for the given trait.
+ */
+typedef struct MainFeatureVtbl_FeaturesGroupContainer_____c_void__CArc_c_void {
+    void (*print_self)(const struct FeaturesGroupContainer_____c_void__CArc_c_void *cont);
+    const struct TraitDescriptor *descriptor;
+} MainFeatureVtbl_FeaturesGroupContainer_____c_void__CArc_c_void;
+
+/**
+ * CGlue vtable for trait Clone.
+ *
+ * This virtual function table contains ABI-safe interface for the given trait.
+ */
+typedef struct CloneVtbl_FeaturesGroupContainer_____c_void__CArc_c_void {
+    struct FeaturesGroupContainer_____c_void__CArc_c_void (*clone)(const struct FeaturesGroupContainer_____c_void__CArc_c_void *cont);
+    const struct TraitDescriptor *descriptor;
+} CloneVtbl_FeaturesGroupContainer_____c_void__CArc_c_void;
+
+/**
+ * CGlue vtable for trait KeyValueDumper.
+ *
+ * This virtual function table contains ABI-safe interface for the given trait.
+ */
+typedef struct KeyValueDumperVtbl_FeaturesGroupContainer_____c_void__CArc_c_void {
+    void (*dump_key_values)(const struct FeaturesGroupContainer_____c_void__CArc_c_void *cont,
+                            KeyValueCallback callback);
+    void (*print_ints)(const struct FeaturesGroupContainer_____c_void__CArc_c_void *cont,
+                       struct CIterator_i32 iter);
+    const struct TraitDescriptor *descriptor;
+} KeyValueDumperVtbl_FeaturesGroupContainer_____c_void__CArc_c_void;
+
+/**
+ * CGlue vtable for trait KeyValueStore.
+ *
+ * This virtual function table contains ABI-safe interface for the given trait.
+ */
+typedef struct KeyValueStoreVtbl_FeaturesGroupContainer_____c_void__CArc_c_void {
+    void (*write_key_value)(struct FeaturesGroupContainer_____c_void__CArc_c_void *cont,
+                            struct CSliceRef_u8 name,
+                            uintptr_t val);
+    uintptr_t (*get_key_value)(const struct FeaturesGroupContainer_____c_void__CArc_c_void *cont,
+                               struct CSliceRef_u8 name);
+    const struct TraitDescriptor *descriptor;
+} KeyValueStoreVtbl_FeaturesGroupContainer_____c_void__CArc_c_void;
+
+/**
+ * Trait group potentially implementing `MainFeature < > + :: cglue :: ext :: core :: clone :: Clone < > + KeyValueDumper < > + KeyValueStore < >` traits.
+ *
+ * Optional traits are not implemented here, however. There are numerous conversion
+ * functions available for safely retrieving a concrete collection of traits.
+ *
+ * `check_impl_` functions allow to check if the object implements the wanted traits.
+ *
+ * `into_impl_` functions consume the object and produce a new final structure that
+ * keeps only the required information.
+ *
+ * `cast_impl_` functions merely check and transform the object into a type that can
+ *be transformed back into `FeaturesGroup` without losing data.
+ *
+ * `as_ref_`, and `as_mut_` functions obtain references to safe objects, but do not
+ * perform any memory transformations either. They are the safest to use, because
+ * there is no risk of accidentally consuming the whole object.
+ */
+typedef struct FeaturesGroup_____c_void__CArc_c_void {
+    const struct MainFeatureVtbl_FeaturesGroupContainer_____c_void__CArc_c_void *vtbl_mainfeature;
+    const struct CloneVtbl_FeaturesGroupContainer_____c_void__CArc_c_void *vtbl_clone;
+    const struct KeyValueDumperVtbl_FeaturesGroupContainer_____c_void__CArc_c_void *vtbl_keyvaluedumper;
+    const struct KeyValueStoreVtbl_FeaturesGroupContainer_____c_void__CArc_c_void *vtbl_keyvaluestore;
+    struct FeaturesGroupContainer_____c_void__CArc_c_void container;
+} FeaturesGroup_____c_void__CArc_c_void;
+
+/**
+ * Temporary return value structure, for returning wrapped references.
+ *
+ * This structure contains data for each vtable function that returns a reference to
+ * an associated type. Note that these temporary values should not be accessed
+ * directly. Use the trait functions.
+ */
+typedef struct PluginInnerRetTmp_CArc_c_void {
+    struct FeaturesGroup_____c_void__CArc_c_void mut_features;
+} PluginInnerRetTmp_CArc_c_void;
+
+/**
+ * Simple CGlue trait object container.
+ *
+ * This is the simplest form of container, represented by an instance, clone context, and
+ * temporary return context.
+ *
+ * `instance` value usually is either a reference, or a mutable reference, or a `CBox`, which
+ * contains static reference to the instance, and a dedicated drop function for freeing resources.
+ *
+ * `context` is either `PhantomData` representing nothing, or typically a `CArc` that can be
+ * cloned at will, reference counting some resource, like a `Library` for automatic unloading.
+ *
+ * `ret_tmp` is usually `PhantomData` representing nothing, unless the trait has functions that
+ * return references to associated types, in which case space is reserved for wrapping structures.
+ */
+typedef struct CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void {
+    struct CBox_c_void instance;
+    struct CArc_c_void context;
+    struct PluginInnerRetTmp_CArc_c_void ret_tmp;
+} CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void;
+
+typedef struct FeaturesGroupContainer_____c_void__Context {
+    void *instance;
+    Context context;
+    struct MainFeatureRetTmp_Context ret_tmp_mainfeature;
+    struct CloneRetTmp_Context ret_tmp_clone;
+    struct KeyValueDumperRetTmp_Context ret_tmp_keyvaluedumper;
+    struct KeyValueStoreRetTmp_Context ret_tmp_keyvaluestore;
+} FeaturesGroupContainer_____c_void__Context;
+
+/**
+ * CGlue vtable for trait MainFeature.
+ *
+ * This virtual function table contains ABI-safe interface for the given trait.
+ */
+typedef struct MainFeatureVtbl_FeaturesGroupContainer_____c_void__Context {
+    void (*print_self)(const struct FeaturesGroupContainer_____c_void__Context *cont);
+    const struct TraitDescriptor *descriptor;
+} MainFeatureVtbl_FeaturesGroupContainer_____c_void__Context;
+
+/**
+ * CGlue vtable for trait Clone.
+ *
+ * This virtual function table contains ABI-safe interface for the given trait.
+ */
+typedef struct CloneVtbl_FeaturesGroupContainer_____c_void__Context {
+    struct FeaturesGroupContainer_____c_void__Context (*clone)(const struct FeaturesGroupContainer_____c_void__Context *cont);
+    const struct TraitDescriptor *descriptor;
+} CloneVtbl_FeaturesGroupContainer_____c_void__Context;
+
+/**
+ * CGlue vtable for trait KeyValueDumper.
+ *
+ * This virtual function table contains ABI-safe interface for the given trait.
+ */
+typedef struct KeyValueDumperVtbl_FeaturesGroupContainer_____c_void__Context {
+    void (*dump_key_values)(const struct FeaturesGroupContainer_____c_void__Context *cont,
+                            KeyValueCallback callback);
+    void (*print_ints)(const struct FeaturesGroupContainer_____c_void__Context *cont,
+                       struct CIterator_i32 iter);
+    const struct TraitDescriptor *descriptor;
+} KeyValueDumperVtbl_FeaturesGroupContainer_____c_void__Context;
+
+/**
+ * CGlue vtable for trait KeyValueStore.
+ *
+ * This virtual function table contains ABI-safe interface for the given trait.
+ */
+typedef struct KeyValueStoreVtbl_FeaturesGroupContainer_____c_void__Context {
+    void (*write_key_value)(struct FeaturesGroupContainer_____c_void__Context *cont,
+                            struct CSliceRef_u8 name,
+                            uintptr_t val);
+    uintptr_t (*get_key_value)(const struct FeaturesGroupContainer_____c_void__Context *cont,
+                               struct CSliceRef_u8 name);
+    const struct TraitDescriptor *descriptor;
+} KeyValueStoreVtbl_FeaturesGroupContainer_____c_void__Context;
+
+/**
+ * Trait group potentially implementing `MainFeature < > + :: cglue :: ext :: core :: clone :: Clone < > + KeyValueDumper < > + KeyValueStore < >` traits.
+ *
+ * Optional traits are not implemented here, however. There are numerous conversion
+ * functions available for safely retrieving a concrete collection of traits.
+ *
+ * `check_impl_` functions allow to check if the object implements the wanted traits.
+ *
+ * `into_impl_` functions consume the object and produce a new final structure that
+ * keeps only the required information.
+ *
+ * `cast_impl_` functions merely check and transform the object into a type that can
+ *be transformed back into `FeaturesGroup` without losing data.
+ *
+ * `as_ref_`, and `as_mut_` functions obtain references to safe objects, but do not
+ * perform any memory transformations either. They are the safest to use, because
+ * there is no risk of accidentally consuming the whole object.
+ */
+typedef struct FeaturesGroup_____c_void__Context {
+    const struct MainFeatureVtbl_FeaturesGroupContainer_____c_void__Context *vtbl_mainfeature;
+    const struct CloneVtbl_FeaturesGroupContainer_____c_void__Context *vtbl_clone;
+    const struct KeyValueDumperVtbl_FeaturesGroupContainer_____c_void__Context *vtbl_keyvaluedumper;
+    const struct KeyValueStoreVtbl_FeaturesGroupContainer_____c_void__Context *vtbl_keyvaluestore;
+    struct FeaturesGroupContainer_____c_void__Context container;
+} FeaturesGroup_____c_void__Context;
+
+/**
+ * CGlue vtable for trait PluginInner.
+ *
+ * This virtual function table contains ABI-safe interface for the given trait.
+ */
+typedef struct PluginInnerVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void {
+    struct FeaturesGroup_CBox_c_void_____Context (*borrow_features)(struct CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void *cont);
+    struct FeaturesGroup_CBox_c_void_____Context (*into_features)(struct CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void cont);
+    struct FeaturesGroup_____c_void__Context *(*mut_features)(struct CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void *cont);
+    const struct TraitDescriptor *descriptor;
+} PluginInnerVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void;
+
+/**
+ * Simple CGlue trait object.
+ *
+ * This is the simplest form of CGlue object, represented by a container and vtable for a single
+ * trait.
+ *
+ * Container merely is a this pointer with some optional temporary return reference context.
+ */
+typedef struct CGlueTraitObj_CBox_c_void_____PluginInnerVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void______________CArc_c_void_____PluginInnerRetTmp_CArc_c_void {
+    const struct PluginInnerVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void *vtbl;
+    struct CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void container;
+} CGlueTraitObj_CBox_c_void_____PluginInnerVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void______________CArc_c_void_____PluginInnerRetTmp_CArc_c_void;
+
+/**
+ * Base CGlue trait object for trait PluginInner.
+ */
+typedef struct CGlueTraitObj_CBox_c_void_____PluginInnerVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void______________CArc_c_void_____PluginInnerRetTmp_CArc_c_void PluginInnerBase_CBox_c_void_____CArc_c_void;
+
+/**
+ * CtxBoxed CGlue trait object for trait PluginInner with context.
+ */
+typedef PluginInnerBase_CBox_c_void_____CArc_c_void PluginInnerBaseCtxBox_c_void__CArc_c_void;
+
+/**
+ * Boxed CGlue trait object for trait PluginInner with a [`CArc`](cglue::arc::CArc) reference counted context.
+ */
+typedef PluginInnerBaseCtxBox_c_void__CArc_c_void PluginInnerBaseArcBox_c_void__c_void;
+
+/**
+ * Opaque Boxed CGlue trait object for trait PluginInner with a [`CArc`](cglue::arc::CArc) reference counted context.
+ */
+typedef PluginInnerBaseArcBox_c_void__c_void PluginInnerArcBox;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Load the plugin with the given name.
+ *
+ * The only available plugin is `kvstore`, -1 is returned for any other name.
+ */
+int32_t load_plugin(ReprCStr name, PluginInnerArcBox *ok_out);
+
+/**
+ * Get the number of plugin stores that are still alive.
+ */
+int32_t plugin_live_stores(void);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
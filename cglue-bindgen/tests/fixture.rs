@@ -0,0 +1,27 @@
+//! Regenerates `tests/data/plugin_api.h` out of the `tests/plugin` crate.
+//!
+//! cbindgen needs a nightly compiler to expand CGlue macros, thus the test is ignored by default.
+//! Run it with `cargo +nightly test -p cglue-bindgen --test fixture -- --ignored`.
+
+use cglue_bindgen::{cbindgen, Builder};
+use std::fs;
+use std::path::Path;
+
+#[test]
+#[ignore]
+fn regenerate() {
+    let tests_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let crate_dir = tests_dir.join("plugin");
+    let config = cbindgen::Config::from_root_or_default(&crate_dir);
+
+    let bindings = Builder::new(&crate_dir)
+        .with_cbindgen_config(config)
+        .generate()
+        .unwrap();
+
+    fs::write(
+        tests_dir.join("data/plugin_api.h"),
+        bindings.cbindgen_header().unwrap(),
+    )
+    .unwrap();
+}
@@ -0,0 +1,37 @@
+//! Loads the sample plugin (`tests/plugin`) through the generated cgo bindings.
+//!
+//! The test is skipped if `go` is not available.
+
+mod common;
+
+use cglue_bindgen::{process_header_for, Config, Target};
+use common::*;
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn load_and_call() {
+    let dir = out_dir("go");
+
+    let config = Config {
+        library_name: Some(PLUGIN_LIB.into()),
+        ..Default::default()
+    };
+    let bindings = process_header_for(HEADER, &config, Target::Go).unwrap();
+    fs::create_dir_all(dir.join("cglue")).unwrap();
+    fs::write(dir.join("cglue/bindings.go"), bindings).unwrap();
+    fs::write(dir.join("go.mod"), "module cgluetest\n\ngo 1.18\n").unwrap();
+    fs::copy(data("plugin.go"), dir.join("main.go")).unwrap();
+
+    let lib = plugin_lib();
+    let lib_dir = lib.parent().unwrap();
+
+    run(lib_path(
+        Command::new("go")
+            .args(["run", "."])
+            .current_dir(&dir)
+            .env("CGO_ENABLED", "1")
+            .env("CGO_LDFLAGS", format!("-L{}", lib_dir.display())),
+        lib_dir,
+    ));
+}
@@ -0,0 +1,211 @@
+//! Sample plugin API, alongside a built-in key-value store plugin.
+//!
+//! `tests/data/plugin_api.h` is generated out of this crate, and `tests/python.rs` loads it as a
+//! shared library through the generated ctypes bindings. `tests/com.rs` calls into the COM
+//! interface of the store from C.
+
+use cglue::prelude::v1::*;
+use core::mem::MaybeUninit;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+#[cglue_trait]
+pub trait PluginInner<'a> {
+    #[wrap_with_group(FeaturesGroup)]
+    type BorrowedType: MainFeature + 'a;
+    #[wrap_with_group(FeaturesGroup)]
+    type OwnedType: MainFeature + 'static;
+    #[wrap_with_group_mut(FeaturesGroup)]
+    type OwnedTypeMut: MainFeature + 'a;
+
+    fn borrow_features(&'a mut self) -> Self::BorrowedType;
+
+    fn into_features(self) -> Self::OwnedType;
+
+    fn mut_features(&'a mut self) -> &'a mut Self::OwnedTypeMut;
+}
+
+#[repr(C)]
+pub struct KeyValue<'a>(pub CSliceRef<'a, u8>, pub usize);
+
+pub type KeyValueCallback<'a> = OpaqueCallback<'a, KeyValue<'a>>;
+
+#[cglue_trait]
+#[cglue_forward]
+pub trait MainFeature {
+    fn print_self(&self);
+}
+
+#[cglue_trait]
+#[cglue_forward]
+pub trait KeyValueStore {
+    fn write_key_value(&mut self, name: &str, val: usize);
+    fn get_key_value(&self, name: &str) -> usize;
+}
+
+#[cglue_trait]
+pub trait KeyValueDumper {
+    fn dump_key_values<'a>(&'a self, callback: KeyValueCallback<'a>);
+    fn print_ints(&self, iter: CIterator<i32>);
+}
+
+/// COM compatible view of a key-value store.
+#[cglue_trait(com, guid = "0b3e4e3c-95a4-4c1d-9bd2-5e6a2f43c1a7")]
+pub trait KeyCounter {
+    fn key_count(&self) -> usize;
+    fn add_keys(&mut self, count: usize);
+}
+
+cglue_trait_group!(FeaturesGroup, {
+    MainFeature
+}, {
+    KeyValueStore,
+    KeyValueDumper,
+    Clone
+});
+
+/// Number of stores that have not been dropped yet.
+static LIVE_STORES: AtomicI32 = AtomicI32::new(0);
+
+#[derive(Default)]
+struct KvRoot {
+    store: KvStore,
+}
+
+impl<'a> PluginInner<'a> for KvRoot {
+    type BorrowedType = Fwd<&'a mut KvStore>;
+    type OwnedType = KvStore;
+    type OwnedTypeMut = KvStore;
+
+    fn borrow_features(&'a mut self) -> Self::BorrowedType {
+        self.store.forward_mut()
+    }
+
+    fn into_features(self) -> Self::OwnedType {
+        self.store
+    }
+
+    fn mut_features(&'a mut self) -> &'a mut Self::OwnedTypeMut {
+        &mut self.store
+    }
+}
+
+#[derive(Debug)]
+struct KvStore {
+    map: BTreeMap<String, usize>,
+}
+
+impl Default for KvStore {
+    fn default() -> Self {
+        LIVE_STORES.fetch_add(1, Ordering::Relaxed);
+        Self {
+            map: Default::default(),
+        }
+    }
+}
+
+impl Drop for KvStore {
+    fn drop(&mut self) {
+        LIVE_STORES.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl MainFeature for KvStore {
+    fn print_self(&self) {
+        println!("{:?}", self.map);
+    }
+}
+
+impl KeyValueStore for KvStore {
+    fn write_key_value(&mut self, name: &str, val: usize) {
+        self.map.insert(name.to_string(), val);
+    }
+
+    fn get_key_value(&self, name: &str) -> usize {
+        self.map.get(name).copied().unwrap_or(0)
+    }
+}
+
+impl KeyValueDumper for KvStore {
+    fn dump_key_values<'a>(&'a self, callback: KeyValueCallback<'a>) {
+        self.map
+            .iter()
+            .map(|(k, v)| KeyValue(k.as_str().into(), *v))
+            .feed_into(callback);
+    }
+
+    fn print_ints(&self, iter: CIterator<i32>) {
+        for (cnt, i) in iter.enumerate() {
+            println!("{}: {}", cnt, i);
+        }
+    }
+}
+
+impl KeyCounter for KvStore {
+    fn key_count(&self) -> usize {
+        self.map.len()
+    }
+
+    fn add_keys(&mut self, count: usize) {
+        for _ in 0..count {
+            let key = format!("key{}", self.map.len());
+            self.map.insert(key, 0);
+        }
+    }
+}
+
+// `KeyValueDumper` can not be forwarded automatically, because of the lifetime on its method.
+impl KeyValueDumper for Fwd<&mut KvStore> {
+    fn dump_key_values<'a>(&'a self, callback: KeyValueCallback<'a>) {
+        self.0.dump_key_values(callback)
+    }
+
+    fn print_ints(&self, iter: CIterator<i32>) {
+        self.0.print_ints(iter)
+    }
+}
+
+cglue_impl_group!(KvStore, FeaturesGroup,
+// Owned `KvStore` has these types
+{
+    KeyValueStore,
+    KeyValueDumper,
+},
+// The forward type can not be cloned
+{
+    KeyValueStore,
+    KeyValueDumper,
+});
+
+/// Load the plugin with the given name.
+///
+/// The only available plugin is `kvstore`, -1 is returned for any other name.
+#[no_mangle]
+pub extern "C" fn load_plugin(
+    name: ReprCStr<'_>,
+    ok_out: &mut MaybeUninit<PluginInnerArcBox<'static>>,
+) -> i32 {
+    if name.as_ref() != "kvstore" {
+        return -1;
+    }
+
+    ok_out.write(trait_obj!(
+        (KvRoot::default(), CArc::from(()).into_opaque()) as PluginInner
+    ));
+
+    0
+}
+
+/// Get the number of plugin stores that are still alive.
+#[no_mangle]
+pub extern "C" fn plugin_live_stores() -> i32 {
+    LIVE_STORES.load(Ordering::Relaxed)
+}
+
+/// Create an empty key-value store, and get its `KeyCounter` COM interface pointer.
+///
+/// The store is dropped once the last reference to it is released.
+#[no_mangle]
+pub extern "C" fn plugin_com_store() -> *mut cglue::trait_group::c_void {
+    cglue::com::into_raw(trait_obj!(KvStore::default() as KeyCounter))
+}
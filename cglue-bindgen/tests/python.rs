@@ -0,0 +1,25 @@
+//! Loads the sample plugin (`tests/plugin`) through the generated ctypes bindings.
+//!
+//! The test is skipped if `python3` is not available.
+
+mod common;
+
+use cglue_bindgen::{process_header_for, Config, Target};
+use common::*;
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn load_and_call() {
+    let dir = out_dir("python");
+
+    let bindings = process_header_for(HEADER, &Config::default(), Target::Python).unwrap();
+    fs::write(dir.join("bindings.py"), bindings).unwrap();
+
+    let lib = plugin_lib();
+
+    run(Command::new("python3")
+        .arg(data("plugin.py"))
+        .arg(&dir)
+        .arg(&lib));
+}
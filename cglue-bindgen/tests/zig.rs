@@ -0,0 +1,32 @@
+//! Loads the sample plugin (`tests/plugin`) through the generated Zig bindings.
+//!
+//! The test is skipped if `zig` is not available.
+
+mod common;
+
+use cglue_bindgen::{process_header_for, Config, Target};
+use common::*;
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn load_and_call() {
+    let dir = out_dir("zig");
+
+    let bindings = process_header_for(HEADER, &Config::default(), Target::Zig).unwrap();
+    fs::write(dir.join("bindings.zig"), bindings).unwrap();
+    fs::copy(data("plugin.zig"), dir.join("plugin.zig")).unwrap();
+
+    let lib = plugin_lib();
+    let lib_dir = lib.parent().unwrap();
+
+    run(lib_path(
+        Command::new("zig")
+            .arg("test")
+            .arg(dir.join("plugin.zig"))
+            .args(["-lc", "-L"])
+            .arg(lib_dir)
+            .arg(format!("-l{}", PLUGIN_LIB)),
+        lib_dir,
+    ));
+}
@@ -21,7 +21,7 @@ pub fn get_exports() -> HashMap<Ident, Path> {
     exports
 }
 
-pub fn get_store() -> HashMap<(Path, Ident), ItemTrait> {
+pub fn get_store() -> Result<HashMap<(Path, Ident), ItemTrait>> {
     let mut token_list = vec![];
 
     let mut ext_path: Path = parse2(quote!(::ext)).unwrap();
@@ -32,14 +32,14 @@ pub fn get_store() -> HashMap<(Path, Ident), ItemTrait> {
     let mut parsed_traits = HashMap::new();
 
     for (path, body) in token_list {
-        let traits = Parser::parse2(parse_traits, body).expect("Failed to parse traits");
+        let traits = Parser::parse2(parse_traits, body)?;
 
         for tr in traits {
             parsed_traits.insert((path.clone(), tr.ident.clone()), tr);
         }
     }
 
-    parsed_traits
+    Ok(parsed_traits)
 }
 
 fn subpath_to_tokens(path: &Path, skip: usize) -> TokenStream {
@@ -68,7 +68,7 @@ fn impl_mod(
     mut mod_impl: TokenStream,
     children: HashSet<Ident>,
     modules: &mut Modules,
-) -> TokenStream {
+) -> Result<TokenStream> {
     let child_depth = depth + 1;
 
     for ident in children {
@@ -85,18 +85,19 @@ fn impl_mod(
 
         let (ts, children) = modules
             .get_mut(&child_depth)
-            .expect("Module depth not found")
-            .remove(&path)
-            .expect("Child module not found");
+            .and_then(|m| m.remove(&path))
+            .ok_or_else(|| {
+                Error::new_spanned(&path, format!("child module `{}` not found", name))
+            })?;
 
-        mod_impl.extend(impl_mod(&path, &name, child_depth, ts, children, modules));
+        mod_impl.extend(impl_mod(&path, &name, child_depth, ts, children, modules)?);
     }
 
-    quote! {
+    Ok(quote! {
         pub mod #name {
             #mod_impl
         }
-    }
+    })
 }
 
 /// Remaps all Ident paths that are in the export list to become ::ext::Ident
@@ -164,7 +165,7 @@ pub fn ext_abs_remap(path: Path) -> Path {
     }
 }
 
-pub fn impl_ext_forward() -> TokenStream {
+pub fn impl_ext_forward() -> Result<TokenStream> {
     impl_inner(
         |_, _| quote!(),
         |p, _| quote!(#[cglue_forward_ext(::#p)]),
@@ -173,7 +174,7 @@ pub fn impl_ext_forward() -> TokenStream {
 }
 
 /// Implement the external trait store.
-pub fn impl_store() -> TokenStream {
+pub fn impl_store() -> Result<TokenStream> {
     impl_inner(
         |subpath, name| quote!(pub use #subpath #name;),
         |_, _| quote!(#[cglue_trait_ext]),
@@ -221,11 +222,11 @@ fn impl_inner(
     use_gen: impl Fn(&TokenStream, &Ident) -> TokenStream,
     attribute_gen: impl Fn(&TokenStream, &ItemTrait) -> TokenStream,
     exports_gen: impl Fn(HashMap<Ident, Path>, &mut TokenStream),
-) -> TokenStream {
+) -> Result<TokenStream> {
     let mut out = TokenStream::new();
 
     let exports = get_exports();
-    let store = get_store();
+    let store = get_store()?;
 
     let mut modules = HashMap::<usize, HashMap<Path, (TokenStream, HashSet<Ident>)>>::new();
 
@@ -235,7 +236,12 @@ fn impl_inner(
         // exclude :: ext :: segment, and the whole layer altogether
         let segments = p.segments.len();
 
-        assert!(segments > 1, "External traits defined in external root!");
+        if segments <= 1 {
+            return Err(Error::new_spanned(
+                &t.ident,
+                "external traits can not be defined in the external root",
+            ));
+        }
 
         let depth = segments - 2;
 
@@ -267,13 +273,16 @@ fn impl_inner(
     if let Some(root) = modules.remove(&0) {
         for (p, (ts, children)) in root {
             let name = &p.segments.iter().next_back().unwrap().ident;
-            out.extend(impl_mod(&p, name, 0, ts, children, &mut modules))
+            out.extend(impl_mod(&p, name, 0, ts, children, &mut modules)?)
         }
     } else if !modules.is_empty() {
-        panic!("Module implementations defined, but everything is disjoint from the root!");
+        return Err(Error::new(
+            proc_macro2::Span::call_site(),
+            "module implementations defined, but everything is disjoint from the root",
+        ));
     }
 
-    out
+    Ok(out)
 }
 
 fn push_to_parent(depth: usize, path: &Path, modules: &mut Modules) {
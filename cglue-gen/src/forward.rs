@@ -8,7 +8,7 @@ use super::generics::ParsedGenerics;
 use quote::*;
 use syn::*;
 
-pub fn gen_forward(tr: ItemTrait, ext_path: Option<TokenStream>) -> TokenStream {
+pub fn gen_forward(tr: ItemTrait, ext_path: Option<TokenStream>) -> Result<TokenStream> {
     let crate_path = crate::util::crate_path();
 
     let mut types = BTreeMap::new();
@@ -16,7 +16,7 @@ pub fn gen_forward(tr: ItemTrait, ext_path: Option<TokenStream>) -> TokenStream
     types.insert(
         format_ident!("Self"),
         WrappedType {
-            ty: parse2(quote!(Self)).unwrap(),
+            ty: parse2(quote!(Self))?,
             ty_ret_tmp: None,
             ty_static: None,
             return_conv: None,
@@ -36,12 +36,14 @@ pub fn gen_forward(tr: ItemTrait, ext_path: Option<TokenStream>) -> TokenStream
         &tr,
         &crate_path,
         false,
+        false,
         |(ty_ident, _, ty_where_clause, _), _, _, _, _, _| {
             if let Some(ident) = ty_ident {
                 wrapped_types.extend(quote!(type #ident = CGlueT::#ident #ty_where_clause;));
             }
+            Ok(())
         },
-    );
+    )?;
 
     let ParsedGenerics {
         life_declare,
@@ -86,12 +88,12 @@ pub fn gen_forward(tr: ItemTrait, ext_path: Option<TokenStream>) -> TokenStream
     });
 
     let send_bound = if needs_send { quote!(+ Send) } else { quote!() };
-    quote! {
+    Ok(quote! {
         #tr_impl
 
         impl<#life_declare CGlueO: #required_mutability #send_bound, CGlueT, #gen_declare> #ext_path #trait_name<#life_use #gen_use> for #crate_path::forward::Fwd<CGlueO> where CGlueT: #ext_path #trait_name<#life_use #gen_use>, #gen_where_bounds {
             #wrapped_types
             #impls
         }
-    }
+    })
 }
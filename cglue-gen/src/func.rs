@@ -34,18 +34,21 @@ impl AssocType {
     ///
     /// Currently the only supported configuration is a single generic lifetime.
     ///
-    /// # Panics
+    /// # Errors
     ///
     /// If generic types are not supported for remapping.
-    pub fn remap_for_hrtb(&self) -> Self {
+    pub fn remap_for_hrtb(&self) -> Result<Self> {
         let mut params = self.generics.params.iter();
         match (params.next(), params.next()) {
-            (Some(GenericParam::Lifetime(_)), None) => Self {
+            (Some(GenericParam::Lifetime(_)), None) => Ok(Self {
                 ident: self.ident.clone(),
-                generics: syn::parse2(quote!(<'cglue_b>)).unwrap(),
-            },
-            (None, _) => self.clone(),
-            _ => panic!("Unsupported generic parameter configuration!"),
+                generics: syn::parse2(quote!(<'cglue_b>))?,
+            }),
+            (None, _) => Ok(self.clone()),
+            _ => Err(Error::new_spanned(
+                &self.generics,
+                "unsupported generic parameter configuration, only a single lifetime parameter is allowed on wrapped associated types",
+            )),
         }
     }
 }
@@ -140,6 +143,15 @@ impl Parse for CustomFuncImpl {
     }
 }
 
+/// Conversion of a single argument, in the order of [`TraitArgConv`] fields.
+type ArgConv = (
+    TokenStream,
+    TokenStream,
+    TokenStream,
+    TokenStream,
+    TokenStream,
+);
+
 /// TraitArgConv stores implementations for Unstable-C-Unstable ABI transitions.
 struct TraitArgConv {
     /// Called in trait impl to define arguments. Useful when need to destruct a tuple/struct.
@@ -154,22 +166,21 @@ struct TraitArgConv {
     to_trait_arg: TokenStream,
 }
 
+type WrapTypeOut<'a> = Option<(Type, Option<AssocType>, &'a WrappedType)>;
+
 fn ret_wrap_type<'a>(
     ty: &mut Type,
     targets: &'a BTreeMap<Option<AssocType>, WrappedType>,
-) -> Option<(Type, Option<AssocType>, &'a WrappedType)> {
+) -> Result<WrapTypeOut<'a>> {
     // None means handle only the C side - the function will not be called on Rust side.
     // This is useful for providing functionality for C users that can be done faster in Rust.
     // TODO: perhaps switch targets to an enum to indicate C side or not.
     if let Some(wrapped) = targets.get(&None) {
         let WrappedType { ty: new_ty, .. } = wrapped;
 
-        let ret = std::mem::replace(
-            ty,
-            syn::parse2(new_ty.to_token_stream()).expect("Failed to parse wrap_type"),
-        );
+        let ret = std::mem::replace(ty, syn::parse2(new_ty.to_token_stream())?);
 
-        Some((ret, None, wrapped))
+        Ok(Some((ret, None, wrapped)))
     } else {
         do_wrap_type(ty, targets)
     }
@@ -178,7 +189,7 @@ fn ret_wrap_type<'a>(
 fn do_wrap_type<'a>(
     ty: &mut Type,
     targets: &'a BTreeMap<Option<AssocType>, WrappedType>,
-) -> Option<(Type, Option<AssocType>, &'a WrappedType)> {
+) -> Result<WrapTypeOut<'a>> {
     match ty {
         Type::Reference(r) => do_wrap_type(&mut r.elem, targets),
         Type::Slice(s) => do_wrap_type(&mut s.elem, targets),
@@ -193,24 +204,20 @@ fn do_wrap_type<'a>(
                             std::mem::drop(iter);
 
                             let ident = p2.ident.clone();
-                            let generics: Generics = syn::parse2(p2.arguments.to_token_stream())
-                                .expect("Failed to parse generics");
+                            let generics: Generics = syn::parse2(p2.arguments.to_token_stream())?;
 
-                            let ret = std::mem::replace(
-                                ty,
-                                syn::parse2(new_ty.to_token_stream())
-                                    .expect("Failed to parse wrap_type"),
-                            );
+                            let ret = std::mem::replace(ty, syn::parse2(new_ty.to_token_stream())?);
 
-                            return Some((ret, Some(AssocType::new(ident, generics)), wrapped));
+                            return Ok(Some((ret, Some(AssocType::new(ident, generics)), wrapped)));
                         }
                     }
                 }
                 (None, None, Some(p1), None) => {
                     if p1.ident == "Self" {
-                        let self_return_wrap = targets
-                            .get(&Some(p1.ident.clone().into()))
-                            .expect("No self-wrap rule specified");
+                        let self_return_wrap =
+                            targets.get(&Some(p1.ident.clone().into())).ok_or_else(|| {
+                                Error::new_spanned(&p1.ident, "`Self` can not be wrapped here")
+                            })?;
                         let WrappedType { ty: new_ty, .. } = self_return_wrap;
 
                         std::mem::drop(iter);
@@ -218,13 +225,9 @@ fn do_wrap_type<'a>(
                         let ident = p1.ident.clone();
                         // Self has no type parameters, right?
 
-                        let ret = std::mem::replace(
-                            ty,
-                            syn::parse2(new_ty.to_token_stream())
-                                .expect("Failed to parse self-type wrap"),
-                        );
+                        let ret = std::mem::replace(ty, syn::parse2(new_ty.to_token_stream())?);
 
-                        return Some((ret, Some(ident.into()), self_return_wrap));
+                        return Ok(Some((ret, Some(ident.into()), self_return_wrap)));
                     }
                 }
                 _ => {}
@@ -236,21 +239,29 @@ fn do_wrap_type<'a>(
                 if let PathArguments::AngleBracketed(brac) = &mut seg.arguments {
                     for arg in brac.args.iter_mut() {
                         if let GenericArgument::Type(ty) = arg {
-                            let ret = do_wrap_type(ty, targets);
+                            let ret = do_wrap_type(ty, targets)?;
                             if ret.is_some() {
-                                return ret;
+                                return Ok(ret);
                             }
                         }
                     }
                 }
             }
 
-            None
+            Ok(None)
         }
         Type::Ptr(ptr) => do_wrap_type(&mut ptr.elem, targets),
-        Type::Tuple(tup) => tup.elems.iter_mut().find_map(|e| do_wrap_type(e, targets)),
+        Type::Tuple(tup) => {
+            for e in tup.elems.iter_mut() {
+                let ret = do_wrap_type(e, targets)?;
+                if ret.is_some() {
+                    return Ok(ret);
+                }
+            }
+            Ok(None)
+        }
         // TODO: Other types
-        _ => None,
+        _ => Ok(None),
     }
 }
 
@@ -262,13 +273,41 @@ impl TraitArgConv {
         inject_lifetime: Option<&Lifetime>,
         inject_lifetime_cast: Option<&Lifetime>,
         lifetime_map: &BTreeMap<Lifetime, Lifetime>,
-    ) -> Self {
+        com: bool,
+    ) -> Result<Self> {
         let (to_c_args, call_c_args, c_args, c_cast_args, to_trait_arg) = match arg {
             FnArg::Receiver(r) => {
                 let lifetime = inject_lifetime.or_else(|| r.lifetime());
                 let lifetime_cast = inject_lifetime_cast.or_else(|| r.lifetime());
 
-                if r.reference.is_none() {
+                if com {
+                    // COM vtable functions take the interface pointer, which is built on the fly
+                    // when calling from Rust.
+                    let com_this = quote!(#crate_path::com::ComThis);
+                    let lifetime = lifetime.map_or_else(|| quote!('_), |l| quote!(#l));
+                    let lifetime_cast = lifetime_cast.map_or_else(|| quote!('_), |l| quote!(#l));
+
+                    if r.mutability.is_some() {
+                        (
+                            quote! {
+                                let cont: *const _ = self.get_vtbl();
+                                let mut cont = #com_this::new_mut(cont, self.ccont_mut());
+                            },
+                            quote!(&mut cont,),
+                            quote!(this: &mut #com_this<#lifetime, CGlueC>,),
+                            quote!(this: &mut #com_this<#lifetime_cast, CGlueC>,),
+                            quote!(),
+                        )
+                    } else {
+                        (
+                            quote!(let cont = #com_this::new(self.get_vtbl(), self.ccont_ref());),
+                            quote!(&cont,),
+                            quote!(this: &#com_this<#lifetime, CGlueC>,),
+                            quote!(this: &#com_this<#lifetime_cast, CGlueC>,),
+                            quote!(),
+                        )
+                    }
+                } else if r.reference.is_none() {
                     (
                         quote! {
                             let cont = self.into_ccont();
@@ -304,7 +343,7 @@ impl TraitArgConv {
             }
             FnArg::Typed(t) => {
                 let mut t = t.clone();
-                let _old = do_wrap_type(&mut t.ty, targets);
+                let _old = do_wrap_type(&mut t.ty, targets)?;
 
                 let name = &*t.pat;
 
@@ -322,11 +361,47 @@ impl TraitArgConv {
                 let mut ty = ty.clone();
                 remap_type_lifetimes(&mut ty, lifetime_map);
 
-                // TODO: deal with nested conversion
-                //if let (Some(old), Type::Path(p)) = {
-                //}
+                let result_ident = format_ident!("Result");
+
+                // Tuples get converted to CTup, including the ones nested in Option/Result.
+                if let Some(c_ty) = tuple_c_type(&ty, &result_ident, crate_path) {
+                    let to_c = tuple_conv(&ty, quote!(#name), true, &result_ident, crate_path, 0);
+                    let from_c =
+                        tuple_conv(&ty, quote!(#name), false, &result_ident, crate_path, 0);
+
+                    ret = Some((
+                        quote!(let #name = #to_c;),
+                        quote!(#name,),
+                        quote!(#name: #c_ty,),
+                        quote!(#name: #c_ty,),
+                        quote!(#from_c,),
+                    ));
+                } else if let Some(cb) = ClosureArg::parse(&ty) {
+                    ret = Some(cb.arg_conv(&name, crate_path));
+                } else if let Some((item_ty, is_dyn)) = iterator_item(&ty) {
+                    // Iterators get passed over as `CIterator`, which is an iterator itself.
+                    let iter_ty = quote!(#crate_path::iter::CIterator<#item_ty>);
+
+                    let to_trait = if is_dyn {
+                        quote!(&mut { #name },)
+                    } else {
+                        quote!(#name,)
+                    };
+
+                    ret = Some((
+                        quote! {
+                            let mut #name = #name;
+                            let #name: #iter_ty = #crate_path::iter::AsCIterator::as_citer(&mut #name);
+                        },
+                        quote!(#name,),
+                        quote!(#name: #iter_ty,),
+                        quote!(#name: #iter_ty,),
+                        to_trait,
+                    ));
+                }
 
                 match &ty {
+                    _ if ret.is_some() => {}
                     Type::Reference(r) => {
                         let is_mut = r.mutability.is_some();
 
@@ -375,8 +450,18 @@ impl TraitArgConv {
                             ))
                         }
                     }
-                    // TODO: Warn if Box is being used.
                     Type::Path(p) => {
+                        // Owned standard containers get converted to their C counterparts.
+                        if let Some((c_ty, from_c)) = owned_ffi_type(&ty, crate_path) {
+                            ret = Some((
+                                quote!(let #name = #name.into();),
+                                quote!(#name,),
+                                quote!(#name: #c_ty,),
+                                quote!(#name: #c_ty,),
+                                quote!(#name #from_c,),
+                            ));
+                        }
+
                         // Here we check for any Option types, and wrap them to COption if they can
                         // not be NPOd.
                         let last = p.path.segments.last();
@@ -466,13 +551,13 @@ impl TraitArgConv {
             }
         };
 
-        Self {
+        Ok(Self {
             to_c_args,
             call_c_args,
             c_args,
             c_cast_args,
             to_trait_arg,
-        }
+        })
     }
 }
 
@@ -489,6 +574,8 @@ pub struct ParsedFunc {
     sig_generics: ParsedGenerics,
     custom_conv: CustomFuncConv,
     only_c_side: bool,
+    /// Whether the function is a part of a COM compatible vtable.
+    com: bool,
 }
 
 impl ParsedFunc {
@@ -503,7 +590,8 @@ impl ParsedFunc {
         crate_path: &TokenStream,
         only_c_side: bool,
         custom_impl: Option<CustomFuncImpl>,
-    ) -> Option<Self> {
+        com: bool,
+    ) -> Result<Option<Self>> {
         let name = sig.ident;
         let safe = sig.unsafety.is_none();
         let abi = From::from(sig.abi);
@@ -520,7 +608,18 @@ impl ParsedFunc {
             }
         }
 
-        let receiver = receiver?;
+        let receiver = match receiver {
+            Some(receiver) => receiver,
+            None => return Ok(None),
+        };
+
+        // COM clients only hold interface pointers, which can not be consumed.
+        if com && receiver.reference.is_none() {
+            return Err(Error::new_spanned(
+                &receiver,
+                "functions of COM compatible traits must take `&self`, or `&mut self`",
+            ));
+        }
 
         let out = ParsedReturnType::new(
             (sig.output, custom_impl.as_ref().map(|i| &i.c_ret_ty)),
@@ -530,7 +629,7 @@ impl ParsedFunc {
             &unsafety,
             (&name, &receiver),
             (crate_path, &trait_name, generics),
-        );
+        )?;
 
         // If a custom impl is provided, use its arguments
         let custom_conv = if let Some(CustomFuncImpl {
@@ -556,7 +655,8 @@ impl ParsedFunc {
                     out.lifetime.as_ref(),
                     out.lifetime_cast.as_ref(),
                     &out.lifetime_map,
-                ));
+                    com,
+                )?);
             }
 
             CustomFuncConv {
@@ -573,7 +673,8 @@ impl ParsedFunc {
                     out.lifetime.as_ref(),
                     out.lifetime_cast.as_ref(),
                     &out.lifetime_map,
-                );
+                    com,
+                )?;
 
                 args.push(func);
                 orig_args.push(input);
@@ -586,7 +687,7 @@ impl ParsedFunc {
 
         let sig_generics = ParsedGenerics::from(&sig.generics);
 
-        Some(Self {
+        Ok(Some(Self {
             name,
             trait_name,
             safe,
@@ -599,7 +700,8 @@ impl ParsedFunc {
             sig_generics,
             only_c_side,
             custom_conv,
-        })
+            com,
+        }))
     }
 
     pub fn ret_tmp_def(&self, stream: &mut TokenStream) {
@@ -621,6 +723,11 @@ impl ParsedFunc {
         }
     }
 
+    /// Whether the temporary return storage is written to through a shared reference.
+    pub fn ret_tmp_interior_mut(&self) -> bool {
+        self.out.injected_ret_tmp.is_some() && self.receiver.mutability.is_none()
+    }
+
     pub fn ret_default_def(&self, stream: &mut TokenStream) {
         let name = &self.name;
         if self.out.injected_ret_tmp.is_some() {
@@ -749,8 +856,12 @@ impl ParsedFunc {
         for arg in self.orig_args.iter().skip(skip) {
             match arg {
                 FnArg::Typed(ty) => {
-                    let pat = &ty.pat;
-                    quote!(#pat,).to_tokens(&mut ret);
+                    if let Pat::Ident(PatIdent { ident, .. }) = &*ty.pat {
+                        quote!(#ident,).to_tokens(&mut ret);
+                    } else {
+                        let pat = &ty.pat;
+                        quote!(#pat,).to_tokens(&mut ret);
+                    }
                 }
                 FnArg::Receiver(_) => {
                     quote!(self,).to_tokens(&mut ret);
@@ -825,8 +936,10 @@ impl ParsedFunc {
 
         let sig_life_declare = merge_lifetime_declarations(&sig_life_declare, &parse_quote!(#hrtb));
 
+        let vtbl_abi = self.vtbl_abi();
+
         let gen = quote! {
-            #name: for<#sig_life_declare> extern "C" fn(#args #c_ret_params) #c_out,
+            #name: for<#sig_life_declare> #vtbl_abi fn(#args #c_ret_params) #c_out,
         };
 
         stream.extend(gen);
@@ -868,12 +981,14 @@ impl ParsedFunc {
 
         let doc_text = format!(" Getter for {}.", name);
 
+        let vtbl_abi = self.vtbl_abi();
+
         let gen = quote! {
             #[doc = #doc_text]
             ///
             /// Note that this function is wrapped into unsafe, because if already were is an
             /// opaque one, it would allow to invoke undefined behaviour.
-            pub fn #name(&self) -> for<#sig_life_declare> unsafe extern "C" fn(#args #c_ret_params) #c_out {
+            pub fn #name(&self) -> for<#sig_life_declare> unsafe #vtbl_abi fn(#args #c_ret_params) #c_out {
                 unsafe { ::core::mem::transmute(self.#name) }
             }
         };
@@ -897,7 +1012,7 @@ impl ParsedFunc {
                 ///
                 /// This ought to only be used when references to objects are being returned,
                 /// otherwise there is a risk of lifetime rule breakage.
-                unsafe fn #name2(&self) -> for<#lifetime_cast> #safety extern "C" fn(#args_cast #c_ret_params) #c_cast_out {
+                unsafe fn #name2(&self) -> for<#lifetime_cast> #safety #vtbl_abi fn(#args_cast #c_ret_params) #c_cast_out {
                     ::core::mem::transmute(self.#name)
                 }
             };
@@ -1030,10 +1145,24 @@ impl ParsedFunc {
             c_where_bounds
         };
 
+        // COM thunks receive the interface pointer, and recover the container out of it.
+        let c_pre_call = match (self.com, self.receiver.mutability.is_some()) {
+            (true, true) => quote! {
+                let cont = unsafe { this.container_mut() };
+                #c_pre_call
+            },
+            (true, false) => quote! {
+                let cont = unsafe { this.container() };
+                #c_pre_call
+            },
+            _ => c_pre_call,
+        };
+
         let ctx_bound = super::traits::ctx_bound();
+        let vtbl_abi = self.vtbl_abi();
 
         let gen = quote! {
-            #safety extern "C" fn #fnname<#sig_life_declare CGlueC: #container_bound, CGlueCtx: #ctx_bound, #gen_declare>(#args #c_ret_params) #c_out where #gen_where_bounds #c_where_bounds #cglue_c_into_inner CGlueC::ObjType: for<'cglue_b> #trname<#tmp_lifetime #gen_use>, {
+            #safety #vtbl_abi fn #fnname<#sig_life_declare CGlueC: #container_bound, CGlueCtx: #ctx_bound, #gen_declare>(#args #c_ret_params) #c_out where #gen_where_bounds #c_where_bounds #cglue_c_into_inner CGlueC::ObjType: for<'cglue_b> #trname<#tmp_lifetime #gen_use>, {
                 #c_pre_call
                 let ret = #inner_impl;
                 #c_ret
@@ -1051,6 +1180,17 @@ impl ParsedFunc {
         tokens.extend(quote!(#name: #fnname,));
     }
 
+    /// ABI of the functions stored in the vtable.
+    ///
+    /// COM compatible vtables use the `system` ABI (see [`com_abi`](super::traits::com_abi)).
+    fn vtbl_abi(&self) -> TokenStream {
+        if self.com {
+            super::traits::com_abi()
+        } else {
+            quote!(extern "C")
+        }
+    }
+
     pub fn get_safety(&self) -> TokenStream {
         if self.safe {
             quote!()
@@ -1155,6 +1295,75 @@ impl ParsedFunc {
         self.receiver.mutability.is_some()
     }
 
+    /// Generate a trait function implementation that locks a `CSync` wrapper.
+    ///
+    /// Returns `false` if the function can not be forwarded through a lock guard, because it
+    /// either consumes `self`, returns data borrowed from it, or takes arguments with named
+    /// lifetimes, which may tie them to `self`.
+    pub fn lock_wrapped_trait_impl(
+        &self,
+        trait_path: &TokenStream,
+        tokens: &mut TokenStream,
+    ) -> bool {
+        if self.only_c_side {
+            return true;
+        }
+
+        let ParsedReturnType {
+            ty: out,
+            use_wrap,
+            return_self,
+            ..
+        } = &self.out;
+
+        let self_lifetime = matches!(&self.receiver.reference, Some((_, Some(_))));
+
+        if self.receiver.reference.is_none()
+            || self_lifetime
+            || *use_wrap
+            || *return_self
+            || has_borrow(out.to_token_stream())
+            || self.orig_args.iter().any(|a| match a {
+                FnArg::Typed(t) => has_lifetime(t.ty.to_token_stream()),
+                FnArg::Receiver(_) => false,
+            })
+        {
+            return false;
+        }
+
+        let name = &self.name;
+        let args = self.trait_args();
+        let passthrough_args = self.trait_passthrough_args(1);
+        let safety = self.get_safety();
+        let abi = self.abi.prefix();
+
+        let ParsedGenerics {
+            life_declare,
+            gen_declare,
+            ..
+        } = &self.sig_generics;
+
+        let crate_path = crate::util::crate_path();
+
+        let (guard, this) = if self.receiver.mutability.is_some() {
+            (quote!(mut guard), quote!(&mut *guard))
+        } else {
+            (quote!(guard), quote!(&*guard))
+        };
+
+        let gen = quote! {
+            #[inline(always)]
+            #safety #abi fn #name <#life_declare #gen_declare> (#args) #out {
+                let #guard = #crate_path::lock::CSync::lock(self);
+                <CGlueT as #trait_path>::#name(#this, #passthrough_args)
+            }
+        };
+
+        tokens.extend(gen);
+
+        true
+    }
+
     pub fn arc_wrapped_trait_impl(&self, tokens: &mut TokenStream) {
         let name = &self.name;
         let args = self.trait_args();
@@ -1219,6 +1428,265 @@ impl ParsedFunc {
 
         tokens.extend(gen);
     }
+
+    /// Whether the function can be called dynamically, with all arguments being basic values.
+    fn reflect_callable(&self) -> bool {
+        self.safe
+            && !self.only_c_side
+            && self.receiver.reference.is_some()
+            && self.sig_generics.gen_declare.is_empty()
+            && self.orig_args.iter().all(|a| match a {
+                FnArg::Typed(t) => reflect_type(&t.ty).2,
+                FnArg::Receiver(_) => true,
+            })
+            && match &self.out.ty {
+                ReturnType::Default => true,
+                ReturnType::Type(_, ty) => reflect_type(ty).2,
+            }
+    }
+
+    /// Create a `MethodInfo` definition for this function.
+    pub fn reflect_def(&self, crate_path: &TokenStream, stream: &mut TokenStream) {
+        let reflect = quote!(#crate_path::reflect);
+        let name = self.name.to_string();
+
+        let receiver = match (&self.receiver.reference, &self.receiver.mutability) {
+            (None, _) => quote!(#reflect::ArgKind::Owned),
+            (Some(_), None) => quote!(#reflect::ArgKind::Ref),
+            (Some(_), Some(_)) => quote!(#reflect::ArgKind::Mut),
+        };
+
+        let mut args = TokenStream::new();
+
+        for arg in &self.orig_args {
+            if let FnArg::Typed(t) = arg {
+                let arg_name = match &*t.pat {
+                    Pat::Ident(PatIdent { ident, .. }) => ident.to_string(),
+                    _ => "_".to_string(),
+                };
+                let (kind, ty, _) = reflect_type(&t.ty);
+                let ty_name = type_name(&t.ty);
+                args.extend(quote! {
+                    #reflect::ArgInfo {
+                        name: #arg_name,
+                        kind: #reflect::ArgKind::#kind,
+                        ty: #reflect::BasicType::#ty,
+                        ty_name: #ty_name,
+                    },
+                });
+            }
+        }
+
+        let (ret, ret_name) = match &self.out.ty {
+            ReturnType::Default => (format_ident!("Unit"), "()".to_string()),
+            ReturnType::Type(_, ty) => (reflect_type(ty).1, type_name(ty)),
+        };
+
+        let callable = self.reflect_callable();
+        let signature = self.signature();
+
+        stream.extend(quote! {
+            #reflect::MethodInfo {
+                name: #name,
+                receiver: #receiver,
+                args: &[#args],
+                ret: #reflect::BasicType::#ret,
+                ret_name: #ret_name,
+                signature: #signature,
+                callable: #callable,
+            },
+        });
+    }
+
+    /// Signature of the function, as written in the trait.
+    pub fn signature(&self) -> String {
+        let mut ret = String::new();
+
+        if !self.safe {
+            ret.push_str("unsafe ");
+        }
+
+        if self.abi == FuncAbi::ReprC {
+            ret.push_str("extern \"C\" ");
+        }
+
+        let args = self
+            .orig_args
+            .iter()
+            .map(|arg| match arg {
+                FnArg::Receiver(r) => type_name_tokens(r.to_token_stream()),
+                FnArg::Typed(t) => format!(
+                    "{}: {}",
+                    type_name_tokens(t.pat.to_token_stream()),
+                    type_name(&t.ty)
+                ),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        ret.push_str(&format!("fn {}({})", self.name, args));
+
+        if let ReturnType::Type(_, ty) = &self.out.ty {
+            ret.push_str(&format!(" -> {}", type_name(ty)));
+        }
+
+        ret
+    }
+
+    /// Create a match arm calling this function with dynamic arguments.
+    ///
+    /// The arm is expected to be within a function with `this`, and `args` variables, `this`
+    /// implementing the trait.
+    pub fn reflect_call_def(&self, crate_path: &TokenStream, stream: &mut TokenStream) {
+        let reflect = quote!(#crate_path::reflect);
+        let name = &self.name;
+        let name_str = name.to_string();
+
+        if !self.reflect_callable() {
+            stream.extend(quote! {
+                #name_str => Some(Err(#reflect::CallError::NotCallable)),
+            });
+            return;
+        }
+
+        let trait_name = &self.trait_name;
+
+        let mut call_args = TokenStream::new();
+        let mut count = 0usize;
+
+        for arg in &self.orig_args {
+            if let FnArg::Typed(_) = arg {
+                call_args.extend(quote!(#reflect::arg(args, #count)?,));
+                count += 1;
+            }
+        }
+
+        stream.extend(quote! {
+            #name_str => Some(#reflect::invoke(args, #count, |args| {
+                Ok(#reflect::IntoValue::into_value(#trait_name::#name(this, #call_args)))
+            })),
+        });
+    }
+}
+
+/// Classify a type for reflection.
+///
+/// Returns the argument kind, basic type variant, and whether the type can be converted to, and
+/// from dynamic values.
+fn reflect_type(ty: &Type) -> (Ident, Ident, bool) {
+    match ty {
+        Type::Reference(r) => {
+            let (_, basic, _) = reflect_type(&r.elem);
+            let is_str = match &*r.elem {
+                Type::Path(p) => basic_type_name(&p.path).as_deref() == Some("str"),
+                _ => false,
+            };
+            if r.mutability.is_some() {
+                (format_ident!("Mut"), basic, false)
+            } else {
+                (format_ident!("Ref"), basic, is_str)
+            }
+        }
+        Type::Paren(p) => reflect_type(&p.elem),
+        Type::Group(g) => reflect_type(&g.elem),
+        Type::Tuple(t) if t.elems.is_empty() => {
+            (format_ident!("Owned"), format_ident!("Unit"), true)
+        }
+        Type::Path(p) if p.qself.is_none() => {
+            let name = basic_type_name(&p.path);
+            let basic = match name.as_deref() {
+                Some("bool") => "Bool",
+                Some("i8" | "i16" | "i32" | "i64" | "isize") => "Int",
+                Some("u8" | "u16" | "u32" | "u64" | "usize") => "UInt",
+                Some("f32" | "f64") => "Float",
+                Some("char") => "Char",
+                Some("str" | "String") => "Str",
+                _ => "Other",
+            };
+            // `str` is unsized, thus can only be passed by reference.
+            let callable = basic != "Other" && name.as_deref() != Some("str");
+            (format_ident!("Owned"), format_ident!("{}", basic), callable)
+        }
+        _ => (format_ident!("Owned"), format_ident!("Other"), false),
+    }
+}
+
+/// Name of the basic type the path refers to.
+///
+/// Types are recognized by name, either written as a bare identifier (`u32`), or as the full path
+/// to the standard library type (`core::primitive::u32`, `std::string::String`). Type aliases, and
+/// any other paths can not be resolved by the macro, and are left out.
+fn basic_type_name(path: &Path) -> Option<String> {
+    if let Some(ident) = path.get_ident() {
+        return Some(ident.to_string());
+    }
+
+    let segments = path
+        .segments
+        .iter()
+        .map(|s| match s.arguments {
+            PathArguments::None => Some(s.ident.to_string()),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    match segments.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["std" | "core", "primitive", ty] => Some(ty.to_string()),
+        ["std" | "alloc", "string", "String"] => Some("String".to_string()),
+        _ => None,
+    }
+}
+
+/// Format the type the way it is written in code.
+fn type_name(ty: &Type) -> String {
+    type_name_tokens(ty.to_token_stream())
+}
+
+fn type_name_tokens(tokens: TokenStream) -> String {
+    tokens
+        .to_string()
+        .replace(" :: ", "::")
+        .replace(":: ", "::")
+        .replace("& ", "&")
+        .replace(" <", "<")
+        .replace("< ", "<")
+        .replace(" >", ">")
+        .replace(" ,", ",")
+}
+
+/// Check whether the tokens contain a reference, or a lifetime.
+fn has_borrow(tokens: TokenStream) -> bool {
+    use proc_macro2::TokenTree;
+
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Punct(p) => p.as_char() == '&' || p.as_char() == '\'',
+        TokenTree::Group(g) => has_borrow(g.stream()),
+        _ => false,
+    })
+}
+
+/// Check whether the tokens contain a named lifetime other than `'static`.
+///
+/// Elided references in arguments only live for the duration of the call, while named lifetimes
+/// may tie the arguments to `self`.
+fn has_lifetime(tokens: TokenStream) -> bool {
+    use proc_macro2::TokenTree;
+
+    let mut tokens = tokens.into_iter().peekable();
+
+    while let Some(tt) = tokens.next() {
+        match tt {
+            TokenTree::Punct(p) if p.as_char() == '\'' => {
+                if !matches!(tokens.peek(), Some(TokenTree::Ident(i)) if i == "static") {
+                    return true;
+                }
+            }
+            TokenTree::Group(g) if has_lifetime(g.stream()) => return true,
+            _ => {}
+        }
+    }
+
+    false
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -1320,7 +1788,7 @@ impl ParsedReturnType {
         unsafety: &TokenStream,
         (func_name, receiver): (&Ident, &Receiver),
         (crate_path, trait_name, trait_generics): (&TokenStream, &Ident, &ParsedGenerics),
-    ) -> Self {
+    ) -> Result<Self> {
         let mut c_ty = c_override.unwrap_or(&ty).clone();
 
         let mut ret = Self {
@@ -1349,7 +1817,7 @@ impl ParsedReturnType {
         if let ReturnType::Type(_, ty) = &mut c_ty {
             let mut ty_cast = None;
 
-            if let Some(wrapped) = ret_wrap_type(&mut *ty, targets) {
+            if let Some(wrapped) = ret_wrap_type(&mut *ty, targets)? {
                 let old_ty = wrapped.0;
                 let trait_ty = wrapped.1;
                 let WrappedType {
@@ -1385,10 +1853,10 @@ impl ParsedReturnType {
                     }
                     (false, _) => (false, None),
                     _ => {
-                        panic!(
-                            "Wrapped ref return currently only valid for references! (ty: {ty})",
-                            ty = ty.to_token_stream()
-                        )
+                        return Err(Error::new_spanned(
+                            old_ty,
+                            "wrapped ref return is currently only valid for references",
+                        ))
                     }
                 };
 
@@ -1527,7 +1995,50 @@ impl ParsedReturnType {
                 ret.lifetime_cast = lifetime_cast;
             }
 
+            let owned = if ret.use_wrap {
+                None
+            } else {
+                owned_ffi_type(ty, crate_path)
+            };
+
+            if let Some((c_ty, from_c)) = owned {
+                ret.c_out = quote!(-> #c_ty);
+                ret.c_cast_out = quote!(-> #c_ty);
+                ret.c_ret.extend(quote!(.into()));
+                ret.impl_func_ret.extend(from_c);
+            }
+
+            let result_ident = format_ident!("Result");
+            let result_ident = res_override.unwrap_or(&result_ident);
+
+            // Integer results write the value out through a pointer, leave them alone.
+            let is_int_result = int_result
+                && matches!(&**ty, Type::Path(p) if p.path.segments.last().map(|s| &s.ident) == Some(result_ident));
+
+            let tuple = if ret.use_wrap || is_int_result {
+                None
+            } else {
+                tuple_c_type(ty, result_ident, crate_path)
+            };
+
+            if let Some(c_ty) = &tuple {
+                let c_ret = &ret.c_ret;
+                let impl_func_ret = &ret.impl_func_ret;
+                ret.c_out = quote!(-> #c_ty);
+                ret.c_cast_out = quote!(-> #c_ty);
+                ret.c_ret = tuple_conv(ty, quote!(#c_ret), true, result_ident, crate_path, 0);
+                ret.impl_func_ret = tuple_conv(
+                    ty,
+                    quote!(#impl_func_ret),
+                    false,
+                    result_ident,
+                    crate_path,
+                    0,
+                );
+            }
+
             match &mut **ty {
+                _ if tuple.is_some() => {}
                 Type::Reference(r) => {
                     let is_mut = r.mutability.is_some();
 
@@ -1609,8 +2120,7 @@ impl ParsedReturnType {
                                 if let Some(GenericArgument::Type(a)) = args.args.first() {
                                     if !crate::util::is_null_pointer_optimizable(a, &[]) {
                                         let new_path: Path =
-                                            parse2(quote!(#crate_path::option::COption))
-                                                .expect("Failed to parse COption path");
+                                            parse2(quote!(#crate_path::option::COption))?;
 
                                         replace_path_keep_final_args(
                                             Some(&mut **ty),
@@ -1685,8 +2195,7 @@ impl ParsedReturnType {
                                             _,
                                         ) => {
                                             let new_path: Path =
-                                                parse2(quote!(#crate_path::result::CResult))
-                                                    .expect("Failed to parse CResult path");
+                                                parse2(quote!(#crate_path::result::CResult))?;
 
                                             replace_path_keep_final_args(
                                                 Some(&mut **ty),
@@ -1726,10 +2235,440 @@ impl ParsedReturnType {
             }
         }
 
-        ret
+        Ok(ret)
+    }
+}
+
+/// Number of elements in the largest `CTup` type.
+const MAX_CTUP: usize = 4;
+
+/// Split `Option<T>` and `Result<T, E>` types into their type arguments.
+fn option_result_args<'a>(ty: &'a Type, result_ident: &Ident) -> Option<(bool, Vec<&'a Type>)> {
+    let last = match ty {
+        Type::Path(p) if p.qself.is_none() => p.path.segments.last()?,
+        _ => return None,
+    };
+
+    let args = match &last.arguments {
+        PathArguments::AngleBracketed(a) => a
+            .args
+            .iter()
+            .map(|a| match a {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?,
+        _ => return None,
+    };
+
+    match args.len() {
+        1 if last.ident == "Option" => Some((true, args)),
+        2 if &last.ident == result_ident => Some((false, args)),
+        _ => None,
+    }
+}
+
+/// Rewrite tuples into `CTup` types, if the type contains any.
+///
+/// Tuples nested within other tuples, `Option`, and `Result` types are rewritten as well, in
+/// which case the outer `Option` or `Result` becomes `COption` or `CResult`.
+fn tuple_c_type(ty: &Type, result_ident: &Ident, crate_path: &TokenStream) -> Option<TokenStream> {
+    let conv_or_orig = |ty: &Type| {
+        tuple_c_type(ty, result_ident, crate_path).unwrap_or_else(|| ty.to_token_stream())
+    };
+
+    match ty {
+        Type::Tuple(tup) if !tup.elems.is_empty() && tup.elems.len() <= MAX_CTUP => {
+            let name = format_ident!("CTup{}", tup.elems.len());
+            let elems = tup.elems.iter().map(conv_or_orig);
+            Some(quote!(#crate_path::tuple::#name<#(#elems),*>))
+        }
+        Type::Paren(p) => tuple_c_type(&p.elem, result_ident, crate_path),
+        _ => {
+            let (is_option, args) = option_result_args(ty, result_ident)?;
+
+            if args
+                .iter()
+                .all(|a| tuple_c_type(a, result_ident, crate_path).is_none())
+            {
+                return None;
+            }
+
+            let args = args.into_iter().map(conv_or_orig);
+
+            if is_option {
+                Some(quote!(#crate_path::option::COption<#(#args),*>))
+            } else {
+                Some(quote!(#crate_path::result::CResult<#(#args),*>))
+            }
+        }
+    }
+}
+
+/// Build an expression converting `expr` of type `ty` to (or from) its [`tuple_c_type`].
+fn tuple_conv(
+    ty: &Type,
+    expr: TokenStream,
+    into_c: bool,
+    result_ident: &Ident,
+    crate_path: &TokenStream,
+    depth: usize,
+) -> TokenStream {
+    if tuple_c_type(ty, result_ident, crate_path).is_none() {
+        return expr;
+    }
+
+    let conv = |ty: &Type, expr: TokenStream| {
+        tuple_conv(ty, expr, into_c, result_ident, crate_path, depth + 1)
+    };
+
+    match ty {
+        Type::Tuple(tup) => {
+            let name = format_ident!("CTup{}", tup.elems.len());
+            let binds = (0..tup.elems.len())
+                .map(|i| format_ident!("cglue_t{}_{}", depth, i))
+                .collect::<Vec<_>>();
+            let elems = tup
+                .elems
+                .iter()
+                .zip(&binds)
+                .map(|(ty, b)| conv(ty, quote!(#b)));
+
+            if into_c {
+                quote!({
+                    let (#(#binds,)*) = #expr;
+                    #crate_path::tuple::#name(#(#elems),*)
+                })
+            } else {
+                quote!({
+                    let #crate_path::tuple::#name(#(#binds),*) = #expr;
+                    (#(#elems,)*)
+                })
+            }
+        }
+        Type::Paren(p) => conv(&p.elem, expr),
+        _ => {
+            let (is_option, args) = match option_result_args(ty, result_ident) {
+                Some(v) => v,
+                None => return expr,
+            };
+
+            let val = format_ident!("cglue_v{}", depth);
+            let map_ok = conv(args[0], quote!(#val));
+
+            let mapped = if is_option {
+                quote!(.map(|#val| #map_ok))
+            } else {
+                let map_err = conv(args[1], quote!(#val));
+                quote!(.map(|#val| #map_ok).map_err(|#val| #map_err))
+            };
+
+            match (into_c, is_option) {
+                (true, true) => quote!(#crate_path::option::COption::from(#expr #mapped)),
+                (true, false) => quote!(#crate_path::result::CResult::from(#expr #mapped)),
+                (false, true) => quote!(::core::option::Option::from(#expr) #mapped),
+                (false, false) => quote!(::core::result::Result::from(#expr) #mapped),
+            }
+        }
     }
 }
 
+/// Get the single trait bound of `impl Trait`, `&dyn Trait`, or `&mut dyn Trait` types.
+///
+/// Returns the trait path segment, and the mutability of the reference, if the type is a trait
+/// object. Types with multiple bounds (including lifetime ones) are not matched.
+fn impl_or_dyn_bound(ty: &Type) -> Option<(&PathSegment, Option<bool>)> {
+    let (bounds, dyn_ref) = match ty {
+        Type::ImplTrait(t) => (&t.bounds, None),
+        Type::Reference(r) => {
+            let is_mut = Some(r.mutability.is_some());
+            match &*r.elem {
+                Type::TraitObject(t) => (&t.bounds, is_mut),
+                Type::Paren(p) => match &*p.elem {
+                    Type::TraitObject(t) => (&t.bounds, is_mut),
+                    _ => return None,
+                },
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    match bounds.first() {
+        Some(TypeParamBound::Trait(t)) if bounds.len() == 1 && t.path.segments.len() == 1 => {
+            Some((t.path.segments.first()?, dyn_ref))
+        }
+        _ => None,
+    }
+}
+
+/// Parse `impl Iterator<Item = T>` and `&mut dyn Iterator<Item = T>` argument types.
+///
+/// Returns the item type, and whether the iterator is a trait object.
+fn iterator_item(ty: &Type) -> Option<(&Type, bool)> {
+    let (seg, dyn_ref) = impl_or_dyn_bound(ty)?;
+
+    if seg.ident != "Iterator" || dyn_ref == Some(false) {
+        return None;
+    }
+
+    match &seg.arguments {
+        PathArguments::AngleBracketed(a) if a.args.len() == 1 => match a.args.first()? {
+            GenericArgument::Binding(b) if b.ident == "Item" => Some((&b.ty, dyn_ref.is_some())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Closure argument that gets converted to an FFI-safe callback.
+struct ClosureArg<'a> {
+    /// Types of the arguments passed to the closure.
+    inputs: Vec<&'a Type>,
+    /// Return type of the closure, `None` if it returns nothing.
+    output: Option<&'a Type>,
+    /// Whether the closure is `Fn`, as opposed to `FnMut`.
+    is_fn: bool,
+    /// Mutability of the reference, if the closure is passed as a trait object.
+    dyn_ref: Option<bool>,
+}
+
+impl<'a> ClosureArg<'a> {
+    /// Parse `impl FnMut(A, B) -> R` and `&mut dyn FnMut(A, B) -> R` argument types.
+    ///
+    /// `Fn` closures may also be passed as `&dyn Fn(A, B) -> R`. Up to `MAX_CTUP` arguments are
+    /// supported. Closures with explicit lifetime bounds are not converted, because the callback
+    /// only lives for the duration of the call.
+    fn parse(ty: &'a Type) -> Option<Self> {
+        let (seg, dyn_ref) = impl_or_dyn_bound(ty)?;
+
+        let is_fn = match seg.ident.to_string().as_str() {
+            "Fn" => true,
+            "FnMut" if dyn_ref != Some(false) => false,
+            _ => return None,
+        };
+
+        let args = match &seg.arguments {
+            PathArguments::Parenthesized(a) if (1..=MAX_CTUP).contains(&a.inputs.len()) => a,
+            _ => return None,
+        };
+
+        let output = match &args.output {
+            ReturnType::Type(_, ty) if !matches!(&**ty, Type::Tuple(t) if t.elems.is_empty()) => {
+                Some(&**ty)
+            }
+            _ => None,
+        };
+
+        Some(Self {
+            inputs: args.inputs.iter().collect(),
+            output,
+            is_fn,
+            dyn_ref,
+        })
+    }
+
+    /// Build argument conversion for the closure.
+    ///
+    /// The closure gets passed over as a callback, and wrapped back into a closure on the C side.
+    fn arg_conv(&self, name: &TokenStream, crate_path: &TokenStream) -> ArgConv {
+        let opaque = self.is_opaque_callback();
+
+        let (args_ty, args_pat, call_args) = match &*self.inputs {
+            [ty] => (quote!(#ty), quote!(cglue_arg), quote!(cglue_arg)),
+            inputs => {
+                let tup = format_ident!("CTup{}", inputs.len());
+                let binds = (0..inputs.len())
+                    .map(|i| format_ident!("cglue_arg{}", i))
+                    .collect::<Vec<_>>();
+                (
+                    quote!(#crate_path::tuple::#tup<#(#inputs),*>),
+                    quote!(#crate_path::tuple::#tup(#(#binds),*)),
+                    quote!(#(#binds),*),
+                )
+            }
+        };
+
+        let cb_ty = if opaque {
+            quote!(#crate_path::callback::OpaqueCallback<#args_ty>)
+        } else {
+            let ret_ty = self.output.map_or_else(|| quote!(()), |ty| quote!(#ty));
+            if self.is_fn {
+                quote!(#crate_path::callback::CFn<#args_ty, #ret_ty>)
+            } else {
+                quote!(#crate_path::callback::CFnMut<#args_ty, #ret_ty>)
+            }
+        };
+
+        // Callbacks without a return value never stop early.
+        let (call, call_c) = if opaque && self.output.is_none() {
+            (
+                quote!({
+                    #name(#call_args);
+                    true
+                }),
+                quote!({
+                    let _ = #name.call(#args_pat);
+                }),
+            )
+        } else {
+            (quote!(#name(#call_args)), quote!(#name.call(#args_pat)))
+        };
+
+        let to_trait_ref = match self.dyn_ref {
+            Some(true) => quote!(&mut),
+            Some(false) => quote!(&),
+            None => quote!(),
+        };
+
+        let (to_c, to_trait) = if self.is_fn {
+            (
+                quote! {
+                    let #name = |#args_pat| #call;
+                    let #name: #cb_ty = (&#name).into();
+                },
+                quote!(#to_trait_ref move |#call_args| #call_c,),
+            )
+        } else {
+            let rebind = if self.dyn_ref.is_none() {
+                quote!(let mut #name = #name;)
+            } else {
+                quote!()
+            };
+
+            (
+                quote! {
+                    #rebind
+                    let mut #name = |#args_pat| #call;
+                    let #name: #cb_ty = (&mut #name).into();
+                },
+                quote!(#to_trait_ref {
+                    let mut #name = #name;
+                    move |#call_args| #call_c
+                },),
+            )
+        };
+
+        (
+            to_c,
+            quote!(#name,),
+            quote!(#name: #cb_ty,),
+            quote!(#name: #cb_ty,),
+            to_trait,
+        )
+    }
+
+    /// Whether the closure gets passed as an `OpaqueCallback`.
+    ///
+    /// This is the case for `FnMut(T) -> bool` closures, and ones that do not return anything.
+    fn is_opaque_callback(&self) -> bool {
+        let ret_ok = match self.output {
+            None => true,
+            Some(Type::Path(p)) => p.path.is_ident("bool"),
+            _ => false,
+        };
+
+        !self.is_fn && self.inputs.len() == 1 && ret_ok
+    }
+}
+
+/// Map owned `String`, `Vec<T>`, `Box<T>` and `Box<[T]>` to their FFI-safe counterparts.
+///
+/// Boxed closures are mapped using [`fn_box_type`].
+///
+/// Returns the C type, and the method call suffix that converts the C value back into the
+/// original type. Conversion into the C type is always done through `Into`.
+fn owned_ffi_type(ty: &Type, crate_path: &TokenStream) -> Option<(TokenStream, TokenStream)> {
+    let last = match ty {
+        Type::Path(p) if p.qself.is_none() => p.path.segments.last()?,
+        _ => return None,
+    };
+
+    let arg = match &last.arguments {
+        PathArguments::None => None,
+        PathArguments::AngleBracketed(a) if a.args.len() == 1 => match a.args.first() {
+            Some(GenericArgument::Type(ty)) => Some(ty),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    match (last.ident.to_string().as_str(), arg) {
+        ("String", None) => Some((
+            quote!(#crate_path::repr_cstring::ReprCString),
+            quote!(.into()),
+        )),
+        ("Vec", Some(ty)) => Some((quote!(#crate_path::vec::CVec<#ty>), quote!(.into()))),
+        ("Box", Some(Type::Slice(s))) => {
+            let ty = &s.elem;
+            Some((
+                quote!(#crate_path::boxed::CSliceBox<'static, #ty>),
+                quote!(.into_boxed_slice()),
+            ))
+        }
+        ("Box", Some(Type::TraitObject(t))) => {
+            fn_box_type(t, crate_path).map(|c_ty| (c_ty, quote!(.into_box())))
+        }
+        // Unsized types can not be put in a CBox.
+        ("Box", Some(Type::Path(p))) if p.path.is_ident("str") => None,
+        ("Box", Some(ty)) => Some((
+            quote!(#crate_path::boxed::CBox<'static, #ty>),
+            quote!(.into_box()),
+        )),
+        _ => None,
+    }
+}
+
+/// Map boxed `dyn FnMut(T) -> R` and `dyn FnOnce(T) -> R` closures to `CFnBox` and `CFnOnceBox`.
+///
+/// `Send` closures get wrapped in `CSend`, and lifetime bound of the trait object becomes the
+/// lifetime of the callback.
+fn fn_box_type(ty: &TypeTraitObject, crate_path: &TokenStream) -> Option<TokenStream> {
+    let mut func = None;
+    let mut send = false;
+    let mut lifetime = None;
+
+    for bound in &ty.bounds {
+        match bound {
+            TypeParamBound::Lifetime(lt) if lifetime.is_none() => lifetime = Some(lt),
+            TypeParamBound::Trait(t) if !send && t.path.is_ident("Send") => send = true,
+            TypeParamBound::Trait(t) if func.is_none() && t.path.segments.len() == 1 => {
+                func = t.path.segments.first()
+            }
+            _ => return None,
+        }
+    }
+
+    let func = func?;
+
+    let fn_box = match func.ident.to_string().as_str() {
+        "FnMut" => format_ident!("CFnBox"),
+        "FnOnce" => format_ident!("CFnOnceBox"),
+        _ => return None,
+    };
+
+    let (arg, ret) = match &func.arguments {
+        PathArguments::Parenthesized(a) if a.inputs.len() == 1 => (
+            a.inputs.first()?,
+            match &a.output {
+                ReturnType::Default => quote!(()),
+                ReturnType::Type(_, ty) => quote!(#ty),
+            },
+        ),
+        _ => return None,
+    };
+
+    let lifetime = lifetime.map_or_else(|| quote!('static), |lt| quote!(#lt));
+
+    let c_ty = quote!(#crate_path::callback::#fn_box<#lifetime, #arg, #ret>);
+
+    Some(if send {
+        quote!(#crate_path::callback::CSend<#c_ty>)
+    } else {
+        c_ty
+    })
+}
+
 fn replace_path_keep_final_args(ty: Option<&mut Type>, new_path: Path) {
     if let Some(Type::Path(path)) = ty {
         let old_path = std::mem::replace(&mut path.path, new_path);
@@ -108,7 +108,12 @@ impl ParsedGenerics {
 
     /// This function cross references input lifetimes and returns a new Self
     /// that only contains generic type information about those types.
-    pub fn cross_ref<'a>(&self, input: impl IntoIterator<Item = &'a ParsedGenerics>) -> Self {
+    ///
+    /// Fails if the input uses lifetimes, or types that are not declared in `self`.
+    pub fn cross_ref<'a>(
+        &self,
+        input: impl IntoIterator<Item = &'a ParsedGenerics>,
+    ) -> Result<Self> {
         let mut applied_lifetimes = HashSet::<&Ident>::new();
         let mut applied_typenames = HashSet::<&Type>::new();
 
@@ -133,7 +138,9 @@ impl ParsedGenerics {
                     .life_declare
                     .iter()
                     .find(|ld| ld.lifetime.ident == lt.ident)
-                    .expect("Gen 1");
+                    .ok_or_else(|| {
+                        Error::new_spanned(lt, format!("lifetime `{}` is not declared", lt))
+                    })?;
 
                 life_declare.push_value(decl.clone());
                 life_declare.push_punct(Default::default());
@@ -153,7 +160,12 @@ impl ParsedGenerics {
                     .iter()
                     .zip(self.gen_use.iter())
                     .find(|(_, ident)| *ident == ty)
-                    .expect("Gen 2");
+                    .ok_or_else(|| {
+                        Error::new_spanned(
+                            ty,
+                            format!("type `{}` is not declared", ty.to_token_stream()),
+                        )
+                    })?;
 
                 gen_declare.push_value(decl.clone());
                 gen_declare.push_punct(Default::default());
@@ -175,14 +187,14 @@ impl ParsedGenerics {
             }
         }
 
-        Self {
+        Ok(Self {
             life_declare,
             life_use,
             gen_declare,
             gen_use,
             gen_where_bounds,
             gen_remaps: Default::default(),
-        }
+        })
     }
 
     pub fn merge_remaps(&mut self, other: &mut ParsedGenerics) {
@@ -565,7 +577,7 @@ impl Parse for GenericCastType {
         let cast: ExprCast = input.parse()?;
 
         let ident = cast.expr;
-        let target = GenericType::from_type(&cast.ty, true);
+        let target = GenericType::from_type(&cast.ty, true)?;
 
         Ok(Self { ident, target })
     }
@@ -593,11 +605,10 @@ impl GenericType {
         }
     }
 
-    pub fn push_types_start(&mut self, types: TokenStream) {
-        let typestr = types.to_string();
+    pub fn push_types_start(&mut self, types: TokenStream) -> Result<()> {
         let mut types =
-            syn::parse::Parser::parse2(Punctuated::<Type, Comma>::parse_terminated, types)
-                .expect(&format!("Invalid types provided: {}", typestr));
+            syn::parse::Parser::parse2(Punctuated::<Type, Comma>::parse_terminated, types.clone())
+                .map_err(|e| Error::new_spanned(types, format!("invalid types provided: {}", e)))?;
 
         if !types.trailing_punct() {
             types.push_punct(Default::default());
@@ -611,13 +622,13 @@ impl GenericType {
         if !self.generic_types.trailing_punct() {
             self.generic_types.push_punct(Default::default());
         }
+        Ok(())
     }
 
-    fn from_type(target: &Type, cast_to_group: bool) -> Self {
+    fn from_type(target: &Type, cast_to_group: bool) -> Result<Self> {
         let (path, target, generics) = match target {
             Type::Path(ty) => {
-                let (path, target, generics) =
-                    crate::util::split_path_ident(&ty.path).expect("Gen 3");
+                let (path, target, generics) = crate::util::split_path_ident(&ty.path)?;
                 (path, quote!(#target), generics)
             }
             x => (
@@ -654,13 +665,13 @@ impl GenericType {
             quote!()
         };
 
-        Self {
+        Ok(Self {
             path,
             gen_separator,
             generic_lifetimes,
             generic_types,
             target,
-        }
+        })
     }
 }
 
@@ -680,6 +691,6 @@ impl ToTokens for GenericType {
 impl Parse for GenericType {
     fn parse(input: ParseStream) -> Result<Self> {
         let target: Type = input.parse()?;
-        Ok(Self::from_type(&target, false))
+        Self::from_type(&target, false)
     }
 }
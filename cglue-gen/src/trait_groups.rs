@@ -4,7 +4,8 @@ use crate::util::*;
 use itertools::*;
 use proc_macro2::TokenStream;
 use quote::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
 use syn::parse::{Parse, ParseStream};
 use syn::*;
 
@@ -41,14 +42,15 @@ impl PartialOrd for TraitInfo {
     }
 }
 
-impl From<Path> for TraitInfo {
-    fn from(in_path: Path) -> Self {
-        let (path, ident, gens) =
-            split_path_ident(&in_path).expect("Failed to split path by idents");
+impl TryFrom<Path> for TraitInfo {
+    type Error = Error;
+
+    fn try_from(in_path: Path) -> Result<Self> {
+        let (path, ident, gens) = split_path_ident(&in_path)?;
 
         let lc_ident = ident.to_string().to_lowercase();
 
-        Self {
+        Ok(Self {
             vtbl_name: format_ident!("vtbl_{}", lc_ident),
             lc_name: format_ident!("{}", lc_ident),
             vtbl_typename: format_ident!("{}Vtbl", ident),
@@ -58,7 +60,7 @@ impl From<Path> for TraitInfo {
             path,
             ident,
             generics: ParsedGenerics::from(gens.as_ref()),
-        }
+        })
     }
 }
 
@@ -72,10 +74,58 @@ pub struct TraitGroup {
     optional_vtbl: Vec<TraitInfo>,
     ext_traits: HashMap<Ident, (Path, ItemTrait)>,
     extra_filler_traits: bool,
+    args: TraitGroupArgs,
+}
+
+/// Arguments passed through `#[cglue_trait_group(...)]` attribute of the group.
+#[derive(Default)]
+struct TraitGroupArgs {
+    /// Group is `Send`, if the instance, and the context are `Send`.
+    send: bool,
+    /// Group is `Sync`, if the instance, and the context are `Sync`.
+    sync: bool,
+    /// Generate a `CSync` wrapper type for the group. Implies `send`.
+    lock: bool,
+}
+
+impl TraitGroupArgs {
+    fn from_attrs(attrs: Vec<Attribute>) -> Result<Self> {
+        let mut ret = Self::default();
+
+        for attr in attrs {
+            if !attr.path.is_ident("cglue_trait_group") {
+                return Err(Error::new_spanned(attr, "unknown trait group attribute"));
+            }
+
+            let args =
+                attr.parse_args_with(punctuated::Punctuated::<Ident, Token![,]>::parse_terminated)?;
+
+            for ident in args {
+                match ident.to_string().as_str() {
+                    "send" => ret.send = true,
+                    "sync" => ret.sync = true,
+                    "lock" => {
+                        ret.lock = true;
+                        ret.send = true;
+                    }
+                    _ => {
+                        return Err(Error::new(
+                            ident.span(),
+                            "unknown cglue_trait_group argument",
+                        ))
+                    }
+                }
+            }
+        }
+
+        Ok(ret)
+    }
 }
 
 impl Parse for TraitGroup {
     fn parse(input: ParseStream) -> Result<Self> {
+        let args = TraitGroupArgs::from_attrs(input.call(Attribute::parse_outer)?)?;
+
         let name = input.parse()?;
 
         let generics = input.parse()?;
@@ -100,21 +150,44 @@ impl Parse for TraitGroup {
         let mut mandatory_vtbl: Vec<TraitInfo> = mandatory_traits
             .into_iter()
             .map(prelude_remap)
-            .map(TraitInfo::from)
-            .collect();
+            .map(TraitInfo::try_from)
+            .collect::<Result<_>>()?;
         mandatory_vtbl.sort();
 
         let mut optional_vtbl: Vec<TraitInfo> = optional_traits
             .into_iter()
             .map(prelude_remap)
-            .map(TraitInfo::from)
-            .collect();
+            .map(TraitInfo::try_from)
+            .collect::<Result<_>>()?;
         optional_vtbl.sort();
 
+        // Runtime capabilities of the group are stored in a 64-bit `TraitSet`.
+        if let Some(vtbl) = mandatory_vtbl.iter().chain(optional_vtbl.iter()).nth(64) {
+            return Err(Error::new(
+                vtbl.ident.span(),
+                "trait groups can not contain more than 64 traits",
+            ));
+        }
+
+        // Traits are identified by their name at runtime, thus names must be unique.
+        let mut trait_names = HashSet::new();
+
+        for vtbl in mandatory_vtbl.iter().chain(optional_vtbl.iter()) {
+            if !trait_names.insert(&vtbl.ident) {
+                return Err(Error::new(
+                    vtbl.ident.span(),
+                    format!(
+                        "trait `{}` appears in the group more than once. Traits are identified by their name, thus the same name can not be used with different paths, or generic arguments",
+                        vtbl.ident
+                    ),
+                ));
+            }
+        }
+
         let store_exports = get_exports();
-        let store_traits = get_store();
+        let store_traits = get_store()?;
 
-        let mut crate_path: Path = parse2(crate_path()).expect("Failed to parse crate path");
+        let mut crate_path: Path = parse2(crate_path())?;
 
         if !crate_path.segments.empty_or_trailing() {
             crate_path.segments.push_punct(Default::default());
@@ -186,10 +259,10 @@ impl Parse for TraitGroup {
                     }
                     ext_traits.insert(tr.ident.clone(), (vtbl.path.clone(), tr.clone()));
                 } else {
-                    eprintln!(
-                        "Could not find external trait {}. Not changing paths.",
-                        vtbl.ident
-                    );
+                    return Err(Error::new_spanned(
+                        &vtbl.ident,
+                        format!("external trait `{}` not found", vtbl.ident),
+                    ));
                 }
             }
         }
@@ -210,6 +283,7 @@ impl Parse for TraitGroup {
             optional_vtbl,
             ext_traits,
             extra_filler_traits,
+            args,
         })
     }
 }
@@ -267,8 +341,8 @@ impl Parse for TraitGroupImpl {
                 .into_iter()
                 .map(prelude_remap)
                 .map(ext_abs_remap)
-                .map(From::from)
-                .collect();
+                .map(TraitInfo::try_from)
+                .collect::<Result<_>>()?;
 
             implemented_vtbl.sort();
 
@@ -284,8 +358,8 @@ impl Parse for TraitGroupImpl {
                 .into_iter()
                 .map(prelude_remap)
                 .map(ext_abs_remap)
-                .map(From::from)
-                .collect();
+                .map(TraitInfo::try_from)
+                .collect::<Result<_>>()?;
 
             implemented_vtbl.sort();
 
@@ -343,7 +417,9 @@ impl TraitGroupImpl {
             .collect();
 
         // If no lifetimes are used, default to 'cglue_a
-        if life_use.is_empty() {
+        let first_life = if let Some(lifetime) = life_use.first() {
+            lifetime.clone()
+        } else {
             assert!(life_declare.is_empty());
             let lifetime = Lifetime {
                 apostrophe: proc_macro2::Span::call_site(),
@@ -351,12 +427,13 @@ impl TraitGroupImpl {
             };
             life_use.push_value(lifetime.clone());
             life_declare.push_value(LifetimeDef {
-                lifetime,
+                lifetime: lifetime.clone(),
                 attrs: Default::default(),
                 bounds: Default::default(),
                 colon_token: Default::default(),
             });
-        }
+            lifetime
+        };
 
         if !life_declare.trailing_punct() {
             life_declare.push_punct(Default::default());
@@ -366,9 +443,6 @@ impl TraitGroupImpl {
             life_use.push_punct(Default::default());
         }
 
-        // Lifetime should always exist based on previous code
-        let first_life = life_use.first().unwrap();
-
         let gen_lt_bounds = self.generics.declare_lt_for_all(&quote!(#first_life));
         let gen_sabi_bounds = self.generics.declare_sabi_for_all(&crate_path);
 
@@ -390,7 +464,7 @@ impl TraitGroupImpl {
             quote!(CGlueCtx),
             &self.generics,
             Some(quote!(Self)).as_ref(),
-            first_life,
+            &first_life,
         );
 
         let gen = quote! {
@@ -416,7 +490,7 @@ impl TraitGroupImpl {
                 quote!(CGlueCtx),
                 &self.generics,
                 Some(quote!(Self)).as_ref(),
-                first_life,
+                &first_life,
             );
 
             quote! {
@@ -471,8 +545,8 @@ impl Parse for TraitCastGroup {
                 TypeParamBound::Trait(tr) => Some(tr.path),
                 _ => None,
             })
-            .map(From::from)
-            .collect();
+            .map(TraitInfo::try_from)
+            .collect::<Result<_>>()?;
 
         needed_vtbls.sort();
 
@@ -565,7 +639,7 @@ impl TraitGroup {
     ///
     /// This trait group will have all variants generated for converting, building, and
     /// converting it.
-    pub fn create_group(&self) -> TokenStream {
+    pub fn create_group(&self) -> Result<TokenStream> {
         // Path to trait group import.
         let crate_path = crate::util::crate_path();
 
@@ -619,6 +693,7 @@ impl TraitGroup {
         let mand_vtbl_list = self.vtbl_list(self.mandatory_vtbl.iter());
         let full_opt_vtbl_list = self.vtbl_list(self.optional_vtbl.iter());
         let mandatory_as_ref_impls = self.mandatory_as_ref_impls(&trg_path);
+        let query_trait_impls = self.query_trait_impls(&trg_path);
 
         let get_container_impl = self.get_container_impl(name, &trg_path, &self.generics);
 
@@ -627,7 +702,7 @@ impl TraitGroup {
             self.mandatory_vtbl.iter(),
             &self.generics,
             &crate_path,
-        );
+        )?;
         let vtbl_where_bounds = Self::vtbl_where_bounds(
             self.mandatory_vtbl.iter(),
             cont_name,
@@ -666,6 +741,15 @@ impl TraitGroup {
         );
         let ret_tmp_defs = self.ret_tmp_defs(self.optional_vtbl.iter());
 
+        // Without mandatory traits, nothing is known about the thread safety of the instance.
+        let has_mandatory = !self.mandatory_vtbl.is_empty();
+        let thread_marker = super::traits::thread_marker(has_mandatory, has_mandatory);
+
+        let thread_impls = self.thread_impls(&trg_path);
+        let inst_thread_bounds = self.inst_thread_bounds(quote!(CGlueInst));
+        let inst_thread_bounds_boxed =
+            self.inst_thread_bounds(quote!(#crate_path::boxed::CBox<'cglue_a, CGlueT>));
+
         let mut enable_funcs = TokenStream::new();
         let mut enable_funcs_vtbl = TokenStream::new();
 
@@ -707,7 +791,11 @@ impl TraitGroup {
             }
         }
 
-        let mut trait_funcs = TokenStream::new();
+        let mut trait_funcs = self.capability_funcs(&trg_path);
+        trait_funcs.extend(self.query_funcs(&trg_path));
+        trait_funcs.extend(self.descriptor_funcs(&crate_path));
+
+        let reflect_impl = self.reflect_impl(&crate_path, &trg_path);
 
         let mut opt_structs = TokenStream::new();
         let mut opt_struct_imports = TokenStream::new();
@@ -741,6 +829,49 @@ impl TraitGroup {
         let opaque_name_arc_box = format_ident!("{}ArcBox", name);
         let opaque_name_ctx_box = format_ident!("{}CtxBox", name);
 
+        let (sync_imports, sync_types) = if self.args.lock {
+            let base_name_sync = format_ident!("{}BaseSync", name);
+            let opaque_name_sync = format_ident!("{}Sync", name);
+
+            let base_sync_doc = format!(
+                " Thread-safe, reference counted {} that locks on every call.",
+                name
+            );
+            let opaque_sync_doc = format!(
+                " Opaque boxed, thread-safe, reference counted {} that locks on every call.",
+                name
+            );
+
+            (
+                quote! {
+                    #base_name_sync,
+                    #opaque_name_sync,
+                },
+                quote! {
+                    #[doc = #base_sync_doc]
+                    ///
+                    /// Mandatory traits are implemented by locking, optional traits can be
+                    /// accessed through the guard returned by `lock`.
+                    pub type #base_name_sync<CGlueInst, CGlueCtx, #gen_use>
+                        = #crate_path::lock::CSync<#name<'static, CGlueInst, CGlueCtx, #gen_use>>;
+
+                    #[doc = #opaque_sync_doc]
+                    pub type #opaque_name_sync<CGlueCtx, #gen_use>
+                        = #base_name_sync<#crate_path::boxed::CBox<'static, #c_void>, CGlueCtx, #gen_use>;
+
+                    impl<'cglue_a, CGlueInst, CGlueCtx: #ctx_bound, #gen_declare> #crate_path::lock::Lockable
+                        for #name<'cglue_a, CGlueInst, CGlueCtx, #gen_use>
+                    where
+                        #cont_name<CGlueInst, CGlueCtx, #gen_use>: #trg_path::CGlueObjBase,
+                        #gen_where_bounds_base
+                    {
+                    }
+                },
+            )
+        } else {
+            Default::default()
+        };
+
         #[cfg(not(feature = "unstable"))]
         let filler_trait = format_ident!("{}VtableFiller", name);
         #[cfg(not(feature = "unstable"))]
@@ -783,7 +914,7 @@ impl TraitGroup {
                 self.mandatory_vtbl.iter().chain(traits.iter().copied()),
                 &self.generics,
                 &crate_path,
-            );
+            )?;
 
             let get_container_impl_final =
                 self.get_container_impl(&opt_final_name, &trg_path, &self.generics);
@@ -800,7 +931,7 @@ impl TraitGroup {
                 self.mandatory_vtbl.iter().chain(traits.iter().copied()),
                 &self.generics,
                 &crate_path,
-            );
+            )?;
 
             let impl_traits =
                 self.impl_traits(self.mandatory_vtbl.iter().chain(traits.iter().copied()));
@@ -1099,7 +1230,7 @@ impl TraitGroup {
             #extra_filler_traits
         };
 
-        quote! {
+        Ok(quote! {
 
             #[doc(hidden)]
             pub use #submod_name::*;
@@ -1130,6 +1261,7 @@ impl TraitGroup {
                     #opaque_name_arc_box,
                     #opaque_name_ctx_box,
                     #cont_name,
+                    #sync_imports
                     #opt_struct_imports
                 };
 
@@ -1173,8 +1305,11 @@ impl TraitGroup {
                     instance: CGlueInst,
                     context: CGlueCtx,
                     #ret_tmp_defs
+                    _cglue_thread: ::core::marker::PhantomData<#thread_marker>,
                 }
 
+                #thread_impls
+
                 #cglue_obj_impl
 
                 unsafe impl<CGlueInst: #trg_path::Opaquable, CGlueCtx: #ctx_bound, #gen_declare>
@@ -1280,11 +1415,13 @@ impl TraitGroup {
                 pub type #opaque_name_arc_box<'cglue_a, #gen_use>
                     = #base_name_arc_box<'cglue_a, #c_void, #c_void, #gen_use>;
 
+                #sync_types
 
                 impl<'cglue_a, CGlueInst: ::core::ops::Deref, CGlueCtx: #ctx_bound, #gen_declare>
                     From<(CGlueInst, CGlueCtx)> for #cont_name<CGlueInst, CGlueCtx, #gen_use>
                 where
-                    Self: #trg_path::CGlueObjBase
+                    Self: #trg_path::CGlueObjBase,
+                    #inst_thread_bounds
                 {
                     fn from((instance, context): (CGlueInst, CGlueCtx)) -> Self {
                         Self {
@@ -1292,6 +1429,7 @@ impl TraitGroup {
                             context,
                             #mand_ret_tmp_default
                             #full_opt_ret_tmp_default
+                            _cglue_thread: ::core::marker::PhantomData,
                         }
                     }
                 }
@@ -1299,7 +1437,8 @@ impl TraitGroup {
                 impl<'cglue_a, CGlueT, CGlueCtx: #ctx_bound, #gen_declare>
                     From<(CGlueT, CGlueCtx)> for #cont_name<#crate_path::boxed::CBox<'cglue_a, CGlueT>, CGlueCtx, #gen_use>
                 where
-                    Self: #trg_path::CGlueObjBase
+                    Self: #trg_path::CGlueObjBase,
+                    #inst_thread_bounds_boxed
                 {
                     fn from((this, context): (CGlueT, CGlueCtx)) -> Self {
                         Self::from((#crate_path::boxed::CBox::from(this), context))
@@ -1334,6 +1473,7 @@ impl TraitGroup {
                 where
                     Self: From<#cont_name<CGlueInst, CGlueCtx, #gen_use>>,
                     #cont_name<CGlueInst, CGlueCtx, #gen_use>: #trg_path::CGlueObjBase,
+                    #inst_thread_bounds
                     #vtbl_where_bounds #gen_where_bounds
                 {
                     fn from((instance, context): (CGlueInst, CGlueCtx)) -> Self {
@@ -1384,7 +1524,7 @@ impl TraitGroup {
                 {
                     #[doc = #new_doc]
                     pub fn new(instance: CGlueInst, context: CGlueCtx, #optional_vtbl_defs) -> Self
-                        where #vtbl_where_bounds
+                        where #vtbl_where_bounds #inst_thread_bounds
                     {
                         Self {
                             container: #cont_name {
@@ -1392,6 +1532,7 @@ impl TraitGroup {
                                 context,
                                 #mand_ret_tmp_default
                                 #full_opt_ret_tmp_default
+                                _cglue_thread: ::core::marker::PhantomData,
                             },
                             #mand_vtbl_default
                             #full_opt_vtbl_list
@@ -1406,7 +1547,7 @@ impl TraitGroup {
                     ///
                     /// `instance` will be moved onto heap.
                     pub fn new_boxed(this: CGlueT, #optional_vtbl_defs_boxed) -> Self
-                        where #vtbl_where_bounds_boxed
+                        where #vtbl_where_bounds_boxed #inst_thread_bounds_boxed
                     {
                         Self::new(From::from(this), Default::default(), #full_opt_vtbl_list)
                     }
@@ -1441,11 +1582,361 @@ impl TraitGroup {
 
                 #mandatory_as_ref_impls
 
+                #query_trait_impls
+
                 #mandatory_internal_trait_impls
 
+                #reflect_impl
+
                 #opt_structs
             }
             }
+        })
+    }
+
+    /// Generate runtime capability introspection functions.
+    ///
+    /// Traits are indexed in the same order as they are laid out in the group - mandatory ones
+    /// first, then the optional ones.
+    fn capability_funcs(&self, trg_path: &TokenStream) -> TokenStream {
+        let mut names = vec![];
+        let mut checks = TokenStream::new();
+
+        for (i, TraitInfo { ident, .. }) in self.mandatory_vtbl.iter().enumerate() {
+            names.push(ident.to_string());
+            checks.extend(quote!(let caps = caps.with(#i);));
+        }
+
+        for (
+            i,
+            TraitInfo {
+                ident, vtbl_name, ..
+            },
+        ) in self
+            .optional_vtbl
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i + self.mandatory_vtbl.len(), v))
+        {
+            names.push(ident.to_string());
+            checks.extend(quote! {
+                let caps = if self.#vtbl_name.is_some() {
+                    caps.with(#i)
+                } else {
+                    caps
+                };
+            });
+        }
+
+        let name = &self.name;
+        let names_doc = format!(
+            " Names of all traits in {}, ordered by their index in [`TraitSet`](cglue::trait_group::TraitSet).",
+            name
+        );
+
+        quote! {
+            #[doc = #names_doc]
+            pub const CGLUE_CAPABILITY_NAMES: &'static [&'static str] = &[#(#names),*];
+
+            /// Retrieve the set of traits this object implements.
+            ///
+            /// Mandatory traits are always part of the set, while optional traits are only
+            /// included if the object has their vtables filled in.
+            pub fn cglue_capabilities(&self) -> #trg_path::TraitSet {
+                let caps = #trg_path::TraitSet::empty();
+                #checks
+                caps
+            }
+
+            /// Iterate over the names of the traits this object implements.
+            pub fn cglue_capability_names(&self) -> impl Iterator<Item = &'static str> {
+                self.cglue_capabilities().names(Self::CGLUE_CAPABILITY_NAMES)
+            }
+        }
+    }
+
+    /// Generate trait lookup functions.
+    ///
+    /// Every trait of the group is listed under its [`TraitId`](cglue::trait_group::TraitId), and
+    /// can be queried by its vtable type, as long as the vtable is present.
+    fn query_funcs(&self, trg_path: &TokenStream) -> TokenStream {
+        let ids = self
+            .mandatory_vtbl
+            .iter()
+            .chain(self.optional_vtbl.iter())
+            .map(|TraitInfo { ident, .. }| {
+                let name = ident.to_string();
+                quote!(#trg_path::TraitId::of(#name))
+            });
+
+        let name = &self.name;
+        let ids_doc = format!(
+            " IDs of all traits in {}, ordered the same way as `CGLUE_CAPABILITY_NAMES`.",
+            name
+        );
+
+        quote! {
+            #[doc = #ids_doc]
+            pub const TRAIT_IDS: &'static [#trg_path::TraitId] = &[#(#ids),*];
+
+            /// Look up a trait by its vtable type.
+            ///
+            /// Returns `None` if the trait is optional, and its vtable is not present on this
+            /// object.
+            pub fn cglue_query_trait_ref<'b, CGlueV: 'cglue_a>(&'b self) -> ::core::option::Option<&'b <Self as #trg_path::QueryTrait<'cglue_a, CGlueV>>::Obj>
+                where Self: #trg_path::QueryTrait<'cglue_a, CGlueV>
+            {
+                #trg_path::QueryTrait::query_trait_ref(self)
+            }
+
+            /// Look up a trait by its vtable type, with mutable access to the object.
+            ///
+            /// Returns `None` if the trait is optional, and its vtable is not present on this
+            /// object.
+            pub fn cglue_query_trait_mut<'b, CGlueV: 'cglue_a>(&'b mut self) -> ::core::option::Option<&'b mut <Self as #trg_path::QueryTrait<'cglue_a, CGlueV>>::Obj>
+                where Self: #trg_path::QueryTrait<'cglue_a, CGlueV>
+            {
+                #trg_path::QueryTrait::query_trait_mut(self)
+            }
+        }
+    }
+
+    /// `QueryTrait` implementations for all traits of the group.
+    ///
+    /// Mandatory traits are implemented by the group itself, while optional ones by the concrete
+    /// variant of the group with only the given trait implemented.
+    fn query_trait_impls(&self, trg_path: &TokenStream) -> TokenStream {
+        let name = &self.name;
+        let cont_name = &self.cont_name;
+
+        let all_gen_declare = &self.generics.gen_declare;
+        let all_gen_use = &self.generics.gen_use;
+        let all_gen_where_bounds = &self.generics.gen_where_bounds;
+
+        let ctx_bound = super::traits::ctx_bound();
+
+        let vtbls = self
+            .mandatory_vtbl
+            .iter()
+            .map(|v| (v, false))
+            .chain(self.optional_vtbl.iter().map(|v| (v, true)));
+
+        let mut ret = TokenStream::new();
+
+        for (info, optional) in vtbls {
+            let TraitInfo {
+                vtbl_name,
+                path,
+                vtbl_typename,
+                generics: ParsedGenerics { gen_use, .. },
+                ..
+            } = info;
+
+            let (obj, query_ref, query_mut) = if optional {
+                let opt_name = Self::optional_group_ident(name, "", std::iter::once(info));
+
+                (
+                    quote!(#opt_name<'cglue_a, CGlueInst, CGlueCtx, #all_gen_use>),
+                    quote! {
+                        self.#vtbl_name?;
+                        // Safety: structure layouts are fully compatible, and the optional vtable
+                        // was checked to be present.
+                        unsafe { (self as *const Self as *const Self::Obj).as_ref() }
+                    },
+                    quote! {
+                        self.#vtbl_name?;
+                        // Safety: structure layouts are fully compatible, and the optional vtable
+                        // was checked to be present.
+                        unsafe { (self as *mut Self as *mut Self::Obj).as_mut() }
+                    },
+                )
+            } else {
+                (quote!(Self), quote!(Some(self)), quote!(Some(self)))
+            };
+
+            ret.extend(quote! {
+                impl<'cglue_a, CGlueInst, CGlueCtx: #ctx_bound, #all_gen_declare> #trg_path::QueryTrait<'cglue_a, #path #vtbl_typename<'cglue_a, #cont_name<CGlueInst, CGlueCtx, #all_gen_use>, #gen_use>>
+                    for #name<'cglue_a, CGlueInst, CGlueCtx, #all_gen_use>
+                where
+                    #cont_name<CGlueInst, CGlueCtx, #all_gen_use>: #trg_path::CGlueObjBase,
+                    #all_gen_where_bounds
+                {
+                    type Obj = #obj;
+
+                    fn query_trait_ref(&self) -> ::core::option::Option<&Self::Obj> {
+                        #query_ref
+                    }
+
+                    fn query_trait_mut(&mut self) -> ::core::option::Option<&mut Self::Obj> {
+                        #query_mut
+                    }
+                }
+            });
+        }
+
+        ret
+    }
+
+    /// Generate functions listing descriptors of the group's traits.
+    ///
+    /// Descriptors are ordered the same way as `CGLUE_CAPABILITY_NAMES`.
+    fn descriptor_funcs(&self, crate_path: &TokenStream) -> TokenStream {
+        let reflect_path = quote!(#crate_path::reflect);
+
+        let cont_name = &self.cont_name;
+        let all_gen_use = &self.generics.gen_use;
+
+        let mut descriptors = TokenStream::new();
+        let mut present = TokenStream::new();
+
+        let vtbls = self
+            .mandatory_vtbl
+            .iter()
+            .map(|v| (v, false))
+            .chain(self.optional_vtbl.iter().map(|v| (v, true)));
+
+        for (
+            TraitInfo {
+                path,
+                vtbl_typename,
+                vtbl_name,
+                generics: ParsedGenerics { gen_use, .. },
+                ..
+            },
+            optional,
+        ) in vtbls
+        {
+            let vtbl = quote!(#path #vtbl_typename<'cglue_a, #cont_name<CGlueInst, CGlueCtx, #all_gen_use>, #gen_use>);
+
+            descriptors.extend(quote! {
+                #reflect_path::GroupTraitDescriptor {
+                    descriptor: <#vtbl>::DESCRIPTOR,
+                    optional: #optional,
+                },
+            });
+
+            if optional {
+                present.extend(quote!(.chain(self.#vtbl_name.map(|v| v.descriptor()))));
+            } else {
+                present.extend(quote!(.chain(Some(self.#vtbl_name.descriptor()))));
+            }
+        }
+
+        let name = &self.name;
+        let descriptors_doc = format!(
+            " Descriptors of all traits in {}, ordered the same way as `CGLUE_CAPABILITY_NAMES`.",
+            name
+        );
+
+        quote! {
+            #[doc = #descriptors_doc]
+            pub const TRAIT_DESCRIPTORS: &'static [#reflect_path::GroupTraitDescriptor] = &[#descriptors];
+
+            /// Iterate over the descriptors of the traits this object implements.
+            ///
+            /// The descriptors are retrieved from the object's vtables.
+            pub fn trait_descriptors(&self) -> impl Iterator<Item = &'static #reflect_path::TraitDescriptor> + '_ {
+                ::core::iter::empty() #present
+            }
+        }
+    }
+
+    /// Generate `Reflect` implementation for the group.
+    ///
+    /// Methods are looked up in the same order as `CGLUE_CAPABILITY_NAMES`, skipping optional traits
+    /// whose vtables are not present.
+    fn reflect_impl(&self, crate_path: &TokenStream, trg_path: &TokenStream) -> TokenStream {
+        let reflect_path = quote!(#crate_path::reflect);
+
+        let name = &self.name;
+        let cont_name = &self.cont_name;
+        let ctx_bound = super::traits::ctx_bound();
+
+        let ParsedGenerics {
+            gen_declare,
+            gen_use: all_gen_use,
+            gen_where_bounds,
+            ..
+        } = &self.generics;
+
+        let gen_lt_bounds = self.generics.declare_lt_for_all(&quote!('cglue_a));
+        let gen_sabi_bounds = self.generics.declare_sabi_for_all(crate_path);
+
+        let mut bounds = TokenStream::new();
+        let mut metas = TokenStream::new();
+        let mut calls = TokenStream::new();
+
+        for info in &self.mandatory_vtbl {
+            let TraitInfo {
+                path,
+                vtbl_typename,
+                generics: ParsedGenerics { gen_use, .. },
+                ..
+            } = info;
+
+            let vtbl = quote!(#path #vtbl_typename<'cglue_a, #cont_name<CGlueInst, CGlueCtx, #all_gen_use>, #gen_use>);
+            let impl_traits = self.impl_traits(std::iter::once(info));
+
+            bounds.extend(quote!(Self: #impl_traits,));
+            metas.extend(quote!(ret.push(<#vtbl>::META);));
+            calls.extend(quote! {
+                if let Some(ret) = <#vtbl>::cglue_reflect_call(self, method, args) {
+                    return ret;
+                }
+            });
+        }
+
+        for info in &self.optional_vtbl {
+            let TraitInfo {
+                path,
+                vtbl_typename,
+                vtbl_name,
+                generics: ParsedGenerics { gen_use, .. },
+                ..
+            } = info;
+
+            let vtbl = quote!(#path #vtbl_typename<'cglue_a, #cont_name<CGlueInst, CGlueCtx, #all_gen_use>, #gen_use>);
+            let impl_traits =
+                self.impl_traits(self.mandatory_vtbl.iter().chain(std::iter::once(info)));
+            let opt_name = Self::optional_group_ident(name, "", std::iter::once(info));
+            let func_name_mut = Self::optional_func_name("as_mut", std::iter::once(info));
+
+            bounds.extend(quote!(#opt_name<'cglue_a, CGlueInst, CGlueCtx, #all_gen_use>: 'cglue_a + #impl_traits,));
+            metas.extend(quote! {
+                if self.#vtbl_name.is_some() {
+                    ret.push(<#vtbl>::META);
+                }
+            });
+            calls.extend(quote! {
+                if let Some(obj) = self.#func_name_mut() {
+                    if let Some(ret) = <#vtbl>::cglue_reflect_call(obj, method, args) {
+                        return ret;
+                    }
+                }
+            });
+        }
+
+        quote! {
+            impl<'cglue_a, CGlueInst, CGlueCtx: #ctx_bound, #gen_declare> #reflect_path::Reflect
+                for #name<'cglue_a, CGlueInst, CGlueCtx, #all_gen_use>
+            where
+                #cont_name<CGlueInst, CGlueCtx, #all_gen_use>: #trg_path::CGlueObjBase,
+                #gen_where_bounds
+                #gen_lt_bounds
+                #gen_sabi_bounds
+                #bounds
+            {
+                fn traits(&self) -> #reflect_path::Vec<#reflect_path::TraitMeta> {
+                    let mut ret = #reflect_path::Vec::new();
+                    #metas
+                    ret
+                }
+
+                fn call_method(&mut self, method: &str, args: &[#reflect_path::Value]) -> ::core::result::Result<#reflect_path::Value, #reflect_path::CallError> {
+                    #calls
+                    Err(#reflect_path::CallError::NoMethod)
+                }
+            }
         }
     }
 
@@ -1455,7 +1946,7 @@ impl TraitGroup {
         iter: impl Iterator<Item = &'a TraitInfo>,
         all_generics: &ParsedGenerics,
         crate_path: &TokenStream,
-    ) -> TokenStream {
+    ) -> Result<TokenStream> {
         let mut ret = TokenStream::new();
 
         let cont_name = &self.cont_name;
@@ -1485,8 +1976,9 @@ impl TraitGroup {
                     tr_info,
                     crate_path,
                     false,
+                    false,
                     super::traits::process_item,
-                );
+                )?;
 
                 for func in &funcs {
                     func.int_trait_impl(Some(ext_path), &ext_name, &mut impls);
@@ -1507,7 +1999,7 @@ impl TraitGroup {
             }
         }
 
-        ret
+        Ok(ret)
     }
 
     /// Required vtable definitions.
@@ -1604,6 +2096,76 @@ impl TraitGroup {
         ret
     }
 
+    /// Bounds on the instance, required to construct a group marked `send`, or `sync`.
+    fn inst_thread_bounds(&self, inst: TokenStream) -> TokenStream {
+        let mut ret = TokenStream::new();
+
+        if self.args.send {
+            ret.extend(quote!(#inst: Send,));
+        }
+
+        if self.args.sync {
+            ret.extend(quote!(#inst: Sync,));
+        }
+
+        ret
+    }
+
+    /// `Send`, and `Sync` implementations for groups marked `send`, or `sync`.
+    ///
+    /// Temporary return storage is only ever alive while the group is borrowed, thus it does not
+    /// affect whether the group can be sent. It may, however, be written to through a shared
+    /// reference, thus it must be `SyncRetTmp` for the group to be `Sync`.
+    fn thread_impls(&self, trg_path: &TokenStream) -> TokenStream {
+        let ctx_bound = super::traits::ctx_bound();
+
+        let cont_name = &self.cont_name;
+
+        let ParsedGenerics {
+            gen_declare,
+            gen_use,
+            ..
+        } = &self.generics;
+
+        let mut ret = TokenStream::new();
+
+        if self.args.send {
+            ret.extend(quote! {
+                unsafe impl<CGlueInst: Send, CGlueCtx: #ctx_bound + Send, #gen_declare> Send
+                    for #cont_name<CGlueInst, CGlueCtx, #gen_use>
+                {
+                }
+            });
+        }
+
+        if self.args.sync {
+            let mut ret_tmp_bounds = TokenStream::new();
+
+            for TraitInfo {
+                path,
+                ret_tmp_typename,
+                generics: ParsedGenerics { gen_use, .. },
+                ..
+            } in self.mandatory_vtbl.iter().chain(self.optional_vtbl.iter())
+            {
+                ret_tmp_bounds.extend(
+                    quote!(#path #ret_tmp_typename<CGlueCtx, #gen_use>: #trg_path::SyncRetTmp,),
+                );
+            }
+
+            ret.extend(quote! {
+                unsafe impl<CGlueInst: Sync, CGlueCtx: #ctx_bound + Sync, #gen_declare> Sync
+                    for #cont_name<CGlueInst, CGlueCtx, #gen_use>
+                where
+                    #ret_tmp_bounds
+                {
+                }
+            });
+        }
+
+        ret
+    }
+
     fn ret_tmp_defs<'a>(&'a self, iter: impl Iterator<Item = &'a TraitInfo>) -> TokenStream {
         let mut ret = TokenStream::new();
 
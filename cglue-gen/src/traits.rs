@@ -39,12 +39,187 @@ impl Parse for PathTokens {
     }
 }
 
-// TODO: Add dynamic setting of Send / Sync
+/// Arguments passed to `cglue_trait` attribute.
+#[derive(Default)]
+pub struct TraitArgs {
+    /// GUID of the trait, if it is to be COM compatible.
+    pub com_guid: Option<u128>,
+}
+
+impl Parse for TraitArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut com = None;
+        let mut guid = None;
+
+        while !input.is_empty() {
+            let ident: Ident = input.parse()?;
+
+            match ident.to_string().as_str() {
+                "com" => com = Some(ident),
+                "guid" => {
+                    input.parse::<Token![=]>()?;
+                    let lit: LitStr = input.parse()?;
+                    guid = Some((parse_guid(&lit)?, lit));
+                }
+                _ => return Err(Error::new(ident.span(), "unknown cglue_trait argument")),
+            }
+
+            if !input.is_empty() {
+                input.parse::<Comma>()?;
+            }
+        }
+
+        let com_guid = match (com, guid) {
+            (Some(_), Some((guid, _))) => Some(guid),
+            (Some(com), None) => {
+                return Err(Error::new(
+                    com.span(),
+                    "COM compatible traits require a `guid = \"...\"` argument",
+                ))
+            }
+            (None, Some((_, lit))) => {
+                return Err(Error::new(lit.span(), "`guid` is only valid with `com`"))
+            }
+            (None, None) => None,
+        };
+
+        Ok(Self { com_guid })
+    }
+}
+
+/// Parse a GUID in `XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX` form, optionally wrapped in braces.
+fn parse_guid(lit: &LitStr) -> Result<u128> {
+    let value = lit.value();
+    let value = value.trim_start_matches('{').trim_end_matches('}');
+
+    let groups = value.split('-').map(str::len).collect::<Vec<_>>();
+
+    if groups != [8, 4, 4, 4, 12] {
+        return Err(Error::new(lit.span(), "invalid GUID format"));
+    }
+
+    u128::from_str_radix(&value.replace('-', ""), 16)
+        .map_err(|_| Error::new(lit.span(), "invalid GUID format"))
+}
+
 pub fn ctx_bound() -> TokenStream {
     let crate_path = crate::util::crate_path();
     quote!(#crate_path::trait_group::ContextBounds)
 }
 
+/// Get the ABI of COM compatible vtable functions.
+///
+/// abi_stable only supports `extern "C"` function pointers, thus layout checked vtables fall back
+/// to it.
+pub fn com_abi() -> TokenStream {
+    #[cfg(feature = "layout_checks")]
+    let abi = quote!(extern "C");
+    #[cfg(not(feature = "layout_checks"))]
+    let abi = quote!(extern "system");
+    abi
+}
+
+/// Get the thread safety marker type for given `Send`, and `Sync` requirements.
+pub fn thread_marker(send: bool, sync: bool) -> TokenStream {
+    let crate_path = crate::util::crate_path();
+    let marker = match (send, sync) {
+        (false, false) => quote!(NotSendSync),
+        (true, false) => quote!(SendNotSync),
+        (false, true) => quote!(SyncNotSend),
+        (true, true) => quote!(SendSync),
+    };
+    quote!(#crate_path::trait_group::#marker)
+}
+
+/// Check whether the supertraits contain `Send`, and `Sync` bounds.
+fn supertrait_send_sync(supertraits: &Punctuated<TypeParamBound, Add>) -> (bool, bool) {
+    let has = |name: &str| {
+        supertraits.iter().any(|b| match b {
+            TypeParamBound::Trait(t) => {
+                t.path.segments.last().map(|s| s.ident == name) == Some(true)
+            }
+            _ => false,
+        })
+    };
+    (has("Send"), has("Sync"))
+}
+
+/// Implement the trait on `CSync` by locking on every call.
+///
+/// The implementation only applies to types that opt into it through `Lockable`, which trait
+/// groups do with the `lock` option.
+///
+/// Nothing is generated for external traits, traits with supertraits other than `Send` and
+/// `Sync`, or traits with functions that can not be called through a lock guard.
+fn lock_trait_impl(
+    tr: &ItemTrait,
+    ext_name: Option<&Ident>,
+    funcs: &[ParsedFunc],
+    generics: &ParsedGenerics,
+    crate_path: &TokenStream,
+    needs_send: bool,
+) -> TokenStream {
+    let foreign_supertraits = tr.supertraits.iter().any(|b| match b {
+        TypeParamBound::Trait(t) => !t
+            .path
+            .get_ident()
+            .map(|i| i == "Send" || i == "Sync")
+            .unwrap_or(false),
+        _ => true,
+    });
+
+    if ext_name.is_some() || foreign_supertraits {
+        return quote!();
+    }
+
+    let ParsedGenerics {
+        life_declare,
+        life_use,
+        gen_declare,
+        gen_use,
+        gen_where_bounds,
+        ..
+    } = generics;
+
+    let trait_name = &tr.ident;
+    let trait_path = quote!(#trait_name<#life_use #gen_use>);
+
+    let mut types = TokenStream::new();
+
+    for item in &tr.items {
+        match item {
+            TraitItem::Type(ty) if ty.generics.params.is_empty() => {
+                let ident = &ty.ident;
+                types.extend(quote!(type #ident = <CGlueT as #trait_path>::#ident;));
+            }
+            TraitItem::Method(_) => {}
+            _ => return quote!(),
+        }
+    }
+
+    let mut impls = TokenStream::new();
+
+    for func in funcs {
+        if !func.lock_wrapped_trait_impl(&trait_path, &mut impls) {
+            return quote!();
+        }
+    }
+
+    let send_bound = if needs_send { quote!(+ Send) } else { quote!() };
+    let unsafety = &tr.unsafety;
+
+    quote! {
+        #unsafety impl<#life_declare CGlueT: #trait_path + #crate_path::lock::Lockable + 'static #send_bound, #gen_declare> #trait_path
+            for #crate_path::lock::CSync<CGlueT>
+        where
+            #gen_where_bounds
+        {
+            #types
+            #impls
+        }
+    }
+}
+
 pub fn cglue_c_opaque_bound() -> TokenStream {
     let crate_path = crate::util::crate_path();
     quote!(CGlueC::OpaqueTarget: #crate_path::trait_group::Opaquable + #crate_path::trait_group::CGlueObjBase,)
@@ -62,7 +237,7 @@ pub fn process_item(
     trait_type_defs: &mut TokenStream,
     types: &mut BTreeMap<Option<AssocType>, WrappedType>,
     crate_path: &TokenStream,
-) {
+) -> Result<()> {
     let c_void = crate::util::void_type();
 
     let static_lifetime = Lifetime {
@@ -99,8 +274,11 @@ pub fn process_item(
         orig_lifetime_bound
     };
 
-    if lifetime_bounds.next().is_some() {
-        panic!("Traits with multiple lifetime bounds are not supported!");
+    if let Some(lt) = lifetime_bounds.next() {
+        return Err(Error::new_spanned(
+            lt,
+            "associated types with multiple lifetime bounds are not supported",
+        ));
     }
 
     for attr in ty_attrs {
@@ -110,9 +288,7 @@ pub fn process_item(
 
         match x {
             "wrap_with" => {
-                let new_ty = attr
-                    .parse_args::<GenericType>()
-                    .expect("Invalid type in wrap_with.");
+                let new_ty = attr.parse_args::<GenericType>()?;
 
                 if let Some(ty_def) = ty_def {
                     trait_type_defs.extend(quote!(type #ty_def = #new_ty #ty_where_clause;));
@@ -136,13 +312,21 @@ pub fn process_item(
                 );
             }
             "return_wrap" => {
-                let closure = attr
-                    .parse_args::<ExprClosure>()
-                    .expect("A valid closure must be supplied accepting the wrapped type!");
+                let closure = attr.parse_args::<ExprClosure>().map_err(|e| {
+                    Error::new(
+                        e.span(),
+                        "a valid closure must be supplied accepting the wrapped type",
+                    )
+                })?;
 
                 types
                     .get_mut(ty_def)
-                    .expect("Type must be first wrapped with #[wrap_with(T)] atribute.")
+                    .ok_or_else(|| {
+                        Error::new_spanned(
+                            attr,
+                            "type must be first wrapped with `#[wrap_with(T)]` attribute",
+                        )
+                    })?
                     .return_conv = Some(closure);
             }
             "wrap_with_obj"
@@ -151,9 +335,7 @@ pub fn process_item(
             | "wrap_with_group"
             | "wrap_with_group_ref"
             | "wrap_with_group_mut" => {
-                let mut new_ty = attr
-                    .parse_args::<GenericType>()
-                    .expect("Invalid type in wrap_with.");
+                let mut new_ty = attr.parse_args::<GenericType>()?;
 
                 let target = new_ty.target.clone();
 
@@ -228,13 +410,16 @@ pub fn process_item(
                             quote!(#from_lifetime_simple)
                         };
 
-                        let cglue_f_tys = ty_def.as_ref().map(|ty_def| {
-                            let ty_def = ty_def.remap_for_hrtb();
-                            (
-                                quote!(<CGlueC::ObjType as #trait_name<#hrtb_lifetime_use #gen_use>>::#ty_def),
-                                quote!(<CGlueC::ObjType as #trait_name<#simple_lifetime_use #gen_use>>::#ty_def),
-                            )
-                        });
+                        let cglue_f_tys = ty_def
+                            .as_ref()
+                            .map(|ty_def| {
+                                let ty_def = ty_def.remap_for_hrtb()?;
+                                Ok::<_, Error>((
+                                    quote!(<CGlueC::ObjType as #trait_name<#hrtb_lifetime_use #gen_use>>::#ty_def),
+                                    quote!(<CGlueC::ObjType as #trait_name<#simple_lifetime_use #gen_use>>::#ty_def),
+                                ))
+                            })
+                            .transpose()?;
 
                         let mut new_ty_hrtb = from_new_ty.clone();
                         let mut new_ty_simple = from_new_ty_simple.clone();
@@ -243,78 +428,82 @@ pub fn process_item(
                             // <CGlueO::ContType as crate::trait_group::CGlueObjBase>::Context
                             new_ty.push_types_start(
                                 quote!(#crate_path::boxed::CBox<#lifetime, #c_void>, CGlueC::Context, ),
-                            );
+                            )?;
                             new_ty_ret_tmp.push_types_start(
                                 quote!(#crate_path::boxed::CBox<#lifetime, #c_void>, CGlueCtx, ),
-                            );
+                            )?;
                             new_ty_trait_impl.push_types_start(
                                 quote!(#crate_path::boxed::CBox<#lifetime, #c_void>, <CGlueO::ContType as #crate_path::trait_group::CGlueObjBase>::Context, ),
-                            );
+                            )?;
                             new_ty_hrtb.push_types_start(
                                 quote!(#crate_path::boxed::CBox<#from_lifetime, #c_void>, CGlueC::Context,),
-                            );
+                            )?;
                             new_ty_simple.push_types_start(
                                 quote!(#crate_path::boxed::CBox<#from_lifetime_simple, #c_void>, CGlueC::Context,),
-                            );
+                            )?;
                             new_ty_static.push_types_start(
                                 quote!(#crate_path::boxed::CBox<'static, #c_void>, CGlueCtx,),
-                            );
+                            )?;
                             if let Some((cglue_f_ty_def, cglue_f_ty_simple_ident)) = &cglue_f_tys {
                                 from_new_ty.push_types_start(
                                     quote!(#crate_path::boxed::CBox<#from_lifetime, #cglue_f_ty_def>, CGlueC::Context, ),
-                                );
+                                )?;
                                 from_new_ty_simple.push_types_start(
                                     quote!(#crate_path::boxed::CBox<#from_lifetime_simple, #cglue_f_ty_simple_ident>, CGlueC::Context,),
-                                );
+                                )?;
                             }
                         } else if x == "wrap_with_group_ref" || x == "wrap_with_obj_ref" {
                             let no_context = quote!(CGlueC::Context);
-                            new_ty.push_types_start(quote!(&#lifetime #c_void, CGlueC::Context,));
-                            new_ty_ret_tmp.push_types_start(quote!(&#lifetime #c_void, CGlueCtx,));
+                            new_ty
+                                .push_types_start(quote!(&#lifetime #c_void, CGlueC::Context,))?;
+                            new_ty_ret_tmp
+                                .push_types_start(quote!(&#lifetime #c_void, CGlueCtx,))?;
                             new_ty_trait_impl.push_types_start(
                                 quote!(&#lifetime #c_void, <CGlueO::ContType as crate::trait_group::CGlueObjBase>::Context,),
-                            );
+                            )?;
                             new_ty_hrtb.push_types_start(
                                 quote!(&#from_lifetime #c_void, CGlueC::Context,),
-                            );
+                            )?;
                             new_ty_simple.push_types_start(
                                 quote!(&#from_lifetime_simple #c_void, CGlueC::Context,),
-                            );
-                            new_ty_static.push_types_start(quote!(&'static #c_void, CGlueCtx,));
+                            )?;
+                            new_ty_static.push_types_start(quote!(&'static #c_void, CGlueCtx,))?;
                             if let Some((cglue_f_ty_def, cglue_f_ty_simple_ident)) = &cglue_f_tys {
                                 from_new_ty.push_types_start(
                                     quote!(&#from_lifetime #cglue_f_ty_def, #no_context,),
-                                );
+                                )?;
                                 from_new_ty_ref.extend(quote!(&#from_lifetime));
                                 from_new_ty_simple.push_types_start(
                                     quote!(&#from_lifetime_simple #cglue_f_ty_simple_ident, #no_context,),
-                                );
+                                )?;
                                 from_new_ty_simple_ref.extend(quote!(&#from_lifetime_simple));
                             }
                         } else if x == "wrap_with_group_mut" || x == "wrap_with_obj_mut" {
                             let no_context = quote!(CGlueC::Context);
-                            new_ty
-                                .push_types_start(quote!(&#lifetime mut #c_void, CGlueC::Context,));
+                            new_ty.push_types_start(
+                                quote!(&#lifetime mut #c_void, CGlueC::Context,),
+                            )?;
                             new_ty_ret_tmp
-                                .push_types_start(quote!(&#lifetime mut #c_void, CGlueCtx,));
+                                .push_types_start(quote!(&#lifetime mut #c_void, CGlueCtx,))?;
                             new_ty_trait_impl.push_types_start(
                                 quote!(&#lifetime mut #c_void, <CGlueO::ContType as crate::trait_group::CGlueObjBase>::Context,),
-                            );
+                            )?;
                             new_ty_hrtb.push_types_start(
                                 quote!(&#from_lifetime mut #c_void, CGlueC::Context,),
-                            );
+                            )?;
                             new_ty_simple.push_types_start(
                                 quote!(&#from_lifetime_simple mut #c_void, CGlueC::Context,),
-                            );
-                            new_ty_static.push_types_start(quote!(&'static mut #c_void, CGlueCtx,));
+                            )?;
+                            new_ty_static
+                                .push_types_start(quote!(&'static mut #c_void, CGlueCtx,))?;
                             if let Some((cglue_f_ty_def, cglue_f_ty_simple_ident)) = &cglue_f_tys {
                                 from_new_ty.push_types_start(
                                     quote!(&#from_lifetime mut #cglue_f_ty_def, #no_context,),
-                                );
+                                )?;
                                 from_new_ty_ref.extend(quote!(&#from_lifetime mut));
                                 from_new_ty_simple.push_types_start(
                                     quote!(&#from_lifetime_simple mut #cglue_f_ty_simple_ident, #no_context,),
-                                );
+                                )?;
                                 from_new_ty_simple_ref.extend(quote!(&#from_lifetime_simple mut));
                             }
                         } else {
@@ -386,13 +575,11 @@ pub fn process_item(
 
                 let (return_conv, inject_ret_tmp) = match x {
                     "wrap_with_obj" => (
-                        parse2(quote!(|ret| trait_obj!((ret, cglue_ctx) as #target)))
-                            .expect("Internal closure parsing fail"),
+                        parse2(quote!(|ret| trait_obj!((ret, cglue_ctx) as #target)))?,
                         false,
                     ),
                     "wrap_with_group" => (
-                        parse2(quote!(|ret| group_obj!((ret, cglue_ctx) as #target)))
-                            .expect("Internal closure parsing fail"),
+                        parse2(quote!(|ret| group_obj!((ret, cglue_ctx) as #target)))?,
                         false,
                     ),
                     "wrap_with_obj_ref" => (
@@ -400,8 +587,7 @@ pub fn process_item(
                             let ret = trait_obj!((ret, cglue_ctx) as #target);
                             #ret_write_unsafe
                             unsafe { &*ret_tmp.as_ptr() }
-                        }))
-                        .expect("Internal closure parsing fail"),
+                        }))?,
                         true,
                     ),
                     "wrap_with_group_ref" => (
@@ -409,8 +595,7 @@ pub fn process_item(
                             let ret = group_obj!((ret, cglue_ctx) as #target);
                             #ret_write_unsafe
                             unsafe { &*ret_tmp.as_ptr() }
-                        }))
-                        .expect("Internal closure parsing fail"),
+                        }))?,
                         true,
                     ),
                     "wrap_with_obj_mut" => (
@@ -418,8 +603,7 @@ pub fn process_item(
                             let ret = trait_obj!((ret, cglue_ctx) as #target);
                             #ret_write
                             unsafe { &mut *ret_tmp.as_mut_ptr() }
-                        }))
-                        .expect("Internal closure parsing fail"),
+                        }))?,
                         true,
                     ),
                     "wrap_with_group_mut" => (
@@ -427,8 +611,7 @@ pub fn process_item(
                             let ret = group_obj!((ret, cglue_ctx) as #target);
                             #ret_write
                             unsafe { &mut *ret_tmp.as_mut_ptr() }
-                        }))
-                        .expect("Internal closure parsing fail"),
+                        }))?,
                         true,
                     ),
                     _ => unreachable!(),
@@ -472,12 +655,15 @@ pub fn process_item(
             _ => {}
         }
     }
+
+    Ok(())
 }
 
 pub fn parse_trait(
     tr: &ItemTrait,
     crate_path: &TokenStream,
     also_parse_vtbl_only: bool,
+    com: bool,
     mut process_item: impl FnMut(
         (
             &Option<AssocType>,
@@ -490,8 +676,8 @@ pub fn parse_trait(
         &mut TokenStream,
         &mut BTreeMap<Option<AssocType>, WrappedType>,
         &TokenStream,
-    ),
-) -> (Vec<ParsedFunc>, ParsedGenerics, TokenStream) {
+    ) -> Result<()>,
+) -> Result<(Vec<ParsedFunc>, ParsedGenerics, TokenStream)> {
     let mut funcs = vec![];
     let generics = ParsedGenerics::from(&tr.generics);
     let mut trait_type_defs = TokenStream::new();
@@ -503,17 +689,14 @@ pub fn parse_trait(
     types.insert(
         Some(AssocType::from(format_ident!("Self"))),
         WrappedType {
-            ty: parse2(quote!(CGlueC)).unwrap(),
+            ty: parse2(quote!(CGlueC))?,
             // TODO: should we forward ty in here??
             ty_ret_tmp: None,
             ty_static: None,
-            return_conv: Some(
-                parse2(quote!(|ret| {
-                    use #crate_path::from2::From2;
-                    CGlueC::from2((ret, cglue_ctx))
-                }))
-                .expect("Internal closure parsing fail"),
-            ),
+            return_conv: Some(parse2(quote!(|ret| {
+                use #crate_path::from2::From2;
+                CGlueC::from2((ret, cglue_ctx))
+            }))?),
             lifetime_bound: None,
             lifetime_type_bound: None,
             other_bounds: Some(quote!((CGlueC::ObjType, CGlueCtx): Into<CGlueC>,)),
@@ -550,7 +733,7 @@ pub fn parse_trait(
                 &mut trait_type_defs,
                 &mut types,
                 crate_path,
-            ),
+            )?,
             TraitItem::Method(m) => {
                 let attrs = m
                     .attrs
@@ -565,9 +748,9 @@ pub fn parse_trait(
                 let custom_impl = m
                     .attrs
                     .iter()
-                    .filter(|a| a.path.to_token_stream().to_string() == "custom_impl")
-                    .map(|a| a.parse_args::<CustomFuncImpl>().unwrap())
-                    .next();
+                    .find(|a| a.path.to_token_stream().to_string() == "custom_impl")
+                    .map(|a| a.parse_args::<CustomFuncImpl>())
+                    .transpose()?;
 
                 let only_c_side = m
                     .attrs
@@ -616,7 +799,7 @@ pub fn parse_trait(
                             &mut trait_type_defs,
                             &mut types_c_side_vtbl,
                             crate_path,
-                        );
+                        )?;
                     }
                     (true, &types_c_side_vtbl)
                 } else {
@@ -627,7 +810,13 @@ pub fn parse_trait(
 
                 if custom_impl.is_none() && iter.any(|p| !matches!(p, GenericParam::Lifetime(_))) {
                     if m.default.is_none() {
-                        panic!("Generic function `{}` detected with neither a default nor custom implementation! This is not supported.", m.sig.ident);
+                        return Err(Error::new_spanned(
+                            &m.sig.ident,
+                            format!(
+                                "generic function `{}` has neither a default nor a custom implementation",
+                                m.sig.ident
+                            ),
+                        ));
                     }
                     continue;
                 }
@@ -656,16 +845,21 @@ pub fn parse_trait(
                     crate_path,
                     only_c_side,
                     custom_impl,
-                ));
+                    com,
+                )?);
             }
             _ => {}
         }
     }
 
-    (funcs, generics, trait_type_defs)
+    Ok((funcs, generics, trait_type_defs))
 }
 
-pub fn gen_trait(mut tr: ItemTrait, ext_name: Option<&Ident>) -> TokenStream {
+pub fn gen_trait(
+    mut tr: ItemTrait,
+    ext_name: Option<&Ident>,
+    args: &TraitArgs,
+) -> Result<TokenStream> {
     // Path to trait group import.
     let crate_path = crate::util::crate_path();
     let trg_path: TokenStream = quote!(#crate_path::trait_group);
@@ -707,7 +901,13 @@ pub fn gen_trait(mut tr: ItemTrait, ext_name: Option<&Ident>) -> TokenStream {
     let opaque_ctx_ref_trait_obj_ident = format_ident!("{}CtxRef", trait_name);
     let opaque_arc_ref_trait_obj_ident = format_ident!("{}ArcRef", trait_name);
 
-    let (funcs, generics, trait_type_defs) = parse_trait(&tr, &crate_path, true, process_item);
+    let (funcs, generics, trait_type_defs) = parse_trait(
+        &tr,
+        &crate_path,
+        true,
+        args.com_guid.is_some(),
+        process_item,
+    )?;
 
     let cglue_c_opaque_bound = cglue_c_opaque_bound();
     let ctx_bound = ctx_bound();
@@ -755,27 +955,87 @@ pub fn gen_trait(mut tr: ItemTrait, ext_name: Option<&Ident>) -> TokenStream {
     #[cfg(not(feature = "layout_checks"))]
     let derive_layouts = quote!();
 
+    // COM compatible vtables are prefixed with IUnknown functions.
+    let (mut vtbl_func_defintions, mut vtbl_getter_defintions, mut vtbl_default_funcs, com_impl) =
+        if let Some(guid) = args.com_guid {
+            let com_path = quote!(#crate_path::com);
+
+            let com_abi = com_abi();
+            let query_interface_ty = quote!(unsafe #com_abi fn(this: *mut #c_void, riid: *const #com_path::Guid, out: *mut *mut #c_void) -> #com_path::HResult);
+            let add_ref_ty = quote!(unsafe #com_abi fn(this: *mut #c_void) -> u32);
+            let release_ty = quote!(unsafe #com_abi fn(this: *mut #c_void) -> u32);
+
+            (
+                quote! {
+                    query_interface: #query_interface_ty,
+                    add_ref: #add_ref_ty,
+                    release: #release_ty,
+                },
+                quote! {
+                    /// Getter for `IUnknown::QueryInterface`.
+                    pub fn query_interface(&self) -> #query_interface_ty {
+                        self.query_interface
+                    }
+
+                    /// Getter for `IUnknown::AddRef`.
+                    pub fn add_ref(&self) -> #add_ref_ty {
+                        self.add_ref
+                    }
+
+                    /// Getter for `IUnknown::Release`.
+                    pub fn release(&self) -> #release_ty {
+                        self.release
+                    }
+                },
+                quote! {
+                    query_interface: #com_path::query_interface::<CGlueC, #vtbl_ident<'cglue_a, CGlueC, #gen_use>>,
+                    add_ref: #com_path::add_ref::<CGlueC>,
+                    release: #com_path::release::<CGlueC>,
+                },
+                quote! {
+                    impl<'cglue_a, CGlueC: #trg_path::CGlueObjBase, #gen_declare_stripped> #com_path::ComInterface
+                        for #vtbl_ident<'cglue_a, CGlueC, #gen_use>
+                    where
+                        #gen_where_bounds
+                    {
+                        const IID: #com_path::Guid = #com_path::Guid::from_u128(#guid);
+                    }
+                },
+            )
+        } else {
+            Default::default()
+        };
+
     // Function definitions in the vtable
-    let mut vtbl_func_defintions = TokenStream::new();
 
     for func in &funcs {
         func.vtbl_def(&mut vtbl_func_defintions);
     }
 
     // Getters for vtable functions
-    let mut vtbl_getter_defintions = TokenStream::new();
-
     for func in &funcs {
         func.vtbl_getter_def(&mut vtbl_getter_defintions);
     }
 
     // Default functions for vtable reference
-    let mut vtbl_default_funcs = TokenStream::new();
-
     for func in &funcs {
         func.vtbl_default_def(&mut vtbl_default_funcs);
     }
 
+    // Reflection metadata, and dynamic calls
+    let mut reflect_methods = TokenStream::new();
+    let mut reflect_arms = TokenStream::new();
+
+    for func in &funcs {
+        func.reflect_def(&crate_path, &mut reflect_methods);
+        func.reflect_call_def(&crate_path, &mut reflect_arms);
+    }
+
+    let method_count = funcs.len();
+    let method_descriptors = (0..method_count).map(
+        |i| quote!(#crate_path::reflect::MethodDescriptor::from_info(&CGLUE_META.methods[#i])),
+    );
+
     // Define wrapped functions for the vtable
     let mut cfuncs = TokenStream::new();
 
@@ -813,6 +1073,19 @@ pub fn gen_trait(mut tr: ItemTrait, ext_name: Option<&Ident>) -> TokenStream {
     let phantom_data_definitions = generics.phantom_data_definitions();
     let phantom_data_init = generics.phantom_data_init();
 
+    // Objects are type erased, thus the temporary storage carries the thread safety of the trait.
+    let (is_send, is_sync) = supertrait_send_sync(&tr.supertraits);
+    let thread_marker = thread_marker(is_send, is_sync);
+
+    let lock_impl = lock_trait_impl(
+        &tr,
+        ext_name,
+        &funcs,
+        &generics,
+        &crate_path,
+        is_send || is_sync,
+    );
+
     // Implement the trait for a type that has CGlueObj<OpaqueCGlueVtblT, RetTmp>
     let mut trait_impl_fns = TokenStream::new();
 
@@ -846,6 +1119,13 @@ pub fn gen_trait(mut tr: ItemTrait, ext_name: Option<&Ident>) -> TokenStream {
 
     let cglue_c_bounds = quote!(: #required_ctx 'cglue_a);
 
+    // By-ref instances of `Send` traits are only safe to send, if the underlying type is `Sync`.
+    let cglue_c_send = if is_send {
+        Some(quote!(CGlueC::InstType: Send,))
+    } else {
+        None
+    };
+
     // Add supertrait bounds here. Note that it probably won't work, unless they are one of the
     // core traits (Send, etc.).
     let supertrait_bounds = {
@@ -934,6 +1214,17 @@ pub fn gen_trait(mut tr: ItemTrait, ext_name: Option<&Ident>) -> TokenStream {
     );
     let submod_name = format_ident!("cglue_{}", trait_name.to_string().to_lowercase());
 
+    let trait_name_str = trait_name.to_string();
+    let reflect_path = quote!(#crate_path::reflect);
+
+    let ret_tmp_sync_impl = if funcs.iter().any(ParsedFunc::ret_tmp_interior_mut) {
+        quote!()
+    } else {
+        quote! {
+            unsafe impl<CGlueCtx: #ctx_bound, #gen_use> #trg_path::SyncRetTmp for #ret_tmp_ident<CGlueCtx, #gen_use> {}
+        }
+    };
+
     let ret_tmp = if !ret_tmp_type_defs.is_empty() {
         quote! {
             /// Temporary return value structure, for returning wrapped references.
@@ -948,8 +1239,11 @@ pub fn gen_trait(mut tr: ItemTrait, ext_name: Option<&Ident>) -> TokenStream {
                 #ret_tmp_type_defs
                 #phantom_data_definitions
                 _ty_cglue_ctx: ::core::marker::PhantomData<CGlueCtx>,
+                _cglue_thread: ::core::marker::PhantomData<#thread_marker>,
             }
 
+            #ret_tmp_sync_impl
+
             impl<CGlueCtx: #ctx_bound, #gen_use> #ret_tmp_ident<CGlueCtx, #gen_use>
             {
                 #ret_tmp_getter_defs
@@ -962,6 +1256,7 @@ pub fn gen_trait(mut tr: ItemTrait, ext_name: Option<&Ident>) -> TokenStream {
                         #ret_tmp_default_defs
                         #phantom_data_init
                         _ty_cglue_ctx: ::core::marker::PhantomData{},
+                        _cglue_thread: ::core::marker::PhantomData{},
                     }
                 }
             }
@@ -975,6 +1270,7 @@ pub fn gen_trait(mut tr: ItemTrait, ext_name: Option<&Ident>) -> TokenStream {
             {
                 #phantom_data_definitions
                 _ty_cglue_ctx: ::core::marker::PhantomData<CGlueCtx>,
+                _cglue_thread: ::core::marker::PhantomData<#thread_marker>,
             }
 
             /// Type definition for temporary return value wrapping storage.
@@ -1008,7 +1304,7 @@ pub fn gen_trait(mut tr: ItemTrait, ext_name: Option<&Ident>) -> TokenStream {
     let (layout_checkable_bound, objcont_accessor_bound) = (quote!(), quote!());
 
     // Glue it all together
-    quote! {
+    Ok(quote! {
         #tr
 
         #[doc(hidden)]
@@ -1049,6 +1345,18 @@ pub fn gen_trait(mut tr: ItemTrait, ext_name: Option<&Ident>) -> TokenStream {
             use super::*;
             use super::#trait_impl_name;
 
+            /* Reflection metadata, and trait descriptor built out of it. */
+
+            const CGLUE_META: #reflect_path::TraitMeta = #reflect_path::TraitMeta {
+                name: #trait_name_str,
+                methods: &[#reflect_methods],
+            };
+
+            const CGLUE_METHOD_DESCRIPTORS: [#reflect_path::MethodDescriptor; #method_count] = [#(#method_descriptors),*];
+
+            const CGLUE_DESCRIPTOR: #reflect_path::TraitDescriptor =
+                #reflect_path::TraitDescriptor::from_meta(&CGLUE_META, &CGLUE_METHOD_DESCRIPTORS);
+
             /* Primary vtable definition. */
 
             #[doc = #vtbl_doc]
@@ -1061,16 +1369,60 @@ pub fn gen_trait(mut tr: ItemTrait, ext_name: Option<&Ident>) -> TokenStream {
                 #gen_where_bounds_base
             {
                 #vtbl_func_defintions
+                descriptor: ::core::option::Option<&'static #reflect_path::TraitDescriptor>,
                 _lt_cglue_a: ::core::marker::PhantomData<&'cglue_a CGlueC>,
             }
 
+            // Vtables only hold function pointers, thus, thread safety is determined by the
+            // container, and not the vtable reference.
+            unsafe impl<'cglue_a, CGlueC: #trg_path::CGlueObjBase, #gen_declare_stripped> Send for #vtbl_ident<'cglue_a, CGlueC, #gen_use>
+            where
+                #gen_where_bounds_base
+            {}
+
+            unsafe impl<'cglue_a, CGlueC: #trg_path::CGlueObjBase, #gen_declare_stripped> Sync for #vtbl_ident<'cglue_a, CGlueC, #gen_use>
+            where
+                #gen_where_bounds_base
+            {}
+
             impl<'cglue_a, CGlueC: #trg_path::CGlueObjBase, #gen_declare_stripped> #vtbl_ident<'cglue_a, CGlueC, #gen_use>
             where
                 #gen_where_bounds
             {
                 #vtbl_getter_defintions
+
+                /// Descriptor of the trait.
+                pub const DESCRIPTOR: &'static #reflect_path::TraitDescriptor = &CGLUE_DESCRIPTOR;
+
+                /// Get the descriptor of the trait this vtable implements.
+                ///
+                /// Vtables built outside of Rust may leave the descriptor out, in which case
+                /// [`DESCRIPTOR`](Self::DESCRIPTOR) is returned.
+                pub fn descriptor(&self) -> &'static #reflect_path::TraitDescriptor {
+                    self.descriptor.unwrap_or(Self::DESCRIPTOR)
+                }
+
+                /// Reflection metadata of the trait's methods.
+                pub const META: #reflect_path::TraitMeta = CGLUE_META;
+
+                /// Call a method of the trait by name, with dynamically typed arguments.
+                ///
+                /// Returns `None` if the trait has no method with given name.
+                #[allow(unused_variables)]
+                pub fn cglue_reflect_call<CGlueT: ?Sized + for<#life_declare> #trait_name<#life_use #gen_use>>(
+                    this: &mut CGlueT,
+                    method: &str,
+                    args: &[#reflect_path::Value],
+                ) -> ::core::option::Option<::core::result::Result<#reflect_path::Value, #reflect_path::CallError>> {
+                    match method {
+                        #reflect_arms
+                        _ => None,
+                    }
+                }
             }
 
+            #com_impl
+
             #ret_tmp
 
             /* Default implementation. */
@@ -1078,7 +1430,7 @@ pub fn gen_trait(mut tr: ItemTrait, ext_name: Option<&Ident>) -> TokenStream {
             /// Default vtable reference creation.
             impl<'cglue_a, CGlueC #cglue_c_bounds, CGlueCtx: #ctx_bound, #gen_declare_stripped> Default
                 for &'cglue_a #vtbl_ident<'cglue_a, CGlueC, #gen_use>
-            where #gen_where_bounds #trait_type_bounds #cglue_c_into_inner
+            where #gen_where_bounds #trait_type_bounds #cglue_c_into_inner #cglue_c_send
                 CGlueC::ObjType: for<#life_declare> #trait_name<#life_use #gen_use>,
                 CGlueC: #trg_path::Opaquable,
                 CGlueC::OpaqueTarget: #trg_path::GenericTypeBounds,
@@ -1088,6 +1440,7 @@ pub fn gen_trait(mut tr: ItemTrait, ext_name: Option<&Ident>) -> TokenStream {
                 fn default() -> Self {
                     &#vtbl_ident {
                         #vtbl_default_funcs
+                        descriptor: Some(&CGLUE_DESCRIPTOR),
                         _lt_cglue_a: ::core::marker::PhantomData,
                     }
                 }
@@ -1252,7 +1605,9 @@ pub fn gen_trait(mut tr: ItemTrait, ext_name: Option<&Ident>) -> TokenStream {
             }
 
             #internal_trait_impl
+
+            #lock_impl
             }
         }
-    }
+    })
 }
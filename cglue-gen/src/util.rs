@@ -117,8 +117,7 @@ pub fn split_path_ident(in_path: &Path) -> Result<(Path, Ident, GenericsOut)> {
         }
     }
 
-    let ident =
-        ident.ok_or_else(|| Error::new(proc_macro2::Span::call_site(), "Ident not found!"))?;
+    let ident = ident.ok_or_else(|| Error::new_spanned(in_path, "expected a trait path"))?;
 
     Ok((path, ident, generics))
 }
@@ -0,0 +1,326 @@
+//! Macro expansion snapshots.
+//!
+//! Every test here expands a macro input and compares the pretty-printed output against a stored
+//! snapshot in `tests/snapshots`. Any change to the generated code, and by extension the
+//! generated ABI, shows up as a snapshot diff that needs to be reviewed and accepted explicitly
+//! (`cargo insta review`, or `INSTA_UPDATE=always cargo test -p cglue-gen`).
+
+// Optional features alter the generated code, only snapshot the default configuration.
+#![cfg(not(any(feature = "layout_checks", feature = "rust_void", feature = "unstable")))]
+
+use cglue_gen::forward::gen_forward;
+use cglue_gen::trait_groups::{TraitGroup, TraitGroupImpl};
+use cglue_gen::traits::{gen_trait, TraitArgs};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse2, parse_quote, File, ItemTrait};
+
+fn pretty(tokens: TokenStream) -> String {
+    let file: File = parse2(tokens).expect("generated code does not parse");
+    prettyplease::unparse(&file)
+}
+
+fn expand_trait(args: TokenStream, tr: ItemTrait) -> String {
+    let args: TraitArgs = parse2(args).unwrap();
+    pretty(gen_trait(tr, None, &args).unwrap())
+}
+
+#[test]
+fn trait_simple() {
+    insta::assert_snapshot!(expand_trait(
+        quote!(),
+        parse_quote! {
+            pub trait TA {
+                extern "C" fn ta_1(&self) -> usize;
+            }
+        }
+    ));
+}
+
+#[test]
+fn trait_receivers() {
+    insta::assert_snapshot!(expand_trait(
+        quote!(),
+        parse_quote! {
+            pub trait TC {
+                fn tc_1(&self);
+                extern "C" fn tc_2(&mut self);
+                fn tc_3(&mut self, mut _ignored: usize) {
+                    self.tc_2()
+                }
+                fn tc_4(self) -> Self;
+                unsafe fn tc_5(&self, ptr: *const u8);
+            }
+        }
+    ));
+}
+
+#[test]
+fn trait_arg_conversions() {
+    insta::assert_snapshot!(expand_trait(
+        quote!(),
+        parse_quote! {
+            pub trait Conv {
+                fn name(&self, name: &str) -> usize;
+                fn read(&mut self, buf: &mut [u8]) -> Option<usize>;
+                fn write(&mut self, buf: &[u8]) -> Result<usize, usize>;
+                fn maybe(&self, val: Option<u64>, res: Result<u64, u32>);
+            }
+        }
+    ));
+}
+
+#[test]
+fn trait_owned_conversions() {
+    insta::assert_snapshot!(expand_trait(
+        quote!(),
+        parse_quote! {
+            pub trait Owned {
+                fn string(&self, val: String) -> String;
+                fn vec(&self, val: Vec<u32>) -> Vec<u32>;
+                fn boxed(&self, val: Box<u64>) -> Box<u64>;
+                fn boxed_slice(&self, val: Box<[u8]>) -> Box<[u8]>;
+            }
+        }
+    ));
+}
+
+#[test]
+fn trait_tuple_conversions() {
+    insta::assert_snapshot!(expand_trait(
+        quote!(),
+        parse_quote! {
+            pub trait Tuples {
+                fn pair(&self, val: (u8, u16)) -> (u16, u8);
+                fn nested(&self, val: Option<(u8, (u16, u32))>) -> Result<(u8, bool), ()>;
+            }
+        }
+    ));
+}
+
+#[test]
+fn trait_callbacks() {
+    insta::assert_snapshot!(expand_trait(
+        quote!(),
+        parse_quote! {
+            pub trait Callbacks {
+                fn each(&self, cb: impl FnMut(usize) -> bool);
+                fn each_dyn(&self, cb: &mut dyn FnMut(usize));
+            }
+        }
+    ));
+}
+
+#[test]
+fn trait_value_callbacks() {
+    insta::assert_snapshot!(expand_trait(
+        quote!(),
+        parse_quote! {
+            pub trait ValueCallbacks {
+                fn query(&self, f: impl Fn(u32) -> u64) -> u64;
+                fn add(&self, f: &mut dyn FnMut(u32, u64) -> u64) -> u64;
+            }
+        }
+    ));
+}
+
+#[test]
+fn trait_iterators() {
+    insta::assert_snapshot!(expand_trait(
+        quote!(),
+        parse_quote! {
+            pub trait Iterators {
+                fn sum(&self, iter: impl Iterator<Item = usize>) -> usize;
+                fn next_dyn(&self, iter: &mut dyn Iterator<Item = usize>) -> Option<usize>;
+            }
+        }
+    ));
+}
+
+#[test]
+fn trait_fn_boxes() {
+    insta::assert_snapshot!(expand_trait(
+        quote!(),
+        parse_quote! {
+            pub trait FnBoxes {
+                fn subscribe(&mut self, f: Box<dyn FnMut(u32) -> bool + Send>);
+                fn once(&self, f: Box<dyn FnOnce(u32)>) -> Box<dyn FnMut(u32) -> u32>;
+            }
+        }
+    ));
+}
+
+#[test]
+fn trait_ext() {
+    let tr: ItemTrait = parse_quote! {
+        pub trait Clone {
+            fn clone(&self) -> Self;
+        }
+    };
+    let ext_ident = format_ident!("{}Ext", tr.ident);
+    insta::assert_snapshot!(pretty(
+        gen_trait(tr, Some(&ext_ident), &Default::default()).unwrap()
+    ));
+}
+
+#[test]
+fn trait_com() {
+    insta::assert_snapshot!(expand_trait(
+        quote!(com, guid = "6d2a0f4c-3b1e-4f8a-9c7d-2e5b8a1f0c3d"),
+        parse_quote! {
+            pub trait ComTrait {
+                fn value(&self) -> u32;
+            }
+        }
+    ));
+}
+
+#[test]
+fn wrap_with() {
+    insta::assert_snapshot!(expand_trait(
+        quote!(),
+        parse_quote! {
+            pub trait WrapWith {
+                #[wrap_with(u64)]
+                #[return_wrap(|ret| ret + 1)]
+                type Item;
+
+                fn get(&self) -> Self::Item;
+            }
+        }
+    ));
+}
+
+#[test]
+fn wrap_with_obj() {
+    insta::assert_snapshot!(expand_trait(
+        quote!(),
+        parse_quote! {
+            pub trait WrapObj {
+                #[wrap_with_obj(TA)]
+                type Owned: TA;
+                #[wrap_with_obj_ref(TA)]
+                type Borrowed: TA;
+                #[wrap_with_obj_mut(TA)]
+                type BorrowedMut: TA;
+
+                fn owned(&self) -> Self::Owned;
+                fn borrowed(&self) -> &Self::Borrowed;
+                fn borrowed_mut(&mut self) -> &mut Self::BorrowedMut;
+            }
+        }
+    ));
+}
+
+#[test]
+fn wrap_with_group() {
+    insta::assert_snapshot!(expand_trait(
+        quote!(),
+        parse_quote! {
+            pub trait WrapGroup {
+                #[wrap_with_group(TestGroup)]
+                type Owned: TA;
+                #[wrap_with_group_ref(TestGroup)]
+                type Borrowed: TA;
+                #[wrap_with_group_mut(TestGroup)]
+                type BorrowedMut: TA;
+
+                fn owned(&self) -> Self::Owned;
+                fn borrowed(&self) -> &Self::Borrowed;
+                fn borrowed_mut(&mut self) -> &mut Self::BorrowedMut;
+            }
+        }
+    ));
+}
+
+#[test]
+fn hrtb() {
+    insta::assert_snapshot!(expand_trait(
+        quote!(),
+        parse_quote! {
+            pub trait PluginInner<'a> {
+                #[wrap_with_obj(SubPlugin)]
+                type Ret: SubPlugin<'a> + 'a;
+
+                fn get_plug(&'a mut self) -> Self::Ret;
+            }
+        }
+    ));
+}
+
+#[test]
+fn gat() {
+    insta::assert_snapshot!(expand_trait(
+        quote!(),
+        parse_quote! {
+            pub trait GroupGatReturn {
+                #[wrap_with_group(TestGroup)]
+                type ReturnType<'abc>: TA + 'abc
+                where
+                    Self: 'abc;
+
+                fn ggr_1<'a>(&'a mut self, val: &'a u32) -> Self::ReturnType<'a>;
+            }
+        }
+    ));
+}
+
+#[test]
+fn generic_trait() {
+    insta::assert_snapshot!(expand_trait(
+        quote!(),
+        parse_quote! {
+            pub trait Getter<T: Copy + 'static> {
+                fn get(&self) -> T;
+                fn set(&mut self, val: T);
+            }
+        }
+    ));
+}
+
+#[test]
+fn forward() {
+    let tr: ItemTrait = parse_quote! {
+        pub trait TB {
+            extern "C" fn tb_1(&self, val: usize) -> usize;
+            fn tb_2(&mut self, val: usize) -> usize;
+        }
+    };
+    insta::assert_snapshot!(pretty(gen_forward(tr, None).unwrap()));
+}
+
+#[test]
+fn group() {
+    let group: TraitGroup = parse2(quote!(TestGroup, TA, { TB, TC })).unwrap();
+    insta::assert_snapshot!(pretty(group.create_group().unwrap()));
+}
+
+#[test]
+fn group_send_sync() {
+    let group: TraitGroup = parse2(quote!(
+        #[cglue_trait_group(send, sync)]
+        TestGroup,
+        TA,
+        { TB }
+    ))
+    .unwrap();
+    insta::assert_snapshot!(pretty(group.create_group().unwrap()));
+}
+
+#[test]
+fn group_lock() {
+    let group: TraitGroup = parse2(quote!(
+        #[cglue_trait_group(lock)]
+        TestGroup,
+        TA,
+        {}
+    ))
+    .unwrap();
+    insta::assert_snapshot!(pretty(group.create_group().unwrap()));
+}
+
+#[test]
+fn group_impl() {
+    let group: TraitGroupImpl = parse2(quote!(SA, TestGroup, { TC })).unwrap();
+    insta::assert_snapshot!(pretty(group.implement_group()));
+}
@@ -15,13 +15,22 @@ use syn::*;
 ///
 /// This macro will generate vtable structures alongside required traits and implementations needed
 /// for constructing CGlue objects and their groups.
+///
+/// # Arguments
+///
+/// * `com` - lay out the vtable to be compatible with COM interfaces. The vtable gets prefixed
+///   with `IUnknown` functions, and all functions take the interface pointer, thus methods must
+///   take `&self`, or `&mut self`. Objects are passed to COM clients through
+///   `cglue::com::into_raw`, which moves them into a `CArc`.
+///   Requires `guid = "XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX"` argument to be set.
 #[proc_macro_attribute]
-pub fn cglue_trait(_args: TokenStream, input: TokenStream) -> TokenStream {
+pub fn cglue_trait(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as cglue_gen::traits::TraitArgs);
     let tr = parse_macro_input!(input as ItemTrait);
 
-    let trait_def = cglue_gen::traits::gen_trait(tr, None);
-
-    trait_def.into()
+    cglue_gen::traits::gen_trait(tr, None, &args)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
 }
 
 /// Make an external trait CGlue compatible.
@@ -31,14 +40,15 @@ pub fn cglue_trait(_args: TokenStream, input: TokenStream) -> TokenStream {
 ///
 /// This is very useful when third-party crates are needed to be CGlue compatible.
 #[proc_macro_attribute]
-pub fn cglue_trait_ext(_args: TokenStream, input: TokenStream) -> TokenStream {
+pub fn cglue_trait_ext(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as cglue_gen::traits::TraitArgs);
     let tr = parse_macro_input!(input as ItemTrait);
 
     let ext_ident = format_ident!("{}Ext", tr.ident);
 
-    let trait_def = cglue_gen::traits::gen_trait(tr, Some(&ext_ident));
-
-    trait_def.into()
+    cglue_gen::traits::gen_trait(tr, Some(&ext_ident), &args)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
 }
 
 /// Convert into a CGlue compatible object.
@@ -90,7 +100,9 @@ pub fn trait_obj(args: TokenStream) -> TokenStream {
 #[proc_macro]
 pub fn cglue_trait_group(args: TokenStream) -> TokenStream {
     let args = parse_macro_input!(args as TraitGroup);
-    args.create_group().into()
+    args.create_group()
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
 }
 
 /// Implement a CGlue group for a specific type.
@@ -276,7 +288,9 @@ pub fn check(args: TokenStream) -> TokenStream {
 /// Implement builtin external traits.
 #[proc_macro]
 pub fn cglue_builtin_ext_traits(_: TokenStream) -> TokenStream {
-    cglue_gen::ext::impl_store().into()
+    cglue_gen::ext::impl_store()
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
 }
 
 /// Generate forward trait implementation for Fwd.
@@ -285,7 +299,9 @@ pub fn cglue_builtin_ext_traits(_: TokenStream) -> TokenStream {
 #[proc_macro_attribute]
 pub fn cglue_forward(_: TokenStream, input: TokenStream) -> TokenStream {
     let tr = parse_macro_input!(input as ItemTrait);
-    gen_forward(tr, None).into()
+    gen_forward(tr, None)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
 }
 
 /// Generate forward trait implementation for Fwd.
@@ -295,13 +311,17 @@ pub fn cglue_forward(_: TokenStream, input: TokenStream) -> TokenStream {
 pub fn cglue_forward_ext(args: TokenStream, input: TokenStream) -> TokenStream {
     let path = parse_macro_input!(args as proc_macro2::TokenStream);
     let tr = parse_macro_input!(input as ItemTrait);
-    gen_forward(tr, Some(path)).into()
+    gen_forward(tr, Some(path))
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
 }
 
 /// Implement [macro@cglue_forward_ext] for all builtin external traits.
 #[proc_macro]
 pub fn cglue_builtin_ext_forward(_: TokenStream) -> TokenStream {
-    cglue_gen::ext::impl_ext_forward().into()
+    cglue_gen::ext::impl_ext_forward()
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
 }
 
 // Marker macros for wrapping
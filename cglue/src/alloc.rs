@@ -0,0 +1,182 @@
+//! # FFI-safe allocators.
+//!
+//! [`CAllocator`] allows the host to supply an allocator to plugins, for instance, to place all
+//! of a plugin's memory into an arena, or to account for it. Storage of [`CBox`](crate::boxed::CBox),
+//! [`CSliceBox`](crate::boxed::CSliceBox), and [`CVec`](crate::vec::CVec) created with a
+//! `CAllocator` is prefixed with the allocator, so that it can be freed from any module, without
+//! changing the layout of these types.
+use crate::arc::CArc;
+use crate::trait_group::c_void;
+use crate::trait_group::Opaquable;
+use core::alloc::{GlobalAlloc, Layout};
+
+/// Allocation functions.
+///
+/// Both functions receive the allocator instance stored in [`CAllocator`].
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct AllocVtbl {
+    /// Allocate `size` bytes aligned to `align`. Returns null on failure.
+    pub alloc: unsafe extern "C" fn(&c_void, size: usize, align: usize) -> *mut u8,
+    /// Free memory previously returned by `alloc` with the same `size`, and `align`.
+    pub dealloc: unsafe extern "C" fn(&c_void, ptr: *mut u8, size: usize, align: usize),
+}
+
+/// FFI-safe allocator
+///
+/// Reference counted allocator instance paired with the functions operating on it. Every
+/// allocation keeps the allocator alive until it is freed.
+///
+/// # Examples
+///
+/// ```
+/// use cglue::alloc::CAllocator;
+/// use cglue::boxed::CBox;
+/// use cglue::vec::CVec;
+///
+/// let alloc = CAllocator::new(std::alloc::System);
+///
+/// let b = CBox::new_in(42u64, alloc.clone());
+/// assert_eq!(*b, 42);
+///
+/// let mut v = CVec::with_allocator(alloc);
+/// v.push(1u8);
+/// assert_eq!(&*v, &[1]);
+/// ```
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct CAllocator {
+    instance: CArc<c_void>,
+    vtbl: &'static AllocVtbl,
+}
+
+// Safety: `from_raw_parts` requires the allocator to be usable from any thread.
+unsafe impl Send for CAllocator {}
+unsafe impl Sync for CAllocator {}
+
+impl CAllocator {
+    /// Create a `CAllocator` from a Rust allocator.
+    pub fn new<A: GlobalAlloc + Send + Sync + 'static>(alloc: A) -> Self {
+        let instance = CArc::from(alloc).into_opaque();
+        unsafe { Self::from_raw_parts(instance, global_alloc_vtbl::<A>()) }
+    }
+
+    /// Create a `CAllocator` from host-provided parts.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must be valid, and `vtbl` functions must implement an allocator on it that is
+    /// callable from any thread.
+    pub unsafe fn from_raw_parts(instance: CArc<c_void>, vtbl: &'static AllocVtbl) -> Self {
+        Self { instance, vtbl }
+    }
+
+    /// Allocate memory with given layout. Returns null on failure.
+    ///
+    /// # Safety
+    ///
+    /// `layout` must have non-zero size.
+    pub unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        (self.vtbl.alloc)(self.instance(), layout.size(), layout.align())
+    }
+
+    /// Free memory allocated by this allocator.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by [`alloc`](Self::alloc) of this allocator with the same
+    /// `layout`.
+    pub unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        (self.vtbl.dealloc)(self.instance(), ptr, layout.size(), layout.align())
+    }
+
+    fn instance(&self) -> &c_void {
+        self.instance
+            .as_ref()
+            .expect("CAllocator instance is always valid")
+    }
+}
+
+impl Clone for CAllocator {
+    fn clone(&self) -> Self {
+        Self {
+            instance: self.instance.clone(),
+            vtbl: self.vtbl,
+        }
+    }
+}
+
+fn global_alloc_vtbl<A: GlobalAlloc>() -> &'static AllocVtbl {
+    &AllocVtbl {
+        alloc: global_alloc::<A>,
+        dealloc: global_dealloc::<A>,
+    }
+}
+
+unsafe extern "C" fn global_alloc<A: GlobalAlloc>(
+    this: &c_void,
+    size: usize,
+    align: usize,
+) -> *mut u8 {
+    let this = &*(this as *const c_void as *const A);
+    this.alloc(Layout::from_size_align_unchecked(size, align))
+}
+
+unsafe extern "C" fn global_dealloc<A: GlobalAlloc>(
+    this: &c_void,
+    ptr: *mut u8,
+    size: usize,
+    align: usize,
+) {
+    let this = &*(this as *const c_void as *const A);
+    this.dealloc(ptr, Layout::from_size_align_unchecked(size, align))
+}
+
+/// Layout of the storage with the allocator header, and offset of the value within it.
+fn header_layout(value: Layout) -> (Layout, usize) {
+    Layout::new::<CAllocator>()
+        .extend(value)
+        .expect("allocation too large")
+}
+
+/// Allocate storage for `value` prefixed with the allocator.
+///
+/// Returns pointer to the value.
+pub(crate) fn alloc_with_header(alloc: CAllocator, value: Layout) -> *mut u8 {
+    let (layout, offset) = header_layout(value);
+
+    unsafe {
+        let base = alloc.alloc(layout);
+
+        if base.is_null() {
+            std::alloc::handle_alloc_error(layout);
+        }
+
+        core::ptr::write(base as *mut CAllocator, alloc);
+
+        base.add(offset)
+    }
+}
+
+/// Get the allocator of storage created with `alloc_with_header`.
+///
+/// # Safety
+///
+/// `ptr` must have been returned by `alloc_with_header` with the same `value` layout.
+pub(crate) unsafe fn header<'a>(ptr: *mut u8, value: Layout) -> &'a CAllocator {
+    let (_, offset) = header_layout(value);
+    &*(ptr.sub(offset) as *const CAllocator)
+}
+
+/// Free storage created with `alloc_with_header`.
+///
+/// # Safety
+///
+/// `ptr` must have been returned by `alloc_with_header` with the same `value` layout. The value
+/// must already be dropped.
+pub(crate) unsafe fn dealloc_with_header(ptr: *mut u8, value: Layout) {
+    let (layout, offset) = header_layout(value);
+    let base = ptr.sub(offset);
+    let alloc = core::ptr::read(base as *const CAllocator);
+    alloc.dealloc(base, layout);
+}
@@ -247,6 +247,16 @@ impl<T> CArcSome<T> {
         std::mem::forget(self);
         Arc::from_raw(ptr)
     }
+
+    /// Leak the arc, and get the pointer to its instance.
+    ///
+    /// The reference is given up, and the instance is never dropped, unless the reference is
+    /// reclaimed through the underlying `Arc`.
+    pub(crate) fn into_raw(self) -> &'static T {
+        let instance = self.instance;
+        std::mem::forget(self);
+        instance
+    }
 }
 
 impl<T> From<T> for CArcSome<T> {
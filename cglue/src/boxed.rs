@@ -1,29 +1,34 @@
 //! # FFI-safe wrapped box.
+use crate::alloc::{alloc_with_header, dealloc_with_header, CAllocator};
 use crate::slice::CSliceMut;
 use crate::trait_group::c_void;
 use crate::trait_group::*;
+use core::alloc::Layout;
+use core::mem::ManuallyDrop;
 use core::ops::{Deref, DerefMut};
 use std::boxed::Box;
+use std::vec::Vec;
 
 /// FFI-safe box
 ///
 /// This box has a static self reference, alongside a custom drop function.
 ///
 /// The drop function can be called from anywhere, it will free on correct allocator internally.
+/// The free function releases the storage without dropping the value, and is used when the
+/// value gets moved out of the box.
 #[repr(C)]
 #[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
 pub struct CBox<'a, T: 'a> {
     instance: &'a mut T,
     drop_fn: Option<unsafe extern "C" fn(&mut T)>,
+    free_fn: Option<unsafe extern "C" fn(&mut T)>,
 }
 
 impl<T> super::trait_group::IntoInner for CBox<'_, T> {
     type InnerTarget = T;
 
     unsafe fn into_inner(self) -> Self::InnerTarget {
-        let b = Box::from_raw(self.instance);
-        std::mem::forget(self);
-        *b
+        self.into_value()
     }
 }
 
@@ -47,6 +52,48 @@ impl<T> From<Box<T>> for CBox<'_, T> {
         Self {
             instance,
             drop_fn: Some(cglue_drop_box::<T>),
+            free_fn: Some(cglue_free_box::<T>),
+        }
+    }
+}
+
+impl<T> CBox<'_, T> {
+    /// Allocate the value on given allocator.
+    ///
+    /// The box is freed through the allocator, both on drop, and when the value is moved out
+    /// through [`into_box`](Self::into_box), or [`IntoInner`].
+    pub fn new_in(this: T, alloc: CAllocator) -> Self {
+        let instance = alloc_with_header(alloc, Layout::new::<T>()) as *mut T;
+        unsafe {
+            instance.write(this);
+            Self {
+                instance: &mut *instance,
+                drop_fn: Some(cglue_drop_box_in::<T>),
+                free_fn: Some(cglue_free_box_in::<T>),
+            }
+        }
+    }
+
+    /// Convert back into a standard `Box`.
+    ///
+    /// The value is moved into a new `Box`, and the old storage is released through the box's
+    /// free function, thus the box may come from any allocator. Boxes built by the C and C++
+    /// bindings take the free function alongside the drop function - only boxes that do not own
+    /// their storage should leave it out.
+    pub fn into_box(self) -> Box<T> {
+        Box::new(self.into_value())
+    }
+
+    /// Move the value out, and release the storage.
+    fn into_value(self) -> T {
+        let mut this = ManuallyDrop::new(self);
+        let instance: *mut T = &mut *this.instance;
+        unsafe {
+            let value = instance.read();
+            if let Some(free_fn) = this.free_fn.take() {
+                free_fn(&mut *instance);
+            }
+            value
         }
     }
 }
@@ -82,16 +129,32 @@ unsafe extern "C" fn cglue_drop_box<T>(this: &mut T) {
     let _ = Box::from_raw(this);
 }
 
+unsafe extern "C" fn cglue_free_box<T>(this: &mut T) {
+    let _ = Box::from_raw(this as *mut T as *mut ManuallyDrop<T>);
+}
+
+unsafe extern "C" fn cglue_drop_box_in<T>(this: &mut T) {
+    core::ptr::drop_in_place(this);
+    cglue_free_box_in(this);
+}
+
+unsafe extern "C" fn cglue_free_box_in<T>(this: &mut T) {
+    dealloc_with_header(this as *mut T as *mut u8, Layout::new::<T>());
+}
+
 /// FFI-safe (unsized) boxed slice
 ///
 /// This box has a static self reference, alongside a custom drop function.
 ///
 /// The drop function can be called from anywhere, it will free on correct allocator internally.
+/// The free function releases the storage without dropping the elements, and is used when the
+/// elements get moved out of the box.
 #[repr(C)]
 #[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
 pub struct CSliceBox<'a, T: 'a> {
     instance: CSliceMut<'a, T>,
     drop_fn: Option<unsafe extern "C" fn(&mut CSliceMut<'a, T>)>,
+    free_fn: Option<unsafe extern "C" fn(&mut CSliceMut<'a, T>)>,
 }
 
 impl<T> Deref for CSliceBox<'_, T> {
@@ -114,10 +177,50 @@ impl<T> From<Box<[T]>> for CSliceBox<'_, T> {
         Self {
             instance,
             drop_fn: Some(cglue_drop_slice_box::<T>),
+            free_fn: Some(cglue_free_slice_box::<T>),
         }
     }
 }
 
+impl<T> CSliceBox<'_, T> {
+    /// Move the elements into a slice allocated on given allocator.
+    ///
+    /// The slice is freed through the allocator, both on drop, and when the elements are moved out
+    /// through [`into_boxed_slice`](Self::into_boxed_slice).
+    pub fn new_in(mut elems: Vec<T>, alloc: CAllocator) -> Self {
+        let len = elems.len();
+        let data = alloc_with_header(alloc, slice_layout::<T>(len)) as *mut T;
+        unsafe {
+            core::ptr::copy_nonoverlapping(elems.as_ptr(), data, len);
+            elems.set_len(0);
+            Self {
+                instance: core::slice::from_raw_parts_mut(data, len).into(),
+                drop_fn: Some(cglue_drop_slice_box_in::<T>),
+                free_fn: Some(cglue_free_slice_box_in::<T>),
+            }
+        }
+    }
+
+    /// Convert back into a standard boxed slice.
+    ///
+    /// The elements are moved into a new boxed slice, and the old storage is released through the
+    /// box's free function, thus the slice may come from any allocator. Boxes without a free
+    /// function leak their storage.
+    pub fn into_boxed_slice(self) -> Box<[T]> {
+        let mut this = ManuallyDrop::new(self);
+        let len = this.instance.len();
+        let mut elems = Vec::with_capacity(len);
+        unsafe {
+            core::ptr::copy_nonoverlapping(this.instance.as_ptr(), elems.as_mut_ptr(), len);
+            elems.set_len(len);
+            if let Some(free_fn) = this.free_fn.take() {
+                free_fn(&mut this.instance);
+            }
+        }
+        elems.into_boxed_slice()
+    }
+}
+
 impl<T> Drop for CSliceBox<'_, T> {
     fn drop(&mut self) {
         if let Some(drop_fn) = self.drop_fn.take() {
@@ -136,3 +239,24 @@ unsafe extern "C" fn cglue_drop_slice_box<T>(this: &mut CSliceMut<'_, T>) {
     let extended_instance = (this as *mut CSliceMut<_>).as_mut().unwrap();
     let _ = Box::from_raw(extended_instance.as_slice_mut());
 }
+
+unsafe extern "C" fn cglue_free_slice_box<T>(this: &mut CSliceMut<'_, T>) {
+    let data = this.as_mut_ptr() as *mut ManuallyDrop<T>;
+    let _ = Box::from_raw(core::ptr::slice_from_raw_parts_mut(data, this.len()));
+}
+
+unsafe extern "C" fn cglue_drop_slice_box_in<T>(this: &mut CSliceMut<'_, T>) {
+    core::ptr::drop_in_place(core::ptr::slice_from_raw_parts_mut(
+        this.as_mut_ptr(),
+        this.len(),
+    ));
+    cglue_free_slice_box_in(this);
+}
+
+unsafe extern "C" fn cglue_free_slice_box_in<T>(this: &mut CSliceMut<'_, T>) {
+    dealloc_with_header(this.as_mut_ptr() as *mut u8, slice_layout::<T>(this.len()));
+}
+
+fn slice_layout<T>(len: usize) -> Layout {
+    Layout::array::<T>(len).expect("allocation too large")
+}
@@ -12,8 +12,17 @@
 //! `OpaqueCallback`, as the name suggests, marks the `context` as opaque, casts it to `c_void`
 //! pointer. It allows the code not to care about what's behind the context, it just knows that it
 //! needs to pass it over to the callback.
+//!
+//! `CFn` and `CFnMut` generalize the callback to any return value, and multiple arguments that get
+//! passed as a `CTup`.
+//!
+//! `CFnBox` and `CFnOnceBox` own their context instead of borrowing it, and carry a drop function
+//! alongside. They can be stored for later use, for instance, in event subscriptions.
 
-use crate::trait_group::c_void;
+use crate::boxed::CBox;
+use crate::trait_group::{c_void, Opaquable};
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
 use std::prelude::v1::*;
 
 // C style callbacks that are needed so that C code can easily use callback like functions
@@ -158,3 +167,322 @@ impl<T, F: FnMut(T) -> bool> Callbackable<T> for F {
         (*self)(data)
     }
 }
+
+/// Borrowed FFI-safe closure with an arbitrary return value.
+///
+/// Unlike [`OpaqueCallback`], the closure can return any (FFI-safe) value. Closures with multiple
+/// arguments take them as a single [`CTup`](crate::tuple) value.
+///
+/// # Examples
+///
+/// ```
+/// use cglue::callback::CFnMut;
+/// use cglue::tuple::CTup2;
+///
+/// extern "C" fn apply(mut f: CFnMut<CTup2<u32, u32>, u32>) -> u32 {
+///     f.call(CTup2(1, 2)) + f.call(CTup2(3, 4))
+/// }
+///
+/// let mut calls = 0;
+/// let mut add = |CTup2(a, b)| {
+///     calls += 1;
+///     a + b
+/// };
+///
+/// assert_eq!(apply((&mut add).into()), 10);
+/// assert_eq!(calls, 2);
+/// ```
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct CFnMut<'a, A, R = ()> {
+    context: &'a mut c_void,
+    func: unsafe extern "C" fn(&mut c_void, A) -> R,
+    _unsend: PhantomData<*mut ()>,
+}
+
+impl<'a, A, R> CFnMut<'a, A, R> {
+    pub fn call(&mut self, args: A) -> R {
+        unsafe { (self.func)(self.context, args) }
+    }
+}
+
+impl<'a, F: FnMut(A) -> R, A, R> From<&'a mut F> for CFnMut<'a, A, R> {
+    fn from(func: &'a mut F) -> Self {
+        unsafe extern "C" fn call<F: FnMut(A) -> R, A, R>(func: &mut F, args: A) -> R {
+            func(args)
+        }
+
+        // SAFETY: the context is always passed alongside the function it was created for.
+        let call = unsafe {
+            core::mem::transmute::<
+                unsafe extern "C" fn(&mut F, A) -> R,
+                unsafe extern "C" fn(&mut c_void, A) -> R,
+            >(call::<F, A, R>)
+        };
+
+        Self {
+            context: func.into_opaque(),
+            func: call,
+            _unsend: PhantomData,
+        }
+    }
+}
+
+/// Borrowed FFI-safe closure that does not mutate its state.
+///
+/// This is the shared counterpart of [`CFnMut`], and it can be freely copied around.
+///
+/// # Examples
+///
+/// ```
+/// use cglue::callback::CFn;
+///
+/// extern "C" fn answer(f: CFn<u32, u64>) -> u64 {
+///     f.call(42)
+/// }
+///
+/// let base = 100;
+/// let respond = |query: u32| base + query as u64;
+///
+/// assert_eq!(answer((&respond).into()), 142);
+/// ```
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct CFn<'a, A, R = ()> {
+    context: &'a c_void,
+    func: unsafe extern "C" fn(&c_void, A) -> R,
+    _unsend: PhantomData<*mut ()>,
+}
+
+impl<'a, A, R> Clone for CFn<'a, A, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, A, R> Copy for CFn<'a, A, R> {}
+
+impl<'a, A, R> CFn<'a, A, R> {
+    pub fn call(&self, args: A) -> R {
+        unsafe { (self.func)(self.context, args) }
+    }
+}
+
+impl<'a, F: Fn(A) -> R, A, R> From<&'a F> for CFn<'a, A, R> {
+    fn from(func: &'a F) -> Self {
+        unsafe extern "C" fn call<F: Fn(A) -> R, A, R>(func: &F, args: A) -> R {
+            func(args)
+        }
+
+        // SAFETY: the context is always passed alongside the function it was created for.
+        let call = unsafe {
+            core::mem::transmute::<
+                unsafe extern "C" fn(&F, A) -> R,
+                unsafe extern "C" fn(&c_void, A) -> R,
+            >(call::<F, A, R>)
+        };
+
+        Self {
+            context: func.into_opaque(),
+            func: call,
+            _unsend: PhantomData,
+        }
+    }
+}
+
+/// Owned FFI-safe closure.
+///
+/// Unlike [`OpaqueCallback`], the closure is boxed and gets dropped alongside this object. The
+/// closure is not required to be `Send`, thus neither is `CFnBox`. Use [`CFnBox::new_send`] to
+/// build a callback that can be sent across threads.
+///
+/// # Examples
+///
+/// ```
+/// use cglue::callback::CFnBox;
+///
+/// let mut sum = 0;
+/// let mut cb = CFnBox::new(move |v: u32| {
+///     sum += v;
+///     sum
+/// });
+///
+/// assert_eq!(cb.call(2), 2);
+/// assert_eq!(cb.call(3), 5);
+/// ```
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct CFnBox<'a, T, R = ()> {
+    context: CBox<'a, c_void>,
+    func: unsafe extern "C" fn(&mut c_void, T) -> R,
+    _unsend: PhantomData<*mut ()>,
+}
+
+impl<'a, T, R> CFnBox<'a, T, R> {
+    pub fn new<F: FnMut(T) -> R + 'a>(func: F) -> Self {
+        unsafe extern "C" fn call<F: FnMut(T) -> R, T, R>(func: &mut F, arg: T) -> R {
+            func(arg)
+        }
+
+        // SAFETY: the context is always passed alongside the function it was created for.
+        let call = unsafe {
+            core::mem::transmute::<
+                unsafe extern "C" fn(&mut F, T) -> R,
+                unsafe extern "C" fn(&mut c_void, T) -> R,
+            >(call::<F, T, R>)
+        };
+
+        Self {
+            context: CBox::from(func).into_opaque(),
+            func: call,
+            _unsend: PhantomData,
+        }
+    }
+
+    pub fn new_send<F: FnMut(T) -> R + Send + 'a>(func: F) -> CSend<Self> {
+        CSend(Self::new(func))
+    }
+
+    pub fn call(&mut self, arg: T) -> R {
+        unsafe { (self.func)(&mut self.context, arg) }
+    }
+
+    /// Convert into a boxed Rust closure.
+    pub fn into_box(mut self) -> Box<dyn FnMut(T) -> R + 'a>
+    where
+        T: 'a,
+        R: 'a,
+    {
+        Box::new(move |arg| self.call(arg))
+    }
+}
+
+impl<'a, T, R, F: FnMut(T) -> R + 'a> From<F> for CFnBox<'a, T, R> {
+    fn from(func: F) -> Self {
+        Self::new(func)
+    }
+}
+
+impl<'a, T, R, F: FnMut(T) -> R + Send + 'a> From<F> for CSend<CFnBox<'a, T, R>> {
+    fn from(func: F) -> Self {
+        CFnBox::new_send(func)
+    }
+}
+
+impl<'a, T: 'a, R: 'a> CSend<CFnBox<'a, T, R>> {
+    /// Convert into a boxed, sendable Rust closure.
+    pub fn into_box(mut self) -> Box<dyn FnMut(T) -> R + Send + 'a> {
+        Box::new(move |arg| self.call(arg))
+    }
+}
+
+/// Owned FFI-safe closure that can only be called once.
+///
+/// The closure is consumed, and dropped after the call. The C side must not invoke `func` more
+/// than once, and should call the drop function afterwards, like with any other box.
+///
+/// # Examples
+///
+/// ```
+/// use cglue::callback::CFnOnceBox;
+///
+/// let name = String::from("world");
+/// let cb = CFnOnceBox::new(move |greeting: &str| format!("{}, {}!", greeting, name));
+///
+/// assert_eq!(cb.call("Hello"), "Hello, world!");
+/// ```
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct CFnOnceBox<'a, T, R = ()> {
+    context: CBox<'a, c_void>,
+    func: unsafe extern "C" fn(&mut c_void, T) -> R,
+    _unsend: PhantomData<*mut ()>,
+}
+
+impl<'a, T, R> CFnOnceBox<'a, T, R> {
+    pub fn new<F: FnOnce(T) -> R + 'a>(func: F) -> Self {
+        unsafe extern "C" fn call<F: FnOnce(T) -> R, T, R>(func: &mut Option<F>, arg: T) -> R {
+            (func.take().expect("CFnOnceBox called twice"))(arg)
+        }
+
+        // SAFETY: the context is always passed alongside the function it was created for.
+        let call = unsafe {
+            core::mem::transmute::<
+                unsafe extern "C" fn(&mut Option<F>, T) -> R,
+                unsafe extern "C" fn(&mut c_void, T) -> R,
+            >(call::<F, T, R>)
+        };
+
+        Self {
+            context: CBox::from(Some(func)).into_opaque(),
+            func: call,
+            _unsend: PhantomData,
+        }
+    }
+
+    pub fn new_send<F: FnOnce(T) -> R + Send + 'a>(func: F) -> CSend<Self> {
+        CSend(Self::new(func))
+    }
+
+    pub fn call(mut self, arg: T) -> R {
+        unsafe { (self.func)(&mut self.context, arg) }
+    }
+
+    /// Convert into a boxed Rust closure.
+    pub fn into_box(self) -> Box<dyn FnOnce(T) -> R + 'a>
+    where
+        T: 'a,
+        R: 'a,
+    {
+        Box::new(move |arg| self.call(arg))
+    }
+}
+
+impl<'a, T, R, F: FnOnce(T) -> R + 'a> From<F> for CFnOnceBox<'a, T, R> {
+    fn from(func: F) -> Self {
+        Self::new(func)
+    }
+}
+
+impl<'a, T, R, F: FnOnce(T) -> R + Send + 'a> From<F> for CSend<CFnOnceBox<'a, T, R>> {
+    fn from(func: F) -> Self {
+        CFnOnceBox::new_send(func)
+    }
+}
+
+impl<'a, T: 'a, R: 'a> CSend<CFnOnceBox<'a, T, R>> {
+    /// Convert into a boxed, sendable Rust closure.
+    pub fn into_box(self) -> Box<dyn FnOnce(T) -> R + Send + 'a> {
+        Box::new(move |arg| self.into_inner().call(arg))
+    }
+}
+
+/// Owned callback that can be sent across threads.
+///
+/// This can only be built out of closures that are `Send`, using `new_send` functions of
+/// [`CFnBox`] and [`CFnOnceBox`].
+#[repr(transparent)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct CSend<T>(T);
+
+unsafe impl<T> Send for CSend<T> {}
+
+impl<T> CSend<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for CSend<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for CSend<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
@@ -0,0 +1,305 @@
+//! # COM interoperability.
+//!
+//! Traits defined with `#[cglue_trait(com, guid = "...")]` have their vtables prefixed with the
+//! 3 `IUnknown` functions - `query_interface`, `add_ref`, and `release`. This makes the vtables
+//! layout-compatible with COM interfaces, thus allowing code written against `IUnknown`-style
+//! interfaces to call into CGlue objects.
+//!
+//! Every function in the vtable takes the interface pointer, [`ComThis`], as its first argument.
+//! The wrappers recover the container of the object out of it, and call the trait function, thus
+//! COM clients can call any slot through `this->lpVtbl`. Calling the trait functions from Rust
+//! builds a temporary interface pointer on the stack.
+//!
+//! Objects are handed to COM clients through [`into_raw`], which moves the trait object into a
+//! [`CArc`](crate::arc::CArc). The returned interface pointer is the instance of the arc, and
+//! `add_ref`, and `release` manipulate the reference count of the arc. The object is dropped once
+//! the last reference is released.
+//!
+//! All functions use the `system` calling convention, which matches the COM calling convention on
+//! all targets. The exception is the `layout_checks` feature, because abi_stable only supports the
+//! C calling convention. It differs from the COM one on 32-bit Windows.
+
+use crate::arc::CArcSome;
+use crate::trait_group::{c_void, CGlueObjContainer, CGlueTraitObj};
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use std::sync::Arc;
+
+/// Globally unique interface identifier.
+///
+/// The layout matches the one of Windows `GUID` structure.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct Guid {
+    pub data1: u32,
+    pub data2: u16,
+    pub data3: u16,
+    pub data4: [u8; 8],
+}
+
+impl Guid {
+    /// Create a GUID from its 128-bit representation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cglue::com::{Guid, IID_IUNKNOWN};
+    ///
+    /// let guid = Guid::from_u128(0x00000000_0000_0000_c000_000000000046);
+    ///
+    /// assert_eq!(guid, IID_IUNKNOWN);
+    /// ```
+    pub const fn from_u128(v: u128) -> Self {
+        let b = v.to_be_bytes();
+        Self {
+            data1: (v >> 96) as u32,
+            data2: (v >> 80) as u16,
+            data3: (v >> 64) as u16,
+            data4: [b[8], b[9], b[10], b[11], b[12], b[13], b[14], b[15]],
+        }
+    }
+
+    /// Get the 128-bit representation of the GUID.
+    pub const fn to_u128(&self) -> u128 {
+        ((self.data1 as u128) << 96)
+            | ((self.data2 as u128) << 80)
+            | ((self.data3 as u128) << 64)
+            | u64::from_be_bytes(self.data4) as u128
+    }
+}
+
+/// Identifier of the `IUnknown` interface.
+pub const IID_IUNKNOWN: Guid = Guid::from_u128(0x00000000_0000_0000_c000_000000000046);
+
+/// COM result code.
+pub type HResult = i32;
+
+/// Operation succeeded.
+pub const S_OK: HResult = 0;
+/// Queried interface is not supported.
+pub const E_NOINTERFACE: HResult = 0x80004002u32 as i32;
+/// Invalid pointer was passed.
+pub const E_POINTER: HResult = 0x80004003u32 as i32;
+
+/// Vtable of a COM compatible trait.
+///
+/// This trait is implemented on vtables of traits defined with `#[cglue_trait(com)]`.
+pub trait ComInterface {
+    /// Identifier of the interface.
+    const IID: Guid;
+}
+
+/// Define the `IUnknown` vtable, and its implementation with the given calling convention.
+macro_rules! iunknown {
+    ($abi:literal) => {
+        /// Vtable of the `IUnknown` interface.
+        ///
+        /// Vtables of COM compatible traits start with this layout.
+        #[repr(C)]
+        #[cfg_attr(feature = "layout_checks", derive(::abi_stable::StableAbi))]
+        pub struct IUnknownVtbl {
+            pub query_interface: unsafe extern $abi fn(
+                this: *mut c_void,
+                riid: *const Guid,
+                out: *mut *mut c_void,
+            ) -> HResult,
+            pub add_ref: unsafe extern $abi fn(this: *mut c_void) -> u32,
+            pub release: unsafe extern $abi fn(this: *mut c_void) -> u32,
+        }
+
+        /// `IUnknown::QueryInterface` implementation.
+        ///
+        /// Returns the object itself, if either `IUnknown`, or the trait of vtable `V` is
+        /// queried.
+        ///
+        /// # Safety
+        ///
+        /// `this` must be an interface pointer returned by [`into_raw`] with vtable `V` and
+        /// container `C`. `riid` must be either null, or point to a valid GUID. `out` must be
+        /// either null, or be valid for writes.
+        pub unsafe extern $abi fn query_interface<C, V: ComInterface>(
+            this: *mut c_void,
+            riid: *const Guid,
+            out: *mut *mut c_void,
+        ) -> HResult {
+            let out = match out.as_mut() {
+                Some(out) => out,
+                _ => return E_POINTER,
+            };
+
+            *out = core::ptr::null_mut();
+
+            match riid.as_ref() {
+                Some(riid) if *riid == IID_IUNKNOWN || *riid == V::IID => {
+                    add_ref::<C>(this);
+                    *out = this;
+                    S_OK
+                }
+                Some(_) => E_NOINTERFACE,
+                None => E_POINTER,
+            }
+        }
+
+        /// `IUnknown::AddRef` implementation.
+        ///
+        /// Returns the new reference count.
+        ///
+        /// # Safety
+        ///
+        /// `this` must be an interface pointer returned by [`into_raw`] with container `C`, and
+        /// hold a reference.
+        pub unsafe extern $abi fn add_ref<C>(this: *mut c_void) -> u32 {
+            // `CArcSome` instances created from an `Arc` point to the data of the `Arc`.
+            let obj = this as *const ComObj<'_, C>;
+            Arc::increment_strong_count(obj);
+            let arc = core::mem::ManuallyDrop::new(Arc::from_raw(obj));
+            Arc::strong_count(&arc) as u32
+        }
+
+        /// `IUnknown::Release` implementation.
+        ///
+        /// Returns the new reference count. The object is dropped when it reaches zero.
+        ///
+        /// # Safety
+        ///
+        /// `this` must be an interface pointer returned by [`into_raw`] with container `C`. The
+        /// caller gives up the reference it holds.
+        pub unsafe extern $abi fn release<C>(this: *mut c_void) -> u32 {
+            let arc = Arc::from_raw(this as *const ComObj<'_, C>);
+            let refs = Arc::strong_count(&arc) - 1;
+            core::mem::drop(arc);
+            refs as u32
+        }
+    };
+}
+
+// abi_stable only supports `extern "C"` function pointers.
+#[cfg(not(feature = "layout_checks"))]
+iunknown!("system");
+#[cfg(feature = "layout_checks")]
+iunknown!("C");
+
+/// COM interface pointer.
+///
+/// This is the `this` argument of all functions of COM compatible vtables. The vtable pointer
+/// comes first, just like `lpVtbl` in COM interfaces, and it is followed by a pointer to the
+/// container of the object.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct ComThis<'a, C> {
+    vtbl: *const c_void,
+    container: *mut C,
+    _phantom: PhantomData<&'a mut C>,
+}
+
+impl<'a, C> ComThis<'a, C> {
+    /// Create an interface pointer to a shared container.
+    ///
+    /// The container can only be accessed through [`container`](Self::container).
+    pub fn new<V>(vtbl: *const V, container: &'a C) -> Self {
+        Self {
+            vtbl: vtbl as *const c_void,
+            container: container as *const C as *mut C,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Create an interface pointer to a unique container.
+    pub fn new_mut<V>(vtbl: *const V, container: &'a mut C) -> Self {
+        Self {
+            vtbl: vtbl as *const c_void,
+            container,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Get the vtable of the object.
+    pub fn vtbl(&self) -> *const c_void {
+        self.vtbl
+    }
+
+    /// Get a shared reference to the container.
+    ///
+    /// # Safety
+    ///
+    /// The interface pointer must have been created by CGlue.
+    pub unsafe fn container(&self) -> &'a C {
+        &*self.container
+    }
+
+    /// Get a unique reference to the container.
+    ///
+    /// # Safety
+    ///
+    /// The interface pointer must have been created by CGlue, either through [`new_mut`], or
+    /// [`into_raw`], and there must be no other references to the container.
+    ///
+    /// [`new_mut`]: Self::new_mut
+    pub unsafe fn container_mut(&mut self) -> &'a mut C {
+        &mut *self.container
+    }
+}
+
+/// Reference counted trait object behind an interface pointer.
+#[repr(C)]
+struct ComObj<'a, C> {
+    this: ComThis<'a, C>,
+    container: UnsafeCell<C>,
+}
+
+/// Move a trait object into a reference counted allocation.
+///
+/// Returns the interface pointer, holding a single reference. The object is dropped when the
+/// reference count reaches zero through `release`.
+///
+/// # Examples
+///
+/// ```
+/// use cglue::prelude::v1::*;
+/// use cglue::com::{self, IUnknownVtbl};
+///
+/// #[cglue_trait(com, guid = "6b29fc40-ca47-1067-b31d-00dd010662da")]
+/// pub trait Answer {
+///     fn answer(&self) -> usize;
+/// }
+///
+/// impl Answer for u64 {
+///     fn answer(&self) -> usize {
+///         42
+///     }
+/// }
+///
+/// # fn main() -> () {
+/// let obj = trait_obj!(0u64 as Answer);
+/// let this = com::into_raw(obj);
+///
+/// unsafe {
+///     let vtbl = &**(this as *const *const IUnknownVtbl);
+///     assert_eq!((vtbl.add_ref)(this), 2);
+///     assert_eq!((vtbl.release)(this), 1);
+///     assert_eq!((vtbl.release)(this), 0);
+/// }
+/// # }
+/// ```
+pub fn into_raw<T: 'static, V: ComInterface, C: 'static, R: 'static>(
+    obj: CGlueTraitObj<'static, T, V, C, R>,
+) -> *mut c_void {
+    let (vtbl, container) = obj.into_parts();
+
+    let mut obj = Arc::new(ComObj {
+        this: ComThis {
+            vtbl: vtbl as *const V as *const c_void,
+            container: core::ptr::null_mut(),
+            _phantom: PhantomData,
+        },
+        container: UnsafeCell::new(container),
+    });
+
+    // The arc is not shared yet, and its instance will not move.
+    let obj_mut: &mut ComObj<CGlueObjContainer<T, C, R>> = Arc::get_mut(&mut obj).unwrap();
+    obj_mut.this.container = obj_mut.container.get();
+
+    let obj = CArcSome::<ComObj<_>>::from(obj).into_raw();
+    obj as *const _ as *mut c_void
+}
@@ -29,6 +29,7 @@
 //!   - [Type wrapping](#type-wrapping)
 //!   - [Associated type wrapping](#associated-type-wrapping)
 //!   - [Generic associated types](#generic-associated-types)
+//!   - [Thread safety](#thread-safety)
 //!   - [Plugin system](#plugin-system)
 //!   - [Working with cbindgen](#working-with-cbindgen)
 //!     - [Setup](#setup)
@@ -653,6 +654,64 @@
 //! with_int_result: extern "C" fn(&CGlueC, ok_out: &mut MaybeUninit<usize>) -> i32,
 //! ```
 //!
+//! Owned `String`, `Vec`, `Box` and boxed slices are converted to [ReprCString](crate::repr_cstring::ReprCString), [CVec](crate::vec::CVec), [CBox](crate::boxed::CBox) and [CSliceBox](crate::boxed::CSliceBox) respectively:
+//!
+//! ```ignore
+//! fn with_owned(&self, name: String, data: Vec<u8>) -> Box<[u8]> {}
+//!
+//! // Generated vtable entry:
+//!
+//! with_owned: extern "C" fn(&CGlueC, name: ReprCString, data: CVec<u8>) -> CSliceBox<'static, u8>,
+//! ```
+//!
+//! Returned boxes are moved back into a standard `Box`, so they may be allocated by any module. `ReprCString` is NUL-terminated, so strings are cut at the first interior NUL byte, if any.
+//!
+//! Tuples of up to 4 elements are converted to [CTup](crate::tuple) types, including the ones nested in `Option` and `Result`:
+//!
+//! ```ignore
+//! fn with_tuple(&self, pair: (u8, u16)) -> Option<(usize, bool)> {}
+//!
+//! // Generated vtable entry:
+//!
+//! with_tuple: extern "C" fn(&CGlueC, pair: CTup2<u8, u16>) -> COption<CTup2<usize, bool>>,
+//! ```
+//!
+//! Closure arguments of `impl FnMut(T) -> bool` and `&mut dyn FnMut(T)` form are passed as [OpaqueCallback](crate::callback::OpaqueCallback). Closures without a return value never stop the iteration early. Closures returning other values, taking multiple arguments, or being `Fn` are passed as [CFnMut](crate::callback::CFnMut) and [CFn](crate::callback::CFn), with multiple arguments packed into a `CTup`:
+//!
+//! ```ignore
+//! fn with_callback(&self, cb: impl FnMut(usize) -> bool) {}
+//!
+//! // Generated vtable entry:
+//!
+//! with_callback: extern "C" fn(&CGlueC, cb: OpaqueCallback<usize>),
+//!
+//! fn with_query(&self, query: impl Fn(u32, u32) -> u64) {}
+//!
+//! // Generated vtable entry:
+//!
+//! with_query: extern "C" fn(&CGlueC, query: CFn<CTup2<u32, u32>, u64>),
+//! ```
+//!
+//! Similarly, `impl Iterator<Item = T>` and `&mut dyn Iterator<Item = T>` arguments are passed as [CIterator](crate::iter::CIterator):
+//!
+//! ```ignore
+//! fn with_iter(&self, iter: impl Iterator<Item = usize>) {}
+//!
+//! // Generated vtable entry:
+//!
+//! with_iter: extern "C" fn(&CGlueC, iter: CIterator<usize>),
+//! ```
+//!
+//! Boxed `dyn FnMut(T) -> R` and `dyn FnOnce(T) -> R` closures are converted to owned [CFnBox](crate::callback::CFnBox) and [CFnOnceBox](crate::callback::CFnOnceBox) callbacks, which can be stored for later use. `Send` closures get wrapped in [CSend](crate::callback::CSend):
+//!
+//! ```ignore
+//! fn subscribe(&mut self, f: Box<dyn FnMut(Event) + Send>) {}
+//!
+//! // Generated vtable entry:
+//!
+//! subscribe: extern "C" fn(&mut CGlueC, f: CSend<CFnBox<'static, Event, ()>>),
+//! ```
+//!
 //! All wrapping and conversion is handled transparently behind the scenes, with user's control.
 //!
 //! ### Associated type wrapping
@@ -803,6 +862,90 @@
 //! # }
 //! ```
 //!
+//! ### Thread safety
+//!
+//! CGlue objects are type erased, thus the underlying type's `Send`, and `Sync` implementations
+//! are lost. Instead, generated objects inherit them from the `Send`, and `Sync` supertraits of the
+//! trait. Objects of traits without these supertraits can not be moved across threads:
+//!
+//! ```
+//! use cglue::prelude::v1::*;
+//!
+//! #[cglue_trait]
+//! pub trait Worker: Send {
+//!     fn work(&mut self) -> usize;
+//! }
+//!
+//! impl Worker for usize {
+//!     fn work(&mut self) -> usize {
+//!         *self
+//!     }
+//! }
+//!
+//! # fn main() -> () {
+//! let mut obj = trait_obj!(42usize as Worker);
+//! // `Worker: Send`, thus the object is `Send`.
+//! std::thread::spawn(move || obj.work()).join().unwrap();
+//! # }
+//! ```
+//!
+//! Before 0.3, objects were `Send`, and `Sync` whenever their container, and context were, even if
+//! the underlying type was not. Code that moved objects of traits without these supertraits across
+//! threads needs to add `Send`/`Sync` supertraits to the trait, or use the group options below.
+//!
+//! Groups are `Send`, or `Sync`, if all of their traits are. Since the group macro does not know
+//! the supertraits of the traits, this requirement can be relaxed with `send`, and `sync` options.
+//! The group is then constructible only from `Send`/`Sync` types, and becomes `Send`/`Sync` as long
+//! as the context is:
+//!
+//! ```
+//! # use cglue::prelude::v1::*;
+//! # #[cglue_trait]
+//! # pub trait Worker {}
+//! # #[cglue_trait]
+//! # pub trait InfoPrinter {}
+//! cglue_trait_group!(#[cglue_trait_group(send, sync)] WorkerGroup, Worker, { InfoPrinter });
+//! # fn main() {}
+//! ```
+//!
+//! Do note that `sync` groups require none of the traits to return wrapped references from
+//! functions taking `&self`, as their temporary storage can not be safely shared.
+//!
+//! To share a group between threads, regardless of `Sync`, use the `lock` option. It implies
+//! `send`, and generates `WorkerGroupSync` and `WorkerGroupBaseSync` types, which hold the group in
+//! a reference counted [`CSync`](crate::lock::CSync) mutex. They are `Clone`, `Send`, and `Sync`,
+//! and implement the mandatory traits by locking on every call:
+//!
+//! ```
+//! # use cglue::prelude::v1::*;
+//! # use cglue::trait_group::NoContext;
+//! # #[cglue_trait]
+//! # pub trait Worker {
+//! #     fn work(&mut self) -> usize;
+//! # }
+//! # impl Worker for usize {
+//! #     fn work(&mut self) -> usize {
+//! #         *self
+//! #     }
+//! # }
+//! cglue_trait_group!(#[cglue_trait_group(lock)] WorkerGroup, Worker, {});
+//! cglue_impl_group!(usize, WorkerGroup, {});
+//!
+//! # fn main() -> () {
+//! let group: WorkerGroupSync<NoContext> = CSync::from(group_obj!(42usize as WorkerGroup));
+//! let mut worker = group.clone();
+//!
+//! std::thread::spawn(move || worker.work()).join().unwrap();
+//! # }
+//! ```
+//!
+//! The default lock is built on top of the standard library. A host may supply its own through
+//! [`CRawLock::from_raw_parts`](crate::lock::CRawLock::from_raw_parts), so that all modules share
+//! the same locking primitives. Traits with functions that consume `self`, return references, or
+//! take arguments with named lifetimes can not be called through a lock, and are not implemented
+//! on `CSync`. Types other than groups opt into the locking implementations by implementing
+//! [`Lockable`](crate::lock::Lockable).
+//!
 //! ### Plugin system
 //!
 //! A full example is available in the repo's `examples` subdirectory.
@@ -882,6 +1025,43 @@
 //! # }
 //! ```
 //!
+//! Contexts do not need to be `Send`, or `Sync`. If the plugin is bound to a single thread, for
+//! instance, it wraps a GUI toolkit handle, [`CRc`](crate::rc::CRc) can be used in place of `CArc`.
+//! Objects and groups inherit thread-safety from their context, thus the resulting objects will be
+//! `!Send`, and `!Sync`:
+//!
+//! ```
+//! # use cglue::prelude::v1::*;
+//! # #[cglue_trait]
+//! # pub trait PluginRoot {}
+//! # impl PluginRoot for () {}
+//! # fn main() -> () {
+//! let root = ();
+//! let ref_to_count = CRc::from(());
+//! let obj = trait_obj!((root, ref_to_count) as PluginRoot);
+//! # }
+//! ```
+//!
+//! Plugins allocate on their own global allocator by default. If the host needs control over
+//! plugin memory, for instance, to place it into an arena, or to account for it, it can pass a
+//! [`CAllocator`](crate::alloc::CAllocator) to the plugin, which then uses `CBox::new_in`,
+//! `CSliceBox::new_in`, and `CVec::with_allocator` to allocate through it:
+//!
+//! ```
+//! # use cglue::prelude::v1::*;
+//! # use cglue::alloc::CAllocator;
+//! # #[cglue_trait]
+//! # pub trait PluginRoot {}
+//! # impl PluginRoot for () {}
+//! # fn main() -> () {
+//! # let root = ();
+//! // This could be a host-provided allocator passed to the plugin.
+//! let alloc = CAllocator::new(std::alloc::System);
+//! let obj = trait_obj!((CBox::new_in(root, alloc.clone()), CArc::from(())) as PluginRoot);
+//! let buf = CVec::<u8>::with_allocator(alloc);
+//! # }
+//! ```
+//!
 //! Note that this is not foolproof, and there may be situations where returned data could depend
 //! on the library. The most error prone of which are unhandled `Err(E)` conditions, where `E` is
 //! some static str. `main` function could return an error pointing to the memory of the library,
@@ -991,13 +1171,18 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 extern crate no_std_compat as std;
 
+pub mod alloc;
 pub mod arc;
 pub mod boxed;
 pub mod callback;
+pub mod com;
 pub mod forward;
 pub mod from2;
 pub mod iter;
+pub mod lock;
 pub mod option;
+pub mod rc;
+pub mod reflect;
 pub mod repr_cstring;
 pub mod result;
 pub mod slice;
@@ -1029,10 +1214,15 @@ pub mod prelude {
         pub use crate::{
             arc::{CArc, CArcSome},
             boxed::{CBox, CSliceBox},
-            callback::{Callback, Callbackable, FeedCallback, FromExtend, OpaqueCallback},
+            callback::{
+                CFn, CFnBox, CFnMut, CFnOnceBox, CSend, Callback, Callbackable, FeedCallback,
+                FromExtend, OpaqueCallback,
+            },
             forward::{Forward, ForwardMut, Fwd},
             iter::CIterator,
+            lock::{CMutex, CSync},
             option::COption,
+            rc::CRc,
             repr_cstring::{ReprCStr, ReprCString},
             result::{CResult, IntError, IntResult},
             slice::{CSliceMut, CSliceRef},
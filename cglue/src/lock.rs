@@ -0,0 +1,302 @@
+//! # FFI-safe locking.
+//!
+//! [`CMutex`] guards data with a lock that is implemented behind a [`LockVtbl`]. The host can
+//! supply its own locking primitives, so that every module shares the same lock implementation,
+//! and [`CSync`] combines it with [`CArcSome`] to produce a cheaply clonable, thread-safe handle.
+//!
+//! Traits defined with `#[cglue_trait]` are implemented on [`CSync`] by locking for types that
+//! opt into it by implementing [`Lockable`]. This is only done as long as none of the trait's
+//! functions consume `self`, return references borrowed from it, or take arguments with named
+//! lifetimes (`fn f<'a>(&'a self, cb: OpaqueCallback<'a, T>)`), because such borrows could outlive
+//! the lock guard. See the `#[cglue_trait_group(lock)]` option for a convenient way to share trait
+//! groups across threads.
+use crate::arc::CArcSome;
+use crate::boxed::CBox;
+use crate::trait_group::c_void;
+#[cfg(feature = "std")]
+use crate::trait_group::Opaquable;
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "std")]
+use std::sync::{Condvar, Mutex};
+
+/// Lock functions.
+///
+/// Both functions receive the lock instance stored in [`CRawLock`].
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct LockVtbl {
+    /// Block until the lock is acquired.
+    pub lock: unsafe extern "C" fn(&c_void),
+    /// Release the lock acquired by `lock`.
+    pub unlock: unsafe extern "C" fn(&c_void),
+}
+
+/// FFI-safe raw lock
+///
+/// Opaque lock instance paired with the functions operating on it. The lock is freed by the
+/// module that created it.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct CRawLock {
+    instance: CBox<'static, c_void>,
+    vtbl: &'static LockVtbl,
+}
+
+// Safety: `from_raw_parts` requires the lock to be usable from any thread.
+unsafe impl Send for CRawLock {}
+unsafe impl Sync for CRawLock {}
+
+impl CRawLock {
+    /// Create a lock from host-provided parts.
+    ///
+    /// # Safety
+    ///
+    /// `vtbl` functions must implement mutual exclusion on `instance`, and be callable from any
+    /// thread. `unlock` is only ever called by the thread that currently holds the lock.
+    pub unsafe fn from_raw_parts(instance: CBox<'static, c_void>, vtbl: &'static LockVtbl) -> Self {
+        Self { instance, vtbl }
+    }
+
+    /// Block until the lock is acquired.
+    pub fn lock(&self) {
+        unsafe { (self.vtbl.lock)(&self.instance) }
+    }
+
+    /// Release the lock.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held by the calling thread.
+    pub unsafe fn unlock(&self) {
+        (self.vtbl.unlock)(&self.instance)
+    }
+}
+
+/// Create a lock backed by the standard library.
+#[cfg(feature = "std")]
+impl Default for CRawLock {
+    fn default() -> Self {
+        let instance = CBox::from(DefaultLock::default()).into_opaque();
+        unsafe { Self::from_raw_parts(instance, &DEFAULT_LOCK_VTBL) }
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(Default)]
+struct DefaultLock {
+    locked: Mutex<bool>,
+    unlocked: Condvar,
+}
+
+#[cfg(feature = "std")]
+static DEFAULT_LOCK_VTBL: LockVtbl = LockVtbl {
+    lock: default_lock,
+    unlock: default_unlock,
+};
+
+#[cfg(feature = "std")]
+unsafe extern "C" fn default_lock(this: &c_void) {
+    let this = &*(this as *const c_void as *const DefaultLock);
+    let mut locked = this.locked.lock().unwrap_or_else(|e| e.into_inner());
+    while *locked {
+        locked = this
+            .unlocked
+            .wait(locked)
+            .unwrap_or_else(|e| e.into_inner());
+    }
+    *locked = true;
+}
+
+#[cfg(feature = "std")]
+unsafe extern "C" fn default_unlock(this: &c_void) {
+    let this = &*(this as *const c_void as *const DefaultLock);
+    *this.locked.lock().unwrap_or_else(|e| e.into_inner()) = false;
+    this.unlocked.notify_one();
+}
+
+/// FFI-safe mutex
+///
+/// Mutual exclusion around `T`, using either the default lock, or one provided by the host
+/// through [`CRawLock::from_raw_parts`].
+///
+/// # Examples
+///
+/// ```
+/// use cglue::lock::CMutex;
+///
+/// let mutex = CMutex::new(0u64);
+///
+/// *mutex.lock() += 1;
+///
+/// assert_eq!(mutex.into_inner(), 1);
+/// ```
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct CMutex<T> {
+    raw: CRawLock,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for CMutex<T> {}
+unsafe impl<T: Send> Sync for CMutex<T> {}
+
+impl<T> CMutex<T> {
+    /// Create a mutex using the default lock.
+    #[cfg(feature = "std")]
+    pub fn new(data: T) -> Self {
+        Self::with_lock(data, Default::default())
+    }
+
+    /// Create a mutex using the given lock.
+    pub fn with_lock(data: T, raw: CRawLock) -> Self {
+        Self {
+            raw,
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Block until the lock is acquired, and return a guard to the data.
+    pub fn lock(&self) -> CMutexGuard<'_, T> {
+        self.raw.lock();
+        CMutexGuard {
+            mutex: self,
+            _unsend: PhantomData,
+        }
+    }
+
+    /// Get a mutable reference to the data without locking.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    /// Consume the mutex, returning the data.
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> From<T> for CMutex<T> {
+    fn from(data: T) -> Self {
+        Self::new(data)
+    }
+}
+
+/// Guard of a locked [`CMutex`].
+///
+/// The lock is released when the guard is dropped.
+pub struct CMutexGuard<'a, T> {
+    mutex: &'a CMutex<T>,
+    // The lock must be released on the thread that acquired it.
+    _unsend: PhantomData<*mut ()>,
+}
+
+impl<T> Deref for CMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T> DerefMut for CMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<T> Drop for CMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        unsafe { self.mutex.raw.unlock() }
+    }
+}
+
+/// Types whose CGlue traits are implemented on [`CSync`] by locking.
+///
+/// Trait groups with the `lock` option implement this.
+pub trait Lockable {}
+
+/// Shared, thread-safe handle
+///
+/// Reference counted [`CMutex`]. It is `Send`, `Sync`, and `Clone` whenever `T` is `Send`, and
+/// implements CGlue traits of `T` by locking on every call, if `T` is [`Lockable`].
+///
+/// # Examples
+///
+/// ```
+/// use cglue::lock::Lockable;
+/// use cglue::prelude::v1::*;
+///
+/// #[cglue_trait]
+/// pub trait Counter {
+///     fn incr(&mut self) -> u64;
+/// }
+///
+/// pub struct Count(u64);
+///
+/// impl Counter for Count {
+///     fn incr(&mut self) -> u64 {
+///         self.0 += 1;
+///         self.0
+///     }
+/// }
+///
+/// impl Lockable for Count {}
+///
+/// # fn main() -> () {
+/// let counter = CSync::from(Count(0));
+///
+/// let threads = (0..4)
+///     .map(|_| {
+///         let mut counter = counter.clone();
+///         std::thread::spawn(move || counter.incr())
+///     })
+///     .collect::<Vec<_>>();
+///
+/// threads.into_iter().for_each(|t| { t.join().unwrap(); });
+///
+/// assert_eq!(counter.lock().0, 4);
+/// # }
+/// ```
+#[repr(transparent)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct CSync<T: 'static> {
+    inner: CArcSome<CMutex<T>>,
+}
+
+impl<T> CSync<T> {
+    /// Create a handle using the given lock.
+    pub fn with_lock(data: T, raw: CRawLock) -> Self {
+        CMutex::with_lock(data, raw).into()
+    }
+
+    /// Block until the lock is acquired, and return a guard to the data.
+    pub fn lock(&self) -> CMutexGuard<'_, T> {
+        self.inner.lock()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> From<T> for CSync<T> {
+    fn from(data: T) -> Self {
+        CMutex::new(data).into()
+    }
+}
+
+impl<T> From<CMutex<T>> for CSync<T> {
+    fn from(mutex: CMutex<T>) -> Self {
+        Self {
+            inner: mutex.into(),
+        }
+    }
+}
+
+impl<T> Clone for CSync<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! # FFI-safe Rc.
+//!
+//! Single-threaded counterpart of [`CArcSome`](crate::arc::CArcSome). It is useful as a context of
+//! objects that must stay on one thread, such as GUI toolkit handles, and makes any object, or
+//! group using it `!Send` and `!Sync`.
+use crate::trait_group::c_void;
+use crate::trait_group::Opaquable;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+/// FFI-Safe Rc
+///
+/// The underlying instance is always valid, and clone/drop are performed by the module that
+/// created the Rc, to not mix up global allocators.
+///
+/// Unlike [`CArc`](crate::arc::CArc), this type is neither `Send`, nor `Sync`, regardless of `T`.
+///
+/// # Examples
+///
+/// ```
+/// use cglue::rc::CRc;
+///
+/// let rc = CRc::from(42u64);
+/// let rc2 = rc.clone();
+///
+/// assert_eq!(*rc2, 42);
+/// ```
+///
+/// Objects using `CRc` as their context can not be sent across threads:
+///
+/// ```compile_fail
+/// use cglue::prelude::v1::*;
+///
+/// #[cglue_trait]
+/// pub trait Getter {
+///     fn get(&self) -> u64;
+/// }
+///
+/// impl Getter for u64 {
+///     fn get(&self) -> u64 {
+///         *self
+///     }
+/// }
+///
+/// fn main() {
+///     let obj = trait_obj!((42u64, CRc::<()>::from(())) as Getter);
+///
+///     std::thread::spawn(move || obj.get());
+/// }
+/// ```
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct CRc<T: Sized + 'static> {
+    instance: &'static T,
+    clone_fn: unsafe extern "C" fn(&'static T) -> &'static T,
+    drop_fn: Option<unsafe extern "C" fn(&T)>,
+    _unsend: PhantomData<*mut ()>,
+}
+
+impl<T> CRc<T> {
+    /// Converts `CRc<T>` into `Rc<T>`
+    ///
+    /// # Safety
+    ///
+    /// This function is only safe when the underlying rc was created in the same binary/library.
+    /// If a third-party rc is used, the behavior is undefined.
+    pub unsafe fn into_rc(self) -> Rc<T> {
+        let ptr = self.instance as *const _;
+        std::mem::forget(self);
+        Rc::from_raw(ptr)
+    }
+}
+
+impl<T> From<T> for CRc<T> {
+    fn from(obj: T) -> Self {
+        Self::from(Rc::new(obj))
+    }
+}
+
+impl<T> From<Rc<T>> for CRc<T> {
+    fn from(rc: Rc<T>) -> Self {
+        Self {
+            instance: unsafe { Rc::into_raw(rc).as_ref().unwrap() },
+            clone_fn: c_clone,
+            drop_fn: Some(c_drop),
+            _unsend: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for CRc<T> {
+    fn clone(&self) -> Self {
+        Self {
+            instance: unsafe { (self.clone_fn)(self.instance) },
+            ..*self
+        }
+    }
+}
+
+impl<T> Drop for CRc<T> {
+    fn drop(&mut self) {
+        if let Some(drop_fn) = self.drop_fn {
+            unsafe { drop_fn(self.instance) }
+        }
+    }
+}
+
+impl<T> AsRef<T> for CRc<T> {
+    fn as_ref(&self) -> &T {
+        self.instance
+    }
+}
+
+impl<T> core::ops::Deref for CRc<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.instance
+    }
+}
+
+unsafe impl<T> Opaquable for CRc<T> {
+    type OpaqueTarget = CRc<c_void>;
+}
+
+unsafe extern "C" fn c_clone<T: Sized + 'static>(ptr_to_rc: &'static T) -> &'static T {
+    Rc::increment_strong_count(ptr_to_rc);
+    ptr_to_rc
+}
+
+unsafe extern "C" fn c_drop<T: Sized + 'static>(ptr_to_rc: &T) {
+    let _ = Rc::from_raw(ptr_to_rc);
+}
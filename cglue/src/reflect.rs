@@ -0,0 +1,536 @@
+//! # Method reflection.
+//!
+//! Every `#[cglue_trait]` generates metadata of its methods alongside the vtable - names, argument
+//! kinds and basic types. It is available through the vtable's `META` constant, as a
+//! [`TraitMeta`].
+//!
+//! Methods that only take and return basic values (integers, floats, booleans, characters and
+//! strings) can also be called dynamically, with arguments passed as a slice of [`Value`]s. Trait
+//! groups implement [`Reflect`] to allow calling any method of the traits available on the object
+//! by name. This is the basis for exposing CGlue objects to scripting languages without writing
+//! glue code for every trait.
+//!
+//! Plain trait objects do not implement [`Reflect`]. Their methods can still be called through the
+//! vtable's `cglue_reflect_call` function, or the object can be wrapped in a single-trait group.
+//!
+//! With the `mlua` feature enabled, any [`Reflect`] object can be turned into Lua userdata by
+//! wrapping it in [`LuaObject`](lua::LuaObject). `mlua` requires a Lua version to be selected -
+//! either enable it on `mlua` directly, or through the `lua54` feature of this crate. The
+//! `vendored` feature builds Lua from source instead of linking to the system library.
+//!
+//! In addition, every vtable points to a static, FFI-safe [`TraitDescriptor`], listing the names
+//! and signatures of the trait's methods. It is retrieved with the vtable's `descriptor()`
+//! function, and is also accessible from C and C++ through the vtable's `descriptor` field.
+
+use crate::slice::CSliceRef;
+use core::convert::TryFrom;
+use std::prelude::v1::*;
+
+#[doc(hidden)]
+pub use std::vec::Vec;
+
+/// Basic type of an argument, or return value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BasicType {
+    /// The `()` type.
+    Unit,
+    Bool,
+    /// Signed integer of any width.
+    Int,
+    /// Unsigned integer of any width.
+    UInt,
+    /// Floating point number of any width.
+    Float,
+    Char,
+    /// `&str`, or `String`.
+    Str,
+    /// Any other type. Methods using these types can not be called dynamically.
+    ///
+    /// Types are recognized by the way they are written in the trait, thus basic types behind type
+    /// aliases, or paths other than the full standard library path (`core::primitive::u32`,
+    /// `std::string::String`) are reported as `Other`.
+    Other,
+}
+
+/// How a value is passed to, or returned from a method.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ArgKind {
+    /// By value.
+    Owned,
+    /// By immutable reference.
+    Ref,
+    /// By mutable reference.
+    Mut,
+}
+
+/// Description of a single method argument.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArgInfo {
+    pub name: &'static str,
+    pub kind: ArgKind,
+    pub ty: BasicType,
+    /// Type of the argument, as written in the trait.
+    pub ty_name: &'static str,
+}
+
+/// Description of a single trait method.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MethodInfo {
+    pub name: &'static str,
+    /// How `self` is taken.
+    pub receiver: ArgKind,
+    /// Arguments, excluding `self`.
+    pub args: &'static [ArgInfo],
+    pub ret: BasicType,
+    /// Return type of the method, as written in the trait.
+    pub ret_name: &'static str,
+    /// Signature of the method, as written in the trait, such as `fn get(&self, key: &str) -> usize`.
+    pub signature: &'static str,
+    /// Whether the method can be called through [`Reflect::call_method`].
+    ///
+    /// This is only the case for safe methods that take `self` by reference, and otherwise only
+    /// use basic types. Methods that are not callable are still listed, and calling them returns
+    /// [`CallError::NotCallable`]. Check the [`BasicType`] of the arguments to find out which ones
+    /// could not be reflected.
+    pub callable: bool,
+}
+
+/// Description of all methods of a trait.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraitMeta {
+    pub name: &'static str,
+    pub methods: &'static [MethodInfo],
+}
+
+impl TraitMeta {
+    /// Find a method by its name.
+    pub fn method(&self, name: &str) -> Option<&'static MethodInfo> {
+        self.methods.iter().find(|m| m.name == name)
+    }
+}
+
+/// Dynamically typed value passed to, and returned from dynamic calls.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Unit,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Char(char),
+    Str(String),
+}
+
+impl Value {
+    /// Get the basic type of the value.
+    pub fn ty(&self) -> BasicType {
+        match self {
+            Value::Unit => BasicType::Unit,
+            Value::Bool(_) => BasicType::Bool,
+            Value::Int(_) => BasicType::Int,
+            Value::UInt(_) => BasicType::UInt,
+            Value::Float(_) => BasicType::Float,
+            Value::Char(_) => BasicType::Char,
+            Value::Str(_) => BasicType::Str,
+        }
+    }
+}
+
+/// Error of a dynamic method call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CallError {
+    /// The object has no method with given name.
+    NoMethod,
+    /// The method exists, but can not be called dynamically.
+    NotCallable,
+    /// Wrong number of arguments were passed.
+    ArgCount { expected: usize, got: usize },
+    /// Argument at given index could not be converted to the expected type.
+    ArgType { index: usize, expected: BasicType },
+}
+
+impl core::fmt::Display for CallError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            CallError::NoMethod => write!(f, "no such method"),
+            CallError::NotCallable => write!(f, "method can not be called dynamically"),
+            CallError::ArgCount { expected, got } => {
+                write!(f, "expected {} arguments, got {}", expected, got)
+            }
+            CallError::ArgType { index, expected } => {
+                write!(f, "argument {} is not of type {:?}", index, expected)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CallError {}
+
+/// Conversion out of a dynamic [`Value`].
+pub trait FromValue<'a>: Sized {
+    const TYPE: BasicType;
+
+    fn from_value(value: &'a Value) -> Option<Self>;
+}
+
+/// Conversion into a dynamic [`Value`].
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+macro_rules! int_value {
+    ($ty:ident, $variant:ident, $basic:ident) => {
+        impl<'a> FromValue<'a> for $ty {
+            const TYPE: BasicType = BasicType::$basic;
+
+            fn from_value(value: &'a Value) -> Option<Self> {
+                use core::convert::TryFrom;
+
+                match *value {
+                    Value::Int(v) => Self::try_from(v).ok(),
+                    Value::UInt(v) => Self::try_from(v).ok(),
+                    _ => None,
+                }
+            }
+        }
+
+        impl IntoValue for $ty {
+            fn into_value(self) -> Value {
+                Value::$variant(self as _)
+            }
+        }
+    };
+}
+
+int_value!(i8, Int, Int);
+int_value!(i16, Int, Int);
+int_value!(i32, Int, Int);
+int_value!(i64, Int, Int);
+int_value!(isize, Int, Int);
+int_value!(u8, UInt, UInt);
+int_value!(u16, UInt, UInt);
+int_value!(u32, UInt, UInt);
+int_value!(u64, UInt, UInt);
+int_value!(usize, UInt, UInt);
+
+macro_rules! float_value {
+    ($ty:ident) => {
+        impl<'a> FromValue<'a> for $ty {
+            const TYPE: BasicType = BasicType::Float;
+
+            fn from_value(value: &'a Value) -> Option<Self> {
+                match *value {
+                    Value::Float(v) => Some(v as _),
+                    Value::Int(v) => Some(v as _),
+                    Value::UInt(v) => Some(v as _),
+                    _ => None,
+                }
+            }
+        }
+
+        impl IntoValue for $ty {
+            fn into_value(self) -> Value {
+                Value::Float(self as _)
+            }
+        }
+    };
+}
+
+float_value!(f32);
+float_value!(f64);
+
+impl<'a> FromValue<'a> for () {
+    const TYPE: BasicType = BasicType::Unit;
+
+    fn from_value(value: &'a Value) -> Option<Self> {
+        match value {
+            Value::Unit => Some(()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for () {
+    fn into_value(self) -> Value {
+        Value::Unit
+    }
+}
+
+impl<'a> FromValue<'a> for bool {
+    const TYPE: BasicType = BasicType::Bool;
+
+    fn from_value(value: &'a Value) -> Option<Self> {
+        match *value {
+            Value::Bool(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self) -> Value {
+        Value::Bool(self)
+    }
+}
+
+impl<'a> FromValue<'a> for char {
+    const TYPE: BasicType = BasicType::Char;
+
+    fn from_value(value: &'a Value) -> Option<Self> {
+        match value {
+            Value::Char(v) => Some(*v),
+            Value::Str(s) => {
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Some(c),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for char {
+    fn into_value(self) -> Value {
+        Value::Char(self)
+    }
+}
+
+impl<'a> FromValue<'a> for &'a str {
+    const TYPE: BasicType = BasicType::Str;
+
+    fn from_value(value: &'a Value) -> Option<Self> {
+        match value {
+            Value::Str(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self) -> Value {
+        Value::Str(self.into())
+    }
+}
+
+impl<'a> FromValue<'a> for String {
+    const TYPE: BasicType = BasicType::Str;
+
+    fn from_value(value: &'a Value) -> Option<Self> {
+        match value {
+            Value::Str(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self) -> Value {
+        Value::Str(self)
+    }
+}
+
+/// Convert argument at given index.
+///
+/// This is used by the generated dynamic call functions.
+pub fn arg<'a, T: FromValue<'a>>(args: &'a [Value], index: usize) -> Result<T, CallError> {
+    T::from_value(&args[index]).ok_or(CallError::ArgType {
+        index,
+        expected: T::TYPE,
+    })
+}
+
+/// Check the number of arguments, and perform the call.
+///
+/// This is used by the generated dynamic call functions.
+pub fn invoke<'a>(
+    args: &'a [Value],
+    count: usize,
+    call: impl FnOnce(&'a [Value]) -> Result<Value, CallError>,
+) -> Result<Value, CallError> {
+    if args.len() != count {
+        Err(CallError::ArgCount {
+            expected: count,
+            got: args.len(),
+        })
+    } else {
+        call(args)
+    }
+}
+
+/// FFI-safe description of a trait method.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct MethodDescriptor {
+    pub name: CSliceRef<'static, u8>,
+    /// Same as [`MethodInfo::signature`].
+    pub signature: CSliceRef<'static, u8>,
+}
+
+impl MethodDescriptor {
+    /// Describe a method out of its reflection metadata.
+    pub const fn from_info(info: &MethodInfo) -> Self {
+        Self {
+            name: CSliceRef::from_str(info.name),
+            signature: CSliceRef::from_str(info.signature),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        <&str>::try_from(self.name).unwrap_or_default()
+    }
+
+    pub fn signature(&self) -> &'static str {
+        <&str>::try_from(self.signature).unwrap_or_default()
+    }
+}
+
+/// FFI-safe description of a trait.
+///
+/// Every CGlue vtable refers to a static instance of this structure.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct TraitDescriptor {
+    pub name: CSliceRef<'static, u8>,
+    pub methods: CSliceRef<'static, MethodDescriptor>,
+}
+
+impl TraitDescriptor {
+    /// Describe a trait out of its reflection metadata.
+    ///
+    /// `methods` are expected to be built out of the methods of `meta`, through
+    /// [`MethodDescriptor::from_info`].
+    pub const fn from_meta(meta: &TraitMeta, methods: &'static [MethodDescriptor]) -> Self {
+        Self {
+            name: CSliceRef::from_str(meta.name),
+            methods: CSliceRef::from_slice(methods),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        <&str>::try_from(self.name).unwrap_or_default()
+    }
+
+    pub fn methods(&self) -> &'static [MethodDescriptor] {
+        self.methods.into()
+    }
+}
+
+/// Description of a trait within a trait group.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct GroupTraitDescriptor {
+    pub descriptor: &'static TraitDescriptor,
+    /// Whether the trait is optional within the group.
+    pub optional: bool,
+}
+
+/// Object whose methods can be looked up, and called at runtime.
+///
+/// This is implemented by all trait groups.
+pub trait Reflect {
+    /// Metadata of all traits the object implements.
+    ///
+    /// For trait groups, optional traits are only included if the object has their vtables filled
+    /// in.
+    fn traits(&self) -> Vec<TraitMeta>;
+
+    /// Call a method by name.
+    ///
+    /// If multiple traits have a method with the same name, the first one in
+    /// [`traits`](Reflect::traits) is called.
+    fn call_method(&mut self, name: &str, args: &[Value]) -> Result<Value, CallError>;
+
+    /// Find a method by name, alongside with the trait it belongs to.
+    fn find_method(&self, name: &str) -> Option<(TraitMeta, &'static MethodInfo)> {
+        self.traits()
+            .into_iter()
+            .find_map(|t| t.method(name).map(|m| (t, m)))
+    }
+}
+
+#[cfg(feature = "mlua")]
+pub mod lua {
+    //! Lua integration of [`Reflect`] objects.
+
+    use super::*;
+    use mlua::{AnyUserData, MetaMethod, UserData, UserDataMethods, Variadic};
+
+    /// Lua userdata wrapper for [`Reflect`] objects.
+    ///
+    /// Indexing the userdata by a method name returns a function calling the method. Thus, methods
+    /// are called with the usual `obj:method(args)` syntax.
+    pub struct LuaObject<T>(pub T);
+
+    impl<T> LuaObject<T> {
+        pub fn into_inner(self) -> T {
+            self.0
+        }
+    }
+
+    impl<T: Reflect + 'static> UserData for LuaObject<T> {
+        fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+            methods.add_meta_method(MetaMethod::Index, |lua, this, key: String| {
+                if this.0.find_method(&key).is_none() {
+                    return Ok(mlua::Value::Nil);
+                }
+
+                let func = lua.create_function(
+                    move |lua, (this, args): (AnyUserData, Variadic<mlua::Value>)| {
+                        let args = args
+                            .into_iter()
+                            .map(from_lua)
+                            .collect::<mlua::Result<Vec<_>>>()?;
+                        let ret = this
+                            .borrow_mut::<Self>()?
+                            .0
+                            .call_method(&key, &args)
+                            .map_err(|e| mlua::Error::RuntimeError(format!("{}: {}", key, e)))?;
+                        into_lua(lua, ret)
+                    },
+                )?;
+
+                Ok(mlua::Value::Function(func))
+            });
+        }
+    }
+
+    /// Convert a Lua value into a [`Value`].
+    pub fn from_lua(value: mlua::Value) -> mlua::Result<Value> {
+        match value {
+            mlua::Value::Nil => Ok(Value::Unit),
+            mlua::Value::Boolean(v) => Ok(Value::Bool(v)),
+            mlua::Value::Integer(v) => Ok(Value::Int(v)),
+            mlua::Value::Number(v) => Ok(Value::Float(v)),
+            mlua::Value::String(v) => Ok(Value::Str(v.to_str()?.into())),
+            v => Err(mlua::Error::FromLuaConversionError {
+                from: v.type_name(),
+                to: "Value",
+                message: None,
+            }),
+        }
+    }
+
+    /// Convert a [`Value`] into a Lua value.
+    pub fn into_lua<'lua>(lua: &'lua mlua::Lua, value: Value) -> mlua::Result<mlua::Value<'lua>> {
+        use core::convert::TryFrom;
+
+        Ok(match value {
+            Value::Unit => mlua::Value::Nil,
+            Value::Bool(v) => mlua::Value::Boolean(v),
+            Value::Int(v) => mlua::Value::Integer(v),
+            Value::UInt(v) => match i64::try_from(v) {
+                Ok(v) => mlua::Value::Integer(v),
+                Err(_) => mlua::Value::Number(v as _),
+            },
+            Value::Float(v) => mlua::Value::Number(v),
+            Value::Char(v) => mlua::Value::String(lua.create_string(v.encode_utf8(&mut [0; 4]))?),
+            Value::Str(v) => mlua::Value::String(lua.create_string(&v)?),
+        })
+    }
+}
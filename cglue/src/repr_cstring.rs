@@ -42,6 +42,7 @@ impl From<&[u8]> for ReprCString {
     }
 }
 
+/// The string is cut at the first interior NUL byte.
 impl From<&str> for ReprCString {
     fn from(from: &str) -> Self {
         let b = from
@@ -54,12 +55,19 @@ impl From<&str> for ReprCString {
     }
 }
 
+/// The string is cut at the first interior NUL byte.
 impl From<String> for ReprCString {
     fn from(from: String) -> Self {
         from.as_str().into()
     }
 }
 
+impl From<ReprCString> for String {
+    fn from(from: ReprCString) -> Self {
+        from.as_ref().into()
+    }
+}
+
 impl<'a> std::borrow::Borrow<ReprCStr<'a>> for ReprCString {
     fn borrow(&self) -> &ReprCStr<'a> {
         unsafe { &*(self as *const _ as *const _) }
@@ -0,0 +1,181 @@
+use crate::alloc::*;
+use crate::boxed::{CBox, CSliceBox};
+use crate::trait_group::IntoInner;
+use crate::vec::CVec;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[derive(Default)]
+struct Counters {
+    allocated: AtomicUsize,
+    allocations: AtomicUsize,
+}
+
+/// Allocator that accounts for memory in use.
+struct CountingAlloc(Arc<Counters>);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.0.allocated.fetch_add(layout.size(), Ordering::Relaxed);
+        self.0.allocations.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.0.allocated.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+fn counting_alloc() -> (CAllocator, Arc<Counters>) {
+    let counters = Arc::new(Counters::default());
+    (CAllocator::new(CountingAlloc(counters.clone())), counters)
+}
+
+#[test]
+fn box_in() {
+    let (alloc, counters) = counting_alloc();
+    let value = Arc::new(());
+
+    let b = CBox::new_in((value.clone(), 42u64), alloc);
+
+    assert_eq!(b.1, 42);
+    assert_eq!(Arc::strong_count(&value), 2);
+    assert!(counters.allocated.load(Ordering::Relaxed) > 0);
+
+    std::mem::drop(b);
+
+    assert_eq!(Arc::strong_count(&value), 1);
+    assert_eq!(counters.allocated.load(Ordering::Relaxed), 0);
+    // Allocator is dropped together with the last allocation.
+    assert_eq!(Arc::strong_count(&counters), 1);
+}
+
+#[test]
+fn slice_box_in() {
+    let (alloc, counters) = counting_alloc();
+
+    let b = CSliceBox::new_in(vec![1u16, 2, 3], alloc);
+
+    assert_eq!(&*b, &[1, 2, 3]);
+
+    std::mem::drop(b);
+
+    assert_eq!(counters.allocated.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn box_in_into_box() {
+    let (alloc, counters) = counting_alloc();
+    let value = Arc::new(());
+
+    let b = CBox::new_in((value.clone(), 42u64), alloc.clone()).into_box();
+
+    assert_eq!(b.1, 42);
+    assert_eq!(Arc::strong_count(&value), 2);
+    assert_eq!(counters.allocated.load(Ordering::Relaxed), 0);
+
+    let inner = unsafe { CBox::new_in(b, alloc).into_inner() };
+
+    assert_eq!(inner.1, 42);
+    assert_eq!(counters.allocated.load(Ordering::Relaxed), 0);
+    assert_eq!(Arc::strong_count(&counters), 1);
+
+    std::mem::drop(inner);
+
+    assert_eq!(Arc::strong_count(&value), 1);
+}
+
+#[test]
+fn slice_box_in_into_boxed_slice() {
+    let (alloc, counters) = counting_alloc();
+    let value = Arc::new(());
+
+    let b = CSliceBox::new_in(vec![value.clone(), value.clone()], alloc).into_boxed_slice();
+
+    assert_eq!(b.len(), 2);
+    assert_eq!(Arc::strong_count(&value), 3);
+    assert_eq!(counters.allocated.load(Ordering::Relaxed), 0);
+    assert_eq!(Arc::strong_count(&counters), 1);
+
+    std::mem::drop(b);
+
+    assert_eq!(Arc::strong_count(&value), 1);
+}
+
+#[test]
+fn vec_with_allocator() {
+    let (alloc, counters) = counting_alloc();
+
+    let mut v = CVec::with_allocator(alloc.clone());
+
+    for i in 0..100u64 {
+        v.push(i);
+    }
+
+    assert_eq!(v.len(), 100);
+    assert!(v.capacity() >= 100);
+    assert_eq!(v.iter().sum::<u64>(), 4950);
+    assert!(counters.allocations.load(Ordering::Relaxed) > 1);
+    assert_eq!(v.pop(), Some(99));
+
+    let std_vec = Vec::from(v);
+
+    assert_eq!(std_vec.len(), 99);
+    assert_eq!(counters.allocated.load(Ordering::Relaxed), 0);
+
+    std::mem::drop(alloc);
+    assert_eq!(Arc::strong_count(&counters), 1);
+}
+
+#[test]
+fn vec_zst_with_allocator() {
+    let (alloc, counters) = counting_alloc();
+
+    let mut v = CVec::with_allocator(alloc);
+
+    v.push(());
+    v.push(());
+
+    assert_eq!(v.len(), 2);
+
+    std::mem::drop(v);
+
+    assert_eq!(counters.allocated.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn vec_clone_with_allocator() {
+    let (alloc, counters) = counting_alloc();
+
+    let mut v = CVec::with_allocator(alloc);
+    v.push("a".to_string());
+    v.push("b".to_string());
+
+    let allocated = counters.allocated.load(Ordering::Relaxed);
+
+    let mut clone = v.clone();
+    assert_eq!(&*clone, &["a", "b"]);
+    assert!(counters.allocated.load(Ordering::Relaxed) > allocated);
+
+    clone.push("c".to_string());
+    std::mem::drop(v);
+    assert_eq!(&*clone, &["a", "b", "c"]);
+
+    let empty = CVec::<u64>::with_allocator(counting_alloc().0);
+    assert!(empty.clone().is_empty());
+
+    std::mem::drop(clone);
+    assert_eq!(counters.allocated.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn vec_clone() {
+    let v = CVec::from(vec![1u32, 2, 3]);
+    let mut clone = v.clone();
+    clone.push(4);
+    assert_eq!(&*v, &[1, 2, 3]);
+    assert_eq!(&*clone, &[1, 2, 3, 4]);
+    assert!(CVec::<u32>::default().clone().is_empty());
+}
@@ -0,0 +1,112 @@
+use super::simple::structs::*;
+use crate::arc::*;
+use crate::com::*;
+use crate::trait_group::{c_void, GetVtbl};
+use crate::*;
+use std::sync::Arc;
+
+#[cglue_trait(com, guid = "6b29fc40-ca47-1067-b31d-00dd010662da")]
+pub trait ComThings {
+    fn ct_1(&self) -> usize;
+    fn ct_2(&mut self, val: usize) -> usize;
+}
+
+impl ComThings for SA {
+    fn ct_1(&self) -> usize {
+        42
+    }
+
+    fn ct_2(&mut self, val: usize) -> usize {
+        val + 1
+    }
+}
+
+/// `ComThings` vtable, as seen by a COM client.
+#[repr(C)]
+struct ComThingsClientVtbl {
+    unknown: IUnknownVtbl,
+    ct_1: unsafe extern "system" fn(this: *mut c_void) -> usize,
+    ct_2: unsafe extern "system" fn(this: *mut c_void, val: usize) -> usize,
+}
+
+const GUID: Guid = Guid::from_u128(0x6b29fc40_ca47_1067_b31d_00dd010662da);
+
+fn iid<V: ComInterface>(_: &V) -> Guid {
+    V::IID
+}
+
+#[test]
+fn com_vtbl_layout() {
+    let sa = SA {};
+    let obj = trait_obj!((sa, CArc::from(())) as ComThings);
+    let vtbl = obj.get_vtbl();
+
+    assert_eq!(iid(vtbl), GUID);
+
+    let base = vtbl as *const _ as *const usize;
+    let slots = unsafe { core::slice::from_raw_parts(base, 5) };
+
+    assert_eq!(slots[0], vtbl.query_interface() as usize);
+    assert_eq!(slots[1], vtbl.add_ref() as usize);
+    assert_eq!(slots[2], vtbl.release() as usize);
+    assert_eq!(slots[3], vtbl.ct_1() as usize);
+    assert_eq!(slots[4], vtbl.ct_2() as usize);
+    assert_eq!(obj.ct_1(), 42);
+}
+
+#[test]
+fn com_borrowed_calls() {
+    let mut sa = SA {};
+    let mut obj = trait_obj!(&mut sa as ComThings);
+
+    assert_eq!(obj.ct_1(), 42);
+    assert_eq!(obj.ct_2(1), 2);
+}
+
+#[test]
+fn com_client_ref_counting() {
+    let sa = SA {};
+
+    let arc = Arc::from(());
+    let obj = trait_obj!((sa, CArc::<()>::from(arc.clone())) as ComThings);
+    let this = into_raw(obj);
+
+    // Call through the vtable pointer at the start of the object, just like a COM client would.
+    let client_vtbl = unsafe { &**(this as *const *const ComThingsClientVtbl) };
+    let vtbl = &client_vtbl.unknown;
+
+    assert_eq!(Arc::strong_count(&arc), 2);
+
+    assert_eq!(unsafe { (vtbl.add_ref)(this) }, 2);
+
+    let mut out = core::ptr::null_mut();
+
+    let ret = unsafe { (vtbl.query_interface)(this, &GUID, &mut out) };
+    assert_eq!(ret, S_OK);
+    assert_eq!(out, this);
+
+    let ret = unsafe { (vtbl.query_interface)(this, &IID_IUNKNOWN, &mut out) };
+    assert_eq!(ret, S_OK);
+    assert_eq!(out, this);
+
+    let other = Guid::from_u128(1);
+    let ret = unsafe { (vtbl.query_interface)(this, &other, &mut out) };
+    assert_eq!(ret, E_NOINTERFACE);
+    assert!(out.is_null());
+
+    let ret = unsafe { (vtbl.query_interface)(this, &GUID, core::ptr::null_mut()) };
+    assert_eq!(ret, E_POINTER);
+
+    for refs in (1..4).rev() {
+        assert_eq!(unsafe { (vtbl.release)(this) }, refs);
+    }
+
+    // The object is still alive, and usable through the trait slots of the vtable.
+    assert_eq!(unsafe { (client_vtbl.ct_1)(this) }, 42);
+    assert_eq!(unsafe { (client_vtbl.ct_2)(this, 2) }, 3);
+    assert_eq!(Arc::strong_count(&arc), 2);
+
+    // Releasing the last reference drops the object.
+    assert_eq!(unsafe { (vtbl.release)(this) }, 0);
+    assert_eq!(Arc::strong_count(&arc), 1);
+}
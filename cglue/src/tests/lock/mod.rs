@@ -0,0 +1,162 @@
+use crate::boxed::CBox;
+use crate::callback::OpaqueCallback;
+use crate::lock::*;
+use crate::trait_group::{c_void, NoContext, Opaquable};
+use crate::*;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+#[cglue_trait]
+pub trait Accumulate {
+    fn acc(&mut self, val: usize) -> usize;
+    fn total(&self) -> usize;
+}
+
+#[derive(Default)]
+struct Accumulator {
+    total: usize,
+}
+
+impl Lockable for Accumulator {}
+
+impl Accumulate for Accumulator {
+    fn acc(&mut self, val: usize) -> usize {
+        self.total += val;
+        self.total
+    }
+
+    fn total(&self) -> usize {
+        self.total
+    }
+}
+
+// Arguments with named lifetimes may borrow for the lifetime of `self`, and outlive the lock guard,
+// thus `CSync` must not implement this trait.
+#[cglue_trait]
+pub trait Dump {
+    fn dump<'a>(&'a self, callback: OpaqueCallback<'a, &'a usize>);
+}
+
+impl Dump for Accumulator {
+    fn dump<'a>(&'a self, mut callback: OpaqueCallback<'a, &'a usize>) {
+        let _ = callback.call(&self.total);
+    }
+}
+
+cglue_trait_group!(
+    #[cglue_trait_group(lock)]
+    AccGroup,
+    Accumulate,
+    {}
+);
+cglue_impl_group!(Accumulator, AccGroup, {});
+
+#[test]
+fn use_sync() {
+    let acc = CSync::from(Accumulator::default());
+
+    let threads = (0..8)
+        .map(|_| {
+            let mut acc = acc.clone();
+            std::thread::spawn(move || {
+                for _ in 0..100 {
+                    acc.acc(1);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    threads.into_iter().for_each(|t| t.join().unwrap());
+
+    assert_eq!(acc.total(), 800);
+}
+
+#[test]
+fn use_sync_group() {
+    let group = group_obj!(Accumulator::default() as AccGroup);
+    let group: AccGroupSync<NoContext> = CSync::from(group);
+
+    let threads = (0..8)
+        .map(|_| {
+            let mut group = group.clone();
+            std::thread::spawn(move || {
+                for _ in 0..100 {
+                    group.acc(1);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    threads.into_iter().for_each(|t| t.join().unwrap());
+
+    assert_eq!(group.total(), 800);
+    assert_eq!(group.lock().total(), 800);
+}
+
+#[derive(Default)]
+struct SpinLock {
+    locked: AtomicBool,
+    lock_count: AtomicUsize,
+}
+
+unsafe extern "C" fn spin_lock(this: &c_void) {
+    let this = &*(this as *const c_void as *const SpinLock);
+    while this
+        .locked
+        .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        std::hint::spin_loop();
+    }
+    this.lock_count.fetch_add(1, Ordering::Relaxed);
+}
+
+unsafe extern "C" fn spin_unlock(this: &c_void) {
+    let this = &*(this as *const c_void as *const SpinLock);
+    this.locked.store(false, Ordering::Release);
+}
+
+static SPIN_LOCK_VTBL: LockVtbl = LockVtbl {
+    lock: spin_lock,
+    unlock: spin_unlock,
+};
+
+#[test]
+fn use_host_lock() {
+    let lock = CBox::from(SpinLock::default());
+    let lock_ref = unsafe { &*(&*lock as *const SpinLock) };
+    let raw = unsafe { CRawLock::from_raw_parts(lock.into_opaque(), &SPIN_LOCK_VTBL) };
+
+    let mut acc = CSync::with_lock(Accumulator::default(), raw);
+
+    assert_eq!(acc.acc(5), 5);
+    assert_eq!(acc.total(), 5);
+    assert_eq!(lock_ref.lock_count.load(Ordering::Relaxed), 2);
+    assert!(!lock_ref.locked.load(Ordering::Relaxed));
+}
+
+#[test]
+fn mutex_get_mut() {
+    let mut mutex = CMutex::new(Accumulator::default());
+
+    mutex.get_mut().acc(2);
+    mutex.lock().acc(3);
+
+    assert_eq!(mutex.into_inner().total, 5);
+}
+
+#[test]
+fn use_self_lifetime_args() {
+    let acc = CSync::from(Accumulator { total: 3 });
+
+    let mut out = 0;
+    Dump::dump(
+        &*acc.lock(),
+        (&mut |v: &usize| {
+            out = *v;
+            true
+        })
+            .into(),
+    );
+
+    assert_eq!(out, 3);
+}
@@ -1,5 +1,9 @@
+pub mod alloc;
 pub mod arc;
+pub mod com;
 pub mod ext;
 pub mod extra;
 pub mod generics;
+pub mod lock;
+pub mod rc;
 pub mod simple;
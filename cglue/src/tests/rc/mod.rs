@@ -0,0 +1,84 @@
+use super::arc::*;
+use super::simple::structs::*;
+use crate::rc::*;
+use crate::*;
+use std::rc::Rc;
+
+#[cglue_trait]
+pub trait Counter {
+    fn ctr_1(&mut self) -> usize;
+}
+
+impl Counter for SA {
+    fn ctr_1(&mut self) -> usize {
+        10
+    }
+}
+
+cglue_trait_group!(RcGroup, Counter, { DoThings });
+cglue_impl_group!(SA, RcGroup, { DoThings });
+
+#[test]
+fn use_rc_getter_obj() {
+    let sa = SA {};
+
+    let rc = Rc::new(());
+
+    assert_eq!(Rc::strong_count(&rc), 1);
+
+    let crc = CRc::<()>::from(rc.clone());
+
+    assert_eq!(Rc::strong_count(&rc), 2);
+
+    let getter = trait_obj!((sa, crc) as DoerGetter);
+
+    let doer = getter.dget_1();
+
+    assert_eq!(Rc::strong_count(&rc), 3);
+    assert_eq!(doer.dt_1(), 55);
+
+    std::mem::drop(getter);
+
+    assert_eq!(Rc::strong_count(&rc), 2);
+
+    std::mem::drop(doer);
+
+    assert_eq!(Rc::strong_count(&rc), 1);
+}
+
+#[test]
+fn use_rc_group() {
+    let sa = SA {};
+
+    let rc = Rc::new(());
+
+    let mut group = group_obj!((sa, CRc::<()>::from(rc.clone())) as RcGroup);
+
+    assert_eq!(Rc::strong_count(&rc), 2);
+    assert_eq!(group.ctr_1(), 10);
+
+    let doer = as_ref!(group impl DoThings).unwrap();
+    assert_eq!(doer.dt_1(), 55);
+
+    std::mem::drop(group);
+
+    assert_eq!(Rc::strong_count(&rc), 1);
+}
+
+#[test]
+fn into_rc() {
+    let rc = Rc::new(42u64);
+    let crc = CRc::<u64>::from(rc.clone());
+    let crc2 = crc.clone();
+
+    assert_eq!(Rc::strong_count(&rc), 3);
+
+    let rc2 = unsafe { crc2.into_rc() };
+
+    assert_eq!(*rc2, 42);
+    assert_eq!(Rc::strong_count(&rc), 3);
+
+    std::mem::drop((crc, rc2));
+
+    assert_eq!(Rc::strong_count(&rc), 1);
+}
@@ -1,4 +1,5 @@
 use super::structs::*;
+use super::trait_defs::*;
 use crate::*;
 
 #[cglue_trait]
@@ -22,3 +23,87 @@ fn use_self_constraint() {
 
     assert!(ret);
 }
+
+#[cglue_trait]
+pub trait GenWithSyncConstraint: Send + Sync {
+    fn gwsyc_1(&self) -> usize;
+}
+
+impl GenWithSyncConstraint for SA {
+    fn gwsyc_1(&self) -> usize {
+        42
+    }
+}
+
+cglue_trait_group!(BoundedGroup, GenWithSelfConstraint, {});
+cglue_impl_group!(SA, BoundedGroup, {});
+
+cglue_trait_group!(
+    #[cglue_trait_group(sync)]
+    SyncGroup,
+    GenWithSelfConstraint,
+    { GenWithSyncConstraint }
+);
+cglue_impl_group!(SA, SyncGroup, { GenWithSyncConstraint });
+
+cglue_trait_group!(
+    #[cglue_trait_group(send, sync)]
+    MarkedGroup,
+    TA,
+    { TC }
+);
+cglue_impl_group!(SA, MarkedGroup, { TC });
+
+#[test]
+fn use_sync_constraint() {
+    let sa = SA {};
+
+    let obj = trait_obj!(sa as GenWithSyncConstraint);
+
+    let ret = std::thread::scope(|s| s.spawn(|| obj.gwsyc_1()).join().unwrap());
+
+    assert_eq!(ret, 42);
+}
+
+#[test]
+fn use_bounded_group() {
+    let sa = SA {};
+
+    let group = group_obj!(sa as BoundedGroup);
+
+    let ret = std::thread::spawn(move || group.gwsc_1(&55))
+        .join()
+        .unwrap();
+
+    assert!(ret);
+}
+
+#[test]
+fn use_sync_group() {
+    let sa = SA {};
+
+    let group = group_obj!(sa as SyncGroup);
+
+    let ret = std::thread::scope(|s| {
+        s.spawn(|| as_ref!(group impl GenWithSyncConstraint).unwrap().gwsyc_1())
+            .join()
+            .unwrap()
+    });
+
+    assert_eq!(ret, 42);
+    assert!(!group.gwsc_1(&ret));
+}
+
+#[test]
+fn use_marked_group() {
+    let sa = SA {};
+
+    let mut group = group_obj!(sa as MarkedGroup);
+
+    let ret = std::thread::scope(|s| s.spawn(|| group.ta_1()).join().unwrap());
+    assert_eq!(ret, 5);
+
+    std::thread::spawn(move || as_mut!(group impl TC).unwrap().tc_2())
+        .join()
+        .unwrap();
+}
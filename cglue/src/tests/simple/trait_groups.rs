@@ -1,6 +1,7 @@
 //! These tests check definition and usage of different trait groups
 use super::structs::*;
 use super::trait_defs::*;
+use crate::trait_group::TraitSet;
 use cglue_macro::*;
 
 cglue_trait_group!(TestGroup, TA, { TB, TC });
@@ -64,3 +65,156 @@ fn test_group_2() {
 
     tb.tb_1(1);
 }
+
+#[test]
+fn test_group_capabilities() {
+    let a = SA {};
+    let group = group_obj!(a as TestGroup);
+
+    assert_eq!(TestGroupBox::CGLUE_CAPABILITY_NAMES, &["TA", "TB", "TC"]);
+
+    let caps = group.cglue_capabilities();
+    assert!(caps.contains(0));
+    assert!(!caps.contains(1));
+    assert!(caps.contains(2));
+    assert!(!caps.contains(TraitSet::MAX_LEN));
+    assert_eq!(
+        group.cglue_capability_names().collect::<Vec<_>>(),
+        vec!["TA", "TC"]
+    );
+
+    let b = SB {};
+    let group = group_obj!(b as TestGroup);
+    assert_eq!(
+        group.cglue_capability_names().collect::<Vec<_>>(),
+        vec!["TA", "TB"]
+    );
+}
+
+#[test]
+fn test_group_query_trait_typed() {
+    let a = SA {};
+    let mut group = group_obj!(a as TestGroup);
+
+    let ta = group.cglue_query_trait_ref::<TAVtbl<_>>().unwrap();
+    assert_eq!(ta.ta_1(), 5);
+
+    assert!(group.cglue_query_trait_ref::<TBVtbl<_>>().is_none());
+
+    let tc = group.cglue_query_trait_mut::<TCVtbl<_>>().unwrap();
+    tc.tc_2();
+    tc.tc_1();
+
+    assert!(group.cglue_query_trait_mut::<TBVtbl<_>>().is_none());
+}
+
+#[test]
+fn test_group_reflect() {
+    use crate::reflect::*;
+
+    let a = SA {};
+    let group = group_obj!(a as TestGroup);
+    let traits = group.traits();
+    assert_eq!(traits[1].name, "TC");
+    let tc_3 = traits[1].method("tc_3").unwrap();
+    assert_eq!(tc_3.receiver, ArgKind::Mut);
+    assert_eq!(tc_3.args[0].name, "_ignored");
+    assert_eq!(tc_3.args[0].ty, BasicType::UInt);
+    assert_eq!(tc_3.ret, BasicType::Unit);
+    assert!(tc_3.callable);
+
+    let b = SB {};
+    let mut group = group_obj!(b as TestGroup);
+
+    assert_eq!(
+        group.traits().iter().map(|t| t.name).collect::<Vec<_>>(),
+        vec!["TA", "TB"]
+    );
+    assert_eq!(group.call_method("ta_1", &[]), Ok(Value::UInt(6)));
+    assert_eq!(
+        group.call_method("tb_2", &[Value::Int(5)]),
+        Ok(Value::UInt(25))
+    );
+    assert_eq!(
+        group.call_method("tb_1", &[]),
+        Err(CallError::ArgCount {
+            expected: 1,
+            got: 0
+        })
+    );
+    assert_eq!(
+        group.call_method("tb_1", &[Value::Bool(true)]),
+        Err(CallError::ArgType {
+            index: 0,
+            expected: BasicType::UInt
+        })
+    );
+    assert_eq!(group.call_method("tc_1", &[]), Err(CallError::NoMethod));
+}
+
+#[test]
+fn test_group_descriptors() {
+    let descriptors = TestGroupBox::TRAIT_DESCRIPTORS;
+    assert_eq!(
+        descriptors
+            .iter()
+            .map(|d| (d.descriptor.name(), d.optional))
+            .collect::<Vec<_>>(),
+        vec![("TA", false), ("TB", true), ("TC", true)]
+    );
+
+    let tb = descriptors[1].descriptor.methods();
+    assert_eq!(tb[0].name(), "tb_1");
+    assert_eq!(
+        tb[0].signature(),
+        "extern \"C\" fn tb_1(&self, val: usize) -> usize"
+    );
+    assert_eq!(tb[1].signature(), "fn tb_2(&self, val: usize) -> usize");
+
+    let tc = descriptors[2].descriptor.methods();
+    assert_eq!(tc[2].signature(), "fn tc_3(&mut self, mut _ignored: usize)");
+
+    let a = SA {};
+    let group = group_obj!(a as TestGroup);
+    assert_eq!(
+        group
+            .trait_descriptors()
+            .map(|d| d.name())
+            .collect::<Vec<_>>(),
+        vec!["TA", "TC"]
+    );
+
+    // Descriptors are built out of the reflection metadata.
+    use crate::reflect::Reflect;
+    assert_eq!(group.traits()[1].methods[2].signature, tc[2].signature());
+}
+
+#[cfg(feature = "mlua")]
+#[test]
+fn test_group_lua() {
+    use crate::reflect::lua::LuaObject;
+
+    let lua = mlua::Lua::new();
+
+    let a = SA {};
+    let group: TestGroupBox<'static> = group_obj!(a as TestGroup);
+    lua.globals().set("a", LuaObject(group)).unwrap();
+
+    let b = SB {};
+    let group: TestGroupBox<'static> = group_obj!(b as TestGroup);
+    lua.globals().set("b", LuaObject(group)).unwrap();
+
+    let ret: usize = lua
+        .load("return a:ta_1() + b:tb_1(b:ta_1())")
+        .eval()
+        .unwrap();
+    assert_eq!(ret, 17);
+
+    lua.load("a:tc_3(1)").exec().unwrap();
+    assert!(lua
+        .load("return a.tb_1")
+        .eval::<mlua::Value>()
+        .unwrap()
+        .is_nil());
+    assert!(lua.load("b:tb_1('x')").exec().is_err());
+}
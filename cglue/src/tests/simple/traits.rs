@@ -52,6 +52,101 @@ pub trait WithInto {
     fn winto_1(&self, _into: impl Into<usize>) {}
 }
 
+#[cglue_trait]
+#[allow(clippy::boxed_local)]
+pub trait WithOwned {
+    fn wown_1(&self, val: String) -> String {
+        val + "_owned"
+    }
+    fn wown_2(&self, mut val: Vec<u32>) -> Vec<u32> {
+        val.push(42);
+        val
+    }
+    fn wown_3(&self, val: Box<u64>) -> Box<u64> {
+        Box::new(*val + 1)
+    }
+    fn wown_4(&self, val: Box<[u8]>) -> Box<[u8]> {
+        val.iter().rev().copied().collect()
+    }
+}
+
+#[cglue_trait]
+pub trait WithTuples {
+    fn wtup_1(&self, val: usize) -> (usize, bool) {
+        (val, val > 1)
+    }
+    fn wtup_2(&self, val: (u8, u16)) -> u32 {
+        val.0 as u32 + val.1 as u32
+    }
+    fn wtup_3(&self, val: Option<(u8, u16)>) -> Option<(u16, u8)> {
+        val.map(|(a, b)| (b, a))
+    }
+    fn wtup_4(&self, val: u8) -> Result<(u8, (u16, u32)), u8> {
+        if val > 0 {
+            Ok((val, (val as u16, val as u32)))
+        } else {
+            Err(val)
+        }
+    }
+}
+
+#[cglue_trait]
+pub trait WithCallbacks {
+    fn wcb_1(&self, mut cb: impl FnMut(usize) -> bool) -> usize {
+        (0..10).take_while(|&v| cb(v)).count()
+    }
+    fn wcb_2(&self, cb: &mut dyn FnMut(usize)) {
+        (0..3).for_each(cb)
+    }
+    fn wcb_3(&self, query: impl Fn(u32) -> u64) -> u64 {
+        query(1) + query(2)
+    }
+    fn wcb_4(&self, mut add: impl FnMut(u32, u64) -> u64) -> u64 {
+        add(1, 2) + add(3, 4)
+    }
+    fn wcb_5(&self, query: &dyn Fn(u8, u8, u8) -> u8) -> u8 {
+        query(1, 2, 3)
+    }
+}
+
+#[cglue_trait]
+pub trait WithIterators {
+    fn witer_1(&self, iter: impl Iterator<Item = usize>) -> usize {
+        iter.sum()
+    }
+    fn witer_2(&self, iter: &mut dyn Iterator<Item = usize>) -> Option<usize> {
+        iter.next()
+    }
+}
+
+#[cglue_trait]
+pub trait WithFnBoxes {
+    fn wfnbox_1(&self, mut f: Box<dyn FnMut(u32) -> u32>) -> u32 {
+        f(1) + f(2)
+    }
+    fn wfnbox_2(&self, base: u32) -> Box<dyn FnMut(u32) -> u32 + Send> {
+        Box::new(move |v| base + v)
+    }
+    fn wfnbox_3(&self, f: Box<dyn FnOnce(u32)>) {
+        f(3)
+    }
+}
+
+type AliasU32 = u32;
+
+#[cglue_trait]
+pub trait WithPaths {
+    fn wpath_1(&self, val: std::string::String) -> core::primitive::u32 {
+        val.len() as u32
+    }
+    fn wpath_2(&self, val: &str, num: std::primitive::u8) -> u64 {
+        val.len() as u64 + num as u64
+    }
+    fn wpath_3(&self, val: AliasU32) -> u32 {
+        val
+    }
+}
+
 struct Implementor {}
 
 impl WithSlice for Implementor {}
@@ -59,6 +154,12 @@ impl WithOptions for Implementor {}
 impl WithIntResult for Implementor {}
 impl WithAliasIntResult for Implementor {}
 impl WithInto for Implementor {}
+impl WithOwned for Implementor {}
+impl WithTuples for Implementor {}
+impl WithCallbacks for Implementor {}
+impl WithIterators for Implementor {}
+impl WithFnBoxes for Implementor {}
+impl WithPaths for Implementor {}
 
 type ICont<I, C> = crate::trait_group::CGlueObjContainer<I, crate::trait_group::NoContext, C>;
 type IRefCont<C> = ICont<&'static Implementor, C>;
@@ -69,6 +170,12 @@ type WOCont = IMutCont<WithOptionsRetTmp<crate::trait_group::NoContext>>;
 type WIRCont = IRefCont<WithIntResultRetTmp<crate::trait_group::NoContext>>;
 type WAIRCont = IRefCont<WithAliasIntResultRetTmp<crate::trait_group::NoContext>>;
 type WINTOCont = IRefCont<WithIntoRetTmp<crate::trait_group::NoContext>>;
+type WOWNCont = IRefCont<WithOwnedRetTmp<crate::trait_group::NoContext>>;
+type WTUPCont = IRefCont<WithTuplesRetTmp<crate::trait_group::NoContext>>;
+type WCBCont = IRefCont<WithCallbacksRetTmp<crate::trait_group::NoContext>>;
+type WITERCont = IRefCont<WithIteratorsRetTmp<crate::trait_group::NoContext>>;
+type WFNCont = IRefCont<WithFnBoxesRetTmp<crate::trait_group::NoContext>>;
+type WPATHCont = IRefCont<WithPathsRetTmp<crate::trait_group::NoContext>>;
 
 #[test]
 fn slices_wrapped() {
@@ -132,3 +239,207 @@ fn into_t_wrapped() {
     let vtbl = <&WithIntoVtbl<WINTOCont>>::default();
     let _: unsafe extern "C" fn(&WINTOCont, usize) = vtbl.winto_1();
 }
+
+#[test]
+fn owned_wrapped() {
+    use crate::boxed::{CBox, CSliceBox};
+    use crate::repr_cstring::ReprCString;
+    use crate::vec::CVec;
+
+    let vtbl = <&WithOwnedVtbl<WOWNCont>>::default();
+    let _: unsafe extern "C" fn(&WOWNCont, ReprCString) -> ReprCString = vtbl.wown_1();
+    let _: unsafe extern "C" fn(&WOWNCont, CVec<u32>) -> CVec<u32> = vtbl.wown_2();
+    let _: unsafe extern "C" fn(&WOWNCont, CBox<'static, u64>) -> CBox<'static, u64> =
+        vtbl.wown_3();
+    let _: unsafe extern "C" fn(&WOWNCont, CSliceBox<'static, u8>) -> CSliceBox<'static, u8> =
+        vtbl.wown_4();
+}
+
+#[test]
+fn owned_converted() {
+    let obj = trait_obj!(Implementor {} as WithOwned);
+
+    assert_eq!(obj.wown_1("value".to_string()), "value_owned");
+    assert_eq!(obj.wown_2(vec![1, 2]), vec![1, 2, 42]);
+    assert_eq!(obj.wown_3(Box::new(1)), Box::new(2));
+    assert_eq!(&*obj.wown_4(vec![1, 2, 3].into_boxed_slice()), &[3, 2, 1]);
+}
+
+#[test]
+fn tuples_wrapped() {
+    use crate::option::COption;
+    use crate::result::CResult;
+    use crate::tuple::CTup2;
+
+    let vtbl = <&WithTuplesVtbl<WTUPCont>>::default();
+    let _: unsafe extern "C" fn(&WTUPCont, usize) -> CTup2<usize, bool> = vtbl.wtup_1();
+    let _: unsafe extern "C" fn(&WTUPCont, CTup2<u8, u16>) -> u32 = vtbl.wtup_2();
+    let _: unsafe extern "C" fn(&WTUPCont, COption<CTup2<u8, u16>>) -> COption<CTup2<u16, u8>> =
+        vtbl.wtup_3();
+    let _: unsafe extern "C" fn(&WTUPCont, u8) -> CResult<CTup2<u8, CTup2<u16, u32>>, u8> =
+        vtbl.wtup_4();
+}
+
+#[test]
+fn tuples_converted() {
+    let obj = trait_obj!(Implementor {} as WithTuples);
+
+    assert_eq!(obj.wtup_1(3), (3, true));
+    assert_eq!(obj.wtup_2((1, 2)), 3);
+    assert_eq!(obj.wtup_3(Some((1, 2))), Some((2, 1)));
+    assert_eq!(obj.wtup_3(None), None);
+    assert_eq!(obj.wtup_4(5), Ok((5, (5, 5))));
+    assert_eq!(obj.wtup_4(0), Err(0));
+}
+
+#[test]
+fn callbacks_wrapped() {
+    use crate::callback::OpaqueCallback;
+
+    let vtbl = <&WithCallbacksVtbl<WCBCont>>::default();
+    let _: unsafe extern "C" fn(&WCBCont, OpaqueCallback<usize>) -> usize = vtbl.wcb_1();
+    let _: unsafe extern "C" fn(&WCBCont, OpaqueCallback<usize>) = vtbl.wcb_2();
+}
+
+#[test]
+fn value_callbacks_wrapped() {
+    use crate::callback::{CFn, CFnMut};
+    use crate::tuple::{CTup2, CTup3};
+
+    let vtbl = <&WithCallbacksVtbl<WCBCont>>::default();
+    let _: unsafe extern "C" fn(&WCBCont, CFn<u32, u64>) -> u64 = vtbl.wcb_3();
+    let _: unsafe extern "C" fn(&WCBCont, CFnMut<CTup2<u32, u64>, u64>) -> u64 = vtbl.wcb_4();
+    let _: unsafe extern "C" fn(&WCBCont, CFn<CTup3<u8, u8, u8>, u8>) -> u8 = vtbl.wcb_5();
+}
+
+#[test]
+fn value_callbacks_converted() {
+    let obj = trait_obj!(Implementor {} as WithCallbacks);
+
+    assert_eq!(obj.wcb_3(|v| v as u64 * 10), 30);
+
+    let mut calls = 0;
+    assert_eq!(
+        obj.wcb_4(|a, b| {
+            calls += 1;
+            a as u64 * b
+        }),
+        14
+    );
+    assert_eq!(calls, 2);
+
+    assert_eq!(obj.wcb_5(&|a, b, c| a * 100 + b * 10 + c), 123);
+}
+
+#[test]
+fn callbacks_converted() {
+    let obj = trait_obj!(Implementor {} as WithCallbacks);
+
+    let mut seen = vec![];
+    assert_eq!(
+        obj.wcb_1(|v| {
+            seen.push(v);
+            v < 4
+        }),
+        4
+    );
+    assert_eq!(seen, [0, 1, 2, 3, 4]);
+
+    let mut sum = 0;
+    obj.wcb_2(&mut |v| sum += v);
+    assert_eq!(sum, 3);
+}
+
+#[test]
+fn iterators_wrapped() {
+    use crate::iter::CIterator;
+    use crate::option::COption;
+
+    let vtbl = <&WithIteratorsVtbl<WITERCont>>::default();
+    let _: unsafe extern "C" fn(&WITERCont, CIterator<usize>) -> usize = vtbl.witer_1();
+    let _: unsafe extern "C" fn(&WITERCont, CIterator<usize>) -> COption<usize> = vtbl.witer_2();
+}
+
+#[test]
+fn iterators_converted() {
+    let obj = trait_obj!(Implementor {} as WithIterators);
+
+    assert_eq!(obj.witer_1(0..5), 10);
+
+    let mut iter = 5..7;
+    assert_eq!(obj.witer_2(&mut iter), Some(5));
+    assert_eq!(obj.witer_2(&mut iter), Some(6));
+    assert_eq!(obj.witer_2(&mut iter), None);
+}
+
+#[test]
+fn fn_boxes_wrapped() {
+    use crate::callback::{CFnBox, CFnOnceBox, CSend};
+
+    let vtbl = <&WithFnBoxesVtbl<WFNCont>>::default();
+    let _: unsafe extern "C" fn(&WFNCont, CFnBox<'static, u32, u32>) -> u32 = vtbl.wfnbox_1();
+    let _: unsafe extern "C" fn(&WFNCont, u32) -> CSend<CFnBox<'static, u32, u32>> =
+        vtbl.wfnbox_2();
+    let _: unsafe extern "C" fn(&WFNCont, CFnOnceBox<'static, u32, ()>) = vtbl.wfnbox_3();
+}
+
+#[test]
+fn fn_boxes_converted() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let obj = trait_obj!(Implementor {} as WithFnBoxes);
+
+    assert_eq!(obj.wfnbox_1(Box::new(|v| v * 10)), 30);
+
+    let mut f = obj.wfnbox_2(5);
+    assert_eq!(f(1), 6);
+    assert_eq!(std::thread::spawn(move || f(2)).join().unwrap(), 7);
+
+    let called = Rc::new(Cell::new(0));
+    let called2 = called.clone();
+    obj.wfnbox_3(Box::new(move |v| called2.set(v)));
+    assert_eq!(called.get(), 3);
+    assert_eq!(Rc::strong_count(&called), 1);
+}
+
+#[test]
+fn paths_reflected() {
+    use crate::reflect::*;
+
+    type Vtbl = WithPathsVtbl<'static, WPATHCont>;
+
+    let wpath_1 = Vtbl::META.method("wpath_1").unwrap();
+    assert_eq!(wpath_1.args[0].ty, BasicType::Str);
+    assert_eq!(wpath_1.ret, BasicType::UInt);
+    assert!(wpath_1.callable);
+
+    let wpath_2 = Vtbl::META.method("wpath_2").unwrap();
+    assert_eq!(wpath_2.args[0].ty, BasicType::Str);
+    assert_eq!(wpath_2.args[1].ty, BasicType::UInt);
+    assert!(wpath_2.callable);
+
+    // Aliases can not be resolved, but the method is still listed.
+    let wpath_3 = Vtbl::META.method("wpath_3").unwrap();
+    assert_eq!(wpath_3.args[0].ty, BasicType::Other);
+    assert_eq!(wpath_3.args[0].ty_name, "AliasU32");
+    assert!(!wpath_3.callable);
+
+    let mut obj = Implementor {};
+    assert_eq!(
+        Vtbl::cglue_reflect_call(&mut obj, "wpath_1", &[Value::Str("abc".into())]),
+        Some(Ok(Value::UInt(3)))
+    );
+    assert_eq!(
+        Vtbl::cglue_reflect_call(
+            &mut obj,
+            "wpath_2",
+            &[Value::Str("ab".into()), Value::UInt(2)]
+        ),
+        Some(Ok(Value::UInt(4)))
+    );
+    assert_eq!(
+        Vtbl::cglue_reflect_call(&mut obj, "wpath_3", &[Value::UInt(1)]),
+        Some(Err(CallError::NotCallable))
+    );
+}
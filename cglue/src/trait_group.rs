@@ -154,6 +154,12 @@ impl<T, V, C, R> GetVtbl<V> for CGlueTraitObj<'_, T, V, C, R> {
     }
 }
 
+impl<'a, T, V, C, R> CGlueTraitObj<'a, T, V, C, R> {
+    pub(crate) fn into_parts(self) -> (&'a V, CGlueObjContainer<T, C, R>) {
+        (self.vtbl, self.container)
+    }
+}
+
 // Conversions into container type itself.
 // Needed when generated code returns Self
 
@@ -286,20 +292,28 @@ where
 /// These bounds differ depending on features enabled. For instance, enabling `layout_checks` adds
 /// a requirement for `StableAbi` trait.
 ///
+/// Contexts are not required to be `Send` or `Sync`. Objects and groups inherit these auto traits
+/// from their context, thus using a thread-bound context, such as [`CRc`](crate::rc::CRc), yields
+/// `!Send` objects.
+///
 /// Since `layout_checks` is enabled, `StableAbi` requirement has been imposed.
 #[cfg(feature = "layout_checks")]
-pub trait ContextBounds: 'static + Clone + Send + Sync + abi_stable::StableAbi {}
+pub trait ContextBounds: 'static + Clone + abi_stable::StableAbi {}
 #[cfg(feature = "layout_checks")]
-impl<T: 'static + Clone + Send + Sync + abi_stable::StableAbi> ContextBounds for T {}
+impl<T: 'static + Clone + abi_stable::StableAbi> ContextBounds for T {}
 
 /// Describe type bounds for Context type.
 ///
 /// These bounds differ depending on features enabled. For instance, enabling `layout_checks` adds
 /// a requirement for `StableAbi` trait.
+///
+/// Contexts are not required to be `Send` or `Sync`. Objects and groups inherit these auto traits
+/// from their context, thus using a thread-bound context, such as [`CRc`](crate::rc::CRc), yields
+/// `!Send` objects.
 #[cfg(not(feature = "layout_checks"))]
-pub trait ContextBounds: 'static + Clone + Send + Sync {}
+pub trait ContextBounds: 'static + Clone {}
 #[cfg(not(feature = "layout_checks"))]
-impl<T: 'static + Clone + Send + Sync> ContextBounds for T {}
+impl<T: 'static + Clone> ContextBounds for T {}
 
 /// Describe type bounds needed for any generic type in CGlue objects.
 ///
@@ -454,11 +468,206 @@ pub trait OpaqueVtblBounds: Sized + CGlueVtblCont + abi_stable::StableAbi {}
 #[cfg(feature = "layout_checks")]
 impl<T: CGlueVtblCont + abi_stable::StableAbi> OpaqueVtblBounds for T {}
 
+/// FFI-safe set of traits implemented by a trait group object.
+///
+/// Every bit corresponds to a single trait of the group. Bits are ordered the same way as the
+/// group's `CGLUE_CAPABILITY_NAMES` - mandatory traits come first, followed by the optional ones, both
+/// sorted by name. This is the same order vtables are laid out in the group structure.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct TraitSet(u64);
+
+impl TraitSet {
+    /// Maximum number of traits in a set, and thus in a trait group.
+    pub const MAX_LEN: usize = 64;
+
+    /// Create a set with no traits in it.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Create a set out of raw bits.
+    pub const fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    /// Get the raw bits of the set.
+    pub const fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Add the trait at given index to the set.
+    ///
+    /// # Panics
+    ///
+    /// If `idx` is not less than [`MAX_LEN`](Self::MAX_LEN).
+    pub const fn with(self, idx: usize) -> Self {
+        if idx >= Self::MAX_LEN {
+            panic!("trait index out of TraitSet bounds");
+        }
+
+        Self(self.0 | (1 << idx))
+    }
+
+    /// Check whether the trait at given index is in the set.
+    pub const fn contains(self, idx: usize) -> bool {
+        idx < Self::MAX_LEN && self.0 & (1 << idx) != 0
+    }
+
+    /// Number of traits in the set.
+    pub const fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Check whether the set is empty.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Iterate over the names of the traits in the set.
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - list of trait names of the group, typically `CGLUE_CAPABILITY_NAMES`.
+    pub fn names<'a>(self, names: &'a [&'a str]) -> impl Iterator<Item = &'a str> + 'a {
+        names
+            .iter()
+            .enumerate()
+            .filter(move |(i, _)| self.contains(*i))
+            .map(|(_, n)| *n)
+    }
+}
+
+/// Stable identifier of a trait.
+///
+/// The ID is a 64-bit FNV-1a hash of the trait's name, thus it stays the same across different
+/// builds and compilers, and can be computed by the users of the ABI without any knowledge of the
+/// group containing the trait. Neither the path, nor the generic arguments of the trait are
+/// part of the ID, which is why a trait group may only contain a single trait of any given name.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct TraitId(u64);
+
+impl TraitId {
+    /// Compute the ID of a trait with given name.
+    pub const fn of(name: &str) -> Self {
+        let bytes = name.as_bytes();
+        let mut hash = 0xcbf29ce484222325u64;
+        let mut i = 0;
+
+        while i < bytes.len() {
+            hash ^= bytes[i] as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+            i += 1;
+        }
+
+        Self(hash)
+    }
+
+    /// Create an ID out of raw value.
+    pub const fn from_raw(raw: u64) -> Self {
+        Self(raw)
+    }
+
+    /// Get the raw value of the ID.
+    pub const fn raw(self) -> u64 {
+        self.0
+    }
+}
+
+/// Typed access to a trait of a group object.
+///
+/// This is implemented by trait groups for the vtable type `V` of every trait they contain, and
+/// is the basis of the group's `cglue_query_trait_ref` and `cglue_query_trait_mut` functions.
+pub trait QueryTrait<'a, V: 'a> {
+    /// Object implementing the trait.
+    ///
+    /// This is the group itself for mandatory traits, and the group's concrete variant with the
+    /// trait implemented for optional ones.
+    type Obj;
+
+    /// Get a reference to the object implementing the trait, if the vtable is present.
+    fn query_trait_ref(&self) -> Option<&Self::Obj>;
+
+    /// Get a mutable reference to the object implementing the trait, if the vtable is present.
+    fn query_trait_mut(&mut self) -> Option<&mut Self::Obj>;
+}
+
 /// Describes absence of a context.
 ///
 /// This context is used by default whenever a specific context was not supplied.
 pub type NoContext = std::marker::PhantomData<c_void>;
 
+/// Thread safety marker for objects that are neither `Send`, nor `Sync`.
+///
+/// Type erased objects lose the thread safety information of the underlying type. Thus, the
+/// temporary return storage of every trait carries one of these markers, derived from the
+/// trait's `Send` and `Sync` supertraits.
+///
+/// # Examples
+///
+/// Objects of traits without `Send` supertrait can not be sent:
+///
+/// ```compile_fail
+/// use cglue::prelude::v1::*;
+///
+/// #[cglue_trait]
+/// pub trait Getter {
+///     fn get(&self) -> u64;
+/// }
+///
+/// impl Getter for u64 {
+///     fn get(&self) -> u64 {
+///         *self
+///     }
+/// }
+///
+/// fn main() {
+///     let obj = trait_obj!(42u64 as Getter);
+///
+///     std::thread::spawn(move || obj.get());
+/// }
+/// ```
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct NotSendSync(std::marker::PhantomData<*mut ()>);
+
+/// Thread safety marker for objects that are `Send`, but not `Sync`.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct SendNotSync(std::marker::PhantomData<*mut ()>);
+
+unsafe impl Send for SendNotSync {}
+
+/// Thread safety marker for objects that are `Sync`, but not `Send`.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct SyncNotSend(std::marker::PhantomData<*mut ()>);
+
+unsafe impl Sync for SyncNotSend {}
+
+/// Thread safety marker for objects that are both `Send`, and `Sync`.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(::abi_stable::StableAbi))]
+pub struct SendSync(std::marker::PhantomData<*mut ()>);
+
+unsafe impl Send for SendSync {}
+unsafe impl Sync for SendSync {}
+
+/// Temporary return storage that can be shared across threads.
+///
+/// This is implemented for storage without any interior mutability, regardless of its thread
+/// safety marker. Trait groups marked `sync` require this of every trait in the group.
+///
+/// # Safety
+///
+/// The type must not be written to through a shared reference.
+pub unsafe trait SyncRetTmp {}
+
+unsafe impl<T> SyncRetTmp for std::marker::PhantomData<T> {}
+
 unsafe impl<T: Opaquable> Opaquable for std::marker::PhantomData<T> {
     type OpaqueTarget = std::marker::PhantomData<T::OpaqueTarget>;
 }
@@ -1,3 +1,5 @@
+use crate::alloc::{alloc_with_header, dealloc_with_header, header, CAllocator};
+use core::alloc::Layout;
 use core::mem::ManuallyDrop;
 use std::prelude::v1::*;
 
@@ -30,9 +32,39 @@ impl<T> From<Vec<T>> for CVec<T> {
     }
 }
 
+impl<T> From<CVec<T>> for Vec<T> {
+    fn from(mut vec: CVec<T>) -> Self {
+        // Move the elements out instead of taking over the buffer, because it may have been
+        // allocated on a different allocator. CVec drop then only frees the storage.
+        let mut ret = Vec::with_capacity(vec.len);
+        unsafe {
+            core::ptr::copy_nonoverlapping(vec.data, ret.as_mut_ptr(), vec.len);
+            ret.set_len(vec.len);
+        }
+        vec.len = 0;
+        ret
+    }
+}
+
 impl<T: Clone> Clone for CVec<T> {
     fn clone(&self) -> Self {
-        Self::from(Vec::from(&**self))
+        // Start out with the storage of `self`, but without owning it. Reserving then moves the
+        // clone to new storage of the same allocator.
+        let mut ret = Self {
+            data: self.data,
+            len: 0,
+            capacity: 0,
+            drop_fn: None,
+            reserve_fn: self.reserve_fn,
+        };
+
+        (ret.reserve_fn)(&mut ret, core::cmp::max(self.len, 1));
+
+        for elem in self.iter() {
+            ret.push(elem.clone());
+        }
+
+        ret
     }
 }
 
@@ -71,6 +103,25 @@ impl<T> core::ops::DerefMut for CVec<T> {
 }
 
 impl<T> CVec<T> {
+    /// Create an empty vector whose storage is allocated on given allocator.
+    ///
+    /// The allocator is kept alive, and used for reallocation, until the vector is dropped.
+    pub fn with_allocator(alloc: CAllocator) -> Self {
+        let capacity = if core::mem::size_of::<T>() == 0 {
+            usize::MAX
+        } else {
+            0
+        };
+
+        Self {
+            data: alloc_with_header(alloc, array_layout::<T>(capacity)) as *mut T,
+            len: 0,
+            capacity,
+            drop_fn: Some(cglue_drop_vec_in::<T>),
+            reserve_fn: cglue_reserve_vec_in::<T>,
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.len
     }
@@ -201,8 +252,66 @@ unsafe extern "C" fn cglue_drop_vec<T>(data: *mut T, len: usize, capacity: usize
     let _ = Vec::from_raw_parts(data, len, capacity);
 }
 
+/// Reserve space for at least `size` more elements.
+///
+/// Vectors without a drop function do not own their storage. Their elements are moved to new
+/// storage instead, leaving the old storage untouched.
 extern "C" fn cglue_reserve_vec<T>(vec: &mut CVec<T>, size: usize) -> usize {
+    if vec.drop_fn.is_none() {
+        let mut new = Vec::with_capacity(vec.len.checked_add(size).expect("capacity overflow"));
+        unsafe {
+            core::ptr::copy_nonoverlapping(vec.data, new.as_mut_ptr(), vec.len);
+            new.set_len(vec.len);
+        }
+        let mut new = ManuallyDrop::new(new);
+        vec.data = new.as_mut_ptr();
+        vec.capacity = new.capacity();
+        vec.drop_fn = Some(cglue_drop_vec::<T>);
+        return vec.capacity;
+    }
+
     let mut vec = TempVec::from(vec);
     vec.reserve(size);
     vec.capacity()
 }
+
+unsafe extern "C" fn cglue_drop_vec_in<T>(data: *mut T, len: usize, capacity: usize) {
+    core::ptr::drop_in_place(core::ptr::slice_from_raw_parts_mut(data, len));
+    dealloc_with_header(data as *mut u8, array_layout::<T>(capacity));
+}
+
+/// Reserve space for at least `size` more elements on the allocator of the vector.
+///
+/// Same as with [`cglue_reserve_vec`], storage of vectors without a drop function is left
+/// untouched.
+extern "C" fn cglue_reserve_vec_in<T>(vec: &mut CVec<T>, size: usize) -> usize {
+    let required = vec.len.checked_add(size).expect("capacity overflow");
+
+    if required > vec.capacity || vec.drop_fn.is_none() {
+        let capacity = vec
+            .capacity
+            .checked_mul(2)
+            .map_or(required, |c| core::cmp::max(c, required));
+        let old_layout = array_layout::<T>(vec.capacity);
+
+        unsafe {
+            let alloc = header(vec.data as *mut u8, old_layout).clone();
+            let data = alloc_with_header(alloc, array_layout::<T>(capacity)) as *mut T;
+            core::ptr::copy_nonoverlapping(vec.data, data, vec.len);
+            if vec.drop_fn.is_some() {
+                dealloc_with_header(vec.data as *mut u8, old_layout);
+            }
+            vec.data = data;
+        }
+
+        vec.drop_fn = Some(cglue_drop_vec_in::<T>);
+
+        vec.capacity = capacity;
+    }
+
+    vec.capacity
+}
+
+fn array_layout<T>(len: usize) -> Layout {
+    Layout::array::<T>(len).expect("allocation too large")
+}
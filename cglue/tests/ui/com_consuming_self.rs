@@ -0,0 +1,8 @@
+use cglue::*;
+
+#[cglue_trait(com, guid = "6b29fc40-ca47-1067-b31d-00dd010662da")]
+pub trait Getter {
+    fn get(self) -> u64;
+}
+
+fn main() {}
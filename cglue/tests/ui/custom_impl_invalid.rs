@@ -0,0 +1,9 @@
+use cglue::*;
+
+#[cglue_trait]
+pub trait Getter {
+    #[custom_impl(not a valid implementation)]
+    fn get<T: Into<u64>>(&self, val: T) -> u64;
+}
+
+fn main() {}
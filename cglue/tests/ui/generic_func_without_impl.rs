@@ -0,0 +1,8 @@
+use cglue::*;
+
+#[cglue_trait]
+pub trait Getter {
+    fn get<T: Into<u64>>(&self, val: T) -> u64;
+}
+
+fn main() {}
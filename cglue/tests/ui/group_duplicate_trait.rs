@@ -0,0 +1,25 @@
+#![allow(unused_imports)]
+
+use cglue::*;
+
+mod a {
+    use cglue::*;
+
+    #[cglue_trait]
+    pub trait Getter {
+        fn get(&self) -> u64;
+    }
+}
+
+mod b {
+    use cglue::*;
+
+    #[cglue_trait]
+    pub trait Getter {
+        fn get(&self) -> u32;
+    }
+}
+
+cglue_trait_group!(GetterGroup, a::Getter, b::Getter);
+
+fn main() {}
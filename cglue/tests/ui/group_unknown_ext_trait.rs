@@ -0,0 +1,10 @@
+use cglue::*;
+
+#[cglue_trait]
+pub trait Getter {
+    fn get(&self) -> u64;
+}
+
+cglue_trait_group!(GetterGroup, Getter, { ext::Missing });
+
+fn main() {}
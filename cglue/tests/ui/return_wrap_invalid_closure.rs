@@ -0,0 +1,12 @@
+use cglue::*;
+
+#[cglue_trait]
+pub trait Getter {
+    #[wrap_with(u64)]
+    #[return_wrap(u64)]
+    type Item;
+
+    fn get(&self) -> Self::Item;
+}
+
+fn main() {}
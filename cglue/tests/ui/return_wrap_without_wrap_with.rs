@@ -0,0 +1,11 @@
+use cglue::*;
+
+#[cglue_trait]
+pub trait Getter {
+    #[return_wrap(|ret| ret)]
+    type Item;
+
+    fn get(&self) -> Self::Item;
+}
+
+fn main() {}
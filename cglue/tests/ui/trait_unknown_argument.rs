@@ -0,0 +1,8 @@
+use cglue::*;
+
+#[cglue_trait(unknown)]
+pub trait Getter {
+    fn get(&self) -> u64;
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use cglue::*;
+
+#[cglue_trait]
+pub trait Getter {
+    #[wrap_with_obj_ref(Clone)]
+    type Item: Clone;
+
+    fn get(&self) -> Self::Item;
+}
+
+fn main() {}
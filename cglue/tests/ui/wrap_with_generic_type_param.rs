@@ -0,0 +1,11 @@
+use cglue::*;
+
+#[cglue_trait]
+pub trait Getter {
+    #[wrap_with_obj(Clone)]
+    type Item<T>: Clone;
+
+    fn get(&self) -> Self::Item<u64>;
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use cglue::*;
+
+#[cglue_trait]
+pub trait Getter {
+    #[wrap_with(+)]
+    type Item;
+
+    fn get(&self) -> Self::Item;
+}
+
+fn main() {}
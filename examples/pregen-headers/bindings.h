@@ -77,6 +77,94 @@
 
 #define BUF_ITER_ARR(ty, name, buf) BUF_ITER(ty, name, buf, sizeof(buf) / sizeof(*buf))
 
+// Constructs a borrowed callback returning a value
+//
+// `func` gets called with `ctx` and the argument. Multiple arguments get passed as a `CTup`.
+//
+// `ty` is the suffix of the callback type, for instance, `u32__u64` for `CFnMut_u32__u64`,
+// while `arg` and `ret` are the C types of the argument and the return value.
+#define FN_MUT_SPEC(ty, arg, ret, ctx, func) \
+    (struct CFnMut_##ty){ (void *)(ctx), (ret (*)(void *, arg))(func) }
+
+#define FN_MUT(arg, ret, ctx, func) \
+    FN_MUT_SPEC(arg##__##ret, arg, ret, ctx, func)
+
+// Constructs a borrowed callback that does not mutate the context
+//
+// This is the same as `FN_MUT_SPEC`, but the resulting type is `CFn_##ty`.
+#define FN_REF_SPEC(ty, arg, ret, ctx, func) \
+    (struct CFn_##ty){ (const void *)(ctx), (ret (*)(const void *, arg))(func) }
+
+#define FN_REF(arg, ret, ctx, func) \
+    FN_REF_SPEC(arg##__##ret, arg, ret, ctx, func)
+
+// Constructs an owned callback
+//
+// `func` gets called with `ctx` and the argument. Once the callback is no longer needed, `drop`
+// gets called with `ctx`. `drop` may be `NULL`, if there is nothing to free.
+//
+// `ty` is the suffix of the callback type, for instance, `u32__bool` for `CFnBox_u32__bool`,
+// while `arg` and `ret` are the C types of the argument and the return value.
+#define FN_BOX_SPEC(ty, arg, ret, ctx, func, drop) \
+    (struct CFnBox_##ty){ \
+        (struct CBox_c_void){ (void *)(ctx), (void (*)(void *))(drop) }, \
+        (ret (*)(void *, arg))(func) \
+    }
+
+#define FN_BOX(arg, ret, ctx, func, drop) \
+    FN_BOX_SPEC(arg##__##ret, arg, ret, ctx, func, drop)
+
+// Constructs an owned callback that can only be called once
+//
+// This is the same as `FN_BOX_SPEC`, but the resulting type is `CFnOnceBox_##ty`.
+#define FN_ONCE_BOX_SPEC(ty, arg, ret, ctx, func, drop) \
+    (struct CFnOnceBox_##ty){ \
+        (struct CBox_c_void){ (void *)(ctx), (void (*)(void *))(drop) }, \
+        (ret (*)(void *, arg))(func) \
+    }
+
+#define FN_ONCE_BOX(arg, ret, ctx, func, drop) \
+    FN_ONCE_BOX_SPEC(arg##__##ret, arg, ret, ctx, func, drop)
+
+// Initializes Clone vtable out of `impl_` prefixed functions
+//
+// The descriptor is left NULL, thus Rust side uses the descriptor of the trait definition.
+#define CLONE_VTBL(impl) { \
+    .clone = impl##_clone, \
+}
+
+// Initializes KeyValueDumper vtable out of `impl_` prefixed functions
+//
+// The descriptor is left NULL, thus Rust side uses the descriptor of the trait definition.
+#define KEYVALUEDUMPER_VTBL(impl) { \
+    .dump_key_values = impl##_dump_key_values, \
+    .print_ints = impl##_print_ints, \
+}
+
+// Initializes KeyValueStore vtable out of `impl_` prefixed functions
+//
+// The descriptor is left NULL, thus Rust side uses the descriptor of the trait definition.
+#define KEYVALUESTORE_VTBL(impl) { \
+    .write_key_value = impl##_write_key_value, \
+    .get_key_value = impl##_get_key_value, \
+}
+
+// Initializes MainFeature vtable out of `impl_` prefixed functions
+//
+// The descriptor is left NULL, thus Rust side uses the descriptor of the trait definition.
+#define MAINFEATURE_VTBL(impl) { \
+    .print_self = impl##_print_self, \
+}
+
+// Initializes PluginInner vtable out of `impl_` prefixed functions
+//
+// The descriptor is left NULL, thus Rust side uses the descriptor of the trait definition.
+#define PLUGININNER_VTBL(impl) { \
+    .borrow_features = impl##_borrow_features, \
+    .into_features = impl##_into_features, \
+    .mut_features = impl##_mut_features, \
+}
+
 // Forward declarations for vtables and their wrappers
 struct CGlueTraitObj_CBox_c_void_____PluginInnerVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void______________CArc_c_void_____PluginInnerRetTmp_CArc_c_void;
 struct CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void;
@@ -112,11 +200,20 @@ typedef const char *ReprCStr;
  * This box has a static self reference, alongside a custom drop function.
  *
  * The drop function can be called from anywhere, it will free on correct allocator internally.
+ * The free function releases the storage without dropping the value, and is used when the
+ * value gets moved out of the box.
  */
 typedef struct CBox_c_void {
     void *instance;
     void (*drop_fn)(void*);
+    void (*free_fn)(void*);
 } CBox_c_void;
+
+/**
+ * FFI-Safe Arc
+ *
+ * This is an FFI-Safe equivalent of Arc<T> and Option<Arc<T>>.
+ */
 typedef struct CArc_c_void {
     const void *instance;
     const void *(*clone_fn)(const void*);
@@ -127,22 +224,45 @@ typedef struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void {
     struct CBox_c_void instance;
     CArc_c_void context;
 } FeaturesGroupContainer_CBox_c_void_____CArc_c_void;
+
 /**
- * CGlue vtable for trait MainFeature.
+ * Wrapper around const slices.
  *
- * This virtual function table contains ABI-safe interface for the given trait.
+ * This is meant as a safe type to pass across the FFI boundary with similar semantics as regular
+ * slice. However, not all functionality is present, use the slice conversion functions.
+ *
+ * # Examples
+ *
+ * Simple conversion:
+ *
+ * ```
+ * use cglue::slice::CSliceRef;
+ *
+ * let arr = [0, 5, 3, 2];
+ *
+ * let cslice = CSliceRef::from(&arr[..]);
+ *
+ * let slice = cslice.as_slice();
+ *
+ * assert_eq!(&arr, slice);
+ * ```
  */
-typedef struct MainFeatureVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void {
-    void (*print_self)(const struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void *cont);
-} MainFeatureVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void;
+typedef struct CSliceRef_u8 {
+    const uint8_t *data;
+    uintptr_t len;
+} CSliceRef_u8;
+
 /**
- * CGlue vtable for trait Clone.
- *
- * This virtual function table contains ABI-safe interface for the given trait.
+ * FFI-safe description of a trait method.
  */
-typedef struct CloneVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void {
-    struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void (*clone)(const struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void *cont);
-} CloneVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void;
+typedef struct MethodDescriptor {
+    struct CSliceRef_u8 name;
+    /**
+     * Same as [`MethodInfo::signature`].
+     */
+    struct CSliceRef_u8 signature;
+} MethodDescriptor;
+
 /**
  * Wrapper around const slices.
  *
@@ -165,10 +285,40 @@ typedef struct CloneVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void {
  * assert_eq!(&arr, slice);
  * ```
  */
-typedef struct CSliceRef_u8 {
-    const uint8_t *data;
+typedef struct CSliceRef_MethodDescriptor {
+    const struct MethodDescriptor *data;
     uintptr_t len;
-} CSliceRef_u8;
+} CSliceRef_MethodDescriptor;
+
+/**
+ * FFI-safe description of a trait.
+ *
+ * Every CGlue vtable refers to a static instance of this structure.
+ */
+typedef struct TraitDescriptor {
+    struct CSliceRef_u8 name;
+    struct CSliceRef_MethodDescriptor methods;
+} TraitDescriptor;
+
+/**
+ * CGlue vtable for trait MainFeature.
+ *
+ * This virtual function table contains ABI-safe interface for the given trait.
+ */
+typedef struct MainFeatureVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void {
+    void (*print_self)(const struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void *cont);
+    const struct TraitDescriptor *descriptor;
+} MainFeatureVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void;
+
+/**
+ * CGlue vtable for trait Clone.
+ *
+ * This virtual function table contains ABI-safe interface for the given trait.
+ */
+typedef struct CloneVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void {
+    struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void (*clone)(const struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void *cont);
+    const struct TraitDescriptor *descriptor;
+} CloneVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void;
 
 typedef struct KeyValue {
     struct CSliceRef_u8 _0;
@@ -232,18 +382,27 @@ typedef struct CIterator_i32 {
  * This virtual function table contains ABI-safe interface for the given trait.
  */
 typedef struct KeyValueDumperVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void {
-    void (*dump_key_values)(const struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void *cont, KeyValueCallback callback);
-    void (*print_ints)(const struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void *cont, struct CIterator_i32 iter);
+    void (*dump_key_values)(const struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void *cont,
+                            KeyValueCallback callback);
+    void (*print_ints)(const struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void *cont,
+                       struct CIterator_i32 iter);
+    const struct TraitDescriptor *descriptor;
 } KeyValueDumperVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void;
+
 /**
  * CGlue vtable for trait KeyValueStore.
  *
  * This virtual function table contains ABI-safe interface for the given trait.
  */
 typedef struct KeyValueStoreVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void {
-    void (*write_key_value)(struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void *cont, struct CSliceRef_u8 name, uintptr_t val);
-    uintptr_t (*get_key_value)(const struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void *cont, struct CSliceRef_u8 name);
+    void (*write_key_value)(struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void *cont,
+                            struct CSliceRef_u8 name,
+                            uintptr_t val);
+    uintptr_t (*get_key_value)(const struct FeaturesGroupContainer_CBox_c_void_____CArc_c_void *cont,
+                               struct CSliceRef_u8 name);
+    const struct TraitDescriptor *descriptor;
 } KeyValueStoreVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void;
+
 /**
  * Trait group potentially implementing `MainFeature < > + :: cglue :: ext :: core :: clone :: Clone < > + KeyValueDumper < > + KeyValueStore < >` traits.
  *
@@ -272,11 +431,6 @@ typedef struct FeaturesGroup_CBox_c_void_____CArc_c_void {
 
 // Typedef for default container and context type
 typedef struct FeaturesGroup_CBox_c_void_____CArc_c_void FeaturesGroup;
-/**
- * FFI-Safe Arc
- *
- * This is an FFI-Safe equivalent of Arc<T> and Option<Arc<T>>.
- */
 
 typedef struct FeaturesGroupContainer_____c_void__CArc_c_void {
     void *instance;
@@ -290,6 +444,7 @@ typedef struct FeaturesGroupContainer_____c_void__CArc_c_void {
  */
 typedef struct MainFeatureVtbl_FeaturesGroupContainer_____c_void__CArc_c_void {
     void (*print_self)(const struct FeaturesGroupContainer_____c_void__CArc_c_void *cont);
+    const struct TraitDescriptor *descriptor;
 } MainFeatureVtbl_FeaturesGroupContainer_____c_void__CArc_c_void;
 
 /**
@@ -299,6 +454,7 @@ typedef struct MainFeatureVtbl_FeaturesGroupContainer_____c_void__CArc_c_void {
  */
 typedef struct CloneVtbl_FeaturesGroupContainer_____c_void__CArc_c_void {
     struct FeaturesGroupContainer_____c_void__CArc_c_void (*clone)(const struct FeaturesGroupContainer_____c_void__CArc_c_void *cont);
+    const struct TraitDescriptor *descriptor;
 } CloneVtbl_FeaturesGroupContainer_____c_void__CArc_c_void;
 
 /**
@@ -307,8 +463,11 @@ typedef struct CloneVtbl_FeaturesGroupContainer_____c_void__CArc_c_void {
  * This virtual function table contains ABI-safe interface for the given trait.
  */
 typedef struct KeyValueDumperVtbl_FeaturesGroupContainer_____c_void__CArc_c_void {
-    void (*dump_key_values)(const struct FeaturesGroupContainer_____c_void__CArc_c_void *cont, KeyValueCallback callback);
-    void (*print_ints)(const struct FeaturesGroupContainer_____c_void__CArc_c_void *cont, struct CIterator_i32 iter);
+    void (*dump_key_values)(const struct FeaturesGroupContainer_____c_void__CArc_c_void *cont,
+                            KeyValueCallback callback);
+    void (*print_ints)(const struct FeaturesGroupContainer_____c_void__CArc_c_void *cont,
+                       struct CIterator_i32 iter);
+    const struct TraitDescriptor *descriptor;
 } KeyValueDumperVtbl_FeaturesGroupContainer_____c_void__CArc_c_void;
 
 /**
@@ -317,8 +476,12 @@ typedef struct KeyValueDumperVtbl_FeaturesGroupContainer_____c_void__CArc_c_void
  * This virtual function table contains ABI-safe interface for the given trait.
  */
 typedef struct KeyValueStoreVtbl_FeaturesGroupContainer_____c_void__CArc_c_void {
-    void (*write_key_value)(struct FeaturesGroupContainer_____c_void__CArc_c_void *cont, struct CSliceRef_u8 name, uintptr_t val);
-    uintptr_t (*get_key_value)(const struct FeaturesGroupContainer_____c_void__CArc_c_void *cont, struct CSliceRef_u8 name);
+    void (*write_key_value)(struct FeaturesGroupContainer_____c_void__CArc_c_void *cont,
+                            struct CSliceRef_u8 name,
+                            uintptr_t val);
+    uintptr_t (*get_key_value)(const struct FeaturesGroupContainer_____c_void__CArc_c_void *cont,
+                               struct CSliceRef_u8 name);
+    const struct TraitDescriptor *descriptor;
 } KeyValueStoreVtbl_FeaturesGroupContainer_____c_void__CArc_c_void;
 
 /**
@@ -388,6 +551,7 @@ typedef struct PluginInnerVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void____
     struct FeaturesGroup_CBox_c_void_____CArc_c_void (*borrow_features)(struct CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void *cont);
     struct FeaturesGroup_CBox_c_void_____CArc_c_void (*into_features)(struct CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void cont);
     struct FeaturesGroup_____c_void__CArc_c_void *(*mut_features)(struct CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void *cont);
+    const struct TraitDescriptor *descriptor;
 } PluginInnerVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void;
 
 /**
@@ -405,18 +569,22 @@ typedef struct CGlueTraitObj_CBox_c_void_____PluginInnerVtbl_CGlueObjContainer_C
 
 // Typedef for default container and context type
 typedef struct CGlueTraitObj_CBox_c_void_____PluginInnerVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void______________CArc_c_void_____PluginInnerRetTmp_CArc_c_void PluginInner;
+
 /**
  * Base CGlue trait object for trait PluginInner.
  */
 typedef struct CGlueTraitObj_CBox_c_void_____PluginInnerVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void______________CArc_c_void_____PluginInnerRetTmp_CArc_c_void PluginInnerBase_CBox_c_void_____CArc_c_void;
+
 /**
  * CtxBoxed CGlue trait object for trait PluginInner with context.
  */
 typedef PluginInnerBase_CBox_c_void_____CArc_c_void PluginInnerBaseCtxBox_c_void__CArc_c_void;
+
 /**
  * Boxed CGlue trait object for trait PluginInner with a [`CArc`](cglue::arc::CArc) reference counted context.
  */
 typedef PluginInnerBaseCtxBox_c_void__CArc_c_void PluginInnerBaseArcBox_c_void__c_void;
+
 /**
  * Opaque Boxed CGlue trait object for trait PluginInner with a [`CArc`](cglue::arc::CArc) reference counted context.
  */
@@ -444,6 +612,7 @@ extern const TypeLayout *ROOT_LAYOUT;
  * version mismatches.
  */
 int32_t load_plugin(ReprCStr name, PluginInnerArcBox *ok_out);
+
 static CArc_c_void ctx_arc_clone(CArc_c_void *self) {
     CArc_c_void ret = *self;
     ret.instance = self->clone_fn(self->instance);
@@ -457,6 +626,21 @@ void cont_box_drop(CBox_c_void *self) {
     if (self->drop_fn && self->instance) self->drop_fn(self->instance);
 }
 
+// Builds PluginInner object, that takes ownership of the instance and the context.
+//
+// `drop_fn` gets called on the instance when the object is dropped. `free_fn` releases the
+// instance's storage without dropping it, and gets called when Rust moves the instance out of the
+// object. Optional vtables may be NULL.
+static inline struct CGlueTraitObj_CBox_c_void_____PluginInnerVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void______________CArc_c_void_____PluginInnerRetTmp_CArc_c_void plugininner_new(void *instance, void (*drop_fn)(void *), void (*free_fn)(void *), struct CArc_c_void context, const struct PluginInnerVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void *vtbl) {
+    struct CGlueTraitObj_CBox_c_void_____PluginInnerVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void______________CArc_c_void_____PluginInnerRetTmp_CArc_c_void ret = { 0 };
+    ret.vtbl = vtbl;
+    ret.container.instance.instance = instance;
+    ret.container.instance.drop_fn = drop_fn;
+    ret.container.instance.free_fn = free_fn;
+    ret.container.context = context;
+    return ret;
+}
+
 static inline struct FeaturesGroup_CBox_c_void_____CArc_c_void borrow_features(void *self)  {
     struct FeaturesGroup_CBox_c_void_____CArc_c_void __ret = (((struct CGlueTraitObj_CBox_c_void_____PluginInnerVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void______________CArc_c_void_____PluginInnerRetTmp_CArc_c_void *)self)->vtbl)->borrow_features(&((struct CGlueTraitObj_CBox_c_void_____PluginInnerVtbl_CGlueObjContainer_CBox_c_void_____CArc_c_void_____PluginInnerRetTmp_CArc_c_void______________CArc_c_void_____PluginInnerRetTmp_CArc_c_void *)self)->container);
     return __ret;
@@ -522,6 +706,43 @@ static inline void featuresgroup_arc_mut_drop(struct FeaturesGroup_____c_void__C
 
 }
 
+// Names of the traits in FeaturesGroup, ordered by their bit in `featuresgroup_capabilities`
+static const char * const featuresgroup_capability_names[] = {
+    "MainFeature",
+    "Clone",
+    "KeyValueDumper",
+    "KeyValueStore",
+    NULL
+};
+
+static inline uint64_t featuresgroup_capabilities(const void *self) {
+    const struct FeaturesGroup_CBox_c_void_____CArc_c_void *group = (const struct FeaturesGroup_CBox_c_void_____CArc_c_void *)self;
+    uint64_t ret = 0;
+    if (group->vtbl_mainfeature) ret |= 1ull << 0;
+    if (group->vtbl_clone) ret |= 1ull << 1;
+    if (group->vtbl_keyvaluedumper) ret |= 1ull << 2;
+    if (group->vtbl_keyvaluestore) ret |= 1ull << 3;
+    return ret;
+}
+
+// Builds FeaturesGroup object, that takes ownership of the instance and the context.
+//
+// `drop_fn` gets called on the instance when the object is dropped. `free_fn` releases the
+// instance's storage without dropping it, and gets called when Rust moves the instance out of the
+// object. Optional vtables may be NULL.
+static inline struct FeaturesGroup_CBox_c_void_____CArc_c_void featuresgroup_new(void *instance, void (*drop_fn)(void *), void (*free_fn)(void *), struct CArc_c_void context, const struct MainFeatureVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void *vtbl_mainfeature, const struct CloneVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void *vtbl_clone, const struct KeyValueDumperVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void *vtbl_keyvaluedumper, const struct KeyValueStoreVtbl_FeaturesGroupContainer_CBox_c_void_____CArc_c_void *vtbl_keyvaluestore) {
+    struct FeaturesGroup_CBox_c_void_____CArc_c_void ret = { 0 };
+    ret.vtbl_mainfeature = vtbl_mainfeature;
+    ret.vtbl_clone = vtbl_clone;
+    ret.vtbl_keyvaluedumper = vtbl_keyvaluedumper;
+    ret.vtbl_keyvaluestore = vtbl_keyvaluestore;
+    ret.container.instance.instance = instance;
+    ret.container.instance.drop_fn = drop_fn;
+    ret.container.instance.free_fn = free_fn;
+    ret.container.context = context;
+    return ret;
+}
+
 struct CollectBase {
     /* Pointer to array of data */
     char *buf;
@@ -590,7 +811,6 @@ static inline bool cb_count_KeyValue(size_t *cnt, KeyValue info) {
     return ++(*cnt);
 }
 
-
 #ifdef __cplusplus
 } // extern "C"
 #endif // __cplusplus
@@ -40,7 +40,7 @@ struct DeferedForget {
 
 /** Workaround for void types in generic functions. */
 struct StoreAll {
-    constexpr auto operator[](StoreAll) const {
+    constexpr bool operator[](StoreAll) const {
         return false;
     }
 
@@ -50,7 +50,7 @@ struct StoreAll {
     }
 
     template <class T>
-    constexpr friend T && operator,(T &&t, StoreAll) {
+    friend T && operator,(T &&t, StoreAll) {
         return std::forward<T>(t);
     }
 };
@@ -68,12 +68,9 @@ template<typename CGlueCtx = void>
 using MainFeatureRetTmp = void;
 
 template<typename T = void>
-using MaybeUninit = T;
-
-template<typename T>
 struct alignas(alignof(T)) RustMaybeUninit {
     char pad[sizeof(T)];
-    constexpr T &assume_init() {
+    inline T &assume_init() {
         return *(T *)this;
     }
     constexpr const T &assume_init() const {
@@ -81,6 +78,11 @@ struct alignas(alignof(T)) RustMaybeUninit {
     }
 };
 
+/**
+ * Description of all methods of a trait.
+ */
+struct TraitMeta;
+
 /**
  * Wrapper around null-terminated C-style strings.
  *
@@ -126,26 +128,34 @@ struct CArc {
  * This box has a static self reference, alongside a custom drop function.
  *
  * The drop function can be called from anywhere, it will free on correct allocator internally.
+ * The free function releases the storage without dropping the value, and is used when the
+ * value gets moved out of the box.
  */
 template<typename T>
 struct CBox {
     T *instance;
     void (*drop_fn)(T*);
+    void (*free_fn)(T*);
 
     CBox() = default;
-    CBox(T *instance) : instance(instance), drop_fn(nullptr) {}
-    CBox(T *instance, void (*drop_fn)(T *)) : instance(instance), drop_fn(drop_fn) {}
+    CBox(T *instance) : instance(instance), drop_fn(nullptr), free_fn(nullptr) {}
+    CBox(T *instance, void (*drop_fn)(T *), void (*free_fn)(T *)) : instance(instance), drop_fn(drop_fn), free_fn(free_fn) {}
     template<typename U = T, class = typename std::enable_if<std::is_same<U, T>::value>::type, class = typename std::enable_if<!std::is_same<U, void>::value>::type>
-    CBox(U &&instance) : instance(new U(instance)), drop_fn(&CBox::delete_fn) {}
+    CBox(U &&instance) : instance(new U(instance)), drop_fn(&CBox::delete_fn), free_fn(&CBox::operator_delete_fn) {}
 
     static void delete_fn(T *v) {
         delete v;
     }
 
+    static void operator_delete_fn(T *v) {
+        ::operator delete((void *)v);
+    }
+
     inline operator CBox<void> () const {
         CBox<void> ret;
         ret.instance = (void*)instance;
         ret.drop_fn = (void(*)(void *))drop_fn;
+        ret.free_fn = (void(*)(void *))free_fn;
         return ret;
     }
 
@@ -153,6 +163,7 @@ struct CBox {
         CBox ret;
         ret.instance = new T;
         ret.drop_fn = &CBox::delete_fn;
+        ret.free_fn = &CBox::operator_delete_fn;
         return ret;
     }
 
@@ -165,6 +176,7 @@ struct CBox {
     inline void forget() noexcept {
         instance = nullptr;
         drop_fn = nullptr;
+        free_fn = nullptr;
     }
 
     inline T *operator->() {
@@ -182,7 +194,7 @@ struct FeaturesGroupContainer {
     CGlueInst instance;
     CGlueCtx context;
 
-    inline auto clone_context() noexcept {
+    inline Context clone_context() noexcept {
         return context.clone();
     }
 
@@ -202,7 +214,7 @@ struct FeaturesGroupContainer<CGlueInst, void> {
     typedef void Context;
     CGlueInst instance;
 
-    inline auto clone_context() noexcept {}
+    inline Context clone_context() noexcept {}
 
     inline void drop() && noexcept {
         mem_drop(std::move(instance));
@@ -213,44 +225,6 @@ struct FeaturesGroupContainer<CGlueInst, void> {
     }
 };
 
-/**
- * CGlue vtable for trait MainFeature.
- *
- * This virtual function table contains ABI-safe interface for the given trait.
- */
-template<typename CGlueC>
-struct MainFeatureVtbl {
-    typedef typename CGlueC::Context Context;
-    void (*print_self)(const CGlueC *cont);
-};
-
-template<typename Impl>
-struct MainFeatureVtblImpl : MainFeatureVtbl<typename Impl::Parent> {
-constexpr MainFeatureVtblImpl() :
-    MainFeatureVtbl<typename Impl::Parent> {
-        &Impl::print_self
-    } {}
-};
-
-/**
- * CGlue vtable for trait Clone.
- *
- * This virtual function table contains ABI-safe interface for the given trait.
- */
-template<typename CGlueC>
-struct CloneVtbl {
-    typedef typename CGlueC::Context Context;
-    CGlueC (*clone)(const CGlueC *cont);
-};
-
-template<typename Impl>
-struct CloneVtblImpl : CloneVtbl<typename Impl::Parent> {
-constexpr CloneVtblImpl() :
-    CloneVtbl<typename Impl::Parent> {
-        &Impl::clone
-    } {}
-};
-
 /**
  * Wrapper around const slices.
  *
@@ -313,6 +287,75 @@ struct CSliceRef {
     }
 };
 
+/**
+ * FFI-safe description of a trait method.
+ */
+struct MethodDescriptor {
+    CSliceRef<uint8_t> name;
+    /**
+     * Same as [`MethodInfo::signature`].
+     */
+    CSliceRef<uint8_t> signature;
+};
+
+/**
+ * FFI-safe description of a trait.
+ *
+ * Every CGlue vtable refers to a static instance of this structure.
+ */
+struct TraitDescriptor {
+    CSliceRef<uint8_t> name;
+    CSliceRef<MethodDescriptor> methods;
+};
+
+/**
+ * CGlue vtable for trait MainFeature.
+ *
+ * This virtual function table contains ABI-safe interface for the given trait.
+ */
+template<typename CGlueC>
+struct MainFeatureVtbl {
+    typedef typename CGlueC::Context Context;
+    void (*print_self)(const CGlueC *cont);
+    const TraitDescriptor *descriptor;
+};
+
+template<typename Impl>
+struct MainFeatureVtblImpl : MainFeatureVtbl<typename Impl::Parent> {
+template<typename CGlueC>
+using Rebind = MainFeatureVtbl<CGlueC>;
+
+constexpr MainFeatureVtblImpl() :
+    MainFeatureVtbl<typename Impl::Parent> {
+        &Impl::print_self
+    } {}
+};
+
+
+/**
+ * CGlue vtable for trait Clone.
+ *
+ * This virtual function table contains ABI-safe interface for the given trait.
+ */
+template<typename CGlueC>
+struct CloneVtbl {
+    typedef typename CGlueC::Context Context;
+    CGlueC (*clone)(const CGlueC *cont);
+    const TraitDescriptor *descriptor;
+};
+
+template<typename Impl>
+struct CloneVtblImpl : CloneVtbl<typename Impl::Parent> {
+template<typename CGlueC>
+using Rebind = CloneVtbl<CGlueC>;
+
+constexpr CloneVtblImpl() :
+    CloneVtbl<typename Impl::Parent> {
+        &Impl::clone
+    } {}
+};
+
+
 struct KeyValue {
     CSliceRef<uint8_t> _0;
     uintptr_t _1;
@@ -396,7 +439,7 @@ using KeyValueCallback = OpaqueCallback<KeyValue>;
 template<typename T>
 struct CIterator {
     void *iter;
-    int32_t (*func)(void*, MaybeUninit<T> *out);
+    int32_t (*func)(void*, T *out);
 
     class iterator : std::iterator<std::input_iterator_tag, T> {
         CIterator<T> *iter;
@@ -430,7 +473,7 @@ struct CIterator {
             return !(*this == other);
         }
 
-        constexpr T &operator*() {
+        inline T &operator*() {
             return data.assume_init();
         }
 
@@ -456,7 +499,7 @@ struct CPPIterator {
     CIterator<T> iter;
     typename Container::iterator cur, end;
 
-    static int32_t next(void *data, MaybeUninit<T> *out) {
+    static int32_t next(void *data, T *out) {
         CPPIterator *i = (CPPIterator *)data;
 
         if (i->cur == i->end) {
@@ -504,10 +547,14 @@ struct KeyValueDumperVtbl {
     typedef typename CGlueC::Context Context;
     void (*dump_key_values)(const CGlueC *cont, KeyValueCallback callback);
     void (*print_ints)(const CGlueC *cont, CIterator<int32_t> iter);
+    const TraitDescriptor *descriptor;
 };
 
 template<typename Impl>
 struct KeyValueDumperVtblImpl : KeyValueDumperVtbl<typename Impl::Parent> {
+template<typename CGlueC>
+using Rebind = KeyValueDumperVtbl<CGlueC>;
+
 constexpr KeyValueDumperVtblImpl() :
     KeyValueDumperVtbl<typename Impl::Parent> {
         &Impl::dump_key_values,
@@ -515,6 +562,7 @@ constexpr KeyValueDumperVtblImpl() :
     } {}
 };
 
+
 /**
  * CGlue vtable for trait KeyValueStore.
  *
@@ -525,10 +573,14 @@ struct KeyValueStoreVtbl {
     typedef typename CGlueC::Context Context;
     void (*write_key_value)(CGlueC *cont, CSliceRef<uint8_t> name, uintptr_t val);
     uintptr_t (*get_key_value)(const CGlueC *cont, CSliceRef<uint8_t> name);
+    const TraitDescriptor *descriptor;
 };
 
 template<typename Impl>
 struct KeyValueStoreVtblImpl : KeyValueStoreVtbl<typename Impl::Parent> {
+template<typename CGlueC>
+using Rebind = KeyValueStoreVtbl<CGlueC>;
+
 constexpr KeyValueStoreVtblImpl() :
     KeyValueStoreVtbl<typename Impl::Parent> {
         &Impl::write_key_value,
@@ -536,6 +588,7 @@ constexpr KeyValueStoreVtblImpl() :
     } {}
 };
 
+
 /**
  * Trait group potentially implementing `MainFeature < > + :: cglue :: ext :: core :: clone :: Clone < > + KeyValueDumper < > + KeyValueStore < >` traits.
  *
@@ -570,7 +623,43 @@ struct FeaturesGroup {
 
     typedef CGlueCtx Context;
 
-    inline auto print_self() const noexcept {
+    inline uint64_t cglue_capabilities() const noexcept {
+        uint64_t ret = 0;
+        if (vtbl_mainfeature) ret |= 1ull << 0;
+        if (vtbl_clone) ret |= 1ull << 1;
+        if (vtbl_keyvaluedumper) ret |= 1ull << 2;
+        if (vtbl_keyvaluestore) ret |= 1ull << 3;
+        return ret;
+    }
+
+    static inline const char * const *cglue_capability_names() noexcept {
+        static const char * const names[] = {
+            "MainFeature",
+            "Clone",
+            "KeyValueDumper",
+            "KeyValueStore",
+            nullptr
+        };
+        return names;
+    }
+
+    inline void set_vtbl(decltype(vtbl_mainfeature) vtbl) noexcept {
+        vtbl_mainfeature = vtbl;
+    }
+
+    inline void set_vtbl(decltype(vtbl_clone) vtbl) noexcept {
+        vtbl_clone = vtbl;
+    }
+
+    inline void set_vtbl(decltype(vtbl_keyvaluedumper) vtbl) noexcept {
+        vtbl_keyvaluedumper = vtbl;
+    }
+
+    inline void set_vtbl(decltype(vtbl_keyvaluestore) vtbl) noexcept {
+        vtbl_keyvaluestore = vtbl;
+    }
+
+    inline void print_self() const noexcept {
     (this->vtbl_mainfeature)->print_self(&this->container);
 
     }
@@ -585,22 +674,22 @@ struct FeaturesGroup {
         return __ret;
     }
 
-    inline auto dump_key_values(KeyValueCallback callback) const noexcept {
+    inline void dump_key_values(KeyValueCallback callback) const noexcept {
     (this->vtbl_keyvaluedumper)->dump_key_values(&this->container, callback);
 
     }
 
-    inline auto print_ints(CIterator<int32_t> iter) const noexcept {
+    inline void print_ints(CIterator<int32_t> iter) const noexcept {
     (this->vtbl_keyvaluedumper)->print_ints(&this->container, iter);
 
     }
 
-    inline auto write_key_value(CSliceRef<uint8_t> name, uintptr_t val) noexcept {
+    inline void write_key_value(CSliceRef<uint8_t> name, uintptr_t val) noexcept {
     (this->vtbl_keyvaluestore)->write_key_value(&this->container, name, val);
 
     }
 
-    inline auto get_key_value(CSliceRef<uint8_t> name) const noexcept {
+    inline uintptr_t get_key_value(CSliceRef<uint8_t> name) const noexcept {
         uintptr_t __ret = (this->vtbl_keyvaluestore)->get_key_value(&this->container, name);
         return __ret;
     }
@@ -616,7 +705,7 @@ struct FeaturesGroup {
  */
 template<typename CGlueCtx = CArc<void>>
 struct PluginInnerRetTmp {
-    MaybeUninit<FeaturesGroup<void*, CGlueCtx>> mut_features;
+    FeaturesGroup<void*, CGlueCtx> mut_features;
 };
 
 /**
@@ -641,7 +730,7 @@ struct CGlueObjContainer {
     C context;
     RustMaybeUninit<R> ret_tmp;
 
-    inline auto clone_context() noexcept {
+    inline Context clone_context() noexcept {
         return context.clone();
     }
 
@@ -662,7 +751,7 @@ struct CGlueObjContainer<T, void, R> {
     T instance;
     RustMaybeUninit<R> ret_tmp;
 
-    inline auto clone_context() noexcept {}
+    inline Context clone_context() noexcept {}
 
     inline void drop() && noexcept {
         mem_drop(std::move(instance));
@@ -679,7 +768,7 @@ struct CGlueObjContainer<T, C, void> {
     T instance;
     C context;
 
-    inline auto clone_context() noexcept {
+    inline Context clone_context() noexcept {
         return context.clone();
     }
 
@@ -699,7 +788,7 @@ struct CGlueObjContainer<T, void, void> {
     typedef void Context;
     T instance;
 
-    auto clone_context() noexcept {}
+    inline Context clone_context() noexcept {}
 
     inline void drop() && noexcept {
         mem_drop(std::move(instance));
@@ -721,10 +810,14 @@ struct PluginInnerVtbl {
     FeaturesGroup<CBox<void>, Context> (*borrow_features)(CGlueC *cont);
     FeaturesGroup<CBox<void>, Context> (*into_features)(CGlueC cont);
     FeaturesGroup<void*, Context> *(*mut_features)(CGlueC *cont);
+    const TraitDescriptor *descriptor;
 };
 
 template<typename Impl>
 struct PluginInnerVtblImpl : PluginInnerVtbl<typename Impl::Parent> {
+template<typename CGlueC>
+using Rebind = PluginInnerVtbl<CGlueC>;
+
 constexpr PluginInnerVtblImpl() :
     PluginInnerVtbl<typename Impl::Parent> {
         &Impl::borrow_features,
@@ -733,6 +826,7 @@ constexpr PluginInnerVtblImpl() :
     } {}
 };
 
+
 /**
  * Simple CGlue trait object.
  *
@@ -769,7 +863,7 @@ using PluginInnerBaseArcBox = PluginInnerBaseCtxBox<CGlueT, CArc<CGlueC>>;
  * Opaque Boxed CGlue trait object for trait PluginInner with a [`CArc`](cglue::arc::CArc) reference counted context.
  */
 using PluginInnerArcBox = PluginInnerBaseArcBox<void, void>;
-// Typedef for default contaienr and context type
+// Typedef for default container and context type
 using PluginInner = PluginInnerArcBox;
 
 extern "C" {
@@ -791,7 +885,7 @@ extern const TypeLayout *ROOT_LAYOUT;
  * [call the one in this crate](self::get_root_layout). It is used to verify
  * version mismatches.
  */
-int32_t load_plugin(ReprCStr name, MaybeUninit<PluginInnerArcBox> *ok_out);
+int32_t load_plugin(ReprCStr name, PluginInnerArcBox *ok_out);
 
 } // extern "C"
 
@@ -808,7 +902,7 @@ struct CGlueTraitObj<T, MainFeatureVtbl<CGlueObjContainer<T, C, R>>, C, R> {
 
     typedef C Context;
 
-    inline auto print_self() const noexcept {
+    inline void print_self() const noexcept {
     (this->vtbl)->print_self(&this->container);
 
     }
@@ -850,12 +944,12 @@ struct CGlueTraitObj<T, KeyValueDumperVtbl<CGlueObjContainer<T, C, R>>, C, R> {
 
     typedef C Context;
 
-    inline auto dump_key_values(KeyValueCallback callback) const noexcept {
+    inline void dump_key_values(KeyValueCallback callback) const noexcept {
     (this->vtbl)->dump_key_values(&this->container, callback);
 
     }
 
-    inline auto print_ints(CIterator<int32_t> iter) const noexcept {
+    inline void print_ints(CIterator<int32_t> iter) const noexcept {
     (this->vtbl)->print_ints(&this->container, iter);
 
     }
@@ -875,12 +969,12 @@ struct CGlueTraitObj<T, KeyValueStoreVtbl<CGlueObjContainer<T, C, R>>, C, R> {
 
     typedef C Context;
 
-    inline auto write_key_value(CSliceRef<uint8_t> name, uintptr_t val) noexcept {
+    inline void write_key_value(CSliceRef<uint8_t> name, uintptr_t val) noexcept {
     (this->vtbl)->write_key_value(&this->container, name, val);
 
     }
 
-    inline auto get_key_value(CSliceRef<uint8_t> name) const noexcept {
+    inline uintptr_t get_key_value(CSliceRef<uint8_t> name) const noexcept {
         uintptr_t __ret = (this->vtbl)->get_key_value(&this->container, name);
         return __ret;
     }
@@ -900,22 +994,123 @@ struct CGlueTraitObj<T, PluginInnerVtbl<CGlueObjContainer<T, C, R>>, C, R> {
 
     typedef C Context;
 
-    inline auto borrow_features() noexcept {
+    inline FeaturesGroup<CBox<void>, Context> borrow_features() noexcept {
         FeaturesGroup<CBox<void>, Context> __ret = (this->vtbl)->borrow_features(&this->container);
         return __ret;
     }
 
-    inline auto into_features() && noexcept {
+    inline FeaturesGroup<CBox<void>, Context> into_features() && noexcept {
         auto ___ctx = StoreAll()[this->container.clone_context(), StoreAll()];
         FeaturesGroup<CBox<void>, Context> __ret = (this->vtbl)->into_features(this->container);
         mem_forget(this->container);
         return __ret;
     }
 
-    inline auto mut_features() noexcept {
+    inline FeaturesGroup<void*, Context> * mut_features() noexcept {
         FeaturesGroup<void*, Context> * __ret = (this->vtbl)->mut_features(&this->container);
         return __ret;
     }
 
 };
 
+namespace cglue {
+
+/** Opaque equivalent of the instance type. */
+template<typename T>
+struct OpaqueInst { typedef T type; };
+
+template<typename T>
+struct OpaqueInst<CBox<T>> { typedef CBox<void> type; };
+
+template<typename T>
+struct OpaqueInst<T *> { typedef void *type; };
+
+template<typename T>
+struct OpaqueInst<const T *> { typedef const void *type; };
+
+/** Container with its instance type replaced with an opaque one. */
+template<typename T>
+struct OpaqueCont;
+
+template<template<typename...> class Cont, typename T, typename... Rest>
+struct OpaqueCont<Cont<T, Rest...>> {
+    typedef Cont<typename OpaqueInst<T>::type, Rest...> type;
+};
+
+/** Group type that contains given container. */
+template<template<typename, typename> class Group, typename Cont>
+struct GroupOf;
+
+template<template<typename, typename> class Group, template<typename, typename> class Cont, typename T, typename C>
+struct GroupOf<Group, Cont<T, C>> {
+    typedef Group<T, C> type;
+};
+
+/** Trait object type that contains given container. */
+template<typename Vtbl, typename Cont>
+struct TraitObjOf;
+
+template<typename Vtbl, typename T, typename C, typename R>
+struct TraitObjOf<Vtbl, CGlueObjContainer<T, C, R>> {
+    typedef CGlueTraitObj<T, Vtbl, C, R> type;
+};
+
+/** Statically allocated vtable. */
+template<typename VtblImpl>
+inline const VtblImpl &static_vtbl() noexcept {
+    static const VtblImpl vtbl;
+    return vtbl;
+}
+
+/** Type erased vtable of `VtblImpl` for container `Cont`. */
+template<template<typename> class VtblImpl, typename Impl, typename Cont>
+inline const typename VtblImpl<Impl>::template Rebind<Cont> *opaque_vtbl() noexcept {
+    return (const typename VtblImpl<Impl>::template Rebind<Cont> *)&static_vtbl<VtblImpl<Impl>>();
+}
+
+/**
+ * Build a type erased trait object.
+ *
+ * `Impl` is the implementation of the trait, with `Impl::Parent` being the `CGlueObjContainer` it
+ * is built for. The arguments are used to initialize the container - typically it is an instance
+ * and a context.
+ *
+ * `make_obj<MainFeatureVtblImpl, Impl>(CBox<T>::new_box(), ctx)`
+ */
+template<template<typename> class VtblImpl, typename Impl, typename... Args>
+inline typename TraitObjOf<
+    typename VtblImpl<Impl>::template Rebind<typename OpaqueCont<typename Impl::Parent>::type>,
+    typename OpaqueCont<typename Impl::Parent>::type
+>::type make_obj(Args &&... args) noexcept {
+    typedef typename OpaqueCont<typename Impl::Parent>::type Cont;
+    typename Impl::Parent container { std::forward<Args>(args)... };
+
+    typename TraitObjOf<typename VtblImpl<Impl>::template Rebind<Cont>, Cont>::type ret;
+    ret.vtbl = opaque_vtbl<VtblImpl, Impl, Cont>();
+    ret.container = *(Cont *)&container;
+    return ret;
+}
+
+/**
+ * Build a type erased group object.
+ *
+ * `Impl` is the implementation of the listed traits, with `Impl::Parent` being the group's
+ * container it is built for. The arguments are used to initialize the container - typically it is
+ * an instance and a context. Traits that are not listed are left unimplemented.
+ *
+ * `make_group<FeaturesGroup, Impl, MainFeatureVtblImpl, KeyValueStoreVtblImpl>(CBox<T>::new_box(), ctx)`
+ */
+template<template<typename, typename> class Group, typename Impl, template<typename> class... VtblImpls, typename... Args>
+inline typename GroupOf<Group, typename OpaqueCont<typename Impl::Parent>::type>::type make_group(Args &&... args) noexcept {
+    typedef typename OpaqueCont<typename Impl::Parent>::type Cont;
+    typename Impl::Parent container { std::forward<Args>(args)... };
+
+    typename GroupOf<Group, Cont>::type ret;
+    int set[] = { 0, (ret.set_vtbl(opaque_vtbl<VtblImpls, Impl, Cont>()), 0)... };
+    (void)set;
+    ret.container = *(Cont *)&container;
+    return ret;
+}
+
+} // namespace cglue
+